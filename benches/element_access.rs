@@ -0,0 +1,104 @@
+//! Access to the children of a node, which alternate between full aligned and
+//! half aligned elements (see `src/green/element.rs`).
+//!
+//! The offset of an element is a `u32` aligned to 4 in both kinds of element,
+//! so reading it is never misaligned; only the pointer of a half aligned element
+//! is. `child_offset` compares the two kinds of element directly; on x86_64,
+//! they measure the same.
+
+use {
+    criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput},
+    sorbus::{green, Kind, TextSize},
+    std::sync::Arc,
+};
+
+const ATOM: Kind = Kind(0);
+const LIST: Kind = Kind(1);
+
+fn black_hole<T>(t: T) {
+    black_box(t);
+}
+
+const WIDTHS: [usize; 4] = [8, 64, 1024, 16384];
+
+/// A node with `width` tokens of varying length.
+fn make_node(width: usize) -> Arc<green::Node> {
+    let mut builder = green::TreeBuilder::new();
+    builder.start_node(LIST);
+    for i in 0..width {
+        builder.token(ATOM, &"x".repeat(1 + i % 4));
+    }
+    builder.finish_node().finish()
+}
+
+/// `count` pseudo-random numbers in `0..n`, the same on every run.
+fn random_indices(count: usize, n: usize) -> Vec<usize> {
+    let mut state = 0x2545_F491_4F6C_DD1D_u64;
+    (0..count)
+        .map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) as usize % n
+        })
+        .collect()
+}
+
+fn random_child(c: &mut Criterion) {
+    let mut group = c.benchmark_group("random_child");
+    for &width in WIDTHS.iter() {
+        let node = make_node(width);
+        let indices = random_indices(1024, width);
+        group.throughput(Throughput::Elements(indices.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(width), &node, |b, node| {
+            b.iter(|| indices.iter().for_each(|&i| black_hole(node[i].get())));
+        });
+    }
+    group.finish();
+}
+
+/// Reading the offsets of only full aligned (even) or only half aligned (odd) children,
+/// to show any penalty for the half aligned reads.
+fn child_offset(c: &mut Criterion) {
+    let mut group = c.benchmark_group("child_offset");
+    let width = 1024;
+    let node = make_node(width);
+    for &(name, parity) in [("full_aligned", 0), ("half_aligned", 1)].iter() {
+        let indices: Vec<_> =
+            random_indices(1024, width / 2).into_iter().map(|i| 2 * i + parity).collect();
+        group.throughput(Throughput::Elements(indices.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &node, |b, node| {
+            b.iter(|| indices.iter().map(|&i| node[i].offset()).fold(0.into(), TextSize::max));
+        });
+    }
+    group.finish();
+}
+
+fn index_of_offset(c: &mut Criterion) {
+    let mut group = c.benchmark_group("random_index_of_offset");
+    for &width in WIDTHS.iter() {
+        let node = make_node(width);
+        let offsets: Vec<TextSize> = random_indices(1024, u32::from(node.len()) as usize)
+            .into_iter()
+            .map(|offset| (offset as u32).into())
+            .collect();
+        group.throughput(Throughput::Elements(offsets.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(width), &node, |b, node| {
+            b.iter(|| offsets.iter().for_each(|&offset| black_hole(node.index_of_offset(offset))));
+        });
+    }
+    group.finish();
+}
+
+fn with_offsets(c: &mut Criterion) {
+    let mut group = c.benchmark_group("children_with_offsets");
+    for &width in WIDTHS.iter() {
+        let node = make_node(width);
+        group.throughput(Throughput::Elements(width as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(width), &node, |b, node| {
+            b.iter(|| node.children().with_offsets().for_each(black_hole));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, random_child, child_offset, index_of_offset, with_offsets);
+criterion_main!(benches);
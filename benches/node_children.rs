@@ -1,6 +1,10 @@
 use {
     criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput},
-    sorbus::{green, ArcBorrow, Kind, NodeOrToken},
+    sorbus::{
+        green::{self, ElementRef},
+        testing::{gen_tree, Profile},
+        Kind, NodeOrToken, TextSize,
+    },
     std::sync::Arc,
 };
 
@@ -58,7 +62,7 @@ fn flat_children_iterate(c: &mut Criterion) {
     group.finish();
 }
 
-fn visit(el: NodeOrToken<ArcBorrow<'_, green::Node>, ArcBorrow<'_, green::Token>>) {
+fn visit(el: ElementRef<'_>) {
     match el {
         NodeOrToken::Node(node) => node.children().for_each(visit),
         NodeOrToken::Token(token) => black_hole(token),
@@ -78,5 +82,93 @@ fn visit_children_iterate(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, flat_children_iterate, visit_children_iterate);
+fn visit_generated_trees(c: &mut Criterion) {
+    let mut group = c.benchmark_group("visit_generated");
+    let profiles = [
+        ("wide_flat", Profile::wide_flat()),
+        ("deep_narrow", Profile::deep_narrow()),
+        ("mixed", Profile::mixed()),
+    ];
+    for &(name, profile) in profiles.iter() {
+        group.throughput(Throughput::Elements(profile.tokens as u64));
+        let tree = gen_tree(0, profile);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &tree, |b, tree| {
+            b.iter(|| tree.children().for_each(visit));
+        });
+    }
+    group.finish();
+}
+
+fn flat_children_reverse_iterate(c: &mut Criterion) {
+    const SCALE: usize = 256;
+    let mut group = c.benchmark_group("flat_children_rev");
+    for &scale in [SCALE, 2 * SCALE, 4 * SCALE, 8 * SCALE, 16 * SCALE].iter() {
+        group.throughput(Throughput::Elements(scale as u64));
+        let tree = make_tree(scale);
+        group.bench_with_input(BenchmarkId::from_parameter(scale), &tree, |b, tree| {
+            b.iter(|| tree.children().rev().for_each(black_hole));
+        });
+    }
+    group.finish();
+}
+
+fn flat_children_rfind(c: &mut Criterion) {
+    const SCALE: usize = 256;
+    let mut group = c.benchmark_group("flat_children_rfind");
+    for &scale in [SCALE, 2 * SCALE, 4 * SCALE, 8 * SCALE, 16 * SCALE].iter() {
+        group.throughput(Throughput::Elements(scale as u64));
+        let tree = make_tree(scale);
+        group.bench_with_input(BenchmarkId::from_parameter(scale), &tree, |b, tree| {
+            // never found, so this scans all of the children
+            b.iter(|| tree.children().rfind(|el| black_box(el).is_token()));
+        });
+    }
+    group.finish();
+}
+
+fn flat_children_position(c: &mut Criterion) {
+    const SCALE: usize = 256;
+    let mut group = c.benchmark_group("flat_children_position");
+    for &scale in [SCALE, 2 * SCALE, 4 * SCALE, 8 * SCALE, 16 * SCALE].iter() {
+        group.throughput(Throughput::Elements(scale as u64));
+        let tree = make_tree(scale);
+        group.bench_with_input(BenchmarkId::from_parameter(scale), &tree, |b, tree| {
+            // never found, so this scans all of the children
+            b.iter(|| tree.children().position(|el| black_box(el).is_token()));
+        });
+    }
+    group.finish();
+}
+
+fn index_of_offset(c: &mut Criterion) {
+    const ATOM: Kind = Kind(3);
+    const LIST: Kind = Kind(4);
+
+    let mut group = c.benchmark_group("index_of_offset");
+    for &len in [2, 4, 8, 16, 64, 256].iter() {
+        let mut builder = green::TreeBuilder::new();
+        builder.start_node(LIST);
+        for i in 0..len {
+            builder.token(ATOM, &i.to_string());
+        }
+        let tree = builder.finish_node().finish();
+        let offsets: Vec<_> = (0..u32::from(tree.len())).map(TextSize::from).collect();
+        group.throughput(Throughput::Elements(offsets.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(len), &tree, |b, tree| {
+            b.iter(|| offsets.iter().for_each(|&offset| black_hole(tree.index_of_offset(offset))));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    flat_children_iterate,
+    visit_children_iterate,
+    visit_generated_trees,
+    flat_children_reverse_iterate,
+    flat_children_rfind,
+    flat_children_position,
+    index_of_offset,
+);
 criterion_main!(benches);
@@ -0,0 +1,149 @@
+use {
+    criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput},
+    sorbus::{green, Kind},
+};
+
+const WS: Kind = Kind(0);
+const ATOM: Kind = Kind(1);
+const OP: Kind = Kind(2);
+const LIST: Kind = Kind(3);
+
+/// Build `depth` nested lists, each with a few tokens before and after the nested list.
+fn build_deep(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tree_builder_deep");
+    for &depth in &[16, 256, 4096] {
+        group.throughput(Throughput::Elements(depth as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &depth, |b, &depth| {
+            let mut builder = green::TreeBuilder::new();
+            b.iter(|| {
+                for _ in 0..depth {
+                    builder.start_node(LIST).token(ATOM, "a").token(WS, " ");
+                }
+                for _ in 0..depth {
+                    builder.token(WS, " ").token(ATOM, "z").finish_node();
+                }
+                black_box(builder.finish())
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Build one list of `width` lists of tokens, as in a token-heavy file.
+fn build_wide(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tree_builder_wide");
+    for &width in &[16, 256, 4096] {
+        group.throughput(Throughput::Elements(width as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(width), &width, |b, &width| {
+            let mut builder = green::TreeBuilder::new();
+            b.iter(|| {
+                builder.start_node(LIST);
+                for _ in 0..width {
+                    builder.start_node(LIST);
+                    for _ in 0..8 {
+                        builder.token(ATOM, "a").token(WS, " ");
+                    }
+                    builder.finish_node();
+                }
+                black_box(builder.finish_node().finish())
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Build a left-leaning chain of `len` binary operations by wrapping at checkpoints,
+/// as a Pratt parser does.
+fn build_checkpoints(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tree_builder_checkpoints");
+    for &len in &[16, 256, 4096] {
+        group.throughput(Throughput::Elements(len as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(len), &len, |b, &len| {
+            let mut builder = green::TreeBuilder::new();
+            b.iter(|| {
+                builder.start_node(LIST);
+                let start = builder.checkpoint();
+                builder.token(ATOM, "a");
+                for _ in 0..len {
+                    builder.start_node_at(start, LIST).token(OP, "+").token(ATOM, "b");
+                    builder.finish_node();
+                }
+                black_box(builder.finish_node().finish())
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Build one list of `width` lists of tokens, each followed by trailing whitespace
+/// in the parent, both by finishing each list at a checkpoint before its trailing
+/// whitespace (shifting it out to the parent, as trivia attachment does) and by
+/// finishing each list before adding it.
+fn build_finish_at(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tree_builder_finish_at");
+    for &width in &[16, 256, 4096] {
+        group.throughput(Throughput::Elements(width as u64));
+        group.bench_with_input(BenchmarkId::new("finish_node", width), &width, |b, &width| {
+            let mut builder = green::TreeBuilder::new();
+            b.iter(|| {
+                builder.start_node(LIST);
+                for _ in 0..width {
+                    builder.start_node(LIST);
+                    for _ in 0..8 {
+                        builder.token(ATOM, "a").token(WS, " ");
+                    }
+                    builder.finish_node().token(WS, " ").token(WS, " ");
+                }
+                black_box(builder.finish_node().finish())
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("finish_node_at", width), &width, |b, &width| {
+            let mut builder = green::TreeBuilder::new();
+            b.iter(|| {
+                builder.start_node(LIST);
+                for _ in 0..width {
+                    builder.start_node(LIST);
+                    for _ in 0..8 {
+                        builder.token(ATOM, "a").token(WS, " ");
+                    }
+                    let trailing = builder.checkpoint();
+                    builder.token(WS, " ").token(WS, " ").finish_node_at(trailing);
+                }
+                black_box(builder.finish_node().finish())
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Intern `count` distinct nodes into a fresh cache, so every lookup misses
+/// and the cache grows (and rehashes its contents) repeatedly.
+fn intern_misses(c: &mut Criterion) {
+    let mut group = c.benchmark_group("builder_intern_misses");
+    for &count in &[256, 4096, 65536] {
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            let mut tokens = green::Builder::new();
+            let atoms: Vec<_> = (0..256).map(|i| tokens.token(ATOM, &i.to_string())).collect();
+            b.iter(|| {
+                let mut builder = tokens.clone();
+                for i in 0..count {
+                    let children = [atoms[i % 256].clone(), atoms[i / 256 % 256].clone()];
+                    black_box(builder.node(LIST, children));
+                }
+                builder
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    build_deep,
+    build_wide,
+    build_checkpoints,
+    build_finish_at,
+    intern_misses
+);
+criterion_main!(benches);
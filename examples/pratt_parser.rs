@@ -4,8 +4,8 @@
 //! converted to use sorbus as the parsed tree.
 
 use {
-    sorbus::{green, Kind, NodeOrToken},
-    std::{collections::VecDeque, fmt, str, sync::Arc},
+    sorbus::{green, recipes, Kind},
+    std::{collections::VecDeque, str, sync::Arc},
 };
 
 // NB: only constructs a green tree at this time.
@@ -76,35 +76,7 @@ impl<'src> Lexer<'src> {
 }
 
 fn to_sexpr(node: &green::Node) -> String {
-    fn display<'a>(
-        el: NodeOrToken<&'a green::Node, &'a green::Token>,
-        f: &'a mut dyn fmt::Write,
-    ) -> fmt::Result {
-        match el {
-            NodeOrToken::Token(token) => write!(f, "{}", token.text())?,
-            NodeOrToken::Node(node) => {
-                let children_of_interest: Vec<_> =
-                    node.children().filter(|el| el.kind() != WS).collect();
-                if children_of_interest.len() == 1 {
-                    display(children_of_interest[0].as_deref(), f)?;
-                } else {
-                    f.write_str("(")?;
-                    for op in children_of_interest.iter().filter(|e| e.kind() == OP) {
-                        display(op.as_deref(), f)?;
-                    }
-                    for expr in children_of_interest.iter().filter(|e| e.kind() == EXPR) {
-                        f.write_str(" ")?;
-                        display(expr.as_deref(), f)?;
-                    }
-                    f.write_str(")")?;
-                }
-            }
-        }
-        Ok(())
-    }
-    let mut s = String::new();
-    display(NodeOrToken::Node(node), &mut s).unwrap();
-    s
+    recipes::collect_sexpr(node, |kind| kind == WS)
 }
 
 fn expr(input: &str) -> Arc<green::Node> {
@@ -113,6 +85,7 @@ fn expr(input: &str) -> Arc<green::Node> {
     expr_bp(&mut lexer, 0);
     eprintln!();
     let node = lexer.builder.finish();
+    assert_eq!(recipes::reconstruct_text(&node), input);
     let display = to_sexpr(&node);
     eprintln!("{}", display);
     node
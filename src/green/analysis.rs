@@ -0,0 +1,402 @@
+//! Measurements of green trees.
+
+use {
+    crate::{
+        green::{Node, Token},
+        ArcBorrow, Kind, NodeOrToken, TextSize,
+    },
+    std::{
+        cmp::Reverse,
+        collections::{BTreeMap, HashMap, HashSet},
+        fmt, mem,
+    },
+};
+
+/// How much of a tree is shared by deduplication. See [`sharing_report`].
+///
+/// Logical counts count every occurrence of an element in the tree,
+/// as if no subtrees were shared; unique counts count distinct allocations.
+/// Sizes are of the elements themselves, not counting reference counts.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct SharingReport {
+    /// The number of nodes in the tree, counting every occurrence.
+    pub logical_nodes: usize,
+    /// The number of distinct node allocations in the tree.
+    pub unique_nodes: usize,
+    /// The number of tokens in the tree, counting every occurrence.
+    pub logical_tokens: usize,
+    /// The number of distinct token allocations in the tree.
+    pub unique_tokens: usize,
+    /// The total bytes saved by sharing, compared to a tree without sharing.
+    pub bytes_saved: usize,
+    /// The shared subtrees saving the most bytes, most first.
+    pub top_shared: Vec<SharedSubtree>,
+}
+
+/// A subtree which occurs multiple times in a tree. See [`SharingReport`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct SharedSubtree {
+    /// The kind of the subtree's root.
+    pub kind: Kind,
+    /// The length of the subtree's text.
+    pub len: TextSize,
+    /// The number of times the subtree occurs.
+    pub occurrences: usize,
+    /// The bytes saved by sharing the whole subtree rather than copying it for each occurrence.
+    pub bytes_saved: usize,
+}
+
+impl fmt::Display for SharingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "nodes: {} logical, {} unique", self.logical_nodes, self.unique_nodes)?;
+        writeln!(f, "tokens: {} logical, {} unique", self.logical_tokens, self.unique_tokens)?;
+        writeln!(f, "bytes saved: {}", self.bytes_saved)?;
+        for shared in &self.top_shared {
+            writeln!(
+                f,
+                "  {:?}@{:?} ×{} saves {} bytes",
+                shared.kind, shared.len, shared.occurrences, shared.bytes_saved,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The number of shared subtrees listed in a [`SharingReport`].
+const TOP_SHARED: usize = 10;
+
+/// Measure how much of a tree is shared by deduplication.
+///
+/// This visits each distinct node once, so it is cheap even for trees
+/// with a large amount of sharing.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let mut builder = green::TreeBuilder::new();
+/// let inner = builder.start_node(LIST).token(ATOM, "a").finish_node().finish();
+/// let tree = builder
+///     .start_node(LIST)
+///     .add(inner.clone())
+///     .add(inner.clone())
+///     .add(inner)
+///     .finish_node()
+///     .finish();
+///
+/// let report = green::analysis::sharing_report(&tree);
+/// assert_eq!((report.logical_nodes, report.unique_nodes), (4, 2));
+/// assert_eq!((report.logical_tokens, report.unique_tokens), (3, 1));
+/// assert_eq!(report.top_shared[0].occurrences, 3);
+/// ```
+pub fn sharing_report(root: &Node) -> SharingReport {
+    // distinct nodes in postorder, so children come before their parents
+    let mut nodes: Vec<&Node> = vec![];
+    let mut node_index: HashMap<*const Node, usize> = HashMap::new();
+    let mut token_index: HashMap<*const Token, usize> = HashMap::new();
+    let mut tokens: Vec<&Token> = vec![];
+
+    fn visit<'a>(
+        node: &'a Node,
+        nodes: &mut Vec<&'a Node>,
+        node_index: &mut HashMap<*const Node, usize>,
+        tokens: &mut Vec<&'a Token>,
+        token_index: &mut HashMap<*const Token, usize>,
+    ) {
+        for child in node.children() {
+            match child {
+                NodeOrToken::Node(child) => {
+                    let child = ArcBorrow::downgrade(child);
+                    if !node_index.contains_key(&(child as *const Node)) {
+                        visit(child, nodes, node_index, tokens, token_index);
+                    }
+                }
+                NodeOrToken::Token(child) => {
+                    let child = ArcBorrow::downgrade(child);
+                    token_index.entry(child as *const Token).or_insert_with(|| {
+                        tokens.push(child);
+                        tokens.len() - 1
+                    });
+                }
+            }
+        }
+        node_index.insert(node as *const Node, nodes.len());
+        nodes.push(node);
+    }
+    visit(root, &mut nodes, &mut node_index, &mut tokens, &mut token_index);
+
+    let child_index = |child: NodeOrToken<&Node, &Token>| {
+        child.map(|node| node_index[&(node as *const _)], |token| token_index[&(token as *const _)])
+    };
+
+    // occurrences flow from parents to children, so visit parents first
+    let mut node_counts = vec![0_usize; nodes.len()];
+    let mut token_counts = vec![0_usize; tokens.len()];
+    *node_counts.last_mut().unwrap() = 1;
+    for (i, node) in nodes.iter().enumerate().rev() {
+        let count = node_counts[i];
+        for child in node.children() {
+            match child_index(child.into()) {
+                NodeOrToken::Node(j) => node_counts[j] = node_counts[j].saturating_add(count),
+                NodeOrToken::Token(j) => token_counts[j] = token_counts[j].saturating_add(count),
+            }
+        }
+    }
+
+    // the size of each subtree if it were not shared at all
+    let mut tree_bytes = vec![0_usize; nodes.len()];
+    for (i, node) in nodes.iter().enumerate() {
+        tree_bytes[i] = node.children().fold(mem::size_of_val(*node), |bytes, child| {
+            let child_bytes = match child_index(child.into()) {
+                NodeOrToken::Node(j) => tree_bytes[j],
+                NodeOrToken::Token(j) => mem::size_of_val(tokens[j]),
+            };
+            bytes.saturating_add(child_bytes)
+        });
+    }
+
+    let saved = |count: usize, bytes: usize| (count - 1).saturating_mul(bytes);
+    let bytes_saved = Iterator::chain(
+        nodes.iter().zip(&node_counts).map(|(node, &count)| saved(count, mem::size_of_val(*node))),
+        tokens
+            .iter()
+            .zip(&token_counts)
+            .map(|(token, &count)| saved(count, mem::size_of_val(*token))),
+    )
+    .fold(0, usize::saturating_add);
+
+    let mut top_shared: Vec<_> = nodes
+        .iter()
+        .zip(&node_counts)
+        .zip(&tree_bytes)
+        .filter(|((_, &count), _)| count > 1)
+        .map(|((node, &occurrences), &bytes)| SharedSubtree {
+            kind: node.kind(),
+            len: node.len(),
+            occurrences,
+            bytes_saved: saved(occurrences, bytes),
+        })
+        .collect();
+    top_shared.sort_by_key(|shared| Reverse(shared.bytes_saved));
+    top_shared.truncate(TOP_SHARED);
+
+    SharingReport {
+        logical_nodes: node_counts.iter().fold(0, |sum, &count| sum.saturating_add(count)),
+        unique_nodes: nodes.len(),
+        logical_tokens: token_counts.iter().fold(0, |sum, &count| sum.saturating_add(count)),
+        unique_tokens: tokens.len(),
+        bytes_saved,
+        top_shared,
+    }
+}
+
+/// Statistics of the shape of a corpus of trees. See [`corpus_stats`].
+///
+/// Distributions count every occurrence of an element, as if no subtrees
+/// were shared. With the `ser` feature, this is serializable as a report.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct CorpusStats {
+    /// The number of trees in the corpus.
+    pub roots: usize,
+    /// The number of children of each node.
+    pub node_widths: Histogram,
+    /// The depth of each token, where children of a root have depth 1.
+    pub token_depths: Histogram,
+    /// The length of each token.
+    pub token_lengths: Histogram,
+    /// The number of elements of each kind.
+    pub kinds: BTreeMap<Kind, usize>,
+    /// The number of distinct node allocations in the corpus.
+    pub unique_nodes: usize,
+    /// The number of distinct token allocations in the corpus.
+    pub unique_tokens: usize,
+}
+
+impl CorpusStats {
+    /// The fraction of nodes which are distinct allocations, or 1 if there are no nodes.
+    ///
+    /// The lower the ratio, the more nodes are deduplicated.
+    pub fn node_dedup_ratio(&self) -> f64 {
+        ratio(self.unique_nodes, self.node_widths.len())
+    }
+
+    /// The fraction of tokens which are distinct allocations, or 1 if there are no tokens.
+    ///
+    /// The lower the ratio, the more tokens are deduplicated.
+    pub fn token_dedup_ratio(&self) -> f64 {
+        ratio(self.unique_tokens, self.token_lengths.len())
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        1.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+impl fmt::Display for CorpusStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "roots: {}", self.roots)?;
+        writeln!(f, "node widths: {}", self.node_widths)?;
+        writeln!(f, "token depths: {}", self.token_depths)?;
+        writeln!(f, "token lengths: {}", self.token_lengths)?;
+        writeln!(f, "node dedup ratio: {:.3}", self.node_dedup_ratio())?;
+        writeln!(f, "token dedup ratio: {:.3}", self.token_dedup_ratio())?;
+        for (kind, count) in &self.kinds {
+            writeln!(f, "  {:?} ×{}", kind, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// A distribution of sizes, as the number of samples of each size.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Histogram {
+    counts: BTreeMap<usize, usize>,
+    len: usize,
+}
+
+impl Histogram {
+    /// Create an empty histogram.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sample.
+    pub fn record(&mut self, value: usize) {
+        *self.counts.entry(value).or_insert(0) += 1;
+        self.len += 1;
+    }
+
+    /// The number of samples.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no samples.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of samples of each value, in increasing order of value.
+    pub fn counts(&self) -> impl '_ + Iterator<Item = (usize, usize)> {
+        self.counts.iter().map(|(&value, &count)| (value, count))
+    }
+
+    /// The smallest sample, if any.
+    pub fn min(&self) -> Option<usize> {
+        self.counts.keys().next().copied()
+    }
+
+    /// The largest sample, if any.
+    pub fn max(&self) -> Option<usize> {
+        self.counts.keys().next_back().copied()
+    }
+
+    /// The mean of the samples, if any.
+    pub fn mean(&self) -> Option<f64> {
+        let sum = self.counts().map(|(value, count)| value as f64 * count as f64).sum::<f64>();
+        Some(sum / self.len as f64).filter(|_| !self.is_empty())
+    }
+
+    /// The smallest sample which at least `quantile` of the samples are at most, if any.
+    ///
+    /// For example, `quantile(0.5)` is the median.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `quantile` is not within `0.0..=1.0`.
+    pub fn quantile(&self, quantile: f64) -> Option<usize> {
+        assert!((0.0..=1.0).contains(&quantile), "quantile {} is not within 0..=1", quantile);
+        let rank = ((quantile * self.len as f64).ceil() as usize).max(1);
+        let mut seen = 0;
+        self.counts()
+            .find(|&(_, count)| {
+                seen += count;
+                seen >= rank
+            })
+            .map(|(value, _)| value)
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min(), self.quantile(0.5), self.mean(), self.max()) {
+            (Some(min), Some(median), Some(mean), Some(max)) => write!(
+                f,
+                "{} samples, min {}, median {}, mean {:.2}, max {}",
+                self.len, min, median, mean, max,
+            ),
+            _ => write!(f, "no samples"),
+        }
+    }
+}
+
+/// Measure the distributions of the shapes of a corpus of trees,
+/// for tuning grammars and the tree representation.
+///
+/// Unlike [`sharing_report`], this visits every occurrence of shared
+/// subtrees, as the depths of their tokens differ between occurrences.
+/// Distinct allocations are counted across the whole corpus.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let mut builder = green::TreeBuilder::new();
+/// let inner = builder.start_node(LIST).token(ATOM, "a").finish_node().finish();
+/// let tree = builder
+///     .start_node(LIST)
+///     .add(inner.clone())
+///     .token(ATOM, "bc")
+///     .finish_node()
+///     .finish();
+///
+/// let stats = green::analysis::corpus_stats(vec![&*tree, &*inner]);
+/// assert_eq!(stats.roots, 2);
+/// assert_eq!(stats.kinds[&LIST], 3);
+/// assert_eq!(stats.token_depths.max(), Some(2));
+/// assert_eq!(stats.token_lengths.quantile(1.0), Some(2));
+/// assert_eq!((stats.unique_nodes, stats.node_widths.len()), (2, 3));
+/// ```
+pub fn corpus_stats<'a>(roots: impl IntoIterator<Item = &'a Node>) -> CorpusStats {
+    let mut stats = CorpusStats::default();
+    let mut nodes: HashSet<*const Node> = HashSet::new();
+    let mut tokens: HashSet<*const Token> = HashSet::new();
+    let mut stack: Vec<(&Node, usize)> = vec![];
+
+    for root in roots {
+        stats.roots += 1;
+        stack.push((root, 0));
+        while let Some((node, depth)) = stack.pop() {
+            nodes.insert(node);
+            stats.node_widths.record(node.children_len());
+            *stats.kinds.entry(node.kind()).or_insert(0) += 1;
+            for child in node.children() {
+                match child {
+                    NodeOrToken::Node(child) => {
+                        stack.push((ArcBorrow::downgrade(child), depth + 1))
+                    }
+                    NodeOrToken::Token(child) => {
+                        let child = ArcBorrow::downgrade(child);
+                        tokens.insert(child);
+                        stats.token_depths.record(depth + 1);
+                        stats.token_lengths.record(child.len().into());
+                        *stats.kinds.entry(child.kind()).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    stats.unique_nodes = nodes.len();
+    stats.unique_tokens = tokens.len();
+    stats
+}
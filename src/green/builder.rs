@@ -1,18 +1,67 @@
 use {
     crate::{
-        green::{pack_node_or_token, Node, PackedNodeOrToken, Token},
-        ArcBorrow, Kind, NodeOrToken,
+        green::{
+            node::{NodeHead, Synthesis},
+            pack_node_or_token, token,
+            tokens::StaticAlphabet,
+            unpack_node_or_token, ElementRef, Node, NodeError, PackedNodeOrToken, Token,
+        },
+        ArcBorrow, Kind, NodeOrToken, TextSize,
     },
     erasable::{ErasablePtr, ErasedPtr},
     hashbrown::{hash_map::RawEntryMut, HashMap},
     std::{
+        any::Any,
+        collections::{BTreeMap, HashMap as StdHashMap},
         fmt,
         hash::{BuildHasher, Hash, Hasher},
-        ptr,
+        iter, ops, ptr, slice, str,
         sync::Arc,
+        vec,
     },
 };
 
+/// An iterator of node children which can also be viewed as a slice of the
+/// remaining children, so that they can be looked up in the cache by reference
+/// before being moved into a new node.
+///
+/// This is implemented for the by-value iterators of `Vec`s and arrays,
+/// so those can be passed as node children without any other allocation.
+pub trait SliceIter: ExactSizeIterator {
+    /// The element type of the slice.
+    type Elem;
+    /// The remaining children, as a slice.
+    fn as_slice(&self) -> &[Self::Elem];
+}
+
+impl<T> SliceIter for vec::IntoIter<T> {
+    type Elem = T;
+    fn as_slice(&self) -> &[T] {
+        vec::IntoIter::as_slice(self)
+    }
+}
+
+impl<T> SliceIter for vec::Drain<'_, T> {
+    type Elem = T;
+    fn as_slice(&self) -> &[T] {
+        vec::Drain::as_slice(self)
+    }
+}
+
+impl<T, const N: usize> SliceIter for std::array::IntoIter<T, N> {
+    type Elem = T;
+    fn as_slice(&self) -> &[T] {
+        std::array::IntoIter::as_slice(self)
+    }
+}
+
+impl<'a, T> SliceIter for slice::Iter<'a, T> {
+    type Elem = T;
+    fn as_slice(&self) -> &[T] {
+        slice::Iter::as_slice(self)
+    }
+}
+
 fn erased_children<'a, I: 'a>(
     children: I,
 ) -> impl 'a + Iterator<Item = ErasedPtr> + ExactSizeIterator
@@ -26,19 +75,19 @@ where
 
 fn thin_node_eq(
     node: &Node,
-    kind: Kind,
+    head: NodeHead,
     children: impl Iterator<Item = ErasedPtr> + ExactSizeIterator,
 ) -> bool {
-    node.kind() == kind && erased_children(node.children()).eq(children)
+    node.head() == head && erased_children(node.children()).eq(children)
 }
 
 fn thin_node_hash(
     hasher: &impl BuildHasher,
-    kind: Kind,
+    head: NodeHead,
     children: impl Iterator<Item = ErasedPtr>,
 ) -> u64 {
     let state = &mut hasher.build_hasher();
-    kind.hash(state);
+    head.hash(state);
     for child in children {
         ptr::hash(child.as_ptr(), state);
     }
@@ -54,10 +103,66 @@ fn thin_node_hash(
 #[derive(Default, Clone)]
 pub struct Builder {
     hasher: ahash::RandomState, // dedupe the 2×u64 hasher state and enforce custom hashing
-    nodes: HashMap<Arc<Node>, (), ()>,
+    nodes: HashMap<CachedNode, (), ()>,
     tokens: HashMap<Arc<Token>, (), ()>,
+    values: StdHashMap<usize, Arc<dyn Any + Send + Sync>>, // keyed by cached token address
+    fragment_kind: Option<Kind>,
+    token_classifier: Option<TokenClassifier>,
+    synthesis: Synthesis,
+    evict_hooks: Vec<EvictHook>,
+    epoch: u64,
+    base: Option<Arc<Builder>>, // read-only, consulted before this layer
+    #[cfg(feature = "tracing")]
+    counters: CacheCounters, // since the last gc
+}
+
+/// A cached node with its hash, so growing the cache doesn't rehash every node's children.
+#[derive(Clone)]
+struct CachedNode {
+    hash: u64,
+    node: Arc<Node>,
 }
 
+impl ops::Deref for CachedNode {
+    type Target = Arc<Node>;
+    fn deref(&self) -> &Arc<Node> {
+        &self.node
+    }
+}
+
+impl fmt::Debug for CachedNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.node.fmt(f)
+    }
+}
+
+/// Cache hit counters, reported by `tracing` events.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Default, Copy, Clone)]
+struct CacheCounters {
+    node_hits: u64,
+    node_misses: u64,
+    token_hits: u64,
+    token_misses: u64,
+}
+
+/// Count a cache hit or miss, if the `tracing` feature is enabled.
+macro_rules! count {
+    ($self:ident.$counter:ident) => {
+        #[cfg(feature = "tracing")]
+        {
+            $self.counters.$counter += 1;
+        }
+    };
+}
+
+/// The number of children above which building a node is traced as a large node.
+#[cfg(feature = "tracing")]
+const LARGE_NODE_CHILDREN: usize = 1 << 12;
+
+type TokenClassifier = Arc<dyn Fn(Kind, &str) -> u8 + Send + Sync>;
+type EvictHook = Arc<dyn Fn(NodeOrToken<&Node, &Token>) + Send + Sync>;
+
 impl fmt::Debug for Builder {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // save space in nonexpanded view
@@ -65,11 +170,25 @@ impl fmt::Debug for Builder {
             f.debug_struct("Builder")
                 .field("nodes", &self.nodes)
                 .field("tokens", &self.tokens)
+                .field("values", &format_args!("{} cached", self.values.len()))
+                .field("fragment_kind", &self.fragment_kind)
+                .field("token_classifier", &self.token_classifier.is_some())
+                .field("synthesis", &self.synthesis)
+                .field("evict_hooks", &format_args!("{} registered", self.evict_hooks.len()))
+                .field("epoch", &self.epoch)
+                .field("base", &self.base)
                 .finish()
         } else {
             f.debug_struct("Builder")
                 .field("nodes", &format_args!("{} cached", self.nodes.len()))
                 .field("tokens", &format_args!("{} cached", self.tokens.len()))
+                .field("values", &format_args!("{} cached", self.values.len()))
+                .field("fragment_kind", &self.fragment_kind)
+                .field("token_classifier", &self.token_classifier.is_some())
+                .field("synthesis", &self.synthesis)
+                .field("evict_hooks", &format_args!("{} registered", self.evict_hooks.len()))
+                .field("epoch", &self.epoch)
+                .field("base", &self.base)
                 .finish()
         }
     }
@@ -87,10 +206,233 @@ impl Builder {
         Self::default()
     }
 
+    /// Create a builder layered over a read-only snapshot of `base`.
+    ///
+    /// The layered builder consults the base cache first, and only caches
+    /// elements it doesn't find there in its own layer, so elements already in
+    /// the base (e.g. keywords and common expressions) are shared rather than
+    /// duplicated. The layer inherits the base's fragment kind, token classifier,
+    /// and synthesis settings, but not its eviction callbacks. [`gc`](Builder::gc)
+    /// and [`clear`](Builder::clear) only evict elements from the layer itself.
+    ///
+    /// Creating the layer snapshots the base, so later changes to `base` are not
+    /// seen by it. Cloning a layered builder shares the snapshot rather than copying
+    /// it again, so many per-document builders can share one immutable base without
+    /// locks by cloning an empty layer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const KW: Kind = Kind(0); const IDENT: Kind = Kind(1);
+    /// let mut shared = green::Builder::new();
+    /// let fn_kw = shared.token(KW, "fn");
+    ///
+    /// let empty = green::Builder::layered(&shared);
+    /// let mut document = empty.clone();
+    /// assert!(std::sync::Arc::ptr_eq(&document.token(KW, "fn"), &fn_kw));
+    /// document.token(IDENT, "main");
+    /// assert_eq!(document.size(), 1);
+    /// ```
+    pub fn layered(base: &Builder) -> Self {
+        Builder {
+            hasher: base.hasher.clone(),
+            fragment_kind: base.fragment_kind,
+            token_classifier: base.token_classifier.clone(),
+            synthesis: base.synthesis,
+            base: Some(Arc::new(Builder { evict_hooks: Vec::new(), ..base.clone() })),
+            ..Builder::default()
+        }
+    }
+
+    /// The read-only layers this builder is layered over, innermost first.
+    fn base_layers(&self) -> impl Iterator<Item = &Builder> {
+        iter::successors(self.base.as_deref(), |layer| layer.base.as_deref())
+    }
+
+    /// Find a cached node in the base layers.
+    fn base_node(&self, hash: u64, mut is_match: impl FnMut(&Node) -> bool) -> Option<Arc<Node>> {
+        self.base_layers().find_map(|layer| {
+            let (node, ()) = layer.nodes.raw_entry().from_hash(hash, |node| is_match(node))?;
+            Some(Arc::clone(node))
+        })
+    }
+
+    /// Find a cached token in the base layers.
+    fn base_token(
+        &self,
+        hash: u64,
+        mut is_match: impl FnMut(&Token) -> bool,
+    ) -> Option<Arc<Token>> {
+        self.base_layers().find_map(|layer| {
+            let (token, ()) = layer.tokens.raw_entry().from_hash(hash, |token| is_match(token))?;
+            Some(Arc::clone(token))
+        })
+    }
+
+    /// Set the kind of fragment nodes used to split oversized child lists.
+    ///
+    /// A node can hold at most `u16::MAX` children. With a fragment kind set,
+    /// [`node`](Builder::node) instead splits a longer list of children into
+    /// fragment nodes of this kind, nested as deeply as needed, rather than failing.
+    /// The text of the node is unchanged, but its children are the fragments.
+    pub fn set_fragment_kind(&mut self, kind: Option<Kind>) -> &mut Self {
+        self.fragment_kind = kind;
+        self
+    }
+
+    /// Set the classifier computing the [`flags`](Token::flags) of new text tokens.
+    ///
+    /// Flags are computed once, when a token is created, so hot paths can check
+    /// a precomputed classification (e.g. "contains newline", "is keyword") per
+    /// token rather than rescanning its text. Flags must be at most
+    /// [`Token::MAX_FLAGS`]. Binary tokens are not classified.
+    ///
+    /// The classifier is called for every token requested, and tokens are only
+    /// deduplicated with cached tokens with the same flags, so changing the
+    /// classifier, or sharing a cache with a builder with another classifier,
+    /// never hands out a token classified differently.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const WS: Kind = Kind(0); const KW: Kind = Kind(1);
+    /// const NEWLINE: u8 = 0b01;
+    /// const KEYWORD: u8 = 0b10;
+    ///
+    /// let mut builder = green::Builder::new();
+    /// builder.set_token_classifier(|kind, text| {
+    ///     let newline = if text.contains('\n') { NEWLINE } else { 0 };
+    ///     let keyword = if kind == KW { KEYWORD } else { 0 };
+    ///     newline | keyword
+    /// });
+    /// assert_eq!(builder.token(WS, " \n").flags(), NEWLINE);
+    /// assert_eq!(builder.token(KW, "fn").flags(), KEYWORD);
+    /// ```
+    pub fn set_token_classifier(
+        &mut self,
+        classifier: impl Fn(Kind, &str) -> u8 + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.token_classifier = Some(Arc::new(classifier));
+        self
+    }
+
+    /// Set whether new nodes track if their text contains a newline.
+    /// See [`Node::is_multiline`].
+    ///
+    /// This is opt-in, as it scans the text of every token when building its parent.
+    /// Nodes already cached don't track it, so this should be set before creating any nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+    /// let mut builder = green::TreeBuilder::new();
+    /// builder.builder().set_track_multiline(true);
+    /// let tree = builder
+    ///     .start_node(LIST)
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "a")
+    ///     .finish_node()
+    ///     .token(WS, "\n")
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// assert!(tree.is_multiline());
+    /// assert!(!tree.children().next().unwrap().unwrap_node().is_multiline());
+    /// ```
+    #[cfg(feature = "node-flags")]
+    pub fn set_track_multiline(&mut self, track: bool) -> &mut Self {
+        self.synthesis.multiline = track;
+        self
+    }
+
+    /// Set which token [`flags`](Token::flags) are propagated to new nodes,
+    /// as the bitwise or of the flags of all of their tokens.
+    /// See [`Node::synthesized_flags`].
+    ///
+    /// Together with [`set_token_classifier`](Builder::set_token_classifier),
+    /// this synthesizes properties of whole subtrees at construction, such as
+    /// whether they contain an error token, or a placeholder. Nodes already
+    /// cached keep their flags, so this should be set before creating any nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `flags` is more than [`Token::MAX_FLAGS`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const ERROR: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+    /// const HAS_ERROR: u8 = 0b1;
+    ///
+    /// let mut builder = green::TreeBuilder::new();
+    /// builder
+    ///     .builder()
+    ///     .set_token_classifier(|kind, _| if kind == ERROR { HAS_ERROR } else { 0 })
+    ///     .set_flag_propagation(HAS_ERROR);
+    /// let tree = builder
+    ///     .start_node(LIST)
+    ///     .start_node(LIST)
+    ///     .token(ERROR, "?")
+    ///     .finish_node()
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "a")
+    ///     .finish_node()
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// assert_eq!(tree.synthesized_flags(), HAS_ERROR);
+    /// let children: Vec<u8> =
+    ///     tree.children().map(|el| el.unwrap_node().synthesized_flags()).collect();
+    /// assert_eq!(children, [HAS_ERROR, 0]);
+    /// ```
+    #[cfg(feature = "node-flags")]
+    pub fn set_flag_propagation(&mut self, flags: u8) -> &mut Self {
+        assert!(flags <= Token::MAX_FLAGS, "flags {:#b} don't fit in `Token::MAX_FLAGS`", flags);
+        self.synthesis.flags = flags;
+        self
+    }
+
+    #[cfg(feature = "de")]
+    pub(super) fn synthesis(&self) -> Synthesis {
+        self.synthesis
+    }
+
     /// The number of cached elements.
+    ///
+    /// For a [layered](Builder::layered) builder, this counts only its own layer.
     pub fn size(&self) -> usize {
         self.nodes.len() + self.tokens.len()
     }
+
+    /// The number of distinct cached nodes and tokens of each kind.
+    ///
+    /// This is useful for spotting kinds which defeat deduplication,
+    /// such as whitespace tokens that are rarely identical.
+    pub fn kind_histogram(&self) -> BTreeMap<Kind, KindStats> {
+        let mut histogram = BTreeMap::<Kind, KindStats>::new();
+        for node in self.nodes.keys() {
+            histogram.entry(node.kind()).or_default().nodes += 1;
+        }
+        for token in self.tokens.keys() {
+            histogram.entry(token.kind()).or_default().tokens += 1;
+        }
+        histogram
+    }
+}
+
+/// Statistics about the cached elements of one kind. See [`Builder::kind_histogram`].
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct KindStats {
+    /// The number of distinct cached nodes of this kind.
+    pub nodes: usize,
+    /// The number of distinct cached tokens of this kind.
+    pub tokens: usize,
 }
 
 impl Builder {
@@ -99,34 +441,185 @@ impl Builder {
     /// This checks children for identity equivalence, not structural,
     /// so it is `O(children.len())` and only caches higher-level nodes
     /// if the lower-level nodes have also been cached.
+    ///
+    /// Children can be passed as a `Vec` or an array; see [`SliceIter`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`try_node`](Builder::try_node) would return an error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use {sorbus::{green, Kind}, std::sync::Arc};
+    /// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+    /// let mut builder = green::Builder::new();
+    /// let a = builder.token(ATOM, "a");
+    /// let from_array = builder.node(LIST, [a.clone(), a.clone()]);
+    /// let from_vec = builder.node(LIST, vec![a.clone(), a]);
+    /// assert!(Arc::ptr_eq(&from_array, &from_vec));
+    /// ```
     pub fn node<I, R>(&mut self, kind: Kind, children: I) -> Arc<Node>
     where
         I: IntoIterator,
         I::Item: Into<NodeOrToken<Arc<Node>, Arc<Token>>>,
-        I::IntoIter: ExactSizeIterator + AsRef<[R]>,
+        I::IntoIter: SliceIter<Elem = R>,
+        for<'a> &'a R: Into<NodeOrToken<&'a Node, &'a Token>>,
+    {
+        self.try_node(kind, children).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Fallible version of [`node`](Builder::node).
+    ///
+    /// Fails if there are more children than fit in a node (and no
+    /// [fragment kind](Builder::set_fragment_kind) is set),
+    /// or if the children iterator misreports its length.
+    pub fn try_node<I, R>(&mut self, kind: Kind, children: I) -> Result<Arc<Node>, NodeError>
+    where
+        I: IntoIterator,
+        I::Item: Into<NodeOrToken<Arc<Node>, Arc<Token>>>,
+        I::IntoIter: SliceIter<Elem = R>,
+        for<'a> &'a R: Into<NodeOrToken<&'a Node, &'a Token>>,
+    {
+        self.try_node_with_head(kind.into(), children)
+    }
+
+    /// Create a new node tagged with a provenance id, such as the
+    /// syntax context of a macro expansion the node was produced by.
+    ///
+    /// Nodes with different provenance are never deduplicated with each other,
+    /// so synthetic parts of a tree stay distinguishable from user-written ones.
+    /// Nodes built without a provenance id have the provenance id 0.
+    /// Provenance ids are not serialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`try_node`](Builder::try_node) would return an error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+    /// let mut builder = green::Builder::new();
+    /// let a = builder.token(ATOM, "a");
+    /// let written = builder.node(LIST, vec![a.clone()]);
+    /// let expanded = builder.node_with_provenance(LIST, 1, vec![a]);
+    /// assert_eq!((written.provenance(), expanded.provenance()), (0, 1));
+    /// assert_ne!(written, expanded);
+    /// ```
+    #[cfg(feature = "provenance")]
+    pub fn node_with_provenance<I, R>(
+        &mut self,
+        kind: Kind,
+        provenance: u32,
+        children: I,
+    ) -> Arc<Node>
+    where
+        I: IntoIterator,
+        I::Item: Into<NodeOrToken<Arc<Node>, Arc<Token>>>,
+        I::IntoIter: SliceIter<Elem = R>,
+        for<'a> &'a R: Into<NodeOrToken<&'a Node, &'a Token>>,
+    {
+        self.try_node_with_head(NodeHead { kind, provenance }, children)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Create a new node like `like`, but with different children.
+    ///
+    /// The new node keeps the kind of `like` and anything else its header
+    /// carries, such as its provenance id. Use this when rebuilding the
+    /// ancestors of an edited node, so they don't lose their provenance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`try_node`](Builder::try_node) would return an error.
+    pub fn node_like<I, R>(&mut self, like: &Node, children: I) -> Arc<Node>
+    where
+        I: IntoIterator,
+        I::Item: Into<NodeOrToken<Arc<Node>, Arc<Token>>>,
+        I::IntoIter: SliceIter<Elem = R>,
+        for<'a> &'a R: Into<NodeOrToken<&'a Node, &'a Token>>,
+    {
+        self.try_node_with_head(like.head(), children).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_node_with_head<I, R>(
+        &mut self,
+        head: NodeHead,
+        children: I,
+    ) -> Result<Arc<Node>, NodeError>
+    where
+        I: IntoIterator,
+        I::Item: Into<NodeOrToken<Arc<Node>, Arc<Token>>>,
+        I::IntoIter: SliceIter<Elem = R>,
         for<'a> &'a R: Into<NodeOrToken<&'a Node, &'a Token>>,
     {
-        let hasher = &self.hasher;
         let children = children.into_iter();
+        #[cfg(feature = "tracing")]
+        let _span = (children.len() > LARGE_NODE_CHILDREN).then(|| {
+            tracing::debug_span!("large node", kind = ?head.kind, children = children.len())
+                .entered()
+        });
+        if let Some(fragment_kind) = self.fragment_kind {
+            if children.len() > u16::MAX as usize {
+                return Ok(self.node_fragmented(head, fragment_kind, children.map(Into::into)));
+            }
+        }
 
-        let hash = thin_node_hash(hasher, kind, erased_children(children.as_ref()));
+        let hasher = &self.hasher;
+        let hash = thin_node_hash(hasher, head, erased_children(children.as_slice()));
+        if let Some(node) = self
+            .base_node(hash, |node| thin_node_eq(node, head, erased_children(children.as_slice())))
+        {
+            count!(self.node_hits);
+            return Ok(node);
+        }
 
         let entry = self
             .nodes
             .raw_entry_mut()
-            .from_hash(hash, |node| thin_node_eq(node, kind, erased_children(children.as_ref())));
+            .from_hash(hash, |node| thin_node_eq(node, head, erased_children(children.as_slice())));
 
         let (node, ()) = match entry {
-            RawEntryMut::Occupied(entry) => entry.into_key_value(),
+            RawEntryMut::Occupied(entry) => {
+                count!(self.node_hits);
+                entry.into_key_value()
+            }
             RawEntryMut::Vacant(entry) => {
-                let node = Node::new(kind, children.map(Into::into).map(pack_node_or_token));
-                entry.insert_with_hasher(hash, node, (), |node| {
-                    thin_node_hash(hasher, node.kind(), erased_children(node.children()))
-                })
+                count!(self.node_misses);
+                trace_event!(trace, kind = ?head.kind, "interned node");
+                let children = children.map(Into::into).map(pack_node_or_token).map(Ok);
+                let node = Node::try_new::<_, _, NodeError>(head, self.synthesis, children)?;
+                entry.insert_with_hasher(hash, CachedNode { hash, node }, (), |node| node.hash)
             }
         };
 
-        Arc::clone(node)
+        Ok(Arc::clone(node))
+    }
+
+    /// Build a node with too many children by splitting them into fragment nodes.
+    fn node_fragmented(
+        &mut self,
+        head: NodeHead,
+        fragment_kind: Kind,
+        children: impl Iterator<Item = NodeOrToken<Arc<Node>, Arc<Token>>>,
+    ) -> Arc<Node> {
+        let fragment_head = NodeHead {
+            kind: fragment_kind,
+            #[cfg(feature = "provenance")]
+            provenance: head.provenance,
+        };
+        let mut children: Vec<_> = children.collect();
+        trace_event!(debug, kind = ?head.kind, children = children.len(), "fragmenting node");
+        while children.len() > u16::MAX as usize {
+            children = children
+                .chunks(u16::MAX as usize)
+                .map(|chunk| self.try_node_with_head(fragment_head, chunk.to_vec()))
+                .map(|node| node.unwrap_or_else(|err| panic!("{}", err)).into())
+                .collect();
+        }
+        self.try_node_with_head(head, children).unwrap_or_else(|err| panic!("{}", err))
     }
 
     /// Version of `Builder::node` taking a pre-packed child element iterator.
@@ -134,29 +627,45 @@ impl Builder {
     where
         I: Iterator<Item = PackedNodeOrToken> + ExactSizeIterator + AsRef<[PackedNodeOrToken]>,
     {
+        let head = NodeHead::from(kind);
+        if let Some(fragment_kind) = self.fragment_kind {
+            if children.len() > u16::MAX as usize {
+                let children = children.map(unpack_node_or_token);
+                return self.node_fragmented(head, fragment_kind, children);
+            }
+        }
+
         let hasher = &self.hasher;
 
         let hash = thin_node_hash(
             hasher,
-            kind,
+            head,
             children.as_ref().iter().map(PackedNodeOrToken::as_untagged_ptr),
         );
-
-        let entry = self.nodes.raw_entry_mut().from_hash(hash, |node| {
+        let is_match = |node: &Node| {
             thin_node_eq(
                 node,
-                kind,
+                head,
                 children.as_ref().iter().map(PackedNodeOrToken::as_untagged_ptr),
             )
-        });
+        };
+        if let Some(node) = self.base_node(hash, is_match) {
+            count!(self.node_hits);
+            return node;
+        }
+
+        let entry = self.nodes.raw_entry_mut().from_hash(hash, |node| is_match(node));
 
         let (node, ()) = match entry {
-            RawEntryMut::Occupied(entry) => entry.into_key_value(),
+            RawEntryMut::Occupied(entry) => {
+                count!(self.node_hits);
+                entry.into_key_value()
+            }
             RawEntryMut::Vacant(entry) => {
-                let node = Node::new(kind, children);
-                entry.insert_with_hasher(hash, node, (), |node| {
-                    thin_node_hash(hasher, node.kind(), erased_children(node.children()))
-                })
+                count!(self.node_misses);
+                trace_event!(trace, kind = ?kind, "interned node");
+                let node = Node::new(head, self.synthesis, children);
+                entry.insert_with_hasher(hash, CachedNode { hash, node }, (), |node| node.hash)
             }
         };
 
@@ -171,46 +680,404 @@ impl Builder {
     pub(super) fn cache_node(&mut self, node: Arc<Node>) -> Arc<Node> {
         let hasher = &self.hasher;
 
-        let hash = thin_node_hash(hasher, node.kind(), erased_children(node.children()));
+        let hash = thin_node_hash(hasher, node.head(), erased_children(node.children()));
+        if let Some(node) =
+            self.base_node(hash, |x| thin_node_eq(x, node.head(), erased_children(node.children())))
+        {
+            return node;
+        }
 
         let entry = self
             .nodes
             .raw_entry_mut()
-            .from_hash(hash, |x| thin_node_eq(x, node.kind(), erased_children(node.children())));
+            .from_hash(hash, |x| thin_node_eq(x, node.head(), erased_children(node.children())));
 
         let (node, ()) = match entry {
             RawEntryMut::Occupied(entry) => entry.into_key_value(),
-            RawEntryMut::Vacant(entry) => entry.insert_with_hasher(hash, node, (), |node| {
-                thin_node_hash(hasher, node.kind(), erased_children(node.children()))
-            }),
+            RawEntryMut::Vacant(entry) => {
+                entry.insert_with_hasher(hash, CachedNode { hash, node }, (), |node| node.hash)
+            }
         };
         Arc::clone(node)
     }
 
     /// Create a new token or clone a new Arc to an existing equivalent one.
+    ///
+    /// The text may be empty; see [`missing`](Builder::missing).
     pub fn token(&mut self, kind: Kind, text: &str) -> Arc<Token> {
+        self.token_raw(kind, text.as_bytes(), false)
+    }
+
+    /// Create an empty token of `kind`, or clone a new Arc to the existing one.
+    ///
+    /// Zero-length tokens stand in for tokens missing from the source, such as
+    /// an expected `;` inserted by error recovery. They are cached like any
+    /// other token, so each kind has a single missing token per builder.
+    /// Zero-length tokens occupy no text; an offset lookup resolves to the
+    /// non-empty element at the offset, rather than an empty one before it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use {sorbus::{green, Kind}, std::sync::Arc};
+    /// # const ATOM: Kind = Kind(0); const SEMI: Kind = Kind(1); const STMT: Kind = Kind(2);
+    /// let mut builder = green::Builder::new();
+    /// let semi = builder.missing(SEMI);
+    /// assert!(semi.is_empty());
+    /// assert!(Arc::ptr_eq(&semi, &builder.token(SEMI, "")));
+    ///
+    /// let a = builder.token(ATOM, "a");
+    /// let stmt = builder.node(STMT, vec![a, semi]);
+    /// assert_eq!(stmt.len(), 1.into());
+    /// assert_eq!(stmt.children_len(), 2);
+    /// ```
+    pub fn missing(&mut self, kind: Kind) -> Arc<Token> {
+        self.token_raw(kind, b"", false)
+    }
+
+    /// Create a new binary token or clone a new Arc to an existing equivalent one.
+    ///
+    /// Binary tokens hold arbitrary bytes, which need not be UTF-8, for
+    /// lossless trees over byte-oriented sources. A binary token is never
+    /// equal to a text token, even if it holds the same bytes.
+    pub fn binary_token(&mut self, kind: Kind, bytes: &[u8]) -> Arc<Token> {
+        self.token_raw(kind, bytes, true)
+    }
+
+    /// Create a new token from chunks of text adding up to `total_len`,
+    /// or clone a new Arc to an existing equivalent one.
+    ///
+    /// The chunks are written directly into the new token's allocation, rather than
+    /// first being concatenated into a temporary string. This is useful for lexers
+    /// over rope-based text, where the text of a token may span chunk boundaries.
+    /// If an equivalent token is already cached, the new allocation is discarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chunks don't add up to `total_len`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// let mut builder = green::Builder::new();
+    /// let token = builder.token_from_chunks(Kind(0), vec!["hel", "lo"], 5.into());
+    /// assert_eq!(token.text(), "hello");
+    /// assert_eq!(token, builder.token(Kind(0), "hello"));
+    /// ```
+    pub fn token_from_chunks<'s>(
+        &mut self,
+        kind: Kind,
+        chunks: impl IntoIterator<Item = &'s str>,
+        total_len: TextSize,
+    ) -> Arc<Token> {
+        let classifier = self.token_classifier.as_deref();
+        let classify = |text: &str| classifier.map_or(0, |classify| classify(kind, text));
+        let (token, len): (Arc<Token>, usize) =
+            Token::from_chunks(kind, chunks, total_len.into(), classify);
+        assert_eq!(len, usize::from(total_len), "token chunks don't add up to `total_len`");
+        if let Some(token) = self.alphabet_token(kind, token.bytes(), false) {
+            count!(self.token_hits);
+            return token;
+        }
+
+        let hasher = &self.hasher;
+        let hash = do_hash(hasher, &*token);
+        if let Some(token) = self.base_token(hash, |x| *x == *token) {
+            count!(self.token_hits);
+            return token;
+        }
+
+        let entry = self.tokens.raw_entry_mut().from_hash(hash, |x| **x == *token);
+        let (token, ()) = match entry {
+            RawEntryMut::Occupied(entry) => {
+                count!(self.token_hits);
+                entry.into_key_value()
+            }
+            RawEntryMut::Vacant(entry) => {
+                count!(self.token_misses);
+                trace_event!(trace, kind = ?kind, "interned token");
+                entry.insert_with_hasher(hash, token, (), |x| do_hash(hasher, x))
+            }
+        };
+        Arc::clone(token)
+    }
+
+    /// Version of `Builder::token` for either kind of token.
+    /// `text` must be UTF-8 unless `binary` is set.
+    fn token_raw(&mut self, kind: Kind, text: &[u8], binary: bool) -> Arc<Token> {
+        if let Some(token) = self.alphabet_token(kind, text, binary) {
+            count!(self.token_hits);
+            return token;
+        }
+
+        let flags = match (&self.token_classifier, binary) {
+            // SAFETY: text tokens are always UTF-8
+            (Some(classify), false) => classify(kind, unsafe { str::from_utf8_unchecked(text) }),
+            _ => 0,
+        };
         let hasher = &self.hasher;
 
         let hash = {
             // spoof Token's hash impl
             let state = &mut hasher.build_hasher();
             kind.hash(state);
+            token::header_flags(binary, flags).hash(state);
             text.hash(state);
             state.finish()
         };
+        let is_match = |token: &Token| {
+            token.kind() == kind
+                && token.is_binary() == binary
+                && token.flags() == flags
+                && token.bytes() == text
+        };
+        if let Some(token) = self.base_token(hash, is_match) {
+            count!(self.token_hits);
+            return token;
+        }
 
-        let entry = self
-            .tokens
-            .raw_entry_mut()
-            .from_hash(hash, |token| token.kind() == kind && token.text() == text);
+        let entry = self.tokens.raw_entry_mut().from_hash(hash, |token| is_match(token));
         let (token, ()) = match entry {
-            RawEntryMut::Occupied(entry) => entry.into_key_value(),
+            RawEntryMut::Occupied(entry) => {
+                count!(self.token_hits);
+                entry.into_key_value()
+            }
             RawEntryMut::Vacant(entry) => {
-                entry.insert_with_hasher(hash, Token::new(kind, text), (), |x| do_hash(hasher, x))
+                count!(self.token_misses);
+                trace_event!(trace, kind = ?kind, "interned token");
+                let token = Token::new(kind, text, binary, flags);
+                entry.insert_with_hasher(hash, token, (), |x| do_hash(hasher, x))
             }
         };
         Arc::clone(token)
     }
+
+    /// Find a token in the global [`StaticAlphabet`], if this builder uses it.
+    fn alphabet_token(&self, kind: Kind, text: &[u8], binary: bool) -> Option<Arc<Token>> {
+        if binary || self.token_classifier.is_some() {
+            return None;
+        }
+        StaticAlphabet::global()?.get_bytes(kind, text).cloned()
+    }
+
+    /// Split a token in two at an offset within it, giving the halves the kinds in `kinds`.
+    ///
+    /// The halves of a binary token are binary tokens.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is past the end of the token,
+    /// or if `at` is not on a char boundary of a text token.
+    pub fn split_token(
+        &mut self,
+        token: &Token,
+        at: TextSize,
+        kinds: (Kind, Kind),
+    ) -> (Arc<Token>, Arc<Token>) {
+        let at = usize::from(at);
+        assert!(at <= token.bytes().len(), "split offset {} is past the end of the token", at);
+        if let Some(text) = token.try_text() {
+            assert!(text.is_char_boundary(at), "split offset {} is not on a char boundary", at);
+        }
+        let (left, right) = token.bytes().split_at(at);
+        let left = self.token_raw(kinds.0, left, token.is_binary());
+        let right = self.token_raw(kinds.1, right, token.is_binary());
+        (left, right)
+    }
+
+    /// Join two tokens into one token of `kind`.
+    ///
+    /// The joined token is a binary token if either token is.
+    pub fn join_tokens(&mut self, a: &Token, b: &Token, kind: Kind) -> Arc<Token> {
+        let bytes = [a.bytes(), b.bytes()].concat();
+        self.token_raw(kind, &bytes, a.is_binary() || b.is_binary())
+    }
+}
+
+fn token_key(token: &Token) -> usize {
+    token as *const Token as *const u8 as usize
+}
+
+impl Builder {
+    /// Create a new token like [`token`](Builder::token),
+    /// and attach a computed value to it (e.g. a parsed literal).
+    ///
+    /// The value is stored in a side table keyed by the identity of the
+    /// deduplicated token, so it can be retrieved with [`token_value`]
+    /// without reparsing the token text, and lives as long as the token
+    /// stays in this cache. If the token already has a value, it is replaced,
+    /// unless it is an equal value of the same type.
+    ///
+    ///   [`token_value`]: Builder::token_value
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green::Builder, Kind};
+    /// # const INT: Kind = Kind(0);
+    /// let mut builder = Builder::new();
+    /// let token = builder.token_with_value(INT, "0x2A", 42_u64);
+    /// assert_eq!(builder.token_value::<u64>(&token), Some(&42));
+    /// assert_eq!(builder.token_value::<i32>(&token), None);
+    /// ```
+    pub fn token_with_value<V>(&mut self, kind: Kind, text: &str, value: V) -> Arc<Token>
+    where
+        V: Any + Eq + Send + Sync,
+    {
+        let token = self.token(kind, text);
+        let key = token_key(&token);
+        if self.token_value::<V>(&token) != Some(&value) {
+            self.values.insert(key, Arc::new(value));
+        }
+        token
+    }
+
+    /// The value attached to `token` by [`token_with_value`](Builder::token_with_value),
+    /// if it has one of type `V`.
+    ///
+    /// A [layered](Builder::layered) builder also finds values attached in its base.
+    pub fn token_value<V: Any>(&self, token: &Token) -> Option<&V> {
+        let key = token_key(token);
+        iter::once(self)
+            .chain(self.base_layers())
+            .find_map(|layer| layer.values.get(&key))?
+            .downcast_ref()
+    }
+}
+
+/// A 64-bit FNV-1a hasher, which unlike the cache's hasher is stable across runs.
+struct StableHasher(u64);
+
+impl StableHasher {
+    fn new() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+impl Builder {
+    /// A sorted, stable textual digest of the elements cached in this builder.
+    ///
+    /// Each cached element is one line: tokens with their kind and text, and nodes
+    /// with their kind (and provenance id, if any) and the digests of their children.
+    /// Digests are structural hashes, so unlike pointer values they are the same on
+    /// every run, and the snapshot can be used to assert caching behavior, such as
+    /// with an insta snapshot. For a [layered](Builder::layered) builder,
+    /// only its own layer is included.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+    /// let mut builder = green::Builder::new();
+    /// let a = builder.token(ATOM, "a");
+    /// let _list = builder.node(LIST, vec![a.clone(), a]);
+    /// assert_eq!(
+    ///     builder.snapshot_keys(),
+    ///     "node #a64d133d818c7363 Kind(1) [#e92b6341ce3f51af, #e92b6341ce3f51af]\n\
+    ///      token #e92b6341ce3f51af Kind(0) \"a\"\n",
+    /// );
+    /// ```
+    pub fn snapshot_keys(&self) -> String {
+        let mut digests = StdHashMap::new();
+        let mut lines: Vec<String> = self
+            .tokens
+            .keys()
+            .map(|token| {
+                let text = match token.try_text() {
+                    Some(text) => format!("{:?}", text),
+                    None => format!("bytes {:?}", token.bytes()),
+                };
+                let digest = stable_digest(NodeOrToken::Token(token), &mut digests);
+                format!("token #{:016x} {:?} {}", digest, token.kind(), text)
+            })
+            .collect();
+        lines.extend(self.nodes.keys().map(|node| {
+            let children: Vec<_> = node
+                .children()
+                .map(|child| format!("#{:016x}", stable_digest(child.as_deref(), &mut digests)))
+                .collect();
+            let digest = stable_digest(NodeOrToken::Node(node), &mut digests);
+            let kind = format!("{:?}", node.kind());
+            #[cfg(feature = "provenance")]
+            let kind = match node.provenance() {
+                0 => kind,
+                provenance => format!("{}@{}", kind, provenance),
+            };
+            format!("node #{:016x} {} [{}]", digest, kind, children.join(", "))
+        }));
+        lines.sort_unstable();
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+}
+
+/// The structural [`StableHasher`] digest of an element, memoized by node address.
+fn stable_digest(
+    element: NodeOrToken<&Node, &Token>,
+    digests: &mut StdHashMap<*const Node, u64>,
+) -> u64 {
+    let mut hasher = StableHasher::new();
+    match element {
+        NodeOrToken::Token(token) => {
+            hasher.write(&[0, token.is_binary() as u8]);
+            hasher.write(&token.kind().0.to_le_bytes());
+            hasher.write(&(token.bytes().len() as u64).to_le_bytes());
+            hasher.write(token.bytes());
+        }
+        NodeOrToken::Node(node) => {
+            if let Some(&digest) = digests.get(&(node as *const Node)) {
+                return digest;
+            }
+            hasher.write(&[1]);
+            hasher.write(&node.kind().0.to_le_bytes());
+            #[cfg(feature = "provenance")]
+            if node.provenance() != 0 {
+                hasher.write(&node.provenance().to_le_bytes());
+            }
+            hasher.write(&(node.children_len() as u64).to_le_bytes());
+            for child in node.children() {
+                hasher.write(&stable_digest(child.as_deref(), digests).to_le_bytes());
+            }
+            digests.insert(node, hasher.0);
+        }
+    }
+    hasher.0
+}
+
+impl Builder {
+    /// Merge runs of adjacent child text tokens of the given kind into single tokens.
+    ///
+    /// Error recovery can split what is logically one token into several
+    /// adjacent tokens of the same kind; this glues them back together.
+    /// Only direct children of `node` are considered.
+    pub fn coalesce_tokens(&mut self, node: &Node, kind: Kind) -> Arc<Node> {
+        let is_run_token = |el: &ElementRef<'_>| match el {
+            NodeOrToken::Token(token) => token.kind() == kind && !token.is_binary(),
+            NodeOrToken::Node(_) => false,
+        };
+
+        let mut children = Vec::with_capacity(node.children_len());
+        let mut iter = node.children().peekable();
+        while let Some(child) = iter.next() {
+            if is_run_token(&child) && matches!(iter.peek(), Some(next) if is_run_token(next)) {
+                let mut text = String::from(child.unwrap_token().text());
+                while let Some(next) = iter.next_if(is_run_token) {
+                    text.push_str(next.unwrap_token().text());
+                }
+                children.push(NodeOrToken::Token(self.token(kind, &text)));
+            } else {
+                children.push(child.upgrade());
+            }
+        }
+        self.node_like(node, children)
+    }
 }
 
 impl Builder {
@@ -219,38 +1086,146 @@ impl Builder {
         // i.e.: elements where the predicate is TRUE are removed and iterated over.
         self.nodes
             .drain_filter(|node, ()| Arc::strong_count(node) <= 1)
-            .map(|(node, _)| node)
+            .map(|(cached, _)| cached.node)
             .collect()
     }
 
     fn collect_tokens(&mut self) {
-        self.tokens.retain(|token, ()| Arc::strong_count(token) > 1)
+        let Builder { tokens, values, evict_hooks, .. } = self;
+        tokens.retain(|token, ()| {
+            let keep = Arc::strong_count(token) > 1;
+            if !keep {
+                values.remove(&token_key(token));
+                evict_hooks.iter().for_each(|hook| hook(NodeOrToken::Token(token)));
+            }
+            keep
+        })
+    }
+
+    /// Register a callback to be called with each node and token evicted from the cache.
+    ///
+    /// Side tables keyed by the identity of cached elements, such as memoized
+    /// analyses, can use this to invalidate their entries in lock-step with the cache.
+    /// The callback is called before the evicted element is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let evicted = Arc::new(Mutex::new(vec![]));
+    /// let mut builder = green::TreeBuilder::new();
+    /// builder.builder().on_evict({
+    ///     let evicted = Arc::clone(&evicted);
+    ///     move |element| evicted.lock().unwrap().push(element.kind())
+    /// });
+    ///
+    /// let tree = builder.start_node(LIST).token(ATOM, "a").finish_node().finish();
+    /// drop(tree);
+    /// builder.builder().gc();
+    /// assert_eq!(*evicted.lock().unwrap(), [LIST, ATOM]);
+    /// ```
+    pub fn on_evict(
+        &mut self,
+        callback: impl Fn(NodeOrToken<&Node, &Token>) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.evict_hooks.push(Arc::new(callback));
+        self
     }
 
     /// Collect all cached nodes that are no longer live outside the cache.
     pub fn gc(&mut self) {
-        let mut to_drop = self.collect_root_nodes();
-        let Builder { hasher, nodes, .. } = self;
+        trace_span!(debug_span, "gc", epoch = self.epoch);
+        #[cfg(feature = "tracing")]
+        let size = self.size();
 
-        while let Some(node) = to_drop.pop() {
+        // NB: root nodes are already removed from the cache
+        let mut to_drop: Vec<_> =
+            self.collect_root_nodes().into_iter().map(|node| (node, true)).collect();
+        let Builder { hasher, nodes, evict_hooks, .. } = self;
+
+        while let Some((node, is_root)) = to_drop.pop() {
             if Arc::strong_count(&node) <= 2 {
+                // remove this node from the cache
+                let hash = thin_node_hash(hasher, node.head(), erased_children(node.children()));
+                let entry = nodes.raw_entry_mut().from_hash(hash, |x| Arc::ptr_eq(x, &node));
+                match entry {
+                    RawEntryMut::Occupied(entry) => {
+                        entry.remove();
+                    }
+                    // not cached in this layer, so it's kept alive by a base layer
+                    RawEntryMut::Vacant(_) if !is_root => continue,
+                    RawEntryMut::Vacant(_) => {}
+                }
+                evict_hooks.iter().for_each(|hook| hook(NodeOrToken::Node(&node)));
+
                 // queue children for (potential) removal from the cache
                 for child in node.children() {
                     if let Some(node) = child.into_node() {
-                        to_drop.push(ArcBorrow::upgrade(node));
+                        to_drop.push((ArcBorrow::upgrade(node), false));
                     }
                 }
-
-                // remove this node from the cache
-                let hash = thin_node_hash(hasher, node.kind(), erased_children(node.children()));
-                let entry = nodes.raw_entry_mut().from_hash(hash, |x| {
-                    thin_node_eq(x, node.kind(), erased_children(node.children()))
-                });
-                if let RawEntryMut::Occupied(entry) = entry {
-                    entry.remove();
-                }
             }
         }
         self.collect_tokens();
+        self.epoch += 1;
+
+        #[cfg(feature = "tracing")]
+        {
+            let CacheCounters { node_hits, node_misses, token_hits, token_misses } =
+                std::mem::take(&mut self.counters);
+            tracing::debug!(
+                evicted = size - self.size(),
+                cached = self.size(),
+                node_hits,
+                node_misses,
+                token_hits,
+                token_misses,
+                "collected cache",
+            );
+        }
+    }
+
+    /// Evict all cached nodes and tokens.
+    ///
+    /// A [layered](Builder::layered) builder keeps its base.
+    ///
+    /// Elements built before clearing are still valid,
+    /// but will not be deduplicated with elements built after.
+    pub fn clear(&mut self) {
+        for node in self.nodes.drain().map(|(cached, ())| cached.node) {
+            self.evict_hooks.iter().for_each(|hook| hook(NodeOrToken::Node(&node)));
+        }
+        for token in self.tokens.drain().map(|(token, ())| token) {
+            self.evict_hooks.iter().for_each(|hook| hook(NodeOrToken::Token(&token)));
+        }
+        self.values.clear();
+        self.epoch += 1;
+    }
+
+    /// The number of times this cache has been [collected](Builder::gc) or
+    /// [cleared](Builder::clear).
+    ///
+    /// Data keyed by the identity of cached elements may be stale if the epoch
+    /// has changed since it was computed, as its elements may have been evicted,
+    /// and their addresses reused by new elements. Checking the epoch is a cheap
+    /// alternative to registering an [eviction callback](Builder::on_evict).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const ATOM: Kind = Kind(0);
+    /// let mut builder = green::Builder::new();
+    /// let epoch = builder.epoch();
+    /// builder.token(ATOM, "a");
+    /// assert_eq!(builder.epoch(), epoch);
+    /// builder.gc();
+    /// assert_ne!(builder.epoch(), epoch);
+    /// ```
+    pub fn epoch(&self) -> u64 {
+        self.epoch
     }
 }
@@ -0,0 +1,147 @@
+use {
+    crate::{
+        green::{Builder, Node, Token},
+        ArcBorrow, KindSet, NodeOrToken,
+    },
+    std::{collections::HashMap, sync::Arc},
+};
+
+/// Where trivia tokens at the edges of nodes are placed in canonical form.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum TriviaPlacement {
+    /// Leave trivia where the parser put it.
+    Keep,
+    /// Move trivia at the start or end of a node out of it, into its parent,
+    /// so that no node but the root starts or ends with trivia.
+    Outside,
+}
+
+impl Default for TriviaPlacement {
+    fn default() -> Self {
+        TriviaPlacement::Keep
+    }
+}
+
+/// The rules defining a canonical form of trees. See [`Builder::canonicalize`].
+#[derive(Debug, Clone, Default)]
+pub struct CanonicalPolicy {
+    /// The kinds of trivia tokens, such as whitespace and comments.
+    pub trivia: KindSet,
+    /// Where trivia at the edges of nodes is placed.
+    pub placement: TriviaPlacement,
+    /// The kinds of nodes which are kept even when empty, such as error nodes.
+    /// Empty nodes of other kinds are removed.
+    pub keep_empty: KindSet,
+}
+
+type OwnedElement = NodeOrToken<Arc<Node>, Arc<Token>>;
+
+/// A canonicalized node, with the trivia moved out of it on either side.
+#[derive(Clone)]
+struct Canonical {
+    leading: Vec<Arc<Token>>,
+    node: Option<Arc<Node>>,
+    trailing: Vec<Arc<Token>>,
+}
+
+impl Builder {
+    /// Rewrite a tree into the canonical form defined by `policy`.
+    ///
+    /// Trees for the same text produced by different parsers (or different
+    /// versions of one parser) often differ in incidental ways, such as where
+    /// trivia is attached or whether empty nodes are produced. Canonicalizing
+    /// both trees makes them comparable by equality.
+    ///
+    /// The text of the tree is unchanged. The root is never removed,
+    /// and trivia is never moved out of it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green::{self, CanonicalPolicy, TriviaPlacement}, Kind, KindSet};
+    /// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+    /// # const ITEM: Kind = Kind(3); const ERROR: Kind = Kind(4);
+    /// let mut builder = green::TreeBuilder::new();
+    /// let one = builder
+    ///     .start_node(LIST)
+    ///     .start_node(ITEM).token(ATOM, "a").token(WS, " ").finish_node()
+    ///     .start_node(ITEM).finish_node()
+    ///     .start_node(ERROR).finish_node()
+    ///     .finish_node()
+    ///     .finish();
+    /// let other = builder
+    ///     .start_node(LIST)
+    ///     .start_node(ITEM).token(ATOM, "a").finish_node()
+    ///     .token(WS, " ")
+    ///     .start_node(ERROR).finish_node()
+    ///     .finish_node()
+    ///     .finish();
+    /// assert_ne!(one, other);
+    ///
+    /// let policy = CanonicalPolicy {
+    ///     trivia: Some(WS).into_iter().collect(),
+    ///     placement: TriviaPlacement::Outside,
+    ///     keep_empty: Some(ERROR).into_iter().collect(),
+    /// };
+    /// let builder = builder.builder();
+    /// assert_eq!(builder.canonicalize(&one, &policy), builder.canonicalize(&other, &policy));
+    /// ```
+    pub fn canonicalize(&mut self, root: &Node, policy: &CanonicalPolicy) -> Arc<Node> {
+        let mut canonicalizer = Canonicalizer { builder: self, policy, done: HashMap::new() };
+        let children = canonicalizer.children(root);
+        self.node_like(root, children)
+    }
+}
+
+struct Canonicalizer<'a> {
+    builder: &'a mut Builder,
+    policy: &'a CanonicalPolicy,
+    // keyed by node address; shared subtrees are only canonicalized once
+    done: HashMap<*const Node, Canonical>,
+}
+
+impl Canonicalizer<'_> {
+    fn is_trivia(&self, el: &OwnedElement) -> bool {
+        el.as_token().map_or(false, |token| self.policy.trivia.contains(token.kind()))
+    }
+
+    /// The canonical children of a node.
+    fn children(&mut self, node: &Node) -> Vec<OwnedElement> {
+        let mut children = Vec::with_capacity(node.children_len());
+        for child in node.children() {
+            match child {
+                NodeOrToken::Token(token) => children.push(ArcBorrow::upgrade(token).into()),
+                NodeOrToken::Node(node) => {
+                    let Canonical { leading, node, trailing } = self.canonicalize(&node);
+                    children.extend(leading.into_iter().map(NodeOrToken::Token));
+                    children.extend(node.map(NodeOrToken::Node));
+                    children.extend(trailing.into_iter().map(NodeOrToken::Token));
+                }
+            }
+        }
+        children
+    }
+
+    fn canonicalize(&mut self, node: &Node) -> Canonical {
+        if let Some(canonical) = self.done.get(&(node as *const Node)) {
+            return canonical.clone();
+        }
+
+        let mut children = self.children(node);
+        let (mut leading, mut trailing) = (vec![], vec![]);
+        if self.policy.placement == TriviaPlacement::Outside {
+            let start = children.iter().position(|el| !self.is_trivia(el));
+            let start = start.unwrap_or(children.len());
+            leading = children.drain(..start).filter_map(NodeOrToken::into_token).collect();
+            let end = children.iter().rposition(|el| !self.is_trivia(el)).map_or(0, |i| i + 1);
+            trailing = children.drain(end..).filter_map(NodeOrToken::into_token).collect();
+        }
+        let keep = !children.is_empty() || self.policy.keep_empty.contains(node.kind());
+        let canonical_node = if keep { Some(self.builder.node_like(node, children)) } else { None };
+
+        let canonical = Canonical { leading, node: canonical_node, trailing };
+        self.done.insert(node as *const Node, canonical.clone());
+        canonical
+    }
+}
@@ -1,7 +1,7 @@
 use {
     crate::{
-        green::{Element, Node, Token},
-        ArcBorrow, NodeOrToken, TextSize,
+        green::{Child, Element, ElementRef},
+        TextRange, TextSize,
     },
     std::{iter::FusedIterator, slice},
 };
@@ -64,6 +64,30 @@ macro_rules! impl_children_iter {
                 Some(element.into())
             }
 
+            /// Get the nth item in the iterator without advancing it,
+            /// as a [`Child`] which also knows its offset from the parent node.
+            ///
+            /// # Examples
+            ///
+            /// ```rust
+            /// # use sorbus::{green, Kind};
+            /// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+            /// let tree = green::TreeBuilder::new()
+            ///     .start_node(LIST)
+            ///     .token(ATOM, "aa")
+            ///     .token(WS, " ")
+            ///     .token(ATOM, "bb")
+            ///     .finish_node()
+            ///     .finish();
+            ///
+            /// let child = tree.children().get_child(2).unwrap();
+            /// assert_eq!((child.kind(), child.offset()), (ATOM, 3.into()));
+            /// ```
+            #[inline]
+            pub fn get_child(&self, n: usize) -> Option<&'a Child> {
+                self.inner.as_slice().get(n).map(Child::new)
+            }
+
             /// Divide this iterator into two at an index.
             ///
             /// The first will contain all indices from `[0, mid)`,
@@ -77,6 +101,51 @@ macro_rules! impl_children_iter {
                 let (left, right) = self.inner.as_slice().split_at(mid);
                 (Self { inner: left.iter() }, Self { inner: right.iter() })
             }
+
+            /// Short-circuiting internal iteration, from the front or the back.
+            ///
+            /// `Iterator::try_fold` can't be overridden on stable, as `Try` is unstable,
+            /// so this backs specialized versions of the searching adapters instead.
+            #[inline]
+            fn search<R>(
+                &mut self,
+                back: bool,
+                mut f: impl FnMut(<Self as Iterator>::Item) -> Option<R>,
+            ) -> Option<R> {
+                // Nota Bene: this mirrors `fold`; see the notes there.
+                let mut el;
+                macro_rules! next {
+                    () => {
+                        el = if back { self.inner.next_back() } else { self.inner.next() }?;
+                    };
+                }
+                macro_rules! check {
+                    ($item:expr) => {
+                        if let Some(found) = f($item.into()) {
+                            return Some(found);
+                        }
+                    };
+                }
+
+                next!();
+                unsafe {
+                    if el.is_half_aligned() {
+                        loop {
+                            check!(el.half_aligned());
+                            next!();
+                            check!(el.full_aligned());
+                            next!();
+                        }
+                    } else {
+                        loop {
+                            check!(el.full_aligned());
+                            next!();
+                            check!(el.half_aligned());
+                            next!();
+                        }
+                    }
+                }
+            }
         }
 
         impl<'a> Iterator for $T<'a> {
@@ -109,6 +178,54 @@ macro_rules! impl_children_iter {
                 Some(element.into())
             }
 
+            #[inline]
+            fn find<P>(&mut self, mut predicate: P) -> Option<Self::Item>
+            where
+                P: FnMut(&Self::Item) -> bool,
+            {
+                self.search(false, |item| if predicate(&item) { Some(item) } else { None })
+            }
+
+            #[inline]
+            fn find_map<B, F>(&mut self, f: F) -> Option<B>
+            where
+                F: FnMut(Self::Item) -> Option<B>,
+            {
+                self.search(false, f)
+            }
+
+            #[inline]
+            fn any<F>(&mut self, mut f: F) -> bool
+            where
+                F: FnMut(Self::Item) -> bool,
+            {
+                self.search(false, |item| if f(item) { Some(()) } else { None }).is_some()
+            }
+
+            #[inline]
+            fn all<F>(&mut self, mut f: F) -> bool
+            where
+                F: FnMut(Self::Item) -> bool,
+            {
+                self.search(false, |item| if f(item) { None } else { Some(()) }).is_none()
+            }
+
+            #[inline]
+            fn position<P>(&mut self, mut predicate: P) -> Option<usize>
+            where
+                P: FnMut(Self::Item) -> bool,
+            {
+                let mut index = 0;
+                self.search(false, |item| {
+                    if predicate(item) {
+                        Some(index)
+                    } else {
+                        index += 1;
+                        None
+                    }
+                })
+            }
+
             #[inline]
             fn fold<B, F>(mut self, init: B, mut f: F) -> B
             where
@@ -179,14 +296,61 @@ macro_rules! impl_children_iter {
                 let element = self.inner.nth_back(n)?;
                 Some(element.into())
             }
+
+            #[inline]
+            fn rfold<B, F>(mut self, init: B, mut f: F) -> B
+            where
+                F: FnMut(B, Self::Item) -> B,
+            {
+                // Nota Bene: this mirrors `fold`; see the notes there.
+                let mut accum = init;
+
+                let mut el;
+                macro_rules! next_back {
+                    () => {
+                        if let Some(element) = self.inner.next_back() {
+                            el = element;
+                        } else {
+                            return accum;
+                        }
+                    };
+                }
+
+                next_back!();
+                unsafe {
+                    if el.is_half_aligned() {
+                        loop {
+                            accum = f(accum, el.half_aligned().into());
+                            next_back!();
+                            accum = f(accum, el.full_aligned().into());
+                            next_back!();
+                        }
+                    } else {
+                        loop {
+                            accum = f(accum, el.full_aligned().into());
+                            next_back!();
+                            accum = f(accum, el.half_aligned().into());
+                            next_back!();
+                        }
+                    }
+                }
+            }
+
+            #[inline]
+            fn rfind<P>(&mut self, mut predicate: P) -> Option<Self::Item>
+            where
+                P: FnMut(&Self::Item) -> bool,
+            {
+                self.search(true, |item| if predicate(&item) { Some(item) } else { None })
+            }
         }
 
         impl FusedIterator for $T<'_> {}
     };
 }
 
-impl_children_iter!(Children of NodeOrToken<ArcBorrow<'a, Node>, ArcBorrow<'a, Token>>);
-impl_children_iter!(ChildrenWithOffsets of (TextSize, NodeOrToken<ArcBorrow<'a, Node>, ArcBorrow<'a, Token>>));
+impl_children_iter!(Children of ElementRef<'a>);
+impl_children_iter!(ChildrenWithOffsets of (TextSize, ElementRef<'a>));
 
 impl<'a> Children<'a> {
     /// Iterate the children with their offsets from the parent node.
@@ -202,4 +366,81 @@ impl<'a> ChildrenWithOffsets<'a> {
     pub fn without_offsets(&self) -> Children<'a> {
         Children { inner: self.inner.clone() }
     }
+
+    /// The range of text covered by the children not yet iterated,
+    /// relative to the start of the parent node, or `None` if none remain.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind, TextRange};
+    /// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+    /// let tree = green::TreeBuilder::new()
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "aa")
+    ///     .token(WS, " ")
+    ///     .token(ATOM, "bb")
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// let mut children = tree.children().with_offsets();
+    /// assert_eq!(children.remaining_range(), Some(TextRange::up_to(tree.len())));
+    /// children.next();
+    /// children.next_back();
+    /// assert_eq!(children.remaining_range(), Some(TextRange::new(2.into(), 3.into())));
+    /// children.next();
+    /// assert_eq!(children.remaining_range(), None);
+    /// ```
+    #[inline]
+    pub fn remaining_range(&self) -> Option<TextRange> {
+        let elements = self.inner.as_slice();
+        let start = elements.first()?.offset();
+        let last = elements.last()?;
+        Some(TextRange::new(start, last.offset() + ElementRef::from(last).len()))
+    }
+
+    /// The children which start before `offset`.
+    ///
+    /// Together with [`from_offset`](ChildrenWithOffsets::from_offset), this
+    /// restricts iteration to the children overlapping a range, found by binary search:
+    /// `children.from_offset(range.start()).until_offset(range.end())`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind, TextRange};
+    /// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+    /// let tree = green::TreeBuilder::new()
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "aa")
+    ///     .token(WS, " ")
+    ///     .token(ATOM, "bb")
+    ///     .token(WS, " ")
+    ///     .token(ATOM, "cc")
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// let range = TextRange::new(1.into(), 4.into());
+    /// let children = tree.children().with_offsets();
+    /// let overlapping = children.from_offset(range.start()).until_offset(range.end());
+    /// let offsets: Vec<_> = overlapping.map(|(offset, _)| u32::from(offset)).collect();
+    /// assert_eq!(offsets, [0, 2, 3]);
+    /// ```
+    #[inline]
+    pub fn until_offset(&self, offset: TextSize) -> Self {
+        let mid = self.inner.as_slice().partition_point(|el| el.offset() < offset);
+        self.split_at(mid).0
+    }
+
+    /// The children which end after `offset`, or start at or after it.
+    ///
+    /// This includes the child containing `offset`, and any empty children at `offset`.
+    /// See [`until_offset`](ChildrenWithOffsets::until_offset).
+    #[inline]
+    pub fn from_offset(&self, offset: TextSize) -> Self {
+        let mid = self.inner.as_slice().partition_point(|el| {
+            el.offset() < offset && el.offset() + ElementRef::from(el).len() <= offset
+        });
+        self.split_at(mid).1
+    }
 }
@@ -0,0 +1,52 @@
+use {crate::green::Node, std::sync::Arc};
+
+/// Metadata identifying the source of a tree.
+///
+/// All of the fields are optional, and are only carried along with the tree;
+/// their interpretation is up to the tools producing and consuming them.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Metadata {
+    /// The path of the source file.
+    pub path: Option<String>,
+    /// The version of the source, such as an editor's document version or a content hash.
+    pub version: Option<String>,
+    /// The identifier of the language the source is in.
+    pub language: Option<String>,
+}
+
+/// A tree root together with metadata about its source.
+///
+/// Keeping the metadata with the tree, including through serialization,
+/// saves caches and cross-file tooling from maintaining a parallel map
+/// of metadata, which can drift out of sync with the trees.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, Document}, Kind};
+/// # const ATOM: Kind = Kind(0); const FILE: Kind = Kind(1);
+/// let root = green::TreeBuilder::new().start_node(FILE).token(ATOM, "a").finish_node().finish();
+/// let mut document = Document::new(root);
+/// document.meta.path = Some("src/a.txt".into());
+/// document.meta.language = Some("atoms".into());
+/// assert_eq!(document.root.kind(), FILE);
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Document {
+    /// Metadata about the source of the tree.
+    pub meta: Metadata,
+    /// The root of the tree.
+    pub root: Arc<Node>,
+}
+
+impl Document {
+    /// Create a document for a tree, without any metadata.
+    pub fn new(root: Arc<Node>) -> Self {
+        Document { meta: Metadata::default(), root }
+    }
+
+    /// Create a document for a tree, with metadata.
+    pub fn with_meta(root: Arc<Node>, meta: Metadata) -> Self {
+        Document { meta, root }
+    }
+}
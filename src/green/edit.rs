@@ -0,0 +1,693 @@
+//! Editing of immutable green trees.
+//!
+//! Green trees are immutable, so "editing" a tree means building a new root
+//! which shares all of the untouched subtrees with the original tree.
+
+use {
+    crate::{
+        green::{Builder, ElementRef, Node, Token},
+        ArcBorrow, Kind, NodeOrToken, TextRange, TextSize,
+    },
+    std::{convert::TryFrom, error::Error, fmt, ops::Range, str, sync::Arc},
+};
+
+type OwnedElement = NodeOrToken<Arc<Node>, Arc<Token>>;
+
+#[derive(Debug, Clone)]
+enum Edit {
+    InsertBefore(OwnedElement),
+    InsertAfter(OwnedElement),
+    Replace(Option<OwnedElement>),
+}
+
+/// Recorder of edits to a green tree.
+///
+/// Elements are addressed by their path from the root: the index of the
+/// child in the root, then the index of the child within that, and so on.
+/// All paths refer to the original tree, no matter which edits have
+/// already been recorded, so edits can be recorded in any order.
+/// The edits are applied all at once by [`commit`](Editor::commit).
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, edit::Editor}, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let mut builder = green::TreeBuilder::new();
+/// let tree = builder
+///     .start_node(LIST)
+///     .token(ATOM, "a")
+///     .token(ATOM, "b")
+///     .finish_node()
+///     .finish();
+/// let c = builder.builder().token(ATOM, "c");
+///
+/// let mut editor = Editor::new(tree);
+/// editor.detach(&[0]).insert_after(&[1], c);
+/// let edited = editor.commit(builder.builder()).into_root();
+///
+/// let expected = builder.start_node(LIST).token(ATOM, "b").token(ATOM, "c").finish_node().finish();
+/// assert_eq!(edited, expected);
+/// ```
+#[derive(Clone)]
+pub struct Editor {
+    root: Arc<Node>,
+    edits: Vec<(Vec<usize>, Edit)>,
+}
+
+impl fmt::Debug for Editor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Editor")
+            .field("root", &format_args!("{:?}@{:?}", self.root.kind(), self.root.len()))
+            .field("edits", &self.edits)
+            .finish()
+    }
+}
+
+impl Editor {
+    /// Start editing a tree.
+    pub fn new(root: Arc<Node>) -> Self {
+        Editor { root, edits: vec![] }
+    }
+
+    /// The original root being edited.
+    pub fn root(&self) -> &Arc<Node> {
+        &self.root
+    }
+
+    /// Whether any edits have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Check that `range` is a valid range to edit in the text of the original tree:
+    /// that it is within the text, and both ends fall on UTF-8 char boundaries.
+    pub fn check_range(&self, range: TextRange) -> Result<(), EditError> {
+        check_range(&self.root, range)
+    }
+
+    fn record(&mut self, path: &[usize], edit: Edit) -> &mut Self {
+        assert!(!path.is_empty(), "cannot edit the root of an `Editor`");
+        assert!(
+            resolve(&self.root, path).is_some(),
+            "path {:?} does not point into the tree",
+            path
+        );
+        self.edits.push((path.to_vec(), edit));
+        self
+    }
+
+    /// Remove the element at `path` from the tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` does not point to an element of the original tree.
+    pub fn detach(&mut self, path: &[usize]) -> &mut Self {
+        self.record(path, Edit::Replace(None))
+    }
+
+    /// Replace the element at `path` with a new element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` does not point to an element of the original tree.
+    pub fn replace_with(
+        &mut self,
+        path: &[usize],
+        element: impl Into<NodeOrToken<Arc<Node>, Arc<Token>>>,
+    ) -> &mut Self {
+        self.record(path, Edit::Replace(Some(element.into())))
+    }
+
+    /// Insert a new element as the sibling immediately before the element at `path`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` does not point to an element of the original tree.
+    pub fn insert_before(
+        &mut self,
+        path: &[usize],
+        element: impl Into<NodeOrToken<Arc<Node>, Arc<Token>>>,
+    ) -> &mut Self {
+        self.record(path, Edit::InsertBefore(element.into()))
+    }
+
+    /// Insert a new element as the sibling immediately after the element at `path`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` does not point to an element of the original tree.
+    pub fn insert_after(
+        &mut self,
+        path: &[usize],
+        element: impl Into<NodeOrToken<Arc<Node>, Arc<Token>>>,
+    ) -> &mut Self {
+        self.record(path, Edit::InsertAfter(element.into()))
+    }
+
+    /// Apply all of the recorded edits, producing a new root.
+    ///
+    /// Multiple insertions at the same place are inserted in the order
+    /// they were recorded. If an element is replaced or detached multiple
+    /// times, the last recorded edit wins, and any edits recorded
+    /// within a replaced or detached element are discarded.
+    pub fn commit(mut self, builder: &mut Builder) -> EditResult {
+        // stable sort keeps the recorded order for edits at the same path
+        self.edits.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+        let mut splices = vec![];
+        let root = rebuild(builder, &self.root, 0.into(), 0, &self.edits, &mut splices);
+        EditResult { root, splices }
+    }
+}
+
+/// Error for an invalid edit of a green tree.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum EditError {
+    /// The edited range extends past the end of the text.
+    #[allow(missing_docs)]
+    OutOfBounds { range: TextRange, len: TextSize },
+    /// An edit boundary falls inside of a UTF-8 encoded char.
+    #[allow(missing_docs)]
+    NotCharBoundary { offset: TextSize },
+    /// Two edited ranges overlap.
+    #[allow(missing_docs)]
+    Overlapping { first: TextRange, second: TextRange },
+    /// There is no token at the edited offset to hold the inserted text.
+    #[allow(missing_docs)]
+    NoToken { offset: TextSize },
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::OutOfBounds { range, len } => {
+                write!(f, "edit range {:?} is out of bounds of text of length {:?}", range, len)
+            }
+            EditError::NotCharBoundary { offset } => {
+                write!(f, "edit boundary {:?} is not on a char boundary", offset)
+            }
+            EditError::Overlapping { first, second } => {
+                write!(f, "edit ranges {:?} and {:?} overlap", first, second)
+            }
+            EditError::NoToken { offset } => {
+                write!(f, "there is no token at edit offset {:?} to insert text into", offset)
+            }
+        }
+    }
+}
+
+impl Error for EditError {}
+
+/// A textual replacement made by an edit: `old` range of the text replaced with `new_len` text.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Splice {
+    old: TextRange,
+    new_len: TextSize,
+}
+
+/// The result of editing a tree: the new root and a mapping from old to new text offsets.
+#[derive(Debug, Clone)]
+pub struct EditResult {
+    root: Arc<Node>,
+    splices: Vec<Splice>, // NB: sorted and disjoint
+}
+
+impl EditResult {
+    /// The edited root.
+    pub fn root(&self) -> &Arc<Node> {
+        &self.root
+    }
+
+    /// Take the edited root, discarding the offset mapping.
+    pub fn into_root(self) -> Arc<Node> {
+        self.root
+    }
+
+    /// The replaced ranges of the old text, each with the range of its replacement in the new text.
+    pub(super) fn changes(&self) -> impl '_ + Iterator<Item = (TextRange, TextRange)> {
+        let (mut added, mut removed) = (TextSize::from(0), TextSize::from(0));
+        self.splices.iter().map(move |splice| {
+            let new = TextRange::at(splice.old.start() + added - removed, splice.new_len);
+            added += splice.new_len;
+            removed += splice.old.len();
+            (splice.old, new)
+        })
+    }
+
+    /// The result of undoing this edit, from the edited root back to `old_root`.
+    pub(super) fn invert(&self, old_root: Arc<Node>) -> EditResult {
+        let splices = self.changes().map(|(old, new)| Splice { old: new, new_len: old.len() });
+        EditResult { root: old_root, splices: splices.collect() }
+    }
+
+    /// Map an offset, optionally moving past text inserted at it,
+    /// or to the end of any replacement strictly containing it.
+    fn map(&self, offset: TextSize, skip_inserted: bool, to_end: bool) -> TextSize {
+        let raw = |size: TextSize| i64::from(u32::from(size));
+        let mut delta = 0;
+        for splice in &self.splices {
+            let (start, end) = (splice.old.start(), splice.old.end());
+            if splice.old.is_empty() {
+                if offset > start || offset == start && skip_inserted {
+                    delta += raw(splice.new_len);
+                } else if offset < start {
+                    break;
+                }
+                continue;
+            }
+            if offset < start || offset == start && to_end {
+                break;
+            }
+            if offset < end {
+                let new_len = if to_end { raw(splice.new_len) } else { 0 };
+                return TextSize::from((raw(start) + delta + new_len) as u32);
+            }
+            delta += raw(splice.new_len) - raw(splice.old.len());
+        }
+        TextSize::from((raw(offset) + delta) as u32)
+    }
+
+    /// Map an offset in the old text to the corresponding offset in the new text.
+    ///
+    /// Offsets within replaced text are mapped to the start of the replacement,
+    /// and offsets where text was inserted stay before the inserted text.
+    pub fn map_offset(&self, offset: TextSize) -> TextSize {
+        self.map(offset, false, false)
+    }
+
+    /// Map a range in the old text to the corresponding range in the new text.
+    ///
+    /// The range is expanded to cover any replacement overlapping its ends,
+    /// but doesn't grow to cover text inserted immediately around it.
+    /// Empty ranges are mapped like [`map_offset`](EditResult::map_offset).
+    pub fn map_range(&self, range: TextRange) -> TextRange {
+        if range.is_empty() {
+            return TextRange::empty(self.map_offset(range.start()));
+        }
+        let start = self.map(range.start(), true, false);
+        let end = self.map(range.end(), false, true);
+        TextRange::new(start, end.max(start))
+    }
+}
+
+impl Builder {
+    /// Wrap the element at `path` in `root` in a new node of `kind`,
+    /// between the tokens of `prefix` and `suffix`.
+    ///
+    /// An empty `path` wraps the root itself. The offset mapping of the result
+    /// maps offsets within the wrapped element to within the new node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` does not point to an element of `root`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind, TextRange};
+    /// # const ATOM: Kind = Kind(0); const PAREN: Kind = Kind(1); const LIST: Kind = Kind(2);
+    /// let mut builder = green::TreeBuilder::new();
+    /// let tree = builder.start_node(LIST).token(ATOM, "a").token(ATOM, "b").finish_node().finish();
+    ///
+    /// let (l, r) = (builder.builder().token(PAREN, "("), builder.builder().token(PAREN, ")"));
+    /// let wrapped = builder.builder().wrap(&tree, &[1], LIST, &[l], &[r]);
+    /// let expected = builder
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "a")
+    ///     .start_node(LIST)
+    ///     .token(PAREN, "(")
+    ///     .token(ATOM, "b")
+    ///     .token(PAREN, ")")
+    ///     .finish_node()
+    ///     .finish_node()
+    ///     .finish();
+    /// assert_eq!(wrapped.root(), &expected);
+    /// let b = TextRange::new(1.into(), 2.into());
+    /// assert_eq!(wrapped.map_range(b), TextRange::new(2.into(), 3.into()));
+    ///
+    /// let unwrapped = builder.builder().unwrap(wrapped.root(), &[1]);
+    /// assert_eq!(unwrapped.root().children().len(), 4);
+    /// ```
+    pub fn wrap(
+        &mut self,
+        root: &Arc<Node>,
+        path: &[usize],
+        kind: Kind,
+        prefix: &[Arc<Token>],
+        suffix: &[Arc<Token>],
+    ) -> EditResult {
+        let (range, element) = resolve_ref(root, path)
+            .unwrap_or_else(|| panic!("path {:?} does not point into the tree", path));
+        let children = prefix.iter().cloned().map(NodeOrToken::Token);
+        let children = children
+            .chain(Some(element.upgrade()))
+            .chain(suffix.iter().cloned().map(NodeOrToken::Token));
+        let node = self.node(kind, children.collect::<Vec<_>>());
+
+        let root = match path.is_empty() {
+            true => node,
+            false => {
+                let mut editor = Editor::new(Arc::clone(root));
+                editor.replace_with(path, node);
+                editor.commit(self).into_root()
+            }
+        };
+        let text_len = |tokens: &[Arc<Token>]| tokens.iter().map(|token| token.len()).sum();
+        let splices = vec![
+            Splice { old: TextRange::empty(range.start()), new_len: text_len(prefix) },
+            Splice { old: TextRange::empty(range.end()), new_len: text_len(suffix) },
+        ];
+        let splices = splices.into_iter().filter(|splice| splice.new_len != 0.into()).collect();
+        EditResult { root, splices }
+    }
+
+    /// Replace the node at `path` in `root` with its children.
+    ///
+    /// The text of the tree is unchanged, so the offset mapping of the result is the identity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` is empty, or does not point to a node of `root`.
+    pub fn unwrap(&mut self, root: &Arc<Node>, path: &[usize]) -> EditResult {
+        assert!(!path.is_empty(), "cannot unwrap the root");
+        let node = resolve_ref(root, path)
+            .and_then(|(_, element)| element.into_node())
+            .unwrap_or_else(|| panic!("path {:?} does not point to a node in the tree", path));
+        let mut editor = Editor::new(Arc::clone(root));
+        for child in node.children() {
+            editor.insert_before(path, child.upgrade());
+        }
+        editor.detach(path);
+        EditResult { root: editor.commit(self).into_root(), splices: vec![] }
+    }
+}
+
+/// Split the token containing `offset` in two at that offset,
+/// giving the halves the kinds in `kinds`. See [`Builder::split_token`].
+///
+/// Returns `None` if `offset` is not strictly within a token,
+/// or is not on a char boundary.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, edit}, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let mut builder = green::TreeBuilder::new();
+/// let tree = builder.start_node(LIST).token(ATOM, "ab").finish_node().finish();
+///
+/// let split = edit::split_token_at(builder.builder(), &tree, 1.into(), (ATOM, ATOM)).unwrap();
+/// let expected = builder.start_node(LIST).token(ATOM, "a").token(ATOM, "b").finish_node().finish();
+/// assert_eq!(split.root(), &expected);
+///
+/// let joined = edit::join_tokens_at(builder.builder(), split.root(), 1.into(), ATOM).unwrap();
+/// assert_eq!(joined.root(), &tree);
+/// ```
+pub fn split_token_at(
+    builder: &mut Builder,
+    root: &Arc<Node>,
+    offset: TextSize,
+    kinds: (Kind, Kind),
+) -> Option<EditResult> {
+    let (path, start, token) = token_path(root, offset)?;
+    if start == offset || !root.is_char_boundary(offset) {
+        return None;
+    }
+    let (left, right) = builder.split_token(token, offset - start, kinds);
+    let mut editor = Editor::new(Arc::clone(root));
+    editor.replace_with(&path, left).insert_after(&path, right);
+    Some(editor.commit(builder))
+}
+
+/// Join the token ending at `offset` with the token starting at `offset`
+/// into one token of `kind`. See [`Builder::join_tokens`].
+///
+/// Returns `None` unless the two tokens are adjacent siblings.
+pub fn join_tokens_at(
+    builder: &mut Builder,
+    root: &Arc<Node>,
+    offset: TextSize,
+    kind: Kind,
+) -> Option<EditResult> {
+    let (path, start, right) = token_path(root, offset)?;
+    let (&index, parent) = path.split_last()?;
+    if start != offset || index == 0 {
+        return None;
+    }
+    let left_path = [parent, &[index - 1]].concat();
+    let left = resolve(root, &left_path)?.into_token()?;
+    let joined = builder.join_tokens(left, right, kind);
+    let mut editor = Editor::new(Arc::clone(root));
+    editor.replace_with(&left_path, joined).detach(&path);
+    Some(editor.commit(builder))
+}
+
+/// Replace multiple ranges of the text of `root` at once.
+///
+/// The edits may be given in any order, but must not overlap; insertions
+/// at the same offset are applied in the order given. Each edit rewrites
+/// the tokens it touches, keeping the kind of the first of them, so the
+/// tree structure around an edit is kept as is. All edits are applied
+/// in one rebuild, sharing every subtree not touched by any edit.
+///
+/// The offset mapping of the result maps through exactly the edited ranges.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, edit}, Kind, TextRange};
+/// # const ATOM: Kind = Kind(0); const WS: Kind = Kind(1); const LIST: Kind = Kind(2);
+/// let mut builder = green::TreeBuilder::new();
+/// let tree = builder
+///     .start_node(LIST)
+///     .token(ATOM, "foo")
+///     .token(WS, " ")
+///     .token(ATOM, "bar")
+///     .finish_node()
+///     .finish();
+///
+/// let edits = [(TextRange::new(4.into(), 7.into()), "baz"), (TextRange::new(0.into(), 1.into()), "g")];
+/// let edited = edit::apply_all(builder.builder(), &tree, &edits).unwrap();
+/// let expected = builder
+///     .start_node(LIST)
+///     .token(ATOM, "goo")
+///     .token(WS, " ")
+///     .token(ATOM, "baz")
+///     .finish_node()
+///     .finish();
+/// assert_eq!(edited.root(), &expected);
+/// assert_eq!(edited.map_offset(5.into()), 4.into());
+///
+/// let overlapping = [(TextRange::new(0.into(), 2.into()), ""), (TextRange::new(1.into(), 3.into()), "")];
+/// assert!(edit::apply_all(builder.builder(), &tree, &overlapping).is_err());
+/// ```
+pub fn apply_all(
+    builder: &mut Builder,
+    root: &Arc<Node>,
+    edits: &[(TextRange, &str)],
+) -> Result<EditResult, EditError> {
+    let mut edits: Vec<(TextRange, &str)> = edits.to_vec();
+    edits.sort_by_key(|&(range, _)| (range.start(), range.end()));
+    for &(range, _) in &edits {
+        check_range(root, range)?;
+    }
+    for pair in edits.windows(2) {
+        let ((first, _), (second, _)) = (pair[0], pair[1]);
+        if first.end() > second.start() {
+            return Err(EditError::Overlapping { first, second });
+        }
+    }
+    edits.retain(|&(range, text)| !(range.is_empty() && text.is_empty()));
+
+    // group the edits touching the same tokens, to rewrite each token once
+    let mut groups: Vec<(Range<usize>, Vec<usize>)> = vec![];
+    for (i, &(range, _)) in edits.iter().enumerate() {
+        let first = edited_token(root, range.start())?.0;
+        let last = match range.is_empty() {
+            true => first.clone(),
+            false => edited_token(root, range.end() - TextSize::from(1))?.0,
+        };
+        match groups.last_mut() {
+            Some((group, group_last)) if *group_last >= first => {
+                group.end = i + 1;
+                *group_last = last;
+            }
+            _ => groups.push((i..i + 1, last)),
+        }
+    }
+
+    let mut editor = Editor::new(Arc::clone(root));
+    for (group, last) in groups {
+        let group = &edits[group];
+        let (first, start, first_token) = edited_token(root, group[0].0.start())?;
+        let (mut paths, mut old) = (vec![first], first_token.bytes().to_vec());
+        while paths.last() != Some(&last) {
+            let offset = start + TextSize::try_from(old.len()).unwrap();
+            let (path, _, token) = token_path(root, offset).expect("edited token should exist");
+            old.extend_from_slice(token.bytes());
+            paths.push(path);
+        }
+
+        let (mut new, mut cursor) = (vec![], 0);
+        for &(range, text) in group {
+            new.extend_from_slice(&old[cursor..usize::from(range.start() - start)]);
+            new.extend_from_slice(text.as_bytes());
+            cursor = usize::from(range.end() - start);
+        }
+        new.extend_from_slice(&old[cursor..]);
+
+        let kind = first_token.kind();
+        for path in &paths[1..] {
+            editor.detach(path);
+        }
+        match str::from_utf8(&new) {
+            _ if new.is_empty() => editor.detach(&paths[0]),
+            Ok(text) => editor.replace_with(&paths[0], builder.token(kind, text)),
+            Err(_) => editor.replace_with(&paths[0], builder.binary_token(kind, &new)),
+        };
+    }
+
+    let root = editor.commit(builder).into_root();
+    let splices =
+        edits.iter().map(|&(old, text)| Splice { old, new_len: TextSize::of(text) }).collect();
+    Ok(EditResult { root, splices })
+}
+
+/// Find the token edited at `offset`: the token containing it,
+/// or the last token if `offset` is the end of the text.
+fn edited_token(
+    root: &Node,
+    offset: TextSize,
+) -> Result<(Vec<usize>, TextSize, &Token), EditError> {
+    let at = match offset == root.len() {
+        true => offset.checked_sub(1.into()),
+        false => Some(offset),
+    };
+    at.and_then(|at| token_path(root, at)).ok_or(EditError::NoToken { offset })
+}
+
+/// Check that `range` is within the text of `root` and on char boundaries.
+fn check_range(root: &Node, range: TextRange) -> Result<(), EditError> {
+    if range.end() > root.len() {
+        return Err(EditError::OutOfBounds { range, len: root.len() });
+    }
+    for &offset in &[range.start(), range.end()] {
+        if !root.is_char_boundary(offset) {
+            return Err(EditError::NotCharBoundary { offset });
+        }
+    }
+    Ok(())
+}
+
+/// Find the path to the token containing `offset` within `node`, and the offset of its start.
+fn token_path(node: &Node, offset: TextSize) -> Option<(Vec<usize>, TextSize, &Token)> {
+    if offset >= node.len() {
+        return None;
+    }
+    let (mut path, mut node, mut start) = (vec![], node, TextSize::from(0));
+    loop {
+        let children = node.children().with_offsets();
+        let mut index = node.index_of_offset(offset - start);
+        // skip empty elements at the offset to the element which contains it
+        while children.get(index)?.1.len() == 0.into() {
+            index += 1;
+        }
+        let (child_offset, child) = children.get(index)?;
+        path.push(index);
+        start += child_offset;
+        match child {
+            NodeOrToken::Node(child) => node = ArcBorrow::downgrade(child),
+            NodeOrToken::Token(token) => return Some((path, start, ArcBorrow::downgrade(token))),
+        }
+    }
+}
+
+/// Find the element at `path` within `node`.
+fn resolve<'a>(node: &'a Node, path: &[usize]) -> Option<NodeOrToken<&'a Node, &'a Token>> {
+    let mut el = NodeOrToken::Node(node);
+    for &index in path {
+        let child = el.into_node()?.children().get(index)?;
+        el = child.map(ArcBorrow::downgrade, ArcBorrow::downgrade);
+    }
+    Some(el)
+}
+
+/// Find the element at `path` within `root`, and its range.
+fn resolve_ref<'a>(root: &'a Arc<Node>, path: &[usize]) -> Option<(TextRange, ElementRef<'a>)> {
+    let mut el = NodeOrToken::Node(ArcBorrow::from(root));
+    let mut offset = TextSize::from(0);
+    for &index in path {
+        let (child_offset, child) =
+            ArcBorrow::downgrade(el.into_node()?).children().with_offsets().get(index)?;
+        offset += child_offset;
+        el = child;
+    }
+    Some((TextRange::at(offset, el.len()), el))
+}
+
+/// Rebuild `node`, which is at depth `depth` and text `offset`, applying `edits` (all within `node`).
+fn rebuild(
+    builder: &mut Builder,
+    node: &Node,
+    offset: TextSize,
+    depth: usize,
+    mut edits: &[(Vec<usize>, Edit)],
+    splices: &mut Vec<Splice>,
+) -> Arc<Node> {
+    let mut children = Vec::with_capacity(node.children_len());
+    for (index, (child_offset, child)) in node.children().with_offsets().enumerate() {
+        let split = edits.iter().position(|(path, _)| path[depth] != index).unwrap_or(edits.len());
+        let (here, rest) = edits.split_at(split);
+        edits = rest;
+
+        let child_range = TextRange::at(offset + child_offset, child.len());
+        let mut child = Some(child.upgrade());
+        let (mut before, mut after) = (vec![], vec![]);
+        let mut replaced = false;
+        let mut nested = &here[..0];
+        for (i, (path, edit)) in here.iter().enumerate() {
+            if path.len() > depth + 1 {
+                nested = &here[i..];
+                break;
+            }
+            match edit {
+                Edit::InsertBefore(el) => before.push(el.clone()),
+                Edit::InsertAfter(el) => after.push(el.clone()),
+                Edit::Replace(el) => {
+                    child = el.clone();
+                    replaced = true;
+                }
+            }
+        }
+
+        let text_len = |els: &[OwnedElement]| els.iter().map(NodeOrToken::len).sum::<TextSize>();
+        if replaced {
+            let new_len = text_len(&before)
+                + child.iter().map(NodeOrToken::len).sum::<TextSize>()
+                + text_len(&after);
+            splices.push(Splice { old: child_range, new_len });
+        } else {
+            if !before.is_empty() {
+                let old = TextRange::empty(child_range.start());
+                splices.push(Splice { old, new_len: text_len(&before) });
+            }
+            if !nested.is_empty() {
+                if let Some(NodeOrToken::Node(node)) = &child {
+                    let node =
+                        rebuild(builder, node, child_range.start(), depth + 1, nested, splices);
+                    child = Some(NodeOrToken::Node(node));
+                }
+            }
+            if !after.is_empty() {
+                let old = TextRange::empty(child_range.end());
+                splices.push(Splice { old, new_len: text_len(&after) });
+            }
+        }
+
+        children.extend(before);
+        children.extend(child);
+        children.extend(after);
+    }
+    builder.node_like(node, children)
+}
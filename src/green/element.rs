@@ -21,8 +21,8 @@
 
 use {
     crate::{
-        green::{Node, Token},
-        ArcBorrow, NodeOrToken, TextSize,
+        green::{ElementRef, Node, Token},
+        ArcBorrow, Kind, NodeOrToken, TextSize,
     },
     erasable::{ErasablePtr, ErasedPtr},
     ptr_union::{Builder2, Enum2, Union2},
@@ -140,7 +140,7 @@ impl Element {
     }
 
     pub(super) unsafe fn full_aligned(&self) -> &FullAlignedElement {
-        debug_assert!(
+        paranoid_assert!(
             self.is_full_aligned(),
             "called Element::full_aligned on half-aligned element; this is UB!",
         );
@@ -148,7 +148,7 @@ impl Element {
     }
 
     pub(super) unsafe fn full_aligned_mut(&mut self) -> &mut FullAlignedElement {
-        debug_assert!(
+        paranoid_assert!(
             self.is_full_aligned(),
             "called Element::full_aligned on half-aligned element; this is UB!",
         );
@@ -166,7 +166,7 @@ impl Element {
     }
 
     pub(super) unsafe fn half_aligned(&self) -> &HalfAlignedElement {
-        debug_assert!(
+        paranoid_assert!(
             self.is_half_aligned(),
             "called Element::half_aligned on full-aligned element; this is UB!",
         );
@@ -174,7 +174,7 @@ impl Element {
     }
 
     pub(super) unsafe fn half_aligned_mut(&mut self) -> &mut HalfAlignedElement {
-        debug_assert!(
+        paranoid_assert!(
             self.is_half_aligned(),
             "called Element::half_aligned on full-aligned element; this is UB!",
         );
@@ -215,7 +215,7 @@ impl PartialEq for Element {
             match (self.is_full_aligned(), other.is_full_aligned()) {
                 (true, true) => self.full_aligned() == other.full_aligned(),
                 (true, false) => self.full_aligned() == other.half_aligned(),
-                (false, true) => self.half_aligned() == other.half_aligned(),
+                (false, true) => self.half_aligned() == other.full_aligned(),
                 (false, false) => self.half_aligned() == other.half_aligned(),
             }
         }
@@ -297,7 +297,7 @@ impl_element!(HalfAlignedElement);
 
 impl FullAlignedElement {
     pub(super) unsafe fn write(ptr: *mut Element, element: PackedNodeOrToken, offset: TextSize) {
-        debug_assert!(
+        paranoid_assert!(
             ptr as usize % 8 == 0,
             "attempted to write full-aligned element to half-aligned place; this is UB!",
         );
@@ -312,7 +312,7 @@ impl FullAlignedElement {
 #[cfg(target_pointer_width = "64")]
 impl HalfAlignedElement {
     pub(super) unsafe fn write(ptr: *mut Element, element: PackedNodeOrToken, offset: TextSize) {
-        debug_assert!(
+        paranoid_assert!(
             ptr as usize % 8 == 4,
             "attempted to write half-aligned element to full-aligned place; this is UB!",
         );
@@ -342,3 +342,63 @@ impl<'a> From<&'a Element> for (TextSize, NodeOrToken<ArcBorrow<'a, Node>, ArcBo
         }
     }
 }
+
+/// A child element of a green node, as borrowed by indexing the node.
+///
+/// This wraps the packed element stored inline in the node.
+/// Use [`get`](Child::get) to unpack it to an [`ElementRef`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, Kind};
+/// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+/// let tree = green::TreeBuilder::new()
+///     .start_node(LIST)
+///     .token(ATOM, "a")
+///     .token(WS, " ")
+///     .token(ATOM, "b")
+///     .finish_node()
+///     .finish();
+///
+/// assert_eq!(tree[2].kind(), ATOM);
+/// assert_eq!(tree[2].offset(), 2.into());
+/// assert_eq!(tree[2].get().as_token().map(|token| token.text()), Some("b"));
+/// ```
+#[repr(transparent)]
+#[derive(Debug, Eq, PartialEq, Hash)]
+pub struct Child {
+    element: Element,
+}
+
+impl Child {
+    pub(super) fn new(element: &Element) -> &Self {
+        // SAFETY: Child is repr(transparent) over Element
+        unsafe { &*(element as *const Element as *const Self) }
+    }
+
+    /// The element, unpacked.
+    pub fn get(&self) -> ElementRef<'_> {
+        (&self.element).into()
+    }
+
+    /// The offset of this element from the start of its parent node.
+    pub fn offset(&self) -> TextSize {
+        self.element.offset()
+    }
+
+    /// The kind of this element.
+    pub fn kind(&self) -> Kind {
+        self.get().kind()
+    }
+
+    /// The length of text at this element.
+    pub fn len(&self) -> TextSize {
+        self.get().len()
+    }
+
+    /// Whether this element contains no text.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0.into()
+    }
+}
@@ -0,0 +1,136 @@
+use {
+    crate::{
+        green::{ElementRef, Node, ShapeError},
+        Kind, KindSet,
+    },
+    std::fmt,
+};
+
+/// A description of the named children of nodes of some kind. See [`Fields`].
+///
+/// Fields are matched in order: each field is the first child of a kind in
+/// its set after the child matched by the previous field, if any. This way
+/// repeated kinds can be told apart by position, such as the two operands
+/// of a binary expression, and optional fields can be skipped.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct FieldSpec {
+    kind: Kind,
+    fields: Vec<(&'static str, KindSet)>,
+}
+
+impl FieldSpec {
+    /// A spec of nodes of `kind` without any fields.
+    pub fn new(kind: Kind) -> Self {
+        FieldSpec { kind, fields: vec![] }
+    }
+
+    /// Add a field named `name` matching a child of a kind in `kinds`.
+    pub fn field(mut self, name: &'static str, kinds: KindSet) -> Self {
+        self.fields.push((name, kinds));
+        self
+    }
+
+    /// The kind of node this spec describes.
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+}
+
+/// The children of a node matched to the fields of a [`FieldSpec`].
+///
+/// This is a lightweight way to write typed accessors for nodes,
+/// without generating a whole typed AST.
+///
+/// # Examples
+///
+/// ```rust
+/// # use {lazy_static::lazy_static, sorbus::{green::{self, FieldSpec, Fields, ShapeError}, Kind}, std::convert::TryFrom};
+/// # const WS: Kind = Kind(0); const OP: Kind = Kind(1); const NUM: Kind = Kind(2); const BIN: Kind = Kind(3);
+/// /// A view of a binary expression.
+/// struct BinExpr<'a>(Fields<'a>);
+///
+/// impl<'a> TryFrom<&'a green::Node> for BinExpr<'a> {
+///     type Error = ShapeError;
+///     fn try_from(node: &'a green::Node) -> Result<Self, ShapeError> {
+///         lazy_static! {
+///             static ref SPEC: FieldSpec = FieldSpec::new(BIN)
+///                 .field("lhs", [NUM, BIN].iter().copied().collect())
+///                 .field("op", [OP].iter().copied().collect())
+///                 .field("rhs", [NUM, BIN].iter().copied().collect());
+///         }
+///         Fields::new(node, &SPEC).map(BinExpr)
+///     }
+/// }
+///
+/// let tree = green::TreeBuilder::new()
+///     .start_node(BIN)
+///     .token(NUM, "1")
+///     .token(WS, " ")
+///     .token(OP, "+")
+///     .token(WS, " ")
+///     .token(NUM, "2")
+///     .finish_node()
+///     .finish();
+///
+/// let expr = BinExpr::try_from(&*tree).unwrap();
+/// assert_eq!(expr.0.field("rhs").unwrap().as_token().unwrap().text(), "2");
+/// assert_eq!(expr.0.index("op"), Some(2));
+/// ```
+#[derive(Clone)]
+pub struct Fields<'a> {
+    node: &'a Node,
+    spec: &'a FieldSpec,
+    indices: Vec<Option<usize>>,
+}
+
+impl fmt::Debug for Fields<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = self.spec.fields.iter().zip(&self.indices);
+        f.debug_map().entries(fields.map(|((name, _), index)| (name, index))).finish()
+    }
+}
+
+impl<'a> Fields<'a> {
+    /// Match the children of `node` to the fields of `spec`.
+    ///
+    /// Fails if `node` is not of the spec's kind. Fields without a matching
+    /// child are `None`; use [`Shape`](crate::green::Shape) to validate arity.
+    pub fn new(node: &'a Node, spec: &'a FieldSpec) -> Result<Self, ShapeError> {
+        if node.kind() != spec.kind {
+            return Err(ShapeError::Kind { path: vec![], expected: spec.kind, found: node.kind() });
+        }
+        let mut next = 0;
+        let indices = spec.fields.iter().map(|(_, kinds)| {
+            let mut children = node.children().enumerate().skip(next);
+            let (index, _) = children.find(|(_, child)| kinds.contains(child.kind()))?;
+            next = index + 1;
+            Some(index)
+        });
+        Ok(Fields { node, spec, indices: indices.collect() })
+    }
+
+    /// The node whose fields these are.
+    pub fn node(&self) -> &'a Node {
+        self.node
+    }
+
+    /// The index of the child matched to the field `name`, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the spec has no field `name`.
+    pub fn index(&self, name: &str) -> Option<usize> {
+        let position = self.spec.fields.iter().position(|&(field, _)| field == name);
+        let position = position.unwrap_or_else(|| panic!("no field named {:?}", name));
+        self.indices[position]
+    }
+
+    /// The child matched to the field `name`, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the spec has no field `name`.
+    pub fn field(&self, name: &str) -> Option<ElementRef<'a>> {
+        self.node.children().get(self.index(name)?)
+    }
+}
@@ -0,0 +1,471 @@
+//! A flattened, struct-of-arrays view of a tree.
+//!
+//! Data-oriented analysis passes, such as dataflow or type inference prototypes,
+//! are much faster over flat arrays indexed by element than chasing pointers
+//! through the tree. A [`FlatTree`] is built from a root in one pass,
+//! and [updated](FlatTree::update) after an edit rather than rebuilt.
+
+use {
+    crate::{
+        green::{ElementRef, Node},
+        ArcBorrow, Kind, NodeOrToken, TextRange, TextSize,
+    },
+    std::{convert::TryFrom, iter, mem, ptr, sync::Arc},
+};
+
+/// The absent id in the link arrays.
+const NONE: u32 = u32::MAX;
+
+/// A tree flattened into parallel arrays indexed by element id.
+///
+/// Every element of the tree, node or token, has an id. When the tree is flattened,
+/// ids are assigned in preorder, so the root has the id 0, and the descendants of
+/// an element have the ids directly after it. Unlike an [`IdMap`](crate::green::ids::IdMap),
+/// each occurrence of a shared element has its own id, as it has its own parent and range.
+///
+/// After an [update](FlatTree::update), unchanged elements keep their ids,
+/// new elements have new ids after all existing ids, and the ids of removed
+/// elements are no longer [live](FlatTree::is_live) and are never reused.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, flat::FlatTree}, Kind, TextRange};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let tree = green::TreeBuilder::new()
+///     .start_node(LIST)
+///     .token(ATOM, "a")
+///     .start_node(LIST)
+///     .token(ATOM, "bc")
+///     .finish_node()
+///     .finish_node()
+///     .finish();
+///
+/// let flat = FlatTree::new(tree);
+/// assert_eq!(flat.kinds(), [LIST, ATOM, LIST, ATOM]);
+/// assert_eq!(flat.children(0).collect::<Vec<_>>(), [1, 2]);
+/// assert_eq!(flat.parent(3), Some(2));
+/// assert_eq!(flat.ranges()[3], TextRange::new(1.into(), 3.into()));
+/// assert_eq!(flat.path(3), [1, 0]);
+/// assert_eq!(flat.element(3).unwrap_token().text(), "bc");
+/// ```
+#[derive(Debug, Clone)]
+pub struct FlatTree {
+    root: Arc<Node>,
+    kinds: Vec<Kind>,
+    ranges: Vec<TextRange>,
+    is_token: Vec<bool>,
+    parents: Vec<u32>,
+    first_children: Vec<u32>,
+    next_siblings: Vec<u32>,
+    indices: Vec<u32>, // index within the parent
+    live: Vec<bool>,
+}
+
+/// The ids affected by a [`FlatTree::update`].
+///
+/// Elements which were only moved, such as by text inserted before them,
+/// are not affected, although their ranges are updated.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct FlatPatch {
+    changed: Vec<u32>,
+    added: Vec<u32>,
+    removed: Vec<u32>,
+}
+
+impl FlatPatch {
+    /// The ids of elements which were kept, but whose text or descendants changed,
+    /// such as the ancestors of an edited token.
+    pub fn changed(&self) -> &[u32] {
+        &self.changed
+    }
+
+    /// The ids of new elements.
+    pub fn added(&self) -> &[u32] {
+        &self.added
+    }
+
+    /// The ids of removed elements, which are no longer live.
+    pub fn removed(&self) -> &[u32] {
+        &self.removed
+    }
+}
+
+/// Whether two elements are the same element, by identity.
+fn same_element(a: ElementRef<'_>, b: ElementRef<'_>) -> bool {
+    match (a, b) {
+        (NodeOrToken::Node(a), NodeOrToken::Node(b)) => ptr::eq(&*a, &*b),
+        (NodeOrToken::Token(a), NodeOrToken::Token(b)) => ptr::eq(&*a, &*b),
+        _ => false,
+    }
+}
+
+fn link(id: u32) -> Option<u32> {
+    if id == NONE {
+        None
+    } else {
+        Some(id)
+    }
+}
+
+impl FlatTree {
+    /// Flatten a tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree has `u32::MAX` or more elements.
+    pub fn new(root: Arc<Node>) -> Self {
+        let mut flat = FlatTree {
+            root: Arc::clone(&root),
+            kinds: vec![],
+            ranges: vec![],
+            is_token: vec![],
+            parents: vec![],
+            first_children: vec![],
+            next_siblings: vec![],
+            indices: vec![],
+            live: vec![],
+        };
+        flat.flatten(NodeOrToken::Node(ArcBorrow::from(&root)), NONE, 0, 0.into());
+        flat
+    }
+
+    /// Append the elements of a subtree in preorder, returning the id of its root.
+    ///
+    /// The root of the subtree is not linked into the children of `parent`.
+    fn flatten(&mut self, root: ElementRef<'_>, parent: u32, index: u32, offset: TextSize) -> u32 {
+        let first = self.len();
+        // (element, parent, index within parent, offset)
+        let mut stack = vec![(root, parent, index, offset)];
+        let mut last_children = vec![];
+        while let Some((element, parent, index, offset)) = stack.pop() {
+            let id = u32::try_from(self.len()).ok().filter(|&id| id != NONE);
+            let id = id.expect("more than u32::MAX - 1 elements");
+            self.kinds.push(element.kind());
+            self.ranges.push(TextRange::at(offset, element.len()));
+            self.is_token.push(element.is_token());
+            self.parents.push(parent);
+            self.first_children.push(NONE);
+            self.next_siblings.push(NONE);
+            self.indices.push(index);
+            self.live.push(true);
+            last_children.push(NONE);
+
+            if id as usize != first {
+                let last_child = &mut last_children[parent as usize - first];
+                match *last_child {
+                    NONE => self.first_children[parent as usize] = id,
+                    sibling => self.next_siblings[sibling as usize] = id,
+                }
+                *last_child = id;
+            }
+
+            if let NodeOrToken::Node(node) = element {
+                // push in reverse, so children are visited in order
+                let children = ArcBorrow::downgrade(node).children().with_offsets().enumerate();
+                stack.extend(children.rev().map(|(index, (child_offset, child))| {
+                    (child, id, index as u32, offset + child_offset)
+                }));
+            }
+        }
+        first as u32
+    }
+
+    /// Update this flat tree to a new version of its tree, such as the result of an edit.
+    ///
+    /// The new tree is diffed against the old tree: elements shared between them
+    /// keep their ids, as do nodes of the same kind in the same place, whose
+    /// children are diffed in turn. Only the remaining elements are flattened anew,
+    /// so analyses keyed by id can be reused for the unaffected elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the trees have `u32::MAX` or more elements between them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green::{self, edit::Editor, flat::FlatTree}, Kind, TextRange};
+    /// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+    /// let mut builder = green::TreeBuilder::new();
+    /// let tree = builder
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "a")
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "b")
+    ///     .finish_node()
+    ///     .finish_node()
+    ///     .finish();
+    /// let mut flat = FlatTree::new(tree.clone());
+    ///
+    /// let c = builder.builder().token(ATOM, "cc");
+    /// let mut editor = Editor::new(tree);
+    /// editor.insert_after(&[0], c);
+    /// let patch = flat.update(editor.commit(builder.builder()).into_root());
+    ///
+    /// assert_eq!((patch.changed(), patch.added(), patch.removed()), (&[0][..], &[4][..], &[][..]));
+    /// assert_eq!(flat.children(0).collect::<Vec<_>>(), [1, 4, 2]);
+    /// assert_eq!(flat.element(4).unwrap_token().text(), "cc");
+    /// assert_eq!(flat.ranges()[3], TextRange::new(3.into(), 4.into()));
+    /// ```
+    pub fn update(&mut self, root: Arc<Node>) -> FlatPatch {
+        let old_root = mem::replace(&mut self.root, root);
+        let new_root = Arc::clone(&self.root);
+        let mut patch = FlatPatch::default();
+        let old = NodeOrToken::Node(ArcBorrow::from(&old_root));
+        let new = NodeOrToken::Node(ArcBorrow::from(&new_root));
+        self.kinds[0] = new_root.kind();
+        self.patch(0, old, new, 0.into(), &mut patch);
+        patch
+    }
+
+    /// Patch the live element `id`, `old`, to `new` at `offset`.
+    /// The elements must both be nodes or both be tokens, of the same kind.
+    fn patch(
+        &mut self,
+        id: u32,
+        old: ElementRef<'_>,
+        new: ElementRef<'_>,
+        offset: TextSize,
+        patch: &mut FlatPatch,
+    ) {
+        if same_element(old, new) {
+            self.shift(id, offset);
+            return;
+        }
+        self.ranges[id as usize] = TextRange::at(offset, new.len());
+        patch.changed.push(id);
+        let (old, new) = match (old, new) {
+            (NodeOrToken::Node(old), NodeOrToken::Node(new)) => {
+                (ArcBorrow::downgrade(old), ArcBorrow::downgrade(new))
+            }
+            _ => return,
+        };
+
+        let old_children: Vec<_> = self.children(id).zip(old.children()).collect();
+        let new_children: Vec<_> = new.children().with_offsets().collect();
+        let prefix = old_children
+            .iter()
+            .zip(&new_children)
+            .take_while(|((_, old), (_, new))| same_element(*old, *new))
+            .count();
+        let suffix = old_children[prefix..]
+            .iter()
+            .rev()
+            .zip(new_children[prefix..].iter().rev())
+            .take_while(|((_, old), (_, new))| same_element(*old, *new))
+            .count();
+        let old_middle = &old_children[prefix..old_children.len() - suffix];
+        let new_middle = prefix..new_children.len() - suffix;
+
+        // match the old children to the new children they are kept as
+        let mut matches: Vec<Option<usize>> = vec![None; new_children.len()];
+        let shift = old_children.len().wrapping_sub(new_children.len());
+        for (index, old) in matches.iter_mut().enumerate() {
+            if index < prefix {
+                *old = Some(index);
+            } else if index >= new_middle.end {
+                *old = Some(index.wrapping_add(shift));
+            }
+        }
+        // in the middle, first keep elements that were moved, in order
+        let mut unmatched: Vec<usize> = (prefix..prefix + old_middle.len()).collect();
+        let mut next_old = 0;
+        for index in new_middle.clone() {
+            let moved = unmatched[next_old..]
+                .iter()
+                .position(|&old| same_element(old_children[old].1, new_children[index].1));
+            if let Some(position) = moved {
+                matches[index] = Some(unmatched.remove(next_old + position));
+                next_old += position;
+            }
+        }
+        // then pair up the rest, if it's one-to-one, as nodes edited in place
+        let unmatched_new: Vec<usize> =
+            new_middle.filter(|&index| matches[index].is_none()).collect();
+        if unmatched_new.len() == unmatched.len() {
+            for (&index, &old) in unmatched_new.iter().zip(&unmatched) {
+                let (old_child, new_child) = (old_children[old].1, new_children[index].1);
+                if old_child.kind() == new_child.kind()
+                    && old_child.is_token() == new_child.is_token()
+                {
+                    matches[index] = Some(old);
+                }
+            }
+        }
+
+        let mut kept = vec![false; old_children.len()];
+        let mut children = Vec::with_capacity(new_children.len());
+        for (index, &(child_offset, new_child)) in new_children.iter().enumerate() {
+            let child = match matches[index] {
+                Some(old) => {
+                    let (child, old_child) = old_children[old];
+                    kept[old] = true;
+                    self.parents[child as usize] = id;
+                    self.indices[child as usize] = index as u32;
+                    self.patch(child, old_child, new_child, offset + child_offset, patch);
+                    child
+                }
+                None => {
+                    let child = self.flatten(new_child, id, index as u32, offset + child_offset);
+                    patch.added.extend(child..self.len() as u32);
+                    child
+                }
+            };
+            children.push(child);
+        }
+        for (&(child, _), kept) in old_children.iter().zip(kept) {
+            if !kept {
+                self.remove(child, patch);
+            }
+        }
+
+        self.first_children[id as usize] = children.first().copied().unwrap_or(NONE);
+        for pair in children.windows(2) {
+            self.next_siblings[pair[0] as usize] = pair[1];
+        }
+        if let Some(&last) = children.last() {
+            self.next_siblings[last as usize] = NONE;
+        }
+    }
+
+    /// Move the subtree at `id` to start at `offset`.
+    fn shift(&mut self, id: u32, offset: TextSize) {
+        let old_offset = self.ranges[id as usize].start();
+        if offset == old_offset {
+            return;
+        }
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let range = &mut self.ranges[id as usize];
+            *range = TextRange::at(range.start() - old_offset + offset, range.len());
+            stack.extend(self.children(id));
+        }
+    }
+
+    /// Mark the subtree at `id` as removed.
+    fn remove(&mut self, id: u32, patch: &mut FlatPatch) {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            self.live[id as usize] = false;
+            patch.removed.push(id);
+            stack.extend(self.children(id));
+        }
+    }
+
+    /// The flattened root.
+    pub fn root(&self) -> &Arc<Node> {
+        &self.root
+    }
+
+    /// The number of ids, including ids which are no longer [live](FlatTree::is_live).
+    pub fn len(&self) -> usize {
+        self.kinds.len()
+    }
+
+    /// Whether the tree has no elements, which is never the case, as the root is an element.
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    /// Whether `id` is an element of the current tree, rather than one removed by an update.
+    ///
+    /// The entries of an id which is no longer live are stale.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` was never an element of the tree.
+    pub fn is_live(&self, id: u32) -> bool {
+        self.live[id as usize]
+    }
+
+    /// The kind of each element, by id.
+    pub fn kinds(&self) -> &[Kind] {
+        &self.kinds
+    }
+
+    /// The text range of each element within the root, by id.
+    pub fn ranges(&self) -> &[TextRange] {
+        &self.ranges
+    }
+
+    /// Whether the element is a token.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not an element of the tree.
+    pub fn is_token(&self, id: u32) -> bool {
+        self.is_token[id as usize]
+    }
+
+    /// The parent of the element, or `None` for the root.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not an element of the tree.
+    pub fn parent(&self, id: u32) -> Option<u32> {
+        link(self.parents[id as usize])
+    }
+
+    /// The first child of the element, or `None` if it has no children.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not an element of the tree.
+    pub fn first_child(&self, id: u32) -> Option<u32> {
+        link(self.first_children[id as usize])
+    }
+
+    /// The next sibling of the element, or `None` if it is the last child of its parent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not an element of the tree.
+    pub fn next_sibling(&self, id: u32) -> Option<u32> {
+        link(self.next_siblings[id as usize])
+    }
+
+    /// The children of the element, in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not an element of the tree.
+    pub fn children(&self, id: u32) -> impl '_ + Iterator<Item = u32> {
+        iter::successors(self.first_child(id), move |&child| self.next_sibling(child))
+    }
+
+    /// The path of the element from the root, as the index of each child on the way.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not an element of the tree.
+    pub fn path(&self, id: u32) -> Vec<usize> {
+        let mut path: Vec<usize> = iter::successors(Some(id), |&id| self.parent(id))
+            .map(|id| self.indices[id as usize] as usize)
+            .collect();
+        path.pop(); // the root has no index
+        path.reverse();
+        path
+    }
+
+    /// The id of the element at `path` from the root, or `None` if there is no such element.
+    pub fn id_at_path(&self, path: &[usize]) -> Option<u32> {
+        path.iter().try_fold(0, |id, &index| self.children(id).nth(index))
+    }
+
+    /// The element with the given id.
+    ///
+    /// This descends from the root along the [`path`](FlatTree::path) of the element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not an element of the tree.
+    pub fn element(&self, id: u32) -> ElementRef<'_> {
+        let mut element = NodeOrToken::Node(ArcBorrow::from(&self.root));
+        for index in self.path(id) {
+            let node = ArcBorrow::downgrade(element.into_node().unwrap());
+            element = node.children().get(index).unwrap();
+        }
+        element
+    }
+}
@@ -0,0 +1,158 @@
+use {
+    crate::{
+        green::{Node, Token},
+        ArcBorrow, TextSize,
+    },
+    std::{iter::FromIterator, sync::Arc},
+};
+
+/// A document made of multiple green trees (chunks) laid end to end.
+///
+/// Offsets into a forest address the concatenated text of all of its chunks.
+/// Replacing one chunk only shifts the offsets of the chunks after it,
+/// so a huge document can be reparsed one chunk at a time.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, Forest}, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let mut builder = green::TreeBuilder::new();
+/// let ab = builder.start_node(LIST).token(ATOM, "ab").finish_node().finish();
+/// let cd = builder.start_node(LIST).token(ATOM, "cd").finish_node().finish();
+///
+/// let mut forest: Forest = vec![ab.clone(), cd].into_iter().collect();
+/// assert_eq!(forest.len(), 4.into());
+/// assert_eq!(forest.chunk_at_offset(2.into()), Some(1));
+///
+/// let (offset, token) = forest.token_at_offset(3.into()).unwrap();
+/// assert_eq!((offset, token.text()), (2.into(), "cd"));
+///
+/// forest.replace_chunk(0, ab.clone());
+/// forest.insert_chunk(0, ab);
+/// assert_eq!(forest.len(), 6.into());
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Forest {
+    chunks: Vec<(TextSize, Arc<Node>)>, // NB: (start offset, chunk)
+}
+
+impl Forest {
+    /// Create a new, empty forest.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The length of text of all chunks.
+    pub fn len(&self) -> TextSize {
+        self.chunks.last().map_or(0.into(), |(offset, chunk)| offset + chunk.len())
+    }
+
+    /// Whether this forest has no text.
+    ///
+    /// Note that a forest without text may still have chunks.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0.into()
+    }
+
+    /// The number of chunks in this forest.
+    pub fn chunks_len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// The chunk at `index`.
+    pub fn chunk(&self, index: usize) -> Option<&Arc<Node>> {
+        self.chunks.get(index).map(|(_, chunk)| chunk)
+    }
+
+    /// The chunks of this forest, with the offset each starts at.
+    pub fn chunks(&self) -> impl '_ + DoubleEndedIterator<Item = (TextSize, &Arc<Node>)> {
+        self.chunks.iter().map(|(offset, chunk)| (*offset, chunk))
+    }
+
+    /// The index of the chunk that contains the given offset.
+    ///
+    /// Empty chunks never contain an offset.
+    /// Returns `None` if the offset is not within this forest.
+    pub fn chunk_at_offset(&self, offset: TextSize) -> Option<usize> {
+        if offset >= self.len() {
+            return None;
+        }
+        let index = self.chunks.partition_point(|&(start, _)| start <= offset);
+        Some(index - 1)
+    }
+
+    /// The token containing the given offset, and the offset of its start.
+    ///
+    /// Returns `None` if the offset is not within this forest.
+    pub fn token_at_offset(&self, offset: TextSize) -> Option<(TextSize, ArcBorrow<'_, Token>)> {
+        let (start, chunk) = &self.chunks[self.chunk_at_offset(offset)?];
+        let (token_offset, token) = chunk.token_at_offset(offset - start)?;
+        Some((start + token_offset, token))
+    }
+
+    /// Add a chunk to the end of this forest.
+    pub fn push_chunk(&mut self, chunk: Arc<Node>) {
+        self.chunks.push((self.len(), chunk));
+    }
+
+    /// Insert a chunk at `index`, shifting the chunks after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > chunks_len`.
+    pub fn insert_chunk(&mut self, index: usize, chunk: Arc<Node>) {
+        self.chunks.insert(index, (0.into(), chunk));
+        self.fix_offsets(index);
+    }
+
+    /// Remove and return the chunk at `index`, shifting the chunks after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove_chunk(&mut self, index: usize) -> Arc<Node> {
+        let (_, chunk) = self.chunks.remove(index);
+        self.fix_offsets(index);
+        chunk
+    }
+
+    /// Replace the chunk at `index`, returning the old chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn replace_chunk(&mut self, index: usize, chunk: Arc<Node>) -> Arc<Node> {
+        let old = std::mem::replace(&mut self.chunks[index].1, chunk);
+        if old.len() != self.chunks[index].1.len() {
+            self.fix_offsets(index + 1);
+        }
+        old
+    }
+
+    /// Recalculate the start offsets of the chunks from `index` on.
+    fn fix_offsets(&mut self, index: usize) {
+        let mut offset = match index.checked_sub(1) {
+            Some(prev) => self.chunks[prev].0 + self.chunks[prev].1.len(),
+            None => 0.into(),
+        };
+        for (start, chunk) in &mut self.chunks[index..] {
+            *start = offset;
+            offset += chunk.len();
+        }
+    }
+}
+
+impl FromIterator<Arc<Node>> for Forest {
+    fn from_iter<I: IntoIterator<Item = Arc<Node>>>(iter: I) -> Self {
+        let mut forest = Forest::new();
+        iter.into_iter().for_each(|chunk| forest.push_chunk(chunk));
+        forest
+    }
+}
+
+impl Extend<Arc<Node>> for Forest {
+    fn extend<I: IntoIterator<Item = Arc<Node>>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|chunk| self.push_chunk(chunk));
+    }
+}
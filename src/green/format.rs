@@ -0,0 +1,187 @@
+//! Primitives for rewriting the whitespace between tokens.
+//!
+//! Because green nodes are deduplicated, rewriting whitespace by splicing
+//! children shares every untouched subtree with the original tree, and
+//! is much cheaper than patching the source text and reparsing it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use sorbus::{green::{self, format::Spacer}, Kind};
+//! const WS: Kind = Kind(0);
+//! const ATOM: Kind = Kind(1);
+//! const PLUS: Kind = Kind(2);
+//! const EXPR: Kind = Kind(3);
+//!
+//! struct SpaceAroundPlus;
+//! impl Spacer for SpaceAroundPlus {
+//!     fn whitespace(&self) -> Kind {
+//!         WS
+//!     }
+//!
+//!     fn spacing(&self, left: Kind, right: Kind) -> Option<&str> {
+//!         if left == PLUS || right == PLUS {
+//!             Some(" ")
+//!         } else {
+//!             None
+//!         }
+//!     }
+//! }
+//!
+//! let mut builder = green::TreeBuilder::new();
+//! let tree = builder
+//!     .start_node(EXPR)
+//!     .token(ATOM, "1")
+//!     .token(PLUS, "+")
+//!     .token(WS, "   ")
+//!     .token(ATOM, "2")
+//!     .finish_node()
+//!     .finish();
+//! let formatted = green::format::respace(builder.builder(), &tree, &SpaceAroundPlus);
+//! let text: String = formatted.children().map(|el| el.unwrap_token().text().to_owned()).collect();
+//! assert_eq!(text, "1 + 2");
+//! ```
+
+use {
+    crate::{
+        green::{Builder, ElementRef, Node, Token},
+        ArcBorrow, Kind, NodeOrToken,
+    },
+    std::sync::Arc,
+};
+
+/// Rules for the whitespace required between two adjacent tokens.
+pub trait Spacer {
+    /// The kind of the whitespace tokens managed by this spacer.
+    fn whitespace(&self) -> Kind;
+
+    /// The whitespace required between a token of kind `left`
+    /// and the following token of kind `right`.
+    ///
+    /// Returning `None` leaves the existing whitespace untouched,
+    /// and returning `Some("")` removes any whitespace between them.
+    fn spacing(&self, left: Kind, right: Kind) -> Option<&str>;
+}
+
+type OwnedElement = NodeOrToken<Arc<Node>, Arc<Token>>;
+
+/// Rewrite the whitespace between the tokens of a tree according to a [`Spacer`].
+///
+/// Whitespace tokens between two significant tokens are replaced by a
+/// single token placed between the siblings in the lowest common ancestor,
+/// even if it was previously attached to the edge of a child node.
+/// Whitespace before the first and after the last token is left untouched.
+pub fn respace(builder: &mut Builder, node: &Node, spacer: &impl Spacer) -> Arc<Node> {
+    Respacer { builder, spacer }.respace(node)
+}
+
+struct Respacer<'a, S> {
+    builder: &'a mut Builder,
+    spacer: &'a S,
+}
+
+impl<S: Spacer> Respacer<'_, S> {
+    fn is_whitespace(&self, el: &OwnedElement) -> bool {
+        matches!(el, NodeOrToken::Token(token) if token.kind() == self.spacer.whitespace())
+    }
+
+    /// The first (or last) non-whitespace token kind in this element.
+    fn edge_kind(&self, el: NodeOrToken<&Node, &Token>, last: bool) -> Option<Kind> {
+        match el {
+            NodeOrToken::Token(token) if token.kind() == self.spacer.whitespace() => None,
+            NodeOrToken::Token(token) => Some(token.kind()),
+            NodeOrToken::Node(node) => {
+                let edge_kind = |child: ElementRef<'_>| self.edge_kind(child.as_deref(), last);
+                if last {
+                    node.children().rev().find_map(edge_kind)
+                } else {
+                    node.children().find_map(edge_kind)
+                }
+            }
+        }
+    }
+
+    /// Remove the whitespace on the leading (or trailing) edge of this element.
+    fn strip_edge(&mut self, el: OwnedElement, last: bool) -> OwnedElement {
+        let node = match el {
+            NodeOrToken::Node(node) => node,
+            token => return token,
+        };
+        let mut children: Vec<OwnedElement> = node.children().map(ElementRef::upgrade).collect();
+        loop {
+            let edge = if last { children.last() } else { children.first() };
+            match edge {
+                Some(el) if self.is_whitespace(el) => {
+                    if last {
+                        children.pop();
+                    } else {
+                        children.remove(0);
+                    }
+                }
+                Some(NodeOrToken::Node(_)) => {
+                    let index = if last { children.len() - 1 } else { 0 };
+                    let edge = children[index].clone();
+                    children[index] = self.strip_edge(edge, last);
+                    break;
+                }
+                _ => break,
+            }
+        }
+        NodeOrToken::Node(self.builder.node_like(&node, children))
+    }
+
+    fn respace(&mut self, node: &Node) -> Arc<Node> {
+        let mut out: Vec<OwnedElement> = Vec::with_capacity(node.children_len());
+        let mut pending_ws: Vec<OwnedElement> = vec![];
+        let mut last_significant: Option<(usize, Kind)> = None;
+
+        for child in node.children() {
+            let child = match child {
+                NodeOrToken::Node(node) => NodeOrToken::Node(self.respace(&node)),
+                NodeOrToken::Token(token) => NodeOrToken::Token(ArcBorrow::upgrade(token)),
+            };
+            if self.is_whitespace(&child) {
+                pending_ws.push(child);
+                continue;
+            }
+            let first = match self.edge_kind(child.as_deref(), false) {
+                Some(kind) => kind,
+                None => {
+                    // no significant tokens, so it doesn't participate in spacing
+                    out.append(&mut pending_ws);
+                    out.push(child);
+                    continue;
+                }
+            };
+
+            let spacing = last_significant.and_then(|(_, left)| self.spacer.spacing(left, first));
+            let child = match (spacing, last_significant) {
+                (Some(spacing), Some((index, _))) => {
+                    pending_ws.clear();
+                    let mut between = out.split_off(index + 1);
+                    between.retain(|el| !self.is_whitespace(el));
+                    let left = out.pop().unwrap();
+                    let left = self.strip_edge(left, true);
+                    out.push(left);
+                    out.append(&mut between);
+                    if !spacing.is_empty() {
+                        let ws = self.builder.token(self.spacer.whitespace(), spacing);
+                        out.push(NodeOrToken::Token(ws));
+                    }
+                    self.strip_edge(child, false)
+                }
+                _ => {
+                    out.append(&mut pending_ws);
+                    child
+                }
+            };
+
+            let last = self.edge_kind(child.as_deref(), true).unwrap_or(first);
+            out.push(child);
+            last_significant = Some((out.len() - 1, last));
+        }
+
+        out.append(&mut pending_ws);
+        self.builder.node_like(node, out)
+    }
+}
@@ -0,0 +1,138 @@
+use {
+    crate::{
+        green::{edit::EditResult, Node},
+        TextSize,
+    },
+    std::{collections::VecDeque, convert::TryFrom, sync::Arc},
+};
+
+/// A bounded history of edited roots, supporting undo and redo.
+///
+/// Green trees are persistent, so keeping previous versions around only
+/// costs the parts of each version which are not shared with the others.
+/// Each version is numbered by how many edits led to it from the start of the
+/// history, and offsets can be mapped between any retained versions. Note that
+/// the numbers of undone versions are reused by pushing edits after the undo.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, edit, History}, Kind, TextRange};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let mut builder = green::TreeBuilder::new();
+/// let tree = builder.start_node(LIST).token(ATOM, "ab").finish_node().finish();
+/// let mut history = History::new(tree.clone(), 10);
+///
+/// let insert = [(TextRange::empty(0.into()), "xy")];
+/// let edited = edit::apply_all(builder.builder(), &tree, &insert).unwrap();
+/// let version = history.push(edited);
+/// assert_eq!(history.map_offset(1.into(), 0, version), Some(3.into()));
+/// assert_eq!(history.map_offset(3.into(), version, 0), Some(1.into()));
+///
+/// assert_eq!(history.undo(), Some(&tree));
+/// assert_eq!(history.version(), 0);
+/// assert!(history.redo().is_some());
+/// assert_eq!(history.version(), version);
+/// ```
+#[derive(Debug, Clone)]
+pub struct History {
+    base: Arc<Node>,
+    edits: VecDeque<EditResult>, // NB: edits[i] edits version i (from base) into version i + 1
+    current: usize,
+    base_version: u64,
+    capacity: usize,
+}
+
+impl History {
+    /// Start a history at `root`, keeping at most `capacity` edits to undo.
+    pub fn new(root: Arc<Node>, capacity: usize) -> Self {
+        History { base: root, edits: VecDeque::new(), current: 0, base_version: 0, capacity }
+    }
+
+    /// The current root.
+    pub fn root(&self) -> &Arc<Node> {
+        self.root_at_index(self.current)
+    }
+
+    /// The number of the current version.
+    pub fn version(&self) -> u64 {
+        self.base_version + self.current as u64
+    }
+
+    /// The root at `version`, if it is still retained.
+    pub fn root_at(&self, version: u64) -> Option<&Arc<Node>> {
+        self.index(version).map(|index| self.root_at_index(index))
+    }
+
+    /// Record an edit of the current root, returning the new version.
+    ///
+    /// This discards any undone versions, which can no longer be redone,
+    /// and the oldest version if there are more than `capacity` edits to undo.
+    pub fn push(&mut self, edit: EditResult) -> u64 {
+        self.edits.truncate(self.current);
+        self.edits.push_back(edit);
+        self.current += 1;
+        while self.edits.len() > self.capacity {
+            self.base = self.edits.pop_front().unwrap().into_root();
+            self.base_version += 1;
+            self.current -= 1;
+        }
+        self.version()
+    }
+
+    /// Whether there is a version to undo to.
+    pub fn can_undo(&self) -> bool {
+        self.current > 0
+    }
+
+    /// Whether there is a version to redo to.
+    pub fn can_redo(&self) -> bool {
+        self.current < self.edits.len()
+    }
+
+    /// Go back to the previous version, returning its root.
+    pub fn undo(&mut self) -> Option<&Arc<Node>> {
+        if !self.can_undo() {
+            return None;
+        }
+        self.current -= 1;
+        Some(self.root())
+    }
+
+    /// Go forward to the next version after an undo, returning its root.
+    pub fn redo(&mut self) -> Option<&Arc<Node>> {
+        if !self.can_redo() {
+            return None;
+        }
+        self.current += 1;
+        Some(self.root())
+    }
+
+    /// Map an offset in the text of version `from` to the text of version `to`,
+    /// through all of the edits between them, in either direction.
+    ///
+    /// Returns `None` if either version is not retained.
+    pub fn map_offset(&self, mut offset: TextSize, from: u64, to: u64) -> Option<TextSize> {
+        let (from, to) = (self.index(from)?, self.index(to)?);
+        for index in from..to {
+            offset = self.edits[index].map_offset(offset);
+        }
+        for index in (to..from).rev() {
+            let old_root = Arc::clone(self.root_at_index(index));
+            offset = self.edits[index].invert(old_root).map_offset(offset);
+        }
+        Some(offset)
+    }
+
+    fn index(&self, version: u64) -> Option<usize> {
+        let index = usize::try_from(version.checked_sub(self.base_version)?).ok()?;
+        Some(index).filter(|&index| index <= self.edits.len())
+    }
+
+    fn root_at_index(&self, index: usize) -> &Arc<Node> {
+        match index {
+            0 => &self.base,
+            _ => self.edits[index - 1].root(),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Dense ids for the elements of a tree.
+//!
+//! Analyses which compute a fact for each element of a tree can store the facts
+//! in plain arrays indexed by id, rather than in hash maps keyed by pointer.
+
+use {
+    crate::{
+        green::{ElementRef, Node, Token},
+        ArcBorrow, NodeOrToken,
+    },
+    std::{collections::HashMap, convert::TryFrom, sync::Arc},
+};
+
+/// Identity map assigning dense `u32` ids to the distinct elements of a tree.
+///
+/// Elements are distinct by identity, so an element shared by several places
+/// in the tree (as deduplicated elements are) has only one id. Ids are assigned
+/// in preorder of first occurrence, so the root has the id 0.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, ids::IdMap}, Kind, NodeOrToken};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let tree = green::TreeBuilder::new()
+///     .start_node(LIST)
+///     .token(ATOM, "a")
+///     .token(ATOM, "b")
+///     .token(ATOM, "a")
+///     .finish_node()
+///     .finish();
+///
+/// let ids = IdMap::new(tree.clone());
+/// assert_eq!(ids.len(), 3);
+/// assert_eq!(ids.id_of(NodeOrToken::Node(&*tree)), Some(0));
+/// let a = ids.id_of(tree.children().next().unwrap().as_deref()).unwrap();
+/// assert_eq!(ids.id_of(tree.children().last().unwrap().as_deref()), Some(a));
+/// assert_eq!(ids.get(a).unwrap().unwrap_token().text(), "a");
+/// ```
+#[derive(Debug, Clone)]
+pub struct IdMap {
+    elements: Vec<NodeOrToken<Arc<Node>, Arc<Token>>>,
+    ids: HashMap<usize, u32>, // keyed by element address
+}
+
+fn key(element: NodeOrToken<&Node, &Token>) -> usize {
+    element
+        .map(|node| node as *const Node as *const u8, |token| token as *const Token as *const u8)
+        .flatten() as usize
+}
+
+impl IdMap {
+    /// Assign ids to the distinct elements of a tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree has more than `u32::MAX` distinct elements.
+    pub fn new(root: Arc<Node>) -> Self {
+        let mut map = IdMap { elements: vec![], ids: HashMap::new() };
+        let mut stack = vec![NodeOrToken::Node(root)];
+        while let Some(element) = stack.pop() {
+            if map.ids.contains_key(&key(element.as_deref())) {
+                continue;
+            }
+            map.insert(element.clone());
+            if let NodeOrToken::Node(node) = element {
+                // push in reverse, so children are visited in order
+                let children = node.children().rev().map(ElementRef::upgrade);
+                stack.extend(children);
+            }
+        }
+        map
+    }
+
+    fn insert(&mut self, element: NodeOrToken<Arc<Node>, Arc<Token>>) {
+        let id = u32::try_from(self.elements.len()).expect("more than u32::MAX elements");
+        self.ids.insert(key(element.as_deref()), id);
+        self.elements.push(element);
+    }
+
+    /// The id of an element of the tree, or `None` if it is not in the tree.
+    pub fn id_of<'a>(&self, element: impl Into<NodeOrToken<&'a Node, &'a Token>>) -> Option<u32> {
+        self.ids.get(&key(element.into())).copied()
+    }
+
+    /// The element with the given id.
+    pub fn get(&self, id: u32) -> Option<ElementRef<'_>> {
+        let element = self.elements.get(id as usize)?;
+        Some(element.as_ref().map(ArcBorrow::from, ArcBorrow::from))
+    }
+
+    /// The number of distinct elements in the tree.
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Whether the map has no ids, which is never the case, as the root always has one.
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+}
@@ -0,0 +1,135 @@
+use {
+    crate::{green::Node, NodeOrToken},
+    std::{
+        cmp::Ordering,
+        collections::hash_map::DefaultHasher,
+        fmt,
+        hash::{Hash, Hasher},
+        ops::Deref,
+        sync::Arc,
+    },
+};
+
+/// A tree root with cheap equality, for storing trees in query databases.
+///
+/// Incremental computation frameworks such as salsa compare the values
+/// of queries to decide whether to recompute dependent queries. Comparing
+/// trees structurally on every revision visits every element, so this wrapper
+/// compares pointers first, then a content hash computed once on creation,
+/// and only compares structurally if both of those are inconclusive.
+///
+/// The content hash is deterministic, depending only on the kinds and text
+/// of the tree, so it is the same across runs and builders.
+///
+/// # Examples
+///
+/// A memoized pass which is only rerun when the tree actually changes:
+///
+/// ```rust
+/// # use sorbus::{green::{self, InternedRoot}, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// use std::collections::HashMap;
+///
+/// fn parse(builder: &mut green::TreeBuilder, text: &str) -> InternedRoot {
+///     builder.start_node(LIST);
+///     for word in text.split_whitespace() {
+///         builder.token(ATOM, word);
+///     }
+///     InternedRoot::new(builder.finish_node().finish())
+/// }
+///
+/// let mut builder = green::TreeBuilder::new();
+/// let mut word_counts = HashMap::new();
+/// let mut runs = 0;
+/// for revision in &["a b c", "a  b c", "a b c d"] {
+///     let root = parse(&mut builder, revision);
+///     word_counts.entry(root.clone()).or_insert_with(|| {
+///         runs += 1;
+///         root.children_len()
+///     });
+/// }
+/// // the second revision only changed whitespace, and produced the same tree
+/// assert_eq!(runs, 2);
+/// ```
+#[derive(Clone)]
+pub struct InternedRoot {
+    node: Arc<Node>,
+    hash: u64,
+}
+
+impl InternedRoot {
+    /// Wrap a tree root, computing its content hash.
+    pub fn new(node: Arc<Node>) -> Self {
+        let mut hasher = DefaultHasher::new();
+        content_hash(&node, &mut hasher);
+        InternedRoot { node, hash: hasher.finish() }
+    }
+
+    /// The wrapped root.
+    pub fn node(&self) -> &Arc<Node> {
+        &self.node
+    }
+
+    /// Take the wrapped root.
+    pub fn into_node(self) -> Arc<Node> {
+        self.node
+    }
+
+    /// The content hash of the tree.
+    pub fn content_hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+fn content_hash(node: &Node, state: &mut impl Hasher) {
+    node.kind().hash(state);
+    node.children_len().hash(state);
+    for child in node.children() {
+        match child {
+            NodeOrToken::Node(node) => content_hash(&node, state),
+            NodeOrToken::Token(token) => {
+                token.kind().hash(state);
+                token.is_binary().hash(state);
+                token.bytes().hash(state);
+            }
+        }
+    }
+}
+
+impl Deref for InternedRoot {
+    type Target = Node;
+    fn deref(&self) -> &Node {
+        &self.node
+    }
+}
+
+impl PartialEq for InternedRoot {
+    fn eq(&self, other: &Self) -> bool {
+        // NB: Node's Eq compares children by identity; its Ord compares them structurally
+        Arc::ptr_eq(&self.node, &other.node)
+            || self.hash == other.hash && self.node.cmp(&other.node) == Ordering::Equal
+    }
+}
+
+impl Eq for InternedRoot {}
+
+impl Hash for InternedRoot {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash)
+    }
+}
+
+impl fmt::Debug for InternedRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InternedRoot")
+            .field(&format_args!("{:?}@{:?}", self.node.kind(), self.node.len()))
+            .field(&format_args!("{:#018x}", self.hash))
+            .finish()
+    }
+}
+
+impl From<Arc<Node>> for InternedRoot {
+    fn from(node: Arc<Node>) -> Self {
+        InternedRoot::new(node)
+    }
+}
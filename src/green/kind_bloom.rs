@@ -0,0 +1,172 @@
+use {
+    crate::{green::Node, Kind, NodeOrToken},
+    std::{collections::HashMap, fmt, ops::Deref, sync::Arc},
+};
+
+/// A 64-bit bloom filter of the kinds in a tree.
+///
+/// Each kind is hashed to one of 64 bits. A clear bit means that no element
+/// of that kind is in the tree; a set bit means that one might be, either
+/// because it is or because another kind in the tree hashed to the same bit.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::KindBloom, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let mut bloom = KindBloom::new();
+/// bloom.insert(ATOM);
+/// assert!(bloom.maybe_contains(ATOM));
+/// assert!(KindBloom::new().union(bloom).maybe_contains(ATOM));
+/// assert!(!KindBloom::new().maybe_contains(LIST));
+/// ```
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct KindBloom(u64);
+
+impl KindBloom {
+    /// An empty filter, which contains no kinds.
+    pub const fn new() -> Self {
+        KindBloom(0)
+    }
+
+    fn bit(kind: Kind) -> u64 {
+        // Fibonacci hashing: the top six bits of the product pick the bit
+        1 << (u64::from(kind.0).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 58)
+    }
+
+    /// Add a kind to the filter.
+    pub fn insert(&mut self, kind: Kind) {
+        self.0 |= Self::bit(kind);
+    }
+
+    /// Whether the filter might contain a kind.
+    ///
+    /// If this returns `false`, the kind is definitely not in the filter.
+    pub fn maybe_contains(self, kind: Kind) -> bool {
+        self.0 & Self::bit(kind) != 0
+    }
+
+    /// A filter containing the kinds of both filters.
+    pub fn union(self, other: KindBloom) -> Self {
+        KindBloom(self.0 | other.0)
+    }
+
+    /// Whether no kinds have been added to the filter.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The raw bits of the filter.
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Compute the filter of the kinds of a node and all of its descendants.
+    ///
+    /// Subtrees shared within the tree are only visited once.
+    pub fn of(node: &Node) -> Self {
+        fn visit(node: &Node, seen: &mut HashMap<*const Node, KindBloom>) -> KindBloom {
+            if let Some(&bloom) = seen.get(&(node as *const Node)) {
+                return bloom;
+            }
+            let mut bloom = KindBloom::new();
+            bloom.insert(node.kind());
+            for child in node.children() {
+                match child {
+                    NodeOrToken::Node(node) => bloom = bloom.union(visit(&node, seen)),
+                    NodeOrToken::Token(token) => bloom.insert(token.kind()),
+                }
+            }
+            seen.insert(node as *const Node, bloom);
+            bloom
+        }
+        visit(node, &mut HashMap::new())
+    }
+}
+
+impl fmt::Debug for KindBloom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("KindBloom").field(&format_args!("{:#066b}", self.0)).finish()
+    }
+}
+
+/// A tree root with a [`KindBloom`] of its kinds, for skipping trees cheaply.
+///
+/// Computing the filter visits the whole tree once, on creation. After
+/// that, checking whether the tree might contain a kind is a single bit test,
+/// so tools running many rules over many files, such as a lint driver, can
+/// skip the files which definitely lack the kinds a rule is triggered by.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, BloomRoot}, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1); const STRING: Kind = Kind(2);
+/// let root = BloomRoot::new(
+///     green::TreeBuilder::new()
+///         .start_node(LIST)
+///         .token(ATOM, "a")
+///         .finish_node()
+///         .finish(),
+/// );
+/// assert!(root.maybe_contains_kind(ATOM));
+/// assert!(root.maybe_contains_kind(LIST));
+/// assert!(!root.maybe_contains_kind(STRING));
+/// ```
+#[derive(Clone)]
+pub struct BloomRoot {
+    node: Arc<Node>,
+    bloom: KindBloom,
+}
+
+impl BloomRoot {
+    /// Wrap a tree root, computing the filter of its kinds.
+    pub fn new(node: Arc<Node>) -> Self {
+        let bloom = KindBloom::of(&node);
+        BloomRoot { node, bloom }
+    }
+
+    /// The wrapped root.
+    pub fn node(&self) -> &Arc<Node> {
+        &self.node
+    }
+
+    /// Take the wrapped root.
+    pub fn into_node(self) -> Arc<Node> {
+        self.node
+    }
+
+    /// The filter of the kinds in the tree.
+    pub fn kind_bloom(&self) -> KindBloom {
+        self.bloom
+    }
+
+    /// Whether the tree might contain an element of this kind.
+    ///
+    /// If this returns `false`, the tree definitely doesn't.
+    pub fn maybe_contains_kind(&self, kind: Kind) -> bool {
+        self.bloom.maybe_contains(kind)
+    }
+}
+
+impl Deref for BloomRoot {
+    type Target = Node;
+    fn deref(&self) -> &Node {
+        &self.node
+    }
+}
+
+impl fmt::Debug for BloomRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BloomRoot")
+            .field(&format_args!("{:?}@{:?}", self.node.kind(), self.node.len()))
+            .field(&self.bloom)
+            .finish()
+    }
+}
+
+impl From<Arc<Node>> for BloomRoot {
+    fn from(node: Arc<Node>) -> Self {
+        BloomRoot::new(node)
+    }
+}
@@ -0,0 +1,343 @@
+use {
+    crate::{
+        green::{Builder, ElementRef, Node, Token},
+        NodeOrToken, TextRange, TextSize,
+    },
+    std::{
+        cmp,
+        collections::{hash_map::DefaultHasher, HashMap},
+        error::Error,
+        fmt,
+        hash::{Hash, Hasher},
+        ptr,
+        sync::Arc,
+    },
+};
+
+/// A region which both sides of a [`merge`] changed in different ways.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct Conflict {
+    /// The conflicting region of the base tree.
+    pub base: TextRange,
+    /// The region replacing it in the left tree.
+    pub left: TextRange,
+    /// The region replacing it in the right tree.
+    pub right: TextRange,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "conflicting changes to {:?} of the base: {:?} on the left, {:?} on the right",
+            self.base, self.left, self.right,
+        )
+    }
+}
+
+impl Error for Conflict {}
+
+type OwnedElement = NodeOrToken<Arc<Node>, Arc<Token>>;
+
+/// Merge the changes that `left` and `right` each made to `base`.
+///
+/// The merge works on whole subtrees. Elements are compared by identity
+/// first, and by content hash and then structure if they aren't the same
+/// allocation, so trees from different builders merge correctly, but trees
+/// which share unchanged subtrees with their base merge fastest.
+/// Only kinds and text are compared: elements which differ only in token
+/// flags or node provenance are the same to the merge, and which of them
+/// ends up in the merged tree is unspecified.
+///
+/// Where only one side changed an element, that side's version is taken.
+/// Where both changed a node of the same kind, its children are aligned with
+/// the base's and merged one by one, so one side can insert or remove children
+/// while the other edits some of them. Changes which can't be reconciled this
+/// way are conflicts; all of them are reported, in order. As in a textual
+/// three-way merge, changes to adjacent children, such as one side inserting
+/// a child right after one which the other side edited, also conflict.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, Kind, TextRange};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let mut builder = green::TreeBuilder::new();
+/// let mut list = |atoms: &[&str]| {
+///     builder.start_node(LIST);
+///     for atom in atoms {
+///         builder.start_node(LIST).token(ATOM, atom).finish_node();
+///     }
+///     builder.finish_node().finish()
+/// };
+/// let base = list(&["a", "b", "c"]);
+/// let left = list(&["x", "b", "c"]);
+/// let right = list(&["a", "b", "y", "z"]);
+/// let merged = list(&["x", "b", "y", "z"]);
+/// let conflicting = list(&["a", "b", "w"]);
+///
+/// let builder = builder.builder();
+/// assert_eq!(green::merge(&base, &left, &right, builder), Ok(merged));
+///
+/// let conflicts = green::merge(&base, &right, &conflicting, builder).unwrap_err();
+/// assert_eq!(conflicts.len(), 1);
+/// assert_eq!(conflicts[0].base, TextRange::new(2.into(), 3.into()));
+/// assert_eq!(conflicts[0].left, TextRange::new(2.into(), 4.into()));
+/// ```
+pub fn merge(
+    base: &Arc<Node>,
+    left: &Arc<Node>,
+    right: &Arc<Node>,
+    builder: &mut Builder,
+) -> Result<Arc<Node>, Vec<Conflict>> {
+    let mut merger = Merger { builder, hashes: HashMap::new(), conflicts: vec![] };
+    let [base, left, right] = [base, left, right].map(|node| NodeOrToken::Node(node.into()));
+    let merged = merger.element(base, left, right, [0.into(); 3]);
+    if merger.conflicts.is_empty() {
+        Ok(merged.into_node().expect("merging nodes produced a token"))
+    } else {
+        Err(merger.conflicts)
+    }
+}
+
+struct Merger<'a> {
+    builder: &'a mut Builder,
+    // content hashes of nodes, keyed by address
+    hashes: HashMap<*const Node, u64>,
+    conflicts: Vec<Conflict>,
+}
+
+fn total_len(elements: &[ElementRef<'_>]) -> TextSize {
+    elements.iter().map(|el| el.len()).sum()
+}
+
+impl Merger<'_> {
+    /// A hash of the kinds and text of a node, the same across builders.
+    /// Like [`same`](Merger::same), this ignores token flags.
+    fn hash(&mut self, node: &Node) -> u64 {
+        if let Some(&hash) = self.hashes.get(&(node as *const Node)) {
+            return hash;
+        }
+        let mut hasher = DefaultHasher::new();
+        node.kind().hash(&mut hasher);
+        node.children_len().hash(&mut hasher);
+        for child in node.children() {
+            match child {
+                NodeOrToken::Node(node) => self.hash(&node).hash(&mut hasher),
+                NodeOrToken::Token(token) => {
+                    token.kind().hash(&mut hasher);
+                    token.bytes().hash(&mut hasher);
+                }
+            }
+        }
+        let hash = hasher.finish();
+        self.hashes.insert(node as *const Node, hash);
+        hash
+    }
+
+    /// Whether two elements have the same kinds and text.
+    ///
+    /// Token flags are ignored, as they are computed by each builder's token
+    /// classifier, and so can differ between trees from different builders.
+    fn same(&mut self, a: ElementRef<'_>, b: ElementRef<'_>) -> bool {
+        match (a, b) {
+            (NodeOrToken::Node(a), NodeOrToken::Node(b)) => {
+                ptr::eq(&*a, &*b)
+                    || a.kind() == b.kind()
+                        && a.len() == b.len()
+                        && a.children_len() == b.children_len()
+                        && self.hash(&a) == self.hash(&b)
+                        && a.children().zip(b.children()).all(|(a, b)| self.same(a, b))
+            }
+            (NodeOrToken::Token(a), NodeOrToken::Token(b)) => {
+                ptr::eq(&*a, &*b)
+                    || a.kind() == b.kind()
+                        && a.is_binary() == b.is_binary()
+                        && a.bytes() == b.bytes()
+            }
+            _ => false,
+        }
+    }
+
+    fn same_slice(&mut self, a: &[ElementRef<'_>], b: &[ElementRef<'_>]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(&a, &b)| self.same(a, b))
+    }
+
+    /// For each element of `a`, the index of the element of `b` it is matched
+    /// with in a longest common subsequence of the two.
+    fn matching(&mut self, a: &[ElementRef<'_>], b: &[ElementRef<'_>]) -> Vec<Option<usize>> {
+        let mut matching = vec![None; a.len()];
+        // most edits are local, so match the common prefix and suffix directly
+        let prefix = a.iter().zip(b).take_while(|&(&a, &b)| self.same(a, b)).count();
+        let suffix = a[prefix..]
+            .iter()
+            .rev()
+            .zip(b[prefix..].iter().rev())
+            .take_while(|&(&a, &b)| self.same(a, b))
+            .count();
+        for (i, matched) in matching.iter_mut().enumerate().take(prefix) {
+            *matched = Some(i);
+        }
+        for i in 0..suffix {
+            matching[a.len() - 1 - i] = Some(b.len() - 1 - i);
+        }
+
+        let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+        self.align(a_mid, b_mid, [prefix; 2], &mut matching);
+        matching
+    }
+
+    /// The length of the longest common subsequence of `a` with each prefix of `b`.
+    fn lcs_lens<'a>(
+        &mut self,
+        a: impl Iterator<Item = ElementRef<'a>>,
+        b: &[ElementRef<'a>],
+    ) -> Vec<usize> {
+        let mut lens = vec![0; b.len() + 1];
+        for a in a {
+            // the LCS length of the earlier elements of `a` with b[..j]
+            let mut diagonal = 0;
+            for (j, &b) in b.iter().enumerate() {
+                let above = lens[j + 1];
+                lens[j + 1] = if self.same(a, b) { diagonal + 1 } else { cmp::max(above, lens[j]) };
+                diagonal = above;
+            }
+        }
+        lens
+    }
+
+    /// Match `a` with `b` along a longest common subsequence of the two,
+    /// in linear space (Hirschberg's algorithm), so that long runs of changed
+    /// children don't need a quadratic table.
+    fn align(
+        &mut self,
+        a: &[ElementRef<'_>],
+        b: &[ElementRef<'_>],
+        [a_offset, b_offset]: [usize; 2],
+        matching: &mut [Option<usize>],
+    ) {
+        if a.is_empty() || b.is_empty() {
+            return;
+        }
+        if a.len() == 1 {
+            if let Some(j) = b.iter().position(|&b| self.same(a[0], b)) {
+                matching[a_offset] = Some(b_offset + j);
+            }
+            return;
+        }
+
+        // split `a` in half, and `b` where the LCS of the halves is longest
+        let mid = a.len() / 2;
+        let forward = self.lcs_lens(a[..mid].iter().copied(), b);
+        let b_rev: Vec<_> = b.iter().rev().copied().collect();
+        let backward = self.lcs_lens(a[mid..].iter().rev().copied(), &b_rev);
+        let split = (0..=b.len())
+            .rev()
+            .max_by_key(|&j| forward[j] + backward[b.len() - j])
+            .expect("split points are never empty");
+
+        self.align(&a[..mid], &b[..split], [a_offset, b_offset], matching);
+        self.align(&a[mid..], &b[split..], [a_offset + mid, b_offset + split], matching);
+    }
+
+    fn conflict(&mut self, offsets: [TextSize; 3], lens: [TextSize; 3]) {
+        self.conflicts.push(Conflict {
+            base: TextRange::at(offsets[0], lens[0]),
+            left: TextRange::at(offsets[1], lens[1]),
+            right: TextRange::at(offsets[2], lens[2]),
+        });
+    }
+
+    /// Merge one element, given the offsets of its three versions.
+    fn element(
+        &mut self,
+        base: ElementRef<'_>,
+        left: ElementRef<'_>,
+        right: ElementRef<'_>,
+        offsets: [TextSize; 3],
+    ) -> OwnedElement {
+        if self.same(base, left) {
+            return right.into();
+        }
+        if self.same(base, right) || self.same(left, right) {
+            return left.into();
+        }
+        match (base, left, right) {
+            (NodeOrToken::Node(base), NodeOrToken::Node(left), NodeOrToken::Node(right))
+                if base.kind() == left.kind() && left.kind() == right.kind() =>
+            {
+                let children = self.children(&base, &left, &right, offsets);
+                self.builder.node_like(&left, children).into()
+            }
+            _ => {
+                self.conflict(offsets, [base.len(), left.len(), right.len()]);
+                left.into()
+            }
+        }
+    }
+
+    /// Merge the children of three versions of a node.
+    fn children(
+        &mut self,
+        base: &Node,
+        left: &Node,
+        right: &Node,
+        mut offsets: [TextSize; 3],
+    ) -> Vec<OwnedElement> {
+        let base: Vec<_> = base.children().collect();
+        let left: Vec<_> = left.children().collect();
+        let right: Vec<_> = right.children().collect();
+        let to_left = self.matching(&base, &left);
+        let to_right = self.matching(&base, &right);
+
+        let mut merged = Vec::with_capacity(cmp::max(left.len(), right.len()));
+        let (mut b, mut l, mut r) = (0, 0, 0);
+        loop {
+            // the next base child which both sides kept, or the end of all three
+            let stable = (b..base.len()).find_map(|i| Some((i, to_left[i]?, to_right[i]?)));
+            let (b_end, l_end, r_end) = stable.unwrap_or((base.len(), left.len(), right.len()));
+            self.chunk(&base[b..b_end], &left[l..l_end], &right[r..r_end], offsets, &mut merged);
+            offsets[0] += total_len(&base[b..b_end]);
+            offsets[1] += total_len(&left[l..l_end]);
+            offsets[2] += total_len(&right[r..r_end]);
+            if stable.is_none() {
+                break merged;
+            }
+            merged.push(left[l_end].into());
+            offsets[0] += base[b_end].len();
+            offsets[1] += left[l_end].len();
+            offsets[2] += right[r_end].len();
+            b = b_end + 1;
+            l = l_end + 1;
+            r = r_end + 1;
+        }
+    }
+
+    /// Merge a run of children which at least one side changed.
+    fn chunk(
+        &mut self,
+        base: &[ElementRef<'_>],
+        left: &[ElementRef<'_>],
+        right: &[ElementRef<'_>],
+        offsets: [TextSize; 3],
+        merged: &mut Vec<OwnedElement>,
+    ) {
+        if self.same_slice(base, left) {
+            merged.extend(right.iter().map(|&el| OwnedElement::from(el)));
+        } else if self.same_slice(base, right) || self.same_slice(left, right) {
+            merged.extend(left.iter().map(|&el| OwnedElement::from(el)));
+        } else if base.len() == left.len() && left.len() == right.len() {
+            let mut offsets = offsets;
+            for ((&base, &left), &right) in base.iter().zip(left).zip(right) {
+                merged.push(self.element(base, left, right, offsets));
+                offsets[0] += base.len();
+                offsets[1] += left.len();
+                offsets[2] += right.len();
+            }
+        } else {
+            self.conflict(offsets, [total_len(base), total_len(left), total_len(right)]);
+            merged.extend(left.iter().map(|&el| OwnedElement::from(el)));
+        }
+    }
+}
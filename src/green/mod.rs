@@ -1,12 +1,48 @@
 //! The green tree is an immutable, persistent, atomically reference counted tree.
 
+use crate::{ArcBorrow, NodeOrToken};
+
 mod builder;
+mod canonical;
 mod children;
+mod document;
 mod element;
+mod fields;
+mod forest;
+mod history;
+mod interned_root;
+mod kind_bloom;
+mod merge;
 mod node;
+mod offset_index;
+mod pool;
+mod self_test;
+mod sexpr;
+mod shape;
+mod summary;
+mod symbols;
 mod token;
 mod tree_builder;
+mod trivia;
+mod utf16;
+mod verify;
+mod visit;
+mod with_offset;
+
+pub mod analysis;
+pub mod edit;
+pub mod flat;
+pub mod format;
+pub mod ids;
+pub mod project;
+pub mod query;
+pub mod reuse;
+pub mod tokens;
 
+#[cfg(feature = "ropey")]
+pub mod rope;
+#[cfg(feature = "schemars")]
+mod schema;
 #[cfg(feature = "serde")]
 mod serde;
 
@@ -16,9 +52,52 @@ pub(self) use self::element::{
 };
 #[doc(inline)]
 pub use self::{
-    builder::Builder,
+    builder::{Builder, KindStats, SliceIter},
+    canonical::{CanonicalPolicy, TriviaPlacement},
     children::{Children, ChildrenWithOffsets},
-    node::Node,
-    token::Token,
-    tree_builder::{Checkpoint, TreeBuilder},
+    document::{Document, Metadata},
+    element::Child,
+    fields::{FieldSpec, Fields},
+    forest::Forest,
+    history::History,
+    interned_root::InternedRoot,
+    kind_bloom::{BloomRoot, KindBloom},
+    merge::{merge, Conflict},
+    node::{drop_deep, drop_in_background, Bias, Node, NodeError},
+    offset_index::OffsetIndex,
+    pool::Pool,
+    self_test::{self_test, SelfTestError},
+    sexpr::{from_sexpr, SexprError},
+    shape::{Shape, ShapeError},
+    summary::Summary,
+    symbols::DocumentSymbol,
+    token::{Keyword, Token},
+    tree_builder::{Checkpoint, TreeBuilder, WhitespacePolicy},
+    utf16::Utf16Index,
+    verify::{verify_text, Mismatch},
+    visit::{KindDispatch, VisitControl, Visitor},
+    with_offset::WithOffset,
 };
+
+#[cfg(feature = "schemars")]
+#[doc(inline)]
+pub use self::schema::json_schema;
+
+/// A borrowed element of a green tree, as yielded by [`Children`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, ElementRef}, Kind};
+/// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+/// let tree = green::TreeBuilder::new()
+///     .start_node(LIST)
+///     .token(ATOM, "a")
+///     .token(WS, " ")
+///     .token(ATOM, "b")
+///     .finish_node()
+///     .finish();
+/// let is_atom = |el: &ElementRef<'_>| el.kind() == ATOM;
+/// assert_eq!(tree.children().filter(is_atom).count(), 2);
+/// ```
+pub type ElementRef<'a> = NodeOrToken<ArcBorrow<'a, Node>, ArcBorrow<'a, Token>>;
@@ -1,114 +1,380 @@
-#[cfg(feature = "de")]
-use slice_dst::TryAllocSliceDst;
 use {
     crate::{
         green::{
-            unpack_node_or_token, Children, Element, FullAlignedElement, HalfAlignedElement,
-            PackedNodeOrToken,
+            unpack_node_or_token, Child, Children, Element, ElementRef, FullAlignedElement,
+            HalfAlignedElement, PackedNodeOrToken, Summary, Token,
         },
-        Kind, TextSize,
+        ArcBorrow, Kind, NodeOrToken, SyntaxKind, TextRange, TextSize,
     },
     erasable::{Erasable, ErasedPtr},
+    lazy_static::lazy_static,
     ptr_union::Enum2,
-    slice_dst::{AllocSliceDst, SliceDst},
-    std::{alloc::Layout, hash, mem::ManuallyDrop, ptr, sync::Arc, u16},
+    slice_dst::{SliceDst, TryAllocSliceDst},
+    std::{
+        alloc::Layout,
+        cell::RefCell,
+        cmp::Ordering,
+        error::Error,
+        fmt, hash, iter,
+        mem::{self, ManuallyDrop},
+        ops::Index,
+        ptr,
+        sync::{mpsc, Arc, Mutex, PoisonError},
+        thread, u16,
+    },
 };
 
 /// A nonleaf node in the immutable green tree.
 ///
 /// Nodes are crated using [`Builder::node`](crate::green::Builder::node).
+///
+/// Nodes are ordered structurally: by kind, then lexicographically by children,
+/// where child nodes sort before child tokens.
+///
+/// # Dropping
+///
+/// Dropping a tree frees it with a loop rather than recursion, so even very deep
+/// trees can be dropped without overflowing the stack. Nodes shared with other
+/// handles are only freed with the last handle, and when threads race to drop
+/// the last handles to a node, whichever thread frees it also frees its
+/// descendants iteratively. [`drop_deep`] makes this explicit at the call site,
+/// and doesn't depend on thread-local state.
 #[repr(C, align(8))] // NB: align >= 8
-#[derive(Debug, Eq)]
+#[derive(Eq)]
 pub struct Node {
     // NB: This is optimal layout, as the order is (u16, u16, u32, [{see element.rs}])
+    // children_len duplicates the length in `&Node`'s fat pointer metadata, but it
+    // can't be dropped: children are stored as thin pointers, and `Erasable::unerase`
+    // needs it to rebuild the fat pointer. Nor would dropping it save anything, as
+    // the children are aligned to 8 and the header would be padded back to 8 bytes.
+    // tests/layout.rs checks the resulting sizes.
     // SAFETY: Must be at offset 0 and accurate to trailing array length.
     children_len: u16,  // align 8 + 0, size 2
     kind: Kind,         // align 8 + 2, size 2
     text_len: TextSize, // align 8 + 4, size 4
+    #[cfg(any(feature = "node-flags", feature = "provenance"))]
+    ext: HeaderExt, // align 8 + 0, size 8
     // SAFETY: Must be aligned to 8
     children: [Element], // align 8 + 0, dyn size
 }
 
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("Node");
+        f.field("children_len", &self.children_len);
+        f.field("kind", &self.kind);
+        f.field("text_len", &self.text_len);
+        #[cfg(feature = "provenance")]
+        if self.ext.provenance != 0 {
+            f.field("provenance", &self.ext.provenance);
+        }
+        f.field("children", &&self.children);
+        f.finish()
+    }
+}
+
+/// The optional part of a node's header, present if any feature needs it.
+///
+/// This is aligned to 8 so that the children after it stay aligned to 8.
+#[cfg(any(feature = "node-flags", feature = "provenance"))]
+#[repr(C, align(8))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct HeaderExt {
+    #[cfg(feature = "provenance")]
+    provenance: u32, // align 8 + 0, size 4
+    #[cfg(feature = "node-flags")]
+    flags: u8, // align 8 + 4, size 1
+}
+
+/// Set in the `flags` header of a node containing a newline, if tracked.
+/// The other bits hold the synthesized token flags.
+#[cfg(feature = "node-flags")]
+const MULTILINE_FLAG: u8 = 1 << 7;
+
+/// The most children for which offset lookup scans linearly rather than binary searching.
+/// Most nodes have only a handful of children, where a linear scan is faster.
+const LINEAR_SEARCH_MAX: usize = 8;
+
+/// What a builder synthesizes for new nodes from their children.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub(super) struct Synthesis {
+    #[cfg(feature = "node-flags")]
+    pub(super) multiline: bool,
+    /// The token flags which are propagated to nodes.
+    #[cfg(feature = "node-flags")]
+    pub(super) flags: u8,
+}
+
+/// The parts of a node's identity besides its children, used as the construction cache key.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub(super) struct NodeHead {
+    pub(super) kind: Kind,
+    #[cfg(feature = "provenance")]
+    pub(super) provenance: u32,
+}
+
+impl From<Kind> for NodeHead {
+    fn from(kind: Kind) -> Self {
+        NodeHead {
+            kind,
+            #[cfg(feature = "provenance")]
+            provenance: 0,
+        }
+    }
+}
+
 // Manually impl Eq/Hash to match Token
-// Plus we can skip .children_len since it's derived from .children
+// Plus we can skip .children_len since it's derived from .children,
+// and the node flags since they're synthesized from the children
 impl PartialEq for Node {
     fn eq(&self, other: &Self) -> bool {
-        self.kind == other.kind
+        self.head() == other.head()
             && self.text_len == other.text_len
             && self.children == other.children
     }
 }
 
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        fn children(node: &Node) -> impl '_ + Iterator<Item = NodeOrToken<&Node, &Token>> {
+            node.children().map(Into::into)
+        }
+        let ordering = self.kind.cmp(&other.kind).then_with(|| children(self).cmp(children(other)));
+        #[cfg(feature = "provenance")]
+        let ordering = ordering.then(self.ext.provenance.cmp(&other.ext.provenance));
+        ordering
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl hash::Hash for Node {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
-        self.kind.hash(state);
+        self.head().hash(state);
         self.text_len.hash(state);
         self.children.hash(state);
     }
 }
 
+impl Index<usize> for Node {
+    type Output = Child;
+
+    /// The child element at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    fn index(&self, index: usize) -> &Child {
+        Child::new(&self.children[index])
+    }
+}
+
+impl<'a> IntoIterator for &'a Node {
+    type Item = ElementRef<'a>;
+    type IntoIter = Children<'a>;
+
+    fn into_iter(self) -> Children<'a> {
+        self.children()
+    }
+}
+
+thread_local! {
+    /// Children of nodes dropped while a node is already being dropped on this thread,
+    /// queued for the outermost drop to take care of. `None` outside of any drop.
+    static DROP_QUEUE: RefCell<Option<Vec<Arc<Node>>>> = RefCell::new(None);
+}
+
+/// Queue this node's children to be dropped if this is the last handle,
+/// then drop the reference counted handle (freeing the node itself),
+/// without recursing into the node's `Drop` implementation.
+///
+/// If this function is used concurrently on two handles to the same node,
+/// it is possible that neither will observe being the last outstanding handle
+/// (before the synchronization in `Arc::drop`) and drop the node handle normally.
+/// The node's destructor then runs nested in this one, and only queues its
+/// children into the thread's `DROP_QUEUE` for the outermost drop to pick up.
+fn maybe_drop_into(mut this: Arc<Node>, stack: &mut Vec<Arc<Node>>) {
+    if let Some(node) = Arc::get_mut(&mut this) {
+        unsafe {
+            // Queue all of the children to be destructed, and
+            drop_into(node, stack);
+            // Skip running the node's destructor.
+            Arc::<ManuallyDrop<Node>>::from_raw(Arc::into_raw(this) as *const _);
+        }
+    } else {
+        // NB: May actually be the last Arc, if above `Arc::get_mut` races with another thread.
+        drop(this);
+    }
+}
+
+/// Queue this node's children into a drop queue.
+///
+/// # Safety
+///
+/// This takes the children out of the node logically but not physically,
+/// like `ManuallyDrop::take`. The node must not be used (even to drop)
+/// after calling this function.
+unsafe fn drop_into(this: &mut Node, stack: &mut Vec<Arc<Node>>) {
+    let mut children = this.children.iter_mut();
+    let mut enqueue =
+        |pack: PackedNodeOrToken| unpack_node_or_token(pack).map(|node| stack.push(node), drop);
+    (|| -> Option<()> {
+        loop {
+            enqueue(children.next()?.full_aligned_mut().take());
+            enqueue(children.next()?.half_aligned_mut().take());
+        }
+    })();
+}
+
 // Element is a union, so we have to make sure to drop them manually here.
 impl Drop for Node {
     #[inline]
     fn drop(&mut self) {
-        /// Queue this node's children to be dropped if this is the last handle,
-        /// then drop the reference counted handle (freeing the node itself),
-        /// without recursing into the node's `Drop` implementation.
-        ///
-        /// Note: this is a best-effort flattening of dropping the tree.
-        /// If this function is used concurrently on two handles to the same node,
-        /// it is possible that neither will observe being the last outstanding handle
-        /// (before the synchronization in `Arc::drop`) and drop the node handle normally.
-        /// The node will still be properly dropped, just by calling its destructor
-        /// rather than taking its children into the iterative drop list.
-        fn maybe_drop_into(mut this: Arc<Node>, stack: &mut Vec<Arc<Node>>) {
-            if let Some(node) = Arc::get_mut(&mut this) {
-                unsafe {
-                    // Queue all of the children to be destructed, and
-                    drop_into(node, stack);
-                    // Skip running the node's destructor.
-                    Arc::<ManuallyDrop<Node>>::from_raw(Arc::into_raw(this) as *const _);
+        unsafe {
+            // If a drop is already running on this thread, leave the children to it.
+            // NB: the queue is unavailable while thread locals are being destroyed;
+            //  then we fall back to a drop which is only iterative on a best-effort basis.
+            let nested = DROP_QUEUE.try_with(|queue| match &mut *queue.borrow_mut() {
+                Some(queue) => {
+                    drop_into(self, queue);
+                    true
                 }
-            } else {
-                // NB: May actually be the last Arc, if above `Arc::get_mut` races with another thread.
-                //  Thus, we only guarantee best-effort iterative drops, as some recursion may happen.
-                //  I believe if only one thread drops at a time, drops will always be fully iterative.
-                drop(this);
-            }
-        }
-
-        /// Queue this node's children into a drop queue.
-        ///
-        /// # Safety
-        ///
-        /// This takes the children out of the node logically but not physically,
-        /// like `ManuallyDrop::take`. The node must not be used (even to drop)
-        /// after calling this function.
-        unsafe fn drop_into(this: &mut Node, stack: &mut Vec<Arc<Node>>) {
-            let mut children = this.children.iter_mut();
-            let mut enqueue = |pack: PackedNodeOrToken| {
-                unpack_node_or_token(pack).map(|node| stack.push(node), drop)
-            };
-            (|| -> Option<()> {
-                loop {
-                    enqueue(children.next()?.full_aligned_mut().take());
-                    enqueue(children.next()?.half_aligned_mut().take());
+                queue @ None => {
+                    *queue = Some(vec![]);
+                    false
                 }
-            })();
-        }
+            });
+            if nested == Ok(true) {
+                return;
+            }
 
-        unsafe {
             let mut stack = vec![];
             drop_into(self, &mut stack);
-            while let Some(element) = stack.pop() {
-                maybe_drop_into(element, &mut stack);
+            loop {
+                while let Some(element) = stack.pop() {
+                    maybe_drop_into(element, &mut stack);
+                }
+                match DROP_QUEUE.try_with(|queue| queue.borrow_mut().as_mut().map(mem::take)) {
+                    Ok(Some(queued)) if !queued.is_empty() => stack = queued,
+                    _ => break,
+                }
             }
+            if nested.is_ok() {
+                DROP_QUEUE.with(|queue| *queue.borrow_mut() = None);
+            }
+        }
+    }
+}
+
+/// Which side of a boundary between elements an offset on it is resolved to.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Bias {
+    /// Resolve to the element ending at the offset.
+    Left,
+    /// Resolve to the element starting at the offset.
+    Right,
+}
+
+/// Drop a tree with an explicit loop, without relying on thread-local state.
+///
+/// Dropping a node is already iterative, but uses a thread-local queue to
+/// combine nested drops on one thread. This keeps its own queue instead, so it
+/// suits thread-local destructors and similar contexts where thread-locals may
+/// already be gone, and documents at the call site that the tree may be deep.
+///
+/// Subtrees shared with other handles are only released, and are freed by
+/// whichever handle is dropped last.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let mut builder = green::TreeBuilder::new();
+/// for _ in 0..100_000 {
+///     builder.start_node(LIST);
+/// }
+/// builder.token(ATOM, "a");
+/// for _ in 0..100_000 {
+///     builder.finish_node();
+/// }
+/// green::drop_deep(builder.finish());
+/// ```
+pub fn drop_deep(node: Arc<Node>) {
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        maybe_drop_into(node, &mut stack);
+    }
+}
+
+/// Drop a tree on a background thread, so that freeing it does not block this thread.
+///
+/// If this is not the last handle to the tree, it is just dropped here,
+/// as that does not free anything. Trees dropped in the background are
+/// freed one at a time, in order, by a single helper thread.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let mut builder = green::TreeBuilder::new();
+/// builder.start_node(LIST);
+/// for i in 0..10_000 {
+///     builder.token(ATOM, &i.to_string());
+/// }
+/// let tree = builder.finish_node().finish();
+/// green::drop_in_background(tree);
+/// ```
+pub fn drop_in_background(node: Arc<Node>) {
+    lazy_static! {
+        static ref BACKGROUND: Option<Mutex<mpsc::Sender<Arc<Node>>>> = {
+            let (send, recv) = mpsc::channel::<Arc<Node>>();
+            let thread = thread::Builder::new().name("sorbus-drop".into());
+            // NB: if we can't spawn the thread, just drop on the calling thread
+            thread.spawn(move || recv.into_iter().for_each(drop)).ok().map(|_| Mutex::new(send))
+        };
+    }
+    if Arc::strong_count(&node) > 1 {
+        return drop(node);
+    }
+    if let Some(send) = &*BACKGROUND {
+        let send = send.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(mpsc::SendError(node)) = send.send(node) {
+            drop(send);
+            drop(node);
         }
     }
 }
 
-#[allow(clippy::len_without_is_empty)]
 impl Node {
+    /// Check the invariants of this node's header and children that unsafe code relies on.
+    #[cfg(feature = "paranoid")]
+    fn check_invariants(&self) {
+        assert_eq!(
+            usize::from(self.children_len),
+            self.children.len(),
+            "node header disagrees with its children length",
+        );
+        let mut end = TextSize::from(0);
+        for (i, element) in self.children.iter().enumerate() {
+            if i % 2 == 0 {
+                assert!(element.is_full_aligned(), "node child {} is not full-aligned", i);
+            } else {
+                assert!(element.is_half_aligned(), "node child {} is not half-aligned", i);
+            }
+            assert_eq!(element.offset(), end, "node child {} is at the wrong offset", i);
+            // NB: don't recursively check the children here, to keep this O(children)
+            let child: NodeOrToken<_, _> = element.into();
+            end += child.map(|node| node.text_len, |token| token.len()).flatten();
+        }
+        assert_eq!(end, self.text_len, "node header disagrees with its text length");
+    }
+
     #[cfg(feature = "de")]
     pub(super) fn set_kind(&mut self, kind: Kind) {
         self.kind = kind;
@@ -120,31 +386,383 @@ impl Node {
         self.kind
     }
 
+    /// The kind of this node, as a language's own kind type.
+    ///
+    /// Returns `None` if the raw kind is not a kind of that type.
+    pub fn kind_as<K: SyntaxKind>(&self) -> Option<K> {
+        K::from_raw(self.kind)
+    }
+
+    /// The provenance id this node was built with,
+    /// or 0 if it was built without one.
+    ///
+    /// See [`Builder::node_with_provenance`](crate::green::Builder::node_with_provenance).
+    #[cfg(feature = "provenance")]
+    #[inline]
+    pub fn provenance(&self) -> u32 {
+        self.ext.provenance
+    }
+
+    #[inline]
+    pub(super) fn head(&self) -> NodeHead {
+        NodeHead {
+            kind: self.kind,
+            #[cfg(feature = "provenance")]
+            provenance: self.ext.provenance,
+        }
+    }
+
     /// The length of text at this node.
     #[inline]
     pub fn len(&self) -> TextSize {
+        #[cfg(feature = "paranoid")]
+        self.check_invariants();
         self.text_len
     }
 
+    /// Whether this node has no text.
+    ///
+    /// Note that a node without text may still have children.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.text_len == 0.into()
+    }
+
+    /// Whether the text of this node contains a newline (`'\n'`).
+    ///
+    /// This is only tracked for nodes built by a builder with
+    /// [`set_track_multiline`](crate::green::Builder::set_track_multiline)
+    /// enabled, so it is always `false` for nodes built without it.
+    #[cfg(feature = "node-flags")]
+    #[inline]
+    pub fn is_multiline(&self) -> bool {
+        self.ext.flags & MULTILINE_FLAG != 0
+    }
+
+    /// The bitwise or of the [`flags`](Token::flags) of all tokens in this node,
+    /// restricted to the flags propagated by the builder which built it.
+    /// See [`Builder::set_flag_propagation`].
+    ///
+    ///   [`Builder::set_flag_propagation`]: crate::green::Builder::set_flag_propagation
+    #[cfg(feature = "node-flags")]
+    #[inline]
+    pub fn synthesized_flags(&self) -> u8 {
+        self.ext.flags & Token::MAX_FLAGS
+    }
+
+    /// The number of child elements of this node.
+    #[inline]
+    pub fn children_len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Whether this node has any child elements.
+    #[inline]
+    pub fn has_children(&self) -> bool {
+        !self.children.is_empty()
+    }
+
     /// Child elements of this node.
     #[inline]
     pub fn children(&self) -> Children<'_> {
+        #[cfg(feature = "paranoid")]
+        self.check_invariants();
         unsafe { Children::new(&self.children) }
     }
 
+    /// The `n`th child of this node with the given kind, counting from 0.
+    ///
+    /// This is a single pass over the children, without collecting them,
+    /// so it suits typed accessors for "the second expression child" and the like.
+    /// The child knows its offset from this node.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const WS: Kind = Kind(0); const OP: Kind = Kind(1); const EXPR: Kind = Kind(2);
+    /// // a + b
+    /// let tree = green::TreeBuilder::new()
+    ///     .start_node(EXPR)
+    ///     .start_node(EXPR).token(OP, "a").finish_node()
+    ///     .token(WS, " ")
+    ///     .token(OP, "+")
+    ///     .token(WS, " ")
+    ///     .start_node(EXPR).token(OP, "b").finish_node()
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// let rhs = tree.nth_of_kind(EXPR, 1).unwrap();
+    /// assert_eq!(rhs.offset(), 4.into());
+    /// assert!(rhs.get().is_node());
+    /// assert!(tree.nth_of_kind(EXPR, 2).is_none());
+    /// ```
+    pub fn nth_of_kind(&self, kind: Kind, n: usize) -> Option<&Child> {
+        let mut seen = 0;
+        let index = self.children().position(|el| {
+            el.kind() == kind && {
+                seen += 1;
+                seen > n
+            }
+        })?;
+        Some(&self[index])
+    }
+
+    /// The direct token children of this node, with their ranges within this node.
+    ///
+    /// Node children are skipped without unpacking them. This is the minimal
+    /// work per element for consumers like syntax highlighters, which style
+    /// the tokens of a node and recurse into its node children themselves.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind, TextRange};
+    /// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+    /// let tree = green::TreeBuilder::new()
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "ab")
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "cd")
+    ///     .finish_node()
+    ///     .token(WS, " ")
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// let tokens: Vec<(TextRange, Kind)> =
+    ///     tree.direct_tokens().map(|(range, token)| (range, token.kind())).collect();
+    /// assert_eq!(tokens, [(TextRange::up_to(2.into()), ATOM), (TextRange::at(4.into(), 1.into()), WS)]);
+    /// ```
+    pub fn direct_tokens(&self) -> impl '_ + Iterator<Item = (TextRange, ArcBorrow<'_, Token>)> {
+        self.children.iter().filter_map(|el| {
+            let token = el.ptr().with_b(|&token| token)?;
+            Some((TextRange::at(el.offset(), token.len()), token))
+        })
+    }
+
     /// The index of the child that contains the given offset.
     ///
     /// If the offset is the start of a node, returns that node.
+    /// Zero-length children contain no offsets, so are never returned.
     ///
     /// # Panics
     ///
     /// Panics if the given offset is outside of this node.
+    /// See [`try_index_of_offset`](Node::try_index_of_offset) for a non-panicking version.
     #[inline]
     pub fn index_of_offset(&self, offset: TextSize) -> usize {
         assert!(offset < self.len());
-        self.children
-            .binary_search_by_key(&offset, |el| el.offset())
-            .unwrap_or_else(|index| index - 1)
+        // NB: the first child always starts at or before the offset
+        if self.children.len() <= LINEAR_SEARCH_MAX {
+            // branchless, so the compiler can unroll and vectorize it
+            let starts_before = |el: &Element| (el.offset() <= offset) as usize;
+            self.children.iter().map(starts_before).sum::<usize>() - 1
+        } else {
+            self.children.partition_point(|el| el.offset() <= offset) - 1
+        }
+    }
+
+    /// The index of the child that contains the given offset,
+    /// or `None` if the offset is outside of this node.
+    ///
+    /// An offset on the boundary between two children, such as a cursor
+    /// between two tokens, is resolved to one of them by `bias`:
+    /// [`Bias::Right`] picks the child starting at the offset, like
+    /// [`index_of_offset`](Node::index_of_offset), and [`Bias::Left`] picks
+    /// the child ending at the offset. Thus with `Bias::Left`, the end of
+    /// this node is in its last child, and the start of this node is in
+    /// its first child.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green::{self, Bias}, Kind};
+    /// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+    /// let tree = green::TreeBuilder::new()
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "a")
+    ///     .token(WS, " ")
+    ///     .token(ATOM, "b")
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// assert_eq!(tree.try_index_of_offset(1.into(), Bias::Right), Some(1));
+    /// assert_eq!(tree.try_index_of_offset(1.into(), Bias::Left), Some(0));
+    /// assert_eq!(tree.try_index_of_offset(3.into(), Bias::Right), None);
+    /// assert_eq!(tree.try_index_of_offset(3.into(), Bias::Left), Some(2));
+    /// ```
+    #[inline]
+    pub fn try_index_of_offset(&self, offset: TextSize, bias: Bias) -> Option<usize> {
+        match bias {
+            Bias::Right if offset < self.len() => Some(self.index_of_offset(offset)),
+            Bias::Left if offset == 0.into() => self.has_children().then(|| 0),
+            // NB: the last child starting before the offset always ends at or after it
+            Bias::Left if offset <= self.len() => {
+                Some(self.children.partition_point(|el| el.offset() < offset) - 1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the given offset is on a UTF-8 char boundary of this node's text.
+    ///
+    /// This descends to the single token containing the offset.
+    /// Every offset within a binary token is considered a boundary.
+    /// Offsets past the end of the text are not boundaries.
+    pub fn is_char_boundary(&self, offset: TextSize) -> bool {
+        if offset >= self.len() {
+            return offset == self.len();
+        }
+        let (child_offset, child) =
+            self.children().with_offsets().get(self.index_of_offset(offset)).unwrap();
+        let offset = offset - child_offset;
+        match child {
+            NodeOrToken::Node(node) => node.is_char_boundary(offset),
+            NodeOrToken::Token(token) => match token.try_text() {
+                Some(text) => text.is_char_boundary(offset.into()),
+                None => true,
+            },
+        }
+    }
+
+    /// The offsets of the boundaries between tokens in this node, in order.
+    ///
+    /// This includes the start and end of this node. Empty tokens don't
+    /// introduce any new boundaries, so every offset is yielded only once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind, TextSize};
+    /// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+    /// let tree = green::TreeBuilder::new()
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "ab")
+    ///     .token(WS, " ")
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "cd")
+    ///     .finish_node()
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// let boundaries: Vec<TextSize> = tree.token_boundaries().collect();
+    /// assert_eq!(boundaries, [0.into(), 2.into(), 3.into(), 5.into()]);
+    /// assert!(tree.is_token_boundary(3.into()));
+    /// assert!(!tree.is_token_boundary(4.into()));
+    /// ```
+    pub fn token_boundaries(&self) -> impl '_ + Iterator<Item = TextSize> {
+        let mut stack = vec![(TextSize::from(0), self.children().with_offsets())];
+        let mut last = None;
+        iter::from_fn(move || loop {
+            if last.is_none() {
+                last = Some(TextSize::from(0));
+                return last;
+            }
+            let (offset, children) = stack.last_mut()?;
+            let offset = *offset;
+            match children.next() {
+                None => {
+                    stack.pop();
+                }
+                Some((child_offset, NodeOrToken::Node(node))) => {
+                    let node = ArcBorrow::downgrade(node);
+                    stack.push((offset + child_offset, node.children().with_offsets()));
+                }
+                Some((child_offset, NodeOrToken::Token(token))) => {
+                    let end = offset + child_offset + token.len();
+                    if Some(end) != last {
+                        last = Some(end);
+                        return last;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Whether the given offset is on a boundary between tokens of this node.
+    /// See [`token_boundaries`](Node::token_boundaries).
+    ///
+    /// This descends only to the single element starting or containing the offset.
+    /// Offsets past the end of the text are not boundaries.
+    pub fn is_token_boundary(&self, offset: TextSize) -> bool {
+        if offset >= self.len() {
+            return offset == self.len();
+        }
+        let (child_offset, child) =
+            self.children().with_offsets().get(self.index_of_offset(offset)).unwrap();
+        // NB: children are contiguous, so a child starting at the offset ends a token there
+        match child {
+            _ if child_offset == offset => true,
+            NodeOrToken::Node(node) => node.is_token_boundary(offset - child_offset),
+            NodeOrToken::Token(_) => false,
+        }
+    }
+
+    /// The token containing the given offset, and the offset of its start.
+    ///
+    /// This descends from this node, skipping any empty elements at the offset.
+    /// Returns `None` if the offset is not within this node.
+    pub fn token_at_offset(&self, offset: TextSize) -> Option<(TextSize, ArcBorrow<'_, Token>)> {
+        if offset >= self.len() {
+            return None;
+        }
+        let mut node = self;
+        let mut start = TextSize::from(0);
+        loop {
+            let relative = offset - start;
+            // the last child starting at or before the offset, as any before it are empty
+            let index = node.children.partition_point(|el| el.offset() <= relative) - 1;
+            let (child_offset, child) = node.children().with_offsets().get(index).unwrap();
+            start += child_offset;
+            match child {
+                NodeOrToken::Node(child) => node = ArcBorrow::downgrade(child),
+                NodeOrToken::Token(token) => return Some((start, token)),
+            }
+        }
+    }
+
+    /// The offset of `target` within this node, found by pointer identity.
+    ///
+    /// This searches the descendants of this node in preorder, skipping
+    /// subtrees too short to contain `target`. If `target` is shared
+    /// (occurs more than once in this tree), the first occurrence is found.
+    /// This node itself is a descendant at offset 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+    /// let mut builder = green::TreeBuilder::new();
+    /// let inner = builder.start_node(LIST).token(ATOM, "b").finish_node().finish();
+    /// let tree = builder
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "a")
+    ///     .add(inner.clone())
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// assert_eq!(tree.offset_of_descendant(&inner), Some(1.into()));
+    /// assert_eq!(inner.offset_of_descendant(&tree), None);
+    /// ```
+    pub fn offset_of_descendant(&self, target: &Node) -> Option<TextSize> {
+        if ptr::eq(self, target) {
+            return Some(0.into());
+        }
+        self.children().with_offsets().find_map(|(offset, child)| match child {
+            NodeOrToken::Node(child) if child.len() >= target.len() => {
+                Some(offset + child.offset_of_descendant(target)?)
+            }
+            _ => None,
+        })
+    }
+
+    /// A truncated printout of this tree, suitable for logging.
+    ///
+    /// Only `max_depth` levels of nodes below this one are printed,
+    /// and only the first `max_children` children of each node.
+    pub fn summary(&self, max_depth: usize, max_children: usize) -> Summary<'_> {
+        Summary::new(self, max_depth, max_children)
     }
 }
 
@@ -153,33 +771,79 @@ struct ChildrenWriter {
     raw: *mut Element,
     len: usize,
     text_len: TextSize,
+    #[cfg_attr(not(feature = "node-flags"), allow(dead_code))]
+    synthesis: Synthesis,
+    #[cfg(feature = "node-flags")]
+    multiline: bool,
+    #[cfg(feature = "node-flags")]
+    flags: u8,
 }
 
+// Element is a union, so dropping it in place does nothing; take each written child instead.
 impl Drop for ChildrenWriter {
     fn drop(&mut self) {
-        unsafe {
-            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.raw, self.len));
+        for i in 0..self.len {
+            unsafe {
+                let element = &mut *self.raw.add(i);
+                drop(if i % 2 == 0 {
+                    element.full_aligned_mut().take()
+                } else {
+                    element.half_aligned_mut().take()
+                });
+            }
         }
     }
 }
 
 impl ChildrenWriter {
-    fn new(raw: *mut Element) -> Self {
-        ChildrenWriter { raw, len: 0, text_len: 0.into() }
+    fn new(raw: *mut Element, synthesis: Synthesis) -> Self {
+        ChildrenWriter {
+            raw,
+            len: 0,
+            text_len: 0.into(),
+            synthesis,
+            #[cfg(feature = "node-flags")]
+            multiline: false,
+            #[cfg(feature = "node-flags")]
+            flags: 0,
+        }
     }
 
-    unsafe fn push(&mut self, element: PackedNodeOrToken) {
+    unsafe fn push(&mut self, element: PackedNodeOrToken) -> Result<(), NodeError> {
         let offset = self.text_len;
-        self.text_len += match element.as_deref_unchecked().unpack() {
-            Enum2::A(node) => node.len(),
-            Enum2::B(token) => token.len(),
+        let len = match element.as_deref_unchecked().unpack() {
+            Enum2::A(node) => {
+                #[cfg(feature = "node-flags")]
+                {
+                    self.multiline |= node.is_multiline();
+                    self.flags |= node.synthesized_flags();
+                }
+                node.len()
+            }
+            Enum2::B(token) => {
+                #[cfg(feature = "node-flags")]
+                {
+                    self.multiline |= self.synthesis.multiline && token.bytes().contains(&b'\n');
+                    self.flags |= token.flags();
+                }
+                token.len()
+            }
         };
+        self.text_len = offset.checked_add(len).ok_or(NodeError::TextTooLong)?;
         if self.len % 2 == 0 {
             FullAlignedElement::write(self.raw.add(self.len), element, offset);
         } else {
             HalfAlignedElement::write(self.raw.add(self.len), element, offset);
         }
         self.len += 1;
+        Ok(())
+    }
+
+    /// The `flags` header of the node.
+    #[cfg(feature = "node-flags")]
+    fn flags(&self) -> u8 {
+        let multiline = if self.synthesis.multiline && self.multiline { MULTILINE_FLAG } else { 0 };
+        multiline | (self.flags & self.synthesis.flags)
     }
 
     fn finish(self) -> TextSize {
@@ -193,75 +857,78 @@ impl Node {
         let (layout, offset_0) = (Layout::new::<u16>(), 0);
         let (layout, offset_1) = layout.extend(Layout::new::<Kind>()).unwrap();
         let (layout, offset_2) = layout.extend(Layout::new::<TextSize>()).unwrap();
+        #[cfg(any(feature = "node-flags", feature = "provenance"))]
+        let layout = layout.extend(Layout::new::<HeaderExt>()).unwrap().0;
         let (layout, offset_3) = layout.extend(Layout::array::<Element>(len).unwrap()).unwrap();
         let layout = layout.align_to(8).unwrap();
         (layout.pad_to_align(), [offset_0, offset_1, offset_2, offset_3])
     }
 
     #[allow(clippy::new_ret_no_self)]
-    pub(super) fn new<A, I>(kind: Kind, mut children: I) -> A
+    pub(super) fn new<A, I>(head: NodeHead, synthesis: Synthesis, children: I) -> A
     where
-        A: AllocSliceDst<Self>,
+        A: TryAllocSliceDst<Self>,
         I: Iterator<Item = PackedNodeOrToken> + ExactSizeIterator,
     {
-        let len = children.len();
-        assert!(len <= u16::MAX as usize, "more children than fit in one node");
-        let children_len = len as u16;
-        let (layout, [children_len_offset, kind_offset, text_len_offset, children_offset]) =
-            Self::layout(len);
-
-        unsafe {
-            // SAFETY: closure fully initializes the place
-            A::new_slice_dst(len, |ptr| {
-                let raw = ptr.as_ptr().cast::<u8>();
-
-                ptr::write(raw.add(children_len_offset).cast(), children_len);
-                ptr::write(raw.add(kind_offset).cast(), kind);
-
-                let mut children_writer = ChildrenWriter::new(raw.add(children_offset).cast());
-                for _ in 0..len {
-                    let child = children.next().expect("children iterator over-reported length");
-                    children_writer.push(child);
-                }
-                assert!(children.next().is_none(), "children iterator under-reported length");
-
-                let text_len = children_writer.finish();
-                ptr::write(raw.add(text_len_offset).cast(), text_len);
-                debug_assert_eq!(layout, Layout::for_value(ptr.as_ref()));
-            })
+        match Self::try_new::<A, _, NodeError>(head, synthesis, children.map(Ok)) {
+            Ok(node) => node,
+            Err(err) => panic!("{}", err),
         }
     }
 
-    #[cfg(feature = "de")]
+    /// Fallible version of `Node::new`.
+    ///
+    /// If the children iterator errors or lies about its length, any children
+    /// already written are dropped and the allocation is freed, so the
+    /// partially initialized node is never observable.
     #[allow(clippy::new_ret_no_self)]
-    pub(super) fn try_new<A, I, E>(kind: Kind, mut children: I) -> Result<A, E>
+    pub(super) fn try_new<A, I, E>(
+        head: NodeHead,
+        synthesis: Synthesis,
+        mut children: I,
+    ) -> Result<A, E>
     where
         A: TryAllocSliceDst<Self>,
         I: Iterator<Item = Result<PackedNodeOrToken, E>> + ExactSizeIterator,
+        E: From<NodeError>,
     {
         let len = children.len();
-        assert!(len <= u16::MAX as usize, "more children than fit in one node");
+        if len > u16::MAX as usize {
+            return Err(NodeError::TooManyChildren { len }.into());
+        }
         let children_len = len as u16;
         let (layout, [children_len_offset, kind_offset, text_len_offset, children_offset]) =
             Self::layout(len);
 
         unsafe {
-            // SAFETY: closure fully initializes the place
+            // SAFETY: closure fully initializes the place, or errors and drops what it wrote
             A::try_new_slice_dst(len, |ptr| {
                 let raw = ptr.as_ptr().cast::<u8>();
 
                 ptr::write(raw.add(children_len_offset).cast(), children_len);
-                ptr::write(raw.add(kind_offset).cast(), kind);
+                ptr::write(raw.add(kind_offset).cast(), head.kind);
 
-                let mut children_writer = ChildrenWriter::new(raw.add(children_offset).cast());
+                let mut children_writer =
+                    ChildrenWriter::new(raw.add(children_offset).cast(), synthesis);
                 for _ in 0..len {
-                    let child = children.next().expect("children iterator over-reported length")?;
-                    children_writer.push(child);
+                    let child = children.next().ok_or(NodeError::IteratorLied)??;
+                    children_writer.push(child)?;
+                }
+                if children.next().is_some() {
+                    return Err(NodeError::IteratorLied.into());
                 }
-                assert!(children.next().is_none(), "children iterator under-reported length");
 
+                #[cfg(any(feature = "node-flags", feature = "provenance"))]
+                let ext = HeaderExt {
+                    #[cfg(feature = "provenance")]
+                    provenance: head.provenance,
+                    #[cfg(feature = "node-flags")]
+                    flags: children_writer.flags(),
+                };
                 let text_len = children_writer.finish();
                 ptr::write(raw.add(text_len_offset).cast(), text_len);
+                #[cfg(any(feature = "node-flags", feature = "provenance"))]
+                ptr::write(raw.add(text_len_offset + 4).cast(), ext);
                 debug_assert_eq!(layout, Layout::for_value(ptr.as_ref()));
                 Ok(())
             })
@@ -269,6 +936,35 @@ impl Node {
     }
 }
 
+/// Error for an invalid set of children for a node. See [`Builder::try_node`].
+///
+///   [`Builder::try_node`]: crate::green::Builder::try_node
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum NodeError {
+    /// There are more children than fit in one node (`u16::MAX`).
+    #[allow(missing_docs)]
+    TooManyChildren { len: usize },
+    /// The children iterator yielded a different number of children than its reported length.
+    IteratorLied,
+    /// The children have more text than fits in one node.
+    TextTooLong,
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeError::TooManyChildren { len } => {
+                write!(f, "{} children is more than fit in one node", len)
+            }
+            NodeError::IteratorLied => write!(f, "children iterator misreported its length"),
+            NodeError::TextTooLong => write!(f, "children have more text than fits in one node"),
+        }
+    }
+}
+
+impl Error for NodeError {}
+
 // SAFETY: un/erase correctly round-trips a pointer
 unsafe impl Erasable for Node {
     unsafe fn unerase(this: ErasedPtr) -> ptr::NonNull<Self> {
@@ -0,0 +1,117 @@
+use {
+    crate::{
+        green::{edit::EditResult, Node, Token},
+        ArcBorrow, NodeOrToken, TextRange, TextSize,
+    },
+    std::sync::Arc,
+};
+
+/// Lookup table from text offsets to the tokens of a tree.
+///
+/// [`Node::token_at_offset`] descends from the root on every query;
+/// building this index once makes each query a binary search over the tokens.
+/// After editing the tree, the index can be [updated](OffsetIndex::update)
+/// by reindexing only the replaced text.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, OffsetIndex}, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let tree = green::TreeBuilder::new()
+///     .start_node(LIST)
+///     .token(ATOM, "a")
+///     .start_node(LIST)
+///     .token(ATOM, "bc")
+///     .finish_node()
+///     .finish_node()
+///     .finish();
+/// let index = OffsetIndex::new(tree);
+/// let (offset, token) = index.token_at_offset(2.into()).unwrap();
+/// assert_eq!((offset, token.text()), (1.into(), "bc"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct OffsetIndex {
+    root: Arc<Node>,
+    tokens: Vec<(TextSize, Arc<Token>)>, // NB: nonempty tokens, sorted by offset
+}
+
+impl OffsetIndex {
+    /// Index the tokens of a tree.
+    pub fn new(root: Arc<Node>) -> Self {
+        let mut tokens = vec![];
+        collect(&mut tokens, &root, 0.into(), TextRange::up_to(root.len()));
+        OffsetIndex { root, tokens }
+    }
+
+    /// The indexed root.
+    pub fn root(&self) -> &Arc<Node> {
+        &self.root
+    }
+
+    /// The token containing the given offset, and the offset of its start.
+    ///
+    /// Returns `None` if the offset is not within the root.
+    pub fn token_at_offset(&self, offset: TextSize) -> Option<(TextSize, &Arc<Token>)> {
+        if offset >= self.root.len() {
+            return None;
+        }
+        let index = self.tokens.partition_point(|&(start, _)| start <= offset) - 1;
+        let (start, token) = &self.tokens[index];
+        Some((*start, token))
+    }
+
+    /// Update the index to the result of editing its root.
+    ///
+    /// Tokens outside of the edited text are shifted, not reindexed.
+    /// The edit must have been made to this index's root.
+    pub fn update(&mut self, edit: &EditResult) {
+        let changes: Vec<_> = edit.changes().collect();
+        let mut pending = changes.iter().peekable();
+        let (mut added, mut removed) = (TextSize::from(0), TextSize::from(0));
+        let mut tokens = Vec::with_capacity(self.tokens.len());
+        for (offset, token) in self.tokens.drain(..) {
+            while let Some(&&(old, new)) = pending.peek() {
+                if old.end() > offset {
+                    break;
+                }
+                added += new.len();
+                removed += old.len();
+                pending.next();
+            }
+            if matches!(pending.peek(), Some((old, _)) if old.contains(offset)) {
+                continue; // replaced
+            }
+            tokens.push((offset + added - removed, token));
+        }
+
+        self.root = Arc::clone(edit.root());
+        for &(_, new) in &changes {
+            collect(&mut tokens, &self.root, 0.into(), new);
+        }
+        tokens.sort_unstable_by_key(|&(offset, _)| offset);
+        self.tokens = tokens;
+    }
+}
+
+/// Collect the nonempty tokens of `node`, which starts at `offset`, that are within `range`.
+fn collect(
+    tokens: &mut Vec<(TextSize, Arc<Token>)>,
+    node: &Node,
+    offset: TextSize,
+    range: TextRange,
+) {
+    for (child_offset, child) in node.children().with_offsets() {
+        let start = offset + child_offset;
+        if start >= range.end() {
+            break;
+        }
+        if child.len() == 0.into() || start + child.len() <= range.start() {
+            continue;
+        }
+        match child {
+            NodeOrToken::Node(node) => collect(tokens, &node, start, range),
+            NodeOrToken::Token(token) => tokens.push((start, ArcBorrow::upgrade(token))),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use {
+    crate::green::Node,
+    std::{
+        fmt, iter, ptr,
+        sync::{
+            atomic::{AtomicPtr, Ordering},
+            Arc,
+        },
+    },
+};
+
+/// An arena of trees kept alive for as long as the pool, handed out as plain references.
+///
+/// Long-lived reference trees, such as a preparsed standard library, can be
+/// frozen into a pool to be shared as `&Node` rather than `Arc<Node>`,
+/// avoiding reference count traffic entirely. Frozen trees are only freed
+/// when the pool is dropped, so a pool in a `static` is never freed, and
+/// hands out `&'static Node`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, Pool}, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// static PRELUDE: Pool = Pool::new();
+///
+/// let tree = green::TreeBuilder::new()
+///     .start_node(LIST)
+///     .token(ATOM, "prelude")
+///     .finish_node()
+///     .finish();
+/// let prelude: &'static green::Node = PRELUDE.freeze(tree);
+/// assert_eq!(prelude.children_len(), 1);
+/// ```
+pub struct Pool {
+    // NB: a push-only linked list rather than a `Mutex<Vec<_>>`, so `Pool::new` can be `const`
+    head: AtomicPtr<Frozen>,
+}
+
+struct Frozen {
+    root: Arc<Node>,
+    next: *mut Frozen,
+}
+
+impl Pool {
+    /// Create a new, empty pool.
+    pub const fn new() -> Self {
+        Pool { head: AtomicPtr::new(ptr::null_mut()) }
+    }
+
+    /// Keep a tree alive for as long as this pool, and borrow it for as long.
+    pub fn freeze(&self, node: Arc<Node>) -> &Node {
+        let frozen = Box::into_raw(Box::new(Frozen { root: node, next: ptr::null_mut() }));
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: `frozen` isn't shared until the exchange succeeds
+            unsafe { (*frozen).next = head };
+            match self.head.compare_exchange_weak(
+                head,
+                frozen,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => head = actual,
+            }
+        }
+        // SAFETY: the pool holds a strong handle to the node until the pool is dropped
+        unsafe { &(*frozen).root }
+    }
+
+    fn frozen(&self) -> impl Iterator<Item = &Frozen> {
+        let mut next = self.head.load(Ordering::Acquire);
+        iter::from_fn(move || {
+            // SAFETY: frozen entries are only freed when the pool is dropped
+            let frozen = unsafe { next.as_ref()? };
+            next = frozen.next;
+            Some(frozen)
+        })
+    }
+
+    /// The number of trees frozen in this pool.
+    pub fn len(&self) -> usize {
+        self.frozen().count()
+    }
+
+    /// Whether no trees are frozen in this pool.
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        let mut next = *self.head.get_mut();
+        while !next.is_null() {
+            // SAFETY: each entry was leaked from a box in `freeze` and is only freed here
+            let frozen = unsafe { Box::from_raw(next) };
+            next = frozen.next;
+        }
+    }
+}
+
+impl Default for Pool {
+    fn default() -> Self {
+        Pool::new()
+    }
+}
+
+impl fmt::Debug for Pool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool").field("len", &self.len()).finish()
+    }
+}
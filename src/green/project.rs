@@ -0,0 +1,371 @@
+//! Archives of whole projects, sharing subtrees across files.
+//!
+//! When the trees of many files are built with one [`Builder`], identical
+//! subtrees are shared between files as well as within them. A project
+//! archive records each distinct node and token once, however many files
+//! it occurs in, and can load each file back on its own.
+//!
+//! # Format
+//!
+//! All integers are little-endian.
+//!
+//! ```text
+//! magic        b"SORBPRJ1"
+//! counts       tokens: u32, nodes: u32, files: u32
+//! token index  [u64; tokens] (archive offset of each token record)
+//! node index   [u64; nodes] (archive offset of each node record)
+//! files        [(root: u32, path_len: u32, path: [u8; path_len]); files]
+//! token record kind: u16, binary: u8, len: u32, bytes: [u8; len]
+//! node record  kind: u16, children_len: u16, children: [u32; children_len]
+//! ```
+//!
+//! A child is the index of a token with the high bit set, or of a node
+//! without it. Nodes are recorded in postorder, so a node's children
+//! always have lower indices than it.
+
+use {
+    crate::{
+        green::{Builder, Node, NodeError, Token},
+        ArcBorrow, Kind, NodeOrToken,
+    },
+    std::{
+        collections::HashMap,
+        convert::{TryFrom, TryInto},
+        error::Error,
+        fmt, str,
+        sync::Arc,
+    },
+};
+
+const MAGIC: &[u8; 8] = b"SORBPRJ1";
+const TOKEN_FLAG: u32 = 1 << 31;
+
+/// Write the trees of a project's files into one archive.
+///
+/// Distinct nodes and tokens are written once, so subtrees shared between
+/// files by a common [`Builder`] are only stored once.
+///
+/// # Panics
+///
+/// Panics if the project has more than `2^31` distinct nodes or tokens.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, project}, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let mut builder = green::TreeBuilder::new();
+/// let mut parse = |text: &str| {
+///     builder.start_node(LIST);
+///     for word in text.split_whitespace() {
+///         builder.token(ATOM, word);
+///     }
+///     builder.finish_node().finish()
+/// };
+/// let files = vec![("a.txt", parse("x y z")), ("b.txt", parse("y z"))];
+/// let bytes = project::write(files.clone());
+///
+/// let archive = project::Archive::new(&bytes).unwrap();
+/// assert_eq!(archive.paths().collect::<Vec<_>>(), ["a.txt", "b.txt"]);
+/// let b = archive.load("b.txt", builder.builder()).unwrap().unwrap();
+/// assert_eq!(b, files[1].1);
+/// ```
+pub fn write<P, N>(files: impl IntoIterator<Item = (P, N)>) -> Vec<u8>
+where
+    P: AsRef<str>,
+    N: AsRef<Node>,
+{
+    let mut writer = Writer::default();
+    let files: Vec<(P, u32)> = files
+        .into_iter()
+        .map(|(path, root)| {
+            let root = writer.node(root.as_ref());
+            (path, root)
+        })
+        .collect();
+
+    let mut out = MAGIC.to_vec();
+    for count in &[writer.tokens.len(), writer.nodes.len(), files.len()] {
+        out.extend(&u32::try_from(*count).unwrap().to_le_bytes());
+    }
+    let index_len = 8 * (writer.tokens.len() + writer.nodes.len());
+    let files_len: usize = files.iter().map(|(path, _)| 8 + path.as_ref().len()).sum();
+    let mut offset = (out.len() + index_len + files_len) as u64;
+    for record in writer.tokens.iter().chain(&writer.nodes) {
+        out.extend(&offset.to_le_bytes());
+        offset += record.len() as u64;
+    }
+    for (path, root) in &files {
+        let path = path.as_ref();
+        out.extend(&root.to_le_bytes());
+        out.extend(&u32::try_from(path.len()).unwrap().to_le_bytes());
+        out.extend(path.as_bytes());
+    }
+    for record in writer.tokens.iter().chain(&writer.nodes) {
+        out.extend(record);
+    }
+    out
+}
+
+/// The records of distinct elements, in the order they are indexed.
+#[derive(Default)]
+struct Writer {
+    tokens: Vec<Vec<u8>>,
+    token_index: HashMap<*const Token, u32>,
+    nodes: Vec<Vec<u8>>,
+    node_index: HashMap<*const Node, u32>,
+}
+
+impl Writer {
+    fn node(&mut self, node: &Node) -> u32 {
+        if let Some(&index) = self.node_index.get(&(node as *const Node)) {
+            return index;
+        }
+        let mut record = vec![];
+        record.extend(&node.kind().0.to_le_bytes());
+        record.extend(&(node.children_len() as u16).to_le_bytes());
+        for child in node.children() {
+            let child = match child {
+                NodeOrToken::Node(node) => self.node(ArcBorrow::downgrade(node)),
+                NodeOrToken::Token(token) => self.token(ArcBorrow::downgrade(token)),
+            };
+            record.extend(&child.to_le_bytes());
+        }
+        let index = index(self.nodes.len());
+        self.nodes.push(record);
+        self.node_index.insert(node, index);
+        index
+    }
+
+    fn token(&mut self, token: &Token) -> u32 {
+        if let Some(&index) = self.token_index.get(&(token as *const Token)) {
+            return index | TOKEN_FLAG;
+        }
+        let mut record = vec![];
+        record.extend(&token.kind().0.to_le_bytes());
+        record.push(token.is_binary() as u8);
+        record.extend(&u32::from(token.len()).to_le_bytes());
+        record.extend(token.bytes());
+        let index = index(self.tokens.len());
+        self.tokens.push(record);
+        self.token_index.insert(token, index);
+        index | TOKEN_FLAG
+    }
+}
+
+fn index(len: usize) -> u32 {
+    u32::try_from(len).ok().filter(|&index| index < TOKEN_FLAG).expect("too many elements")
+}
+
+/// A project archive written by [`write()`], for loading files from lazily.
+///
+/// Creating an archive only reads its index; each file's tree is decoded
+/// when it is [loaded](Archive::load).
+pub struct Archive<'a> {
+    bytes: &'a [u8],
+    token_offsets: &'a [u8],
+    node_offsets: &'a [u8],
+    files: Vec<(&'a str, u32)>,
+}
+
+impl<'a> Archive<'a> {
+    /// Read the index of an archive.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, ArchiveError> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.slice(MAGIC.len())? != MAGIC {
+            return Err(ArchiveError::BadMagic);
+        }
+        let tokens = reader.u32()? as usize;
+        let nodes = reader.u32()? as usize;
+        let file_count = reader.u32()?;
+        let token_offsets = reader.slice(tokens.checked_mul(8).ok_or(ArchiveError::Truncated)?)?;
+        let node_offsets = reader.slice(nodes.checked_mul(8).ok_or(ArchiveError::Truncated)?)?;
+        let mut files = vec![];
+        for _ in 0..file_count {
+            let root = reader.u32()?;
+            if root as usize >= nodes {
+                return Err(ArchiveError::InvalidIndex);
+            }
+            let len = reader.u32()? as usize;
+            let path = str::from_utf8(reader.slice(len)?).map_err(|_| ArchiveError::InvalidUtf8)?;
+            files.push((path, root));
+        }
+        Ok(Archive { bytes, token_offsets, node_offsets, files })
+    }
+
+    /// The paths of the files in this archive, in the order they were written.
+    pub fn paths(&self) -> impl '_ + Iterator<Item = &'a str> {
+        self.files.iter().map(|&(path, _)| path)
+    }
+
+    /// Load the tree of the file at `path`, or `None` if it is not in this archive.
+    ///
+    /// Only the elements of this file are decoded. Loading several files
+    /// with the same builder shares their common subtrees again.
+    pub fn load(
+        &self,
+        path: &str,
+        builder: &mut Builder,
+    ) -> Option<Result<Arc<Node>, ArchiveError>> {
+        let &(_, root) = self.files.iter().find(|&&(file, _)| file == path)?;
+        Some(self.load_node(root, builder))
+    }
+
+    fn load_node(&self, root: u32, builder: &mut Builder) -> Result<Arc<Node>, ArchiveError> {
+        let mut nodes: HashMap<u32, Arc<Node>> = HashMap::new();
+        let mut tokens: HashMap<u32, Arc<Token>> = HashMap::new();
+        // nodes are built bottom-up, after all of their children are built
+        let mut stack = vec![(root, false)];
+        while let Some((index, children_built)) = stack.pop() {
+            if nodes.contains_key(&index) {
+                continue;
+            }
+            let (kind, children) = self.node_record(index)?;
+            if !children_built {
+                stack.push((index, true));
+                for child in children.clone() {
+                    match child {
+                        NodeOrToken::Node(child) if child >= index => {
+                            return Err(ArchiveError::InvalidIndex)
+                        }
+                        NodeOrToken::Node(child) => stack.push((child, false)),
+                        NodeOrToken::Token(_) => {}
+                    }
+                }
+                continue;
+            }
+            let children = children
+                .map(|child| match child {
+                    NodeOrToken::Node(child) => Ok(NodeOrToken::Node(Arc::clone(&nodes[&child]))),
+                    NodeOrToken::Token(child) => match tokens.get(&child) {
+                        Some(token) => Ok(NodeOrToken::Token(Arc::clone(token))),
+                        None => {
+                            let token = self.token(child, builder)?;
+                            tokens.insert(child, Arc::clone(&token));
+                            Ok(NodeOrToken::Token(token))
+                        }
+                    },
+                })
+                .collect::<Result<Vec<_>, ArchiveError>>()?;
+            let node = builder.try_node(kind, children)?;
+            nodes.insert(index, node);
+        }
+        Ok(nodes.remove(&root).unwrap())
+    }
+
+    fn offset(offsets: &[u8], index: u32) -> Result<usize, ArchiveError> {
+        let at = index as usize * 8;
+        let offset = offsets.get(at..at + 8).ok_or(ArchiveError::InvalidIndex)?;
+        usize::try_from(u64::from_le_bytes(offset.try_into().unwrap()))
+            .map_err(|_| ArchiveError::Truncated)
+    }
+
+    fn node_record(
+        &self,
+        index: u32,
+    ) -> Result<(Kind, impl Clone + Iterator<Item = NodeOrToken<u32, u32>> + 'a), ArchiveError>
+    {
+        let mut reader = Reader { bytes: self.bytes, pos: Self::offset(self.node_offsets, index)? };
+        let kind = Kind(reader.u16()?);
+        let len = reader.u16()? as usize;
+        let children = reader.slice(len * 4)?.chunks(4).map(|child| {
+            let child = u32::from_le_bytes(child.try_into().unwrap());
+            if child & TOKEN_FLAG == 0 {
+                NodeOrToken::Node(child)
+            } else {
+                NodeOrToken::Token(child & !TOKEN_FLAG)
+            }
+        });
+        Ok((kind, children))
+    }
+
+    fn token(&self, index: u32, builder: &mut Builder) -> Result<Arc<Token>, ArchiveError> {
+        let mut reader =
+            Reader { bytes: self.bytes, pos: Self::offset(self.token_offsets, index)? };
+        let kind = Kind(reader.u16()?);
+        let binary = reader.slice(1)?[0] != 0;
+        let len = reader.u32()? as usize;
+        let bytes = reader.slice(len)?;
+        if binary {
+            Ok(builder.binary_token(kind, bytes))
+        } else {
+            let text = str::from_utf8(bytes).map_err(|_| ArchiveError::InvalidUtf8)?;
+            Ok(builder.token(kind, text))
+        }
+    }
+}
+
+impl fmt::Debug for Archive<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Archive")
+            .field("len", &self.bytes.len())
+            .field("paths", &self.paths().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn slice(&mut self, len: usize) -> Result<&'a [u8], ArchiveError> {
+        let end = self.pos.checked_add(len).ok_or(ArchiveError::Truncated)?;
+        let slice = self.bytes.get(self.pos..end).ok_or(ArchiveError::Truncated)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> Result<u16, ArchiveError> {
+        Ok(u16::from_le_bytes(self.slice(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, ArchiveError> {
+        Ok(u32::from_le_bytes(self.slice(4)?.try_into().unwrap()))
+    }
+}
+
+/// An error reading a project [`Archive`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ArchiveError {
+    /// The archive does not start with the project archive magic number.
+    BadMagic,
+    /// The archive ended in the middle of a record.
+    Truncated,
+    /// A record referred to an element which does not exist,
+    /// or a node referred to a node not before it.
+    InvalidIndex,
+    /// A path or text token was not UTF-8.
+    InvalidUtf8,
+    /// A node could not be built.
+    Node(NodeError),
+}
+
+impl From<NodeError> for ArchiveError {
+    fn from(err: NodeError) -> Self {
+        ArchiveError::Node(err)
+    }
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::BadMagic => write!(f, "not a sorbus project archive"),
+            ArchiveError::Truncated => write!(f, "project archive is truncated"),
+            ArchiveError::InvalidIndex => write!(f, "project archive has an invalid element index"),
+            ArchiveError::InvalidUtf8 => write!(f, "project archive has invalid UTF-8 text"),
+            ArchiveError::Node(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for ArchiveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ArchiveError::Node(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,250 @@
+//! Declarative structural matching of green trees.
+//!
+//! A [`Pattern`] describes the shape of a tree by kinds, with wildcards
+//! for the parts that don't matter and captures for the parts of interest,
+//! replacing hand-written nested loops over children.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use sorbus::{green::{self, query::Pattern}, Kind, TextRange};
+//! const WS: Kind = Kind(0);
+//! const ATOM: Kind = Kind(1);
+//! const CALL: Kind = Kind(2);
+//! const ARGS: Kind = Kind(3);
+//!
+//! let tree = green::TreeBuilder::new()
+//!     .start_node(CALL)
+//!     .token(ATOM, "f")
+//!     .start_node(ARGS)
+//!     .token(ATOM, "x")
+//!     .token(WS, " ")
+//!     .token(ATOM, "y")
+//!     .finish_node()
+//!     .finish_node()
+//!     .finish();
+//!
+//! // a call whose arguments start with an atom
+//! let pattern = Pattern::Node(
+//!     CALL,
+//!     vec![
+//!         Pattern::Kind(ATOM).capture("callee"),
+//!         Pattern::Node(ARGS, vec![Pattern::Kind(ATOM).capture("first"), Pattern::Rest]),
+//!     ],
+//! );
+//! let captures = pattern.matches(&tree).unwrap();
+//! assert_eq!(captures[0].name, "callee");
+//! assert_eq!(captures[1].name, "first");
+//! assert_eq!(captures[1].range, TextRange::new(1.into(), 2.into()));
+//! ```
+
+use {
+    crate::{
+        green::{Builder, ElementRef, Node, Token},
+        ArcBorrow, Kind, NodeOrToken, TextRange, TextSize,
+    },
+    std::sync::Arc,
+};
+
+/// A structural pattern over green elements.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Pattern {
+    /// Matches any single element.
+    Any,
+    /// Matches any number of elements, including none.
+    ///
+    /// Only meaningful directly within the children of a [`Node`](Pattern::Node)
+    /// pattern; anywhere else it behaves like [`Any`](Pattern::Any).
+    Rest,
+    /// Matches any node or token of the kind.
+    Kind(Kind),
+    /// Matches a node of the kind, with children matching the patterns in order.
+    Node(Kind, Vec<Pattern>),
+    /// Matches the pattern, capturing the matched element under a name.
+    Capture(String, Box<Pattern>),
+}
+
+/// An element captured by a [`Pattern`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Capture<'a> {
+    /// The name of the capture.
+    pub name: &'a str,
+    /// The captured element.
+    pub element: ElementRef<'a>,
+    /// The range of the captured element, relative to the start of the matched node.
+    pub range: TextRange,
+}
+
+impl Pattern {
+    /// Capture the element matched by this pattern under a name.
+    pub fn capture(self, name: impl Into<String>) -> Self {
+        Pattern::Capture(name.into(), Box::new(self))
+    }
+
+    /// Match this pattern against a node, returning the captured elements
+    /// in the order their patterns appear in this pattern.
+    pub fn matches<'a>(&'a self, node: &'a Arc<Node>) -> Option<Vec<Capture<'a>>> {
+        let mut captures = vec![];
+        let element = NodeOrToken::Node(ArcBorrow::from(node));
+        if self.match_element(0.into(), element, &mut captures) {
+            Some(captures)
+        } else {
+            None
+        }
+    }
+
+    fn match_element<'a>(
+        &'a self,
+        offset: TextSize,
+        element: ElementRef<'a>,
+        captures: &mut Vec<Capture<'a>>,
+    ) -> bool {
+        match self {
+            Pattern::Any | Pattern::Rest => true,
+            Pattern::Kind(kind) => element.kind() == *kind,
+            Pattern::Node(kind, children) => match element {
+                NodeOrToken::Node(node) if node.kind() == *kind => {
+                    let elements: Vec<_> = ArcBorrow::downgrade(node)
+                        .children()
+                        .with_offsets()
+                        .map(|(child_offset, child)| (offset + child_offset, child))
+                        .collect();
+                    match_children(children, &elements, captures)
+                }
+                _ => false,
+            },
+            Pattern::Capture(name, pattern) => {
+                let mark = captures.len();
+                if !pattern.match_element(offset, element, captures) {
+                    return false;
+                }
+                let range = TextRange::at(offset, element.len());
+                captures.insert(mark, Capture { name, element, range });
+                true
+            }
+        }
+    }
+}
+
+/// Match a sequence of patterns against a sequence of elements,
+/// backtracking over how many elements each [`Pattern::Rest`] matches.
+fn match_children<'a>(
+    patterns: &'a [Pattern],
+    elements: &[(TextSize, ElementRef<'a>)],
+    captures: &mut Vec<Capture<'a>>,
+) -> bool {
+    let mark = captures.len();
+    let matched = match patterns.split_first() {
+        None => elements.is_empty(),
+        Some((Pattern::Rest, patterns)) => {
+            (0..=elements.len()).any(|skip| match_children(patterns, &elements[skip..], captures))
+        }
+        Some((pattern, patterns)) => match elements.split_first() {
+            Some((&(offset, element), elements)) => {
+                pattern.match_element(offset, element, captures)
+                    && match_children(patterns, elements, captures)
+            }
+            None => false,
+        },
+    };
+    if !matched {
+        captures.truncate(mark);
+    }
+    matched
+}
+
+/// Replace every node matching `pattern` within `root` with the result of `rewrite`,
+/// returning the new root and the number of nodes replaced.
+///
+/// Matches are rewritten bottom-up: the children of a node are rewritten
+/// before the node itself is matched, so `rewrite` sees the already rewritten
+/// children. If `rewrite` returns `None`, the node is kept as is.
+/// Subtrees without any replacements are shared with the original tree.
+///
+/// # Panics
+///
+/// Panics if `root` itself is replaced with a token.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, query::{self, Pattern}}, Kind, NodeOrToken};
+/// # const ATOM: Kind = Kind(0); const PAREN: Kind = Kind(1); const LIST: Kind = Kind(2);
+/// let mut builder = green::TreeBuilder::new();
+/// #[rustfmt::skip]
+/// let tree = builder
+///     .start_node(LIST)
+///         .start_node(LIST)
+///             .token(PAREN, "(")
+///             .token(ATOM, "a")
+///             .token(PAREN, ")")
+///         .finish_node()
+///         .token(ATOM, "b")
+///     .finish_node()
+///     .finish();
+///
+/// // unwrap parenthesized atoms
+/// let pattern = Pattern::Node(
+///     LIST,
+///     vec![Pattern::Kind(PAREN), Pattern::Kind(ATOM).capture("atom"), Pattern::Kind(PAREN)],
+/// );
+/// let (rewritten, count) = query::replace_all(builder.builder(), &tree, &pattern, |_, _, captures| {
+///     Some(captures[0].element.upgrade())
+/// });
+/// assert_eq!(count, 1);
+/// let expected = builder.start_node(LIST).token(ATOM, "a").token(ATOM, "b").finish_node().finish();
+/// assert_eq!(rewritten, expected);
+/// ```
+pub fn replace_all(
+    builder: &mut Builder,
+    root: &Arc<Node>,
+    pattern: &Pattern,
+    mut rewrite: impl FnMut(
+        &mut Builder,
+        &Arc<Node>,
+        &[Capture<'_>],
+    ) -> Option<NodeOrToken<Arc<Node>, Arc<Token>>>,
+) -> (Arc<Node>, usize) {
+    let mut count = 0;
+    match replace_in(builder, root, pattern, &mut rewrite, &mut count) {
+        None => (Arc::clone(root), count),
+        Some(NodeOrToken::Node(root)) => (root, count),
+        Some(NodeOrToken::Token(_)) => panic!("cannot replace the root with a token"),
+    }
+}
+
+type Rewrite<'a> = dyn 'a
+    + FnMut(&mut Builder, &Arc<Node>, &[Capture<'_>]) -> Option<NodeOrToken<Arc<Node>, Arc<Token>>>;
+
+/// Rewrite the matches within `node` bottom-up, returning its replacement if it changed.
+fn replace_in(
+    builder: &mut Builder,
+    node: &Arc<Node>,
+    pattern: &Pattern,
+    rewrite: &mut Rewrite<'_>,
+    count: &mut usize,
+) -> Option<NodeOrToken<Arc<Node>, Arc<Token>>> {
+    let mut changed = false;
+    let mut children = Vec::with_capacity(node.children_len());
+    for child in node.children() {
+        let child = child.upgrade();
+        let replacement = match &child {
+            NodeOrToken::Node(child) => replace_in(builder, child, pattern, rewrite, count),
+            NodeOrToken::Token(_) => None,
+        };
+        changed |= replacement.is_some();
+        children.push(replacement.unwrap_or(child));
+    }
+
+    let node = match changed {
+        true => builder.node_like(node, children),
+        false => Arc::clone(node),
+    };
+    if let Some(captures) = pattern.matches(&node) {
+        if let Some(replacement) = rewrite(builder, &node, &captures) {
+            *count += 1;
+            return Some(replacement);
+        }
+    }
+    Some(NodeOrToken::Node(node)).filter(|_| changed)
+}
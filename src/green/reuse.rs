@@ -0,0 +1,80 @@
+//! Support for reusing green trees in incremental reparsing.
+//!
+//! After an edit, an incremental parser only has to reparse a region of the
+//! text that grammatically stands on its own, such as a block or an item, and
+//! can keep the rest of the old tree. Which kinds of node are "reparseable"
+//! like this depends on the language, so it's given as a predicate.
+
+use {
+    crate::{green::Node, ArcBorrow, NodeOrToken, TextRange, TextSize},
+    std::sync::Arc,
+};
+
+/// The smallest reparseable node enclosing the edited `range` of the old
+/// tree, and its range in the old tree.
+///
+/// A node only encloses the edit if the edit is strictly inside it: an edit
+/// touching either end of a node may join it with its neighbor, so the node
+/// cannot be reparsed on its own. The root always encloses any edit, whether
+/// or not it is reparseable, so a full reparse is the fallback.
+///
+/// # Panics
+///
+/// Panics if `range` is not within the tree.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, reuse}, Kind, TextRange};
+/// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const BLOCK: Kind = Kind(2); const FILE: Kind = Kind(3);
+/// let tree = green::TreeBuilder::new()
+///     .start_node(FILE)
+///     .start_node(BLOCK)
+///     .token(ATOM, "{")
+///     .token(ATOM, "a")
+///     .token(ATOM, "}")
+///     .finish_node()
+///     .token(WS, "\n")
+///     .finish_node()
+///     .finish();
+/// let is_block = |node: &green::Node| node.kind() == BLOCK;
+///
+/// // replacing `a` only requires reparsing the block
+/// let edit = TextRange::new(1.into(), 2.into());
+/// let (range, node) = reuse::damaged_range(&tree, edit, is_block);
+/// assert_eq!((range, node.kind()), (TextRange::new(0.into(), 3.into()), BLOCK));
+///
+/// // but inserting after the block may extend it
+/// let edit = TextRange::empty(3.into());
+/// let (range, node) = reuse::damaged_range(&tree, edit, is_block);
+/// assert_eq!((range, node.kind()), (TextRange::up_to(tree.len()), FILE));
+/// ```
+pub fn damaged_range<'a>(
+    root: &'a Arc<Node>,
+    range: TextRange,
+    mut reparseable: impl FnMut(&Node) -> bool,
+) -> (TextRange, ArcBorrow<'a, Node>) {
+    assert!(range.end() <= root.len(), "edit range is outside of the tree");
+    let mut node = ArcBorrow::from(root);
+    let mut offset = TextSize::from(0);
+    let mut damaged = (TextRange::up_to(root.len()), node);
+    'descend: loop {
+        for (child_offset, child) in ArcBorrow::downgrade(node).children().with_offsets() {
+            let child_range = TextRange::at(offset + child_offset, child.len());
+            if child_range.start() < range.start() && range.end() < child_range.end() {
+                if let NodeOrToken::Node(child) = child {
+                    node = child;
+                    offset = child_range.start();
+                    if reparseable(&child) {
+                        damaged = (child_range, child);
+                    }
+                    continue 'descend;
+                }
+            }
+            if child_range.start() >= range.start() {
+                break;
+            }
+        }
+        return damaged;
+    }
+}
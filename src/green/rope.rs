@@ -0,0 +1,139 @@
+//! Integration with [`ropey`] ropes, for editors storing their text as a rope.
+//!
+//! Offsets in green trees are UTF-8 byte offsets, whereas ropey mostly
+//! addresses text by char index; [`char_range`] and [`text_range`] convert
+//! between the two. Tokens can be built from and trees verified against
+//! rope slices directly, without copying the rope's text into a `String`.
+
+use {
+    crate::{
+        green::{Builder, Mismatch, Node, Token},
+        ArcBorrow, Kind, NodeOrToken, TextRange, TextSize,
+    },
+    ropey::{iter::Chunks, RopeSlice},
+    std::{convert::TryFrom, ops::Range, sync::Arc},
+};
+
+impl Builder {
+    /// Create a new token with the text of a rope slice,
+    /// or clone a new Arc to an existing equivalent one.
+    ///
+    /// See [`token_from_chunks`](Builder::token_from_chunks).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// let rope = ropey::Rope::from_str("fn main() {}");
+    /// let token = green::Builder::new().token_from_rope(Kind(0), rope.slice(3..7));
+    /// assert_eq!(token.text(), "main");
+    /// ```
+    pub fn token_from_rope(&mut self, kind: Kind, slice: RopeSlice<'_>) -> Arc<Token> {
+        self.token_from_chunks(kind, slice.chunks(), offset(slice.len_bytes()))
+    }
+}
+
+/// The range of char indices in `rope` of the byte range `range`.
+///
+/// # Panics
+///
+/// Panics if `range` is out of bounds of `rope`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::rope, TextRange};
+/// let rope = ropey::Rope::from_str("héllo");
+/// assert_eq!(rope::char_range(rope.slice(..), TextRange::new(1.into(), 4.into())), 1..3);
+/// ```
+pub fn char_range(rope: RopeSlice<'_>, range: TextRange) -> Range<usize> {
+    rope.byte_to_char(range.start().into())..rope.byte_to_char(range.end().into())
+}
+
+/// The byte range in `rope` of the range of char indices `chars`.
+///
+/// # Panics
+///
+/// Panics if `chars` is out of bounds of `rope`.
+pub fn text_range(rope: RopeSlice<'_>, chars: Range<usize>) -> TextRange {
+    TextRange::new(offset(rope.char_to_byte(chars.start)), offset(rope.char_to_byte(chars.end)))
+}
+
+/// Check that a tree losslessly represents the text of a rope.
+/// See [`verify_text`](crate::green::verify_text).
+///
+/// The expected slice of a mismatch is limited to the rope chunk containing the mismatch.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, rope}, Kind};
+/// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+/// let tree = green::TreeBuilder::new()
+///     .start_node(LIST)
+///     .token(ATOM, "a")
+///     .token(WS, " ")
+///     .token(ATOM, "bc")
+///     .finish_node()
+///     .finish();
+///
+/// let source = ropey::Rope::from_str("a bc");
+/// assert!(rope::verify_text(&tree, source.slice(..)).is_ok());
+/// let mismatch = rope::verify_text(&tree, source.slice(..3)).unwrap_err();
+/// assert_eq!(mismatch.offset, 3.into());
+/// ```
+pub fn verify_text<'a>(root: &'a Node, rope: RopeSlice<'a>) -> Result<(), Mismatch<'a>> {
+    let mut cursor = Cursor { chunks: rope.chunks(), chunk: &[], pos: 0 };
+    verify_node(root, &mut cursor)?;
+    match cursor.peek_chunk() {
+        None => Ok(()),
+        Some(expected) => Err(Mismatch { offset: offset(cursor.pos), expected, found: &[] }),
+    }
+}
+
+/// Position in the text of a rope, by chunk.
+struct Cursor<'a> {
+    chunks: Chunks<'a>,
+    chunk: &'a [u8], // NB: the rest of the current chunk
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// The rest of the current chunk, or the next nonempty chunk.
+    fn peek_chunk(&mut self) -> Option<&'a [u8]> {
+        while self.chunk.is_empty() {
+            self.chunk = self.chunks.next()?.as_bytes();
+        }
+        Some(self.chunk)
+    }
+}
+
+fn verify_node<'a>(node: &'a Node, cursor: &mut Cursor<'a>) -> Result<(), Mismatch<'a>> {
+    for child in node.children() {
+        match child {
+            NodeOrToken::Node(node) => verify_node(ArcBorrow::downgrade(node), cursor)?,
+            NodeOrToken::Token(token) => {
+                let mut bytes = ArcBorrow::downgrade(token).bytes();
+                while !bytes.is_empty() {
+                    let chunk = cursor.peek_chunk().unwrap_or(&[]);
+                    let same = Iterator::zip(bytes.iter(), chunk).take_while(|(a, b)| a == b);
+                    let same = same.count();
+                    if same < bytes.len().min(chunk.len()) || chunk.is_empty() {
+                        let found = &bytes[same..];
+                        let expected = &chunk[same..chunk.len().min(same + found.len())];
+                        let offset = offset(cursor.pos + same);
+                        return Err(Mismatch { offset, expected, found });
+                    }
+                    bytes = &bytes[same..];
+                    cursor.chunk = &chunk[same..];
+                    cursor.pos += same;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn offset(pos: usize) -> TextSize {
+    TextSize::try_from(pos).expect("text offset should fit in TextSize")
+}
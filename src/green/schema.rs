@@ -0,0 +1,76 @@
+use {
+    crate::{
+        green::{Node, Token},
+        Kind,
+    },
+    schemars::{gen::SchemaGenerator, schema::*, JsonSchema},
+};
+
+/// The JSON Schema of the JSON serialization of a [`Node`].
+///
+/// Non-Rust tooling reading serialized trees can generate types from this.
+/// Binary tokens' bytes are described as an array of bytes,
+/// which is how `serde_json` serializes them.
+///
+/// # Examples
+///
+/// ```rust
+/// let schema = serde_json::to_value(sorbus::green::json_schema()).unwrap();
+/// assert_eq!(schema["title"], "Node");
+/// assert!(schema["definitions"]["NodeOrToken"].is_object());
+/// ```
+pub fn json_schema() -> RootSchema {
+    schemars::schema_for!(Node)
+}
+
+// Mirrors of the serialization in serde/ser.rs, for deriving the schema.
+
+#[derive(JsonSchema)]
+#[schemars(rename = "Kind")]
+#[allow(dead_code)]
+struct KindRepr(u16);
+
+#[derive(JsonSchema)]
+#[schemars(rename = "Token", untagged)]
+#[allow(dead_code)]
+enum TokenRepr {
+    Text { kind: KindRepr, text: String },
+    Binary { kind: KindRepr, bytes: Vec<u8> },
+}
+
+#[derive(JsonSchema)]
+#[schemars(rename = "Node")]
+#[allow(dead_code)]
+struct NodeRepr {
+    kind: KindRepr,
+    children: Vec<ElementRepr>,
+}
+
+#[derive(JsonSchema)]
+#[schemars(rename = "NodeOrToken")]
+#[allow(dead_code)]
+enum ElementRepr {
+    Node { kind: KindRepr, children: Vec<ElementRepr> },
+    Token { kind: KindRepr, text: String },
+    BinaryToken { kind: KindRepr, bytes: Vec<u8> },
+}
+
+macro_rules! impl_json_schema {
+    ($($T:ty => $Repr:ty),* $(,)?) => {$(
+        impl JsonSchema for $T {
+            fn schema_name() -> String {
+                <$Repr>::schema_name()
+            }
+
+            fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+                <$Repr>::json_schema(gen)
+            }
+        }
+    )*};
+}
+
+impl_json_schema! {
+    Kind => KindRepr,
+    Token => TokenRepr,
+    Node => NodeRepr,
+}
@@ -0,0 +1,133 @@
+use {
+    crate::{
+        green::{pack_node_or_token, unpack_node_or_token, Builder, Node, Token},
+        Kind, NodeOrToken, TextSize,
+    },
+    erasable::ErasablePtr,
+    std::{error::Error, fmt, mem, ptr, sync::Arc},
+};
+
+/// A layout invariant which doesn't hold on this target. See [`self_test`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct SelfTestError {
+    /// A description of the invariant that was broken.
+    pub invariant: &'static str,
+}
+
+impl fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "green tree self test failed: {}", self.invariant)
+    }
+}
+
+impl Error for SelfTestError {}
+
+fn check(ok: bool, invariant: &'static str) -> Result<(), SelfTestError> {
+    if ok {
+        Ok(())
+    } else {
+        Err(SelfTestError { invariant })
+    }
+}
+
+/// Check the layout invariants that the green tree's unsafe code relies on.
+///
+/// Elements pack a tagged thin pointer and an offset into 12 bytes, and
+/// alternate between two layouts depending on their alignment within the node.
+/// Thin pointers are expanded by reading the length from the element's header.
+/// These tricks are tested on common
+/// targets, but the fewer tested ones (such as aarch64, big-endian targets, or
+/// wasm) can call this on startup or in their own tests to check them at runtime.
+///
+/// This builds a few small trees in a private cache, so it is cheap but not free.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::green;
+/// green::self_test().unwrap();
+/// ```
+pub fn self_test() -> Result<(), SelfTestError> {
+    const ATOM: Kind = Kind(0);
+    const LIST: Kind = Kind(1);
+    const FLAG: u8 = 0b1;
+
+    let mut builder = Builder::new();
+    #[cfg(feature = "node-flags")]
+    builder.set_track_multiline(true).set_flag_propagation(FLAG);
+    builder.set_token_classifier(|_, text| if text.contains('\n') { FLAG } else { 0 });
+    let tokens = vec![
+        builder.token(ATOM, "a"),
+        builder.token(ATOM, "bc\n"),
+        builder.binary_token(ATOM, &[0xFF, 0xFE]),
+        builder.token(ATOM, ""),
+    ];
+    check(tokens[1].flags() == FLAG, "token flags are kept in the header")?;
+    check(tokens[2].is_binary(), "binary tokens are marked in the header")?;
+    check(tokens[2].len() == TextSize::from(2), "token lengths are kept apart from their flags")?;
+
+    let leaf = builder.node(LIST, vec![tokens[1].clone()]);
+    #[cfg(feature = "node-flags")]
+    {
+        check(leaf.is_multiline(), "node flags are kept in the header")?;
+        check(leaf.synthesized_flags() == FLAG, "node flags are kept in the header")?;
+    }
+    check(leaf.len() == TextSize::from(3), "node lengths are kept apart from their flags")?;
+
+    let mut elements: Vec<NodeOrToken<Arc<Node>, Arc<Token>>> = vec![];
+    for i in 0..7 {
+        elements.push(if i % 3 == 1 { leaf.clone().into() } else { tokens[i % 4].clone().into() });
+        let node = builder.node(LIST, elements.clone());
+        check(mem::align_of_val(&*node) >= 8, "nodes are aligned to 8")?;
+        check(erased_round_trip_node(&node), "erased nodes recover their length")?;
+        check(node.children_len() == elements.len(), "nodes have the right number of children")?;
+
+        let mut offset = TextSize::from(0);
+        for (i, (child, element)) in node.children().zip(&elements).enumerate() {
+            let address = &node[i] as *const _ as usize;
+            let parity = if cfg!(target_pointer_width = "64") { i % 2 * 4 } else { 0 };
+            check(address % 8 == parity, "children alternate between full and half alignment")?;
+            check(node[i].offset() == offset, "children have the right offset")?;
+            let same = match (child, element) {
+                (NodeOrToken::Node(a), NodeOrToken::Node(b)) => ptr::eq(&*a, &**b),
+                (NodeOrToken::Token(a), NodeOrToken::Token(b)) => ptr::eq(&*a, &**b),
+                _ => false,
+            };
+            check(same, "children unpack to the element they were packed from")?;
+            offset += element.len();
+        }
+    }
+
+    for token in &tokens {
+        check(mem::align_of_val(&**token) >= 2, "tokens are aligned to at least 2")?;
+        check(erased_round_trip_token(token), "erased tokens recover their length")?;
+    }
+    for element in [leaf.into(), tokens[1].clone().into()] {
+        let element: NodeOrToken<Arc<Node>, Arc<Token>> = element;
+        let count = element.as_ref().map(Arc::strong_count, Arc::strong_count).flatten();
+        let unpacked = unpack_node_or_token(pack_node_or_token(element.clone()));
+        let same = match (&element, &unpacked) {
+            (NodeOrToken::Node(a), NodeOrToken::Node(b)) => Arc::ptr_eq(a, b),
+            (NodeOrToken::Token(a), NodeOrToken::Token(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        };
+        check(same, "packed pointers unpack to the same element")?;
+        drop(unpacked);
+        let after = element.as_ref().map(Arc::strong_count, Arc::strong_count).flatten();
+        check(after == count, "packing pointers doesn't leak references")?;
+    }
+    Ok(())
+}
+
+fn erased_round_trip_node(node: &Arc<Node>) -> bool {
+    let erased = ErasablePtr::erase(Arc::clone(node));
+    let unerased: Arc<Node> = unsafe { ErasablePtr::unerase(erased) };
+    Arc::ptr_eq(node, &unerased) && mem::size_of_val(&*unerased) == mem::size_of_val(&**node)
+}
+
+fn erased_round_trip_token(token: &Arc<Token>) -> bool {
+    let erased = ErasablePtr::erase(Arc::clone(token));
+    let unerased: Arc<Token> = unsafe { ErasablePtr::unerase(erased) };
+    Arc::ptr_eq(token, &unerased) && unerased.bytes() == token.bytes()
+}
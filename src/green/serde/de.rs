@@ -4,12 +4,15 @@ extern crate serde; // this line required to workaround rust-lang/rust#55779
 
 use {
     crate::{
-        green::{pack_node_or_token, Builder, Node, PackedNodeOrToken, Token},
+        green::{
+            pack_node_or_token, Builder, Document, Metadata, Node, NodeError, PackedNodeOrToken,
+            Token, TreeBuilder,
+        },
         Kind, NodeOrToken,
     },
     rc_box::ArcBox,
     serde::{de::*, Deserialize},
-    std::{borrow::Cow, fmt, marker::PhantomData, ops::Deref, str, sync::Arc},
+    std::{borrow::Cow, convert::TryFrom, fmt, marker::PhantomData, ops::Deref, str, sync::Arc},
 };
 
 /// Helper type to maybe borrow a string from the deserializer.
@@ -32,6 +35,55 @@ impl Deref for Str<'_> {
     }
 }
 
+/// Helper type to deserialize the bytes of a binary token.
+struct ByteBuf(Vec<u8>);
+impl Deref for ByteBuf {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ByteBufVisitor;
+        impl<'de> Visitor<'de> for ByteBufVisitor {
+            type Value = ByteBuf;
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "a byte array")
+            }
+
+            fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(ByteBuf(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(ByteBuf(v))
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(ByteBuf(v.as_bytes().to_vec()))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(ByteBuf(bytes))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(ByteBufVisitor)
+    }
+}
+
 impl<'de> Deserialize<'de> for Kind {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -42,34 +94,331 @@ impl<'de> Deserialize<'de> for Kind {
         struct Repr(u16);
 
         let Repr(raw) = Repr::deserialize(deserializer)?;
-        Ok(Kind(raw))
+        checked_kind(raw)
+    }
+}
+
+/// Reject the reserved tombstone kind, unless the `tombstone-kind` feature allows it.
+pub(super) fn checked_kind<E: Error>(raw: u16) -> Result<Kind, E> {
+    if cfg!(feature = "tombstone-kind") {
+        return Ok(Kind(raw));
     }
+    Kind::new(raw).ok_or_else(|| {
+        Error::invalid_value(Unexpected::Unsigned(raw.into()), &"a kind other than Kind::TOMBSTONE")
+    })
 }
 
 impl Builder {
     /// Deserialize a token using this cache.
+    ///
+    /// Binary tokens serialized on their own only round-trip through
+    /// self-describing formats; as part of a node, they always round-trip.
     pub fn deserialize_token(
         &mut self,
     ) -> impl for<'de> DeserializeSeed<'de, Value = Arc<Token>> + '_ {
-        TokenSeed(self)
+        TokenSeed(self, false, KindResolver::Raw)
     }
 
     /// Deserialize a node using this cache.
     pub fn deserialize_node(
         &mut self,
     ) -> impl for<'de> DeserializeSeed<'de, Value = Arc<Node>> + '_ {
-        NodeSeed(self)
+        NodeSeed(self, KindResolver::Raw)
+    }
+
+    /// Deserialize a node serialized by [`Node::serialize_named`] using this cache.
+    ///
+    /// Kind names are mapped back to raw kinds by `kinds`. Raw numeric kinds
+    /// are also accepted, for kinds that were serialized without a name.
+    /// Unlike raw kinds, names are robust against a grammar renumbering its kinds.
+    ///
+    /// Kinds are deserialized with `deserialize_any`, so this only works
+    /// with self-describing formats.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+    /// use serde::de::DeserializeSeed;
+    ///
+    /// let tree = green::TreeBuilder::new()
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "a")
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// let names = |kind| match kind {
+    ///     ATOM => Some("ATOM"),
+    ///     LIST => Some("LIST"),
+    ///     _ => None,
+    /// };
+    /// let json = serde_json::to_string(&tree.serialize_named(&names)).unwrap();
+    /// assert_eq!(json, r#"{"kind":"LIST","children":[{"Token":{"kind":"ATOM","text":"a"}}]}"#);
+    ///
+    /// let kinds = |name: &str| match name {
+    ///     "ATOM" => Some(ATOM),
+    ///     "LIST" => Some(LIST),
+    ///     _ => None,
+    /// };
+    /// let mut builder = green::Builder::new();
+    /// let mut deserializer = serde_json::Deserializer::from_str(&json);
+    /// let round_trip = builder.deserialize_node_named(&kinds).deserialize(&mut deserializer);
+    /// assert_eq!(*round_trip.unwrap(), *tree);
+    /// ```
+    pub fn deserialize_node_named<'a>(
+        &'a mut self,
+        kinds: &'a dyn Fn(&str) -> Option<Kind>,
+    ) -> impl for<'de> DeserializeSeed<'de, Value = Arc<Node>> + 'a {
+        NodeSeed(self, KindResolver::Named(kinds))
+    }
+
+    /// Deserialize a node using this cache, translating raw kinds through `map`.
+    ///
+    /// A serialized raw kind `n` is deserialized as `Kind(map[n])`, and raw
+    /// kinds outside of the table are rejected. This allows loading trees
+    /// serialized before a grammar reordered its kinds, given a table from
+    /// the old kind numbers to the new ones.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// use serde::de::DeserializeSeed;
+    ///
+    /// // serialized when ATOM was Kind(0) and LIST was Kind(1)
+    /// let json = r#"{"kind":1,"children":[{"Token":{"kind":0,"text":"a"}}]}"#;
+    ///
+    /// // now, ATOM is Kind(1) and LIST is Kind(0)
+    /// const ATOM: Kind = Kind(1); const LIST: Kind = Kind(0);
+    /// let mut builder = green::Builder::new();
+    /// let mut deserializer = serde_json::Deserializer::from_str(json);
+    /// let tree = builder.deserialize_node_with_kind_map(&[1, 0]).deserialize(&mut deserializer);
+    ///
+    /// let expected = green::TreeBuilder::new()
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "a")
+    ///     .finish_node()
+    ///     .finish();
+    /// assert_eq!(*tree.unwrap(), *expected);
+    /// ```
+    pub fn deserialize_node_with_kind_map<'a>(
+        &'a mut self,
+        map: &'a [u16],
+    ) -> impl for<'de> DeserializeSeed<'de, Value = Arc<Node>> + 'a {
+        NodeSeed(self, KindResolver::Mapped(map))
     }
 }
 
-struct TokenSeed<'a>(&'a mut Builder);
+impl TreeBuilder {
+    /// Deserialize a node and add it to the current branch.
+    ///
+    /// The node is deduplicated using this builder's cache, so a subtree
+    /// loaded from a cache can be spliced into a tree that is being parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+    /// let cached = r#"{"kind":1,"children":[{"Token":{"kind":0,"text":"b"}}]}"#;
+    ///
+    /// let mut builder = green::TreeBuilder::new();
+    /// builder.start_node(LIST).token(ATOM, "a");
+    /// builder.deserialize_into_current(&mut serde_json::Deserializer::from_str(cached))?;
+    /// let tree = builder.token(ATOM, "c").finish_node().finish();
+    ///
+    /// let expected = green::TreeBuilder::new()
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "a")
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "b")
+    ///     .finish_node()
+    ///     .token(ATOM, "c")
+    ///     .finish_node()
+    ///     .finish();
+    /// assert_eq!(*tree, *expected);
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn deserialize_into_current<'de, D>(
+        &mut self,
+        deserializer: D,
+    ) -> Result<&mut Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let node = self.builder().deserialize_node().deserialize(deserializer)?;
+        Ok(self.add(node))
+    }
+}
+
+impl<'de> Deserialize<'de> for Metadata {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Default, Deserialize)]
+        #[serde(rename = "Metadata", default)]
+        struct Repr {
+            path: Option<String>,
+            version: Option<String>,
+            language: Option<String>,
+        }
+
+        let Repr { path, version, language } = Repr::deserialize(deserializer)?;
+        Ok(Metadata { path, version, language })
+    }
+}
+
+impl Builder {
+    /// Deserialize a document using this cache.
+    pub fn deserialize_document(
+        &mut self,
+    ) -> impl for<'de> DeserializeSeed<'de, Value = Document> + '_ {
+        DocumentSeed(self)
+    }
+}
+
+struct DocumentSeed<'a>(&'a mut Builder);
+impl<'de> DeserializeSeed<'de> for DocumentSeed<'_> {
+    type Value = Document;
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        trace_span!(debug_span, "deserialize document");
+        const FIELDS: &[&str] = &["meta", "root"];
+        deserializer.deserialize_struct("Document", FIELDS, self)
+    }
+}
+impl<'de> Visitor<'de> for DocumentSeed<'_> {
+    type Value = Document;
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a sorbus green document")
+    }
+
+    fn visit_seq<Seq>(self, mut seq: Seq) -> Result<Self::Value, Seq::Error>
+    where
+        Seq: SeqAccess<'de>,
+    {
+        let meta = seq.next_element()?.ok_or_else(|| Error::invalid_length(0, &self))?;
+        let root = seq
+            .next_element_seed(NodeSeed(self.0, KindResolver::Raw))?
+            .ok_or_else(|| Error::invalid_length(1, &self))?;
+        Ok(Document { meta, root })
+    }
+
+    fn visit_map<Map>(self, mut map: Map) -> Result<Self::Value, Map::Error>
+    where
+        Map: MapAccess<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(field_identifier, rename_all = "lowercase")]
+        enum Field {
+            Meta,
+            Root,
+        }
+
+        let (mut meta, mut root) = (None, None);
+        while let Some(key) = map.next_key()? {
+            match key {
+                Field::Meta if meta.is_some() => Err(Error::duplicate_field("meta"))?,
+                Field::Meta => meta = Some(map.next_value()?),
+                Field::Root if root.is_some() => Err(Error::duplicate_field("root"))?,
+                Field::Root => {
+                    root = Some(map.next_value_seed(NodeSeed(self.0, KindResolver::Raw))?)
+                }
+            }
+        }
+
+        let root = root.ok_or_else(|| Error::missing_field("root"))?;
+        Ok(Document { meta: meta.unwrap_or_default(), root })
+    }
+}
+
+/// How serialized kinds are resolved to kinds.
+#[derive(Clone, Copy)]
+enum KindResolver<'a> {
+    /// Raw kinds, as serialized.
+    Raw,
+    /// Kind names, or raw kinds for kinds serialized without a name.
+    Named(&'a dyn Fn(&str) -> Option<Kind>),
+    /// Raw kinds, translated through a table.
+    Mapped(&'a [u16]),
+}
+
+/// Deserialize a kind, as directed by the resolver.
+#[derive(Clone, Copy)]
+struct KindSeed<'a>(KindResolver<'a>);
+impl KindSeed<'_> {
+    fn raw<E: Error>(self, raw: u16) -> Result<Kind, E> {
+        match self.0 {
+            KindResolver::Mapped(map) => match map.get(usize::from(raw)) {
+                Some(&raw) => checked_kind(raw),
+                None => Err(Error::invalid_value(
+                    Unexpected::Unsigned(raw.into()),
+                    &format!("a kind less than {}", map.len()).as_str(),
+                )),
+            },
+            _ => checked_kind(raw),
+        }
+    }
+}
+impl<'de> DeserializeSeed<'de> for KindSeed<'_> {
+    type Value = Kind;
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match self.0 {
+            KindResolver::Raw => Kind::deserialize(deserializer),
+            KindResolver::Named(_) => deserializer.deserialize_any(self),
+            KindResolver::Mapped(_) => deserializer.deserialize_newtype_struct("Kind", self),
+        }
+    }
+}
+impl<'de> Visitor<'de> for KindSeed<'_> {
+    type Value = Kind;
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            KindResolver::Named(_) => write!(f, "a kind name or raw kind"),
+            _ => write!(f, "a raw kind"),
+        }
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+        match self.0 {
+            KindResolver::Named(resolve) => {
+                resolve(v).ok_or_else(|| Error::invalid_value(Unexpected::Str(v), &self))
+            }
+            _ => Err(Error::invalid_type(Unexpected::Str(v), &self)),
+        }
+    }
+
+    fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> {
+        let raw =
+            u16::try_from(v).map_err(|_| Error::invalid_value(Unexpected::Unsigned(v), &self))?;
+        self.raw(raw)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = u16::deserialize(deserializer)?;
+        self.raw(raw)
+    }
+}
+
+/// Deserialize a token, expecting text or bytes when deserializing from a sequence.
+struct TokenSeed<'a>(&'a mut Builder, bool, KindResolver<'a>);
 impl<'de> DeserializeSeed<'de> for TokenSeed<'_> {
     type Value = Arc<Token>;
     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: Deserializer<'de>,
     {
-        const FIELDS: &[&str] = &["kind", "text"];
+        const FIELDS: &[&str] = &["kind", "text", "bytes"];
         deserializer.deserialize_struct("Token", FIELDS, self)
     }
 }
@@ -83,9 +432,10 @@ impl<'de> Visitor<'de> for TokenSeed<'_> {
     where
         A: SeqAccess<'de>,
     {
-        let kind = seq.next_element()?.ok_or_else(|| Error::invalid_length(0, &self))?;
+        let kind = seq.next_element_seed(KindSeed(self.2))?;
+        let kind = kind.ok_or_else(|| Error::invalid_length(0, &self))?;
         let token = seq
-            .next_element_seed(TokenSeedKind(self.0, kind))?
+            .next_element_seed(TokenSeedKind(self.0, kind, self.1))?
             .ok_or_else(|| Error::invalid_length(1, &self))?;
         Ok(token)
     }
@@ -99,6 +449,7 @@ impl<'de> Visitor<'de> for TokenSeed<'_> {
         enum Field {
             Kind,
             Text,
+            Bytes,
         }
 
         use VisitState::*;
@@ -106,43 +457,68 @@ impl<'de> Visitor<'de> for TokenSeed<'_> {
             Start,
             WithKind(Kind),
             WithText(Str<'de>),
+            WithBytes(ByteBuf),
             Finish(Arc<Token>),
         }
 
         let mut state = Start;
         while let Some(key) = map.next_key()? {
             state = match (key, state) {
-                (Field::Kind, Start) => WithKind(map.next_value()?),
+                (Field::Kind, Start) => WithKind(map.next_value_seed(KindSeed(self.2))?),
                 (Field::Text, Start) => WithText(map.next_value()?),
+                (Field::Bytes, Start) => WithBytes(map.next_value()?),
 
-                (Field::Kind, WithText(text)) => Finish(self.0.token(map.next_value()?, &text)),
+                (Field::Kind, WithText(text)) => {
+                    Finish(self.0.token(map.next_value_seed(KindSeed(self.2))?, &text))
+                }
+                (Field::Kind, WithBytes(bytes)) => {
+                    Finish(self.0.binary_token(map.next_value_seed(KindSeed(self.2))?, &bytes))
+                }
                 (Field::Text, WithKind(kind)) => {
-                    Finish(map.next_value_seed(TokenSeedKind(self.0, kind))?)
+                    Finish(map.next_value_seed(TokenSeedKind(self.0, kind, false))?)
+                }
+                (Field::Bytes, WithKind(kind)) => {
+                    Finish(map.next_value_seed(TokenSeedKind(self.0, kind, true))?)
                 }
 
                 (Field::Kind, WithKind(_)) => Err(Error::duplicate_field("kind"))?,
                 (Field::Kind, Finish(_)) => Err(Error::duplicate_field("kind"))?,
-                (Field::Text, WithText(_)) => Err(Error::duplicate_field("text"))?,
-                (Field::Text, Finish(_)) => Err(Error::duplicate_field("text"))?,
+                (Field::Text, WithBytes(_)) | (Field::Bytes, WithText(_)) => {
+                    Err(Error::custom("token has both `text` and `bytes`"))?
+                }
+                (Field::Text, Finish(token)) if token.is_binary() => {
+                    Err(Error::custom("token has both `text` and `bytes`"))?
+                }
+                (Field::Bytes, Finish(token)) if !token.is_binary() => {
+                    Err(Error::custom("token has both `text` and `bytes`"))?
+                }
+                (Field::Text, _) => Err(Error::duplicate_field("text"))?,
+                (Field::Bytes, _) => Err(Error::duplicate_field("bytes"))?,
             }
         }
 
         match state {
-            Start | WithText(_) => Err(Error::missing_field("kind")),
+            Start | WithText(_) | WithBytes(_) => Err(Error::missing_field("kind")),
             WithKind(_) => Err(Error::missing_field("text")),
             Finish(token) => Ok(token),
         }
     }
 }
 
-struct TokenSeedKind<'a>(&'a mut Builder, Kind);
+/// Deserialize the text (or bytes, if binary) of a token of known kind.
+struct TokenSeedKind<'a>(&'a mut Builder, Kind, bool);
 impl<'de> DeserializeSeed<'de> for TokenSeedKind<'_> {
     type Value = Arc<Token>;
     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_str(self)
+        if self.2 {
+            let bytes = ByteBuf::deserialize(deserializer)?;
+            Ok(self.0.binary_token(self.1, &bytes))
+        } else {
+            deserializer.deserialize_str(self)
+        }
     }
 }
 impl<'de> Visitor<'de> for TokenSeedKind<'_> {
@@ -169,7 +545,7 @@ impl<'de> Visitor<'de> for TokenSeedKind<'_> {
     }
 }
 
-struct NodeSeed<'a>(&'a mut Builder);
+struct NodeSeed<'a>(&'a mut Builder, KindResolver<'a>);
 impl<'de> DeserializeSeed<'de> for NodeSeed<'_> {
     type Value = Arc<Node>;
 
@@ -177,6 +553,8 @@ impl<'de> DeserializeSeed<'de> for NodeSeed<'_> {
     where
         D: Deserializer<'de>,
     {
+        // NB: this is called for every node, so it is only traced at trace level
+        trace_span!(trace_span, "deserialize node");
         const FIELDS: &[&str] = &["kind", "children"];
         deserializer.deserialize_struct("Node", FIELDS, self)
     }
@@ -191,9 +569,10 @@ impl<'de> Visitor<'de> for NodeSeed<'_> {
     where
         Seq: SeqAccess<'de>,
     {
-        let kind = seq.next_element()?.ok_or_else(|| Error::invalid_length(0, &self))?;
+        let kind = seq.next_element_seed(KindSeed(self.1))?;
+        let kind = kind.ok_or_else(|| Error::invalid_length(0, &self))?;
         let node = seq
-            .next_element_seed(NodeSeedKind(self.0, kind))?
+            .next_element_seed(NodeSeedKind(self.0, kind, self.1))?
             .ok_or_else(|| Error::invalid_length(1, &self))?;
         Ok(node)
     }
@@ -220,17 +599,17 @@ impl<'de> Visitor<'de> for NodeSeed<'_> {
         let mut state = Start;
         while let Some(key) = map.next_key()? {
             state = match (key, state) {
-                (Field::Kind, Start) => WithKind(map.next_value()?),
+                (Field::Kind, Start) => WithKind(map.next_value_seed(KindSeed(self.1))?),
                 (Field::Children, Start) => {
-                    WithChildren(map.next_value_seed(NodeChildrenSeed(self.0))?)
+                    WithChildren(map.next_value_seed(NodeChildrenSeed(self.0, self.1))?)
                 }
 
                 (Field::Kind, WithChildren(mut node)) => {
-                    node.set_kind(map.next_value()?);
+                    node.set_kind(map.next_value_seed(KindSeed(self.1))?);
                     Finish(self.0.cache_node(node.into()))
                 }
                 (Field::Children, WithKind(kind)) => {
-                    Finish(map.next_value_seed(NodeSeedKind(self.0, kind))?)
+                    Finish(map.next_value_seed(NodeSeedKind(self.0, kind, self.1))?)
                 }
 
                 (Field::Kind, WithKind(_)) => Err(Error::duplicate_field("kind"))?,
@@ -249,14 +628,14 @@ impl<'de> Visitor<'de> for NodeSeed<'_> {
     }
 }
 
-struct NodeSeedKind<'a>(&'a mut Builder, Kind);
+struct NodeSeedKind<'a>(&'a mut Builder, Kind, KindResolver<'a>);
 impl<'de> DeserializeSeed<'de> for NodeSeedKind<'_> {
     type Value = Arc<Node>;
     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let mut node = NodeChildrenSeed(self.0).deserialize(deserializer)?;
+        let mut node = NodeChildrenSeed(self.0, self.2).deserialize(deserializer)?;
         node.set_kind(self.1);
         Ok(self.0.cache_node(node.into()))
     }
@@ -264,7 +643,7 @@ impl<'de> DeserializeSeed<'de> for NodeSeedKind<'_> {
 
 /// Deserialize node children without knowing the kind.
 /// Uses a kind of `Kind(0)`; fix it and then dedupe the node!
-struct NodeChildrenSeed<'a>(&'a mut Builder);
+struct NodeChildrenSeed<'a>(&'a mut Builder, KindResolver<'a>);
 impl<'de> DeserializeSeed<'de> for NodeChildrenSeed<'_> {
     type Value = ArcBox<Node>;
     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
@@ -284,29 +663,42 @@ impl<'de> Visitor<'de> for NodeChildrenSeed<'_> {
     where
         Seq: SeqAccess<'de>,
     {
+        let synthesis = self.0.synthesis();
         if seq.size_hint().is_some() {
-            let node =
-                Node::try_new(Kind(0), SeqAccessExactSizeIterator(self.0, seq, PhantomData))?;
-            Ok(node)
+            let children = SeqAccessExactSizeIterator(self.0, seq, self.1, PhantomData);
+            let children = children.map(|child| child.map_err(NodeDeError));
+            let node = Node::try_new(Kind(0).into(), synthesis, children);
+            node.map_err(|NodeDeError(err)| err)
         } else {
             let mut children = Vec::with_capacity(seq.size_hint().unwrap_or(0));
-            while let Some(element) = seq.next_element_seed(ElementSeed(self.0))? {
+            while let Some(element) = seq.next_element_seed(ElementSeed(self.0, self.1))? {
                 children.push(element);
             }
-            Ok(Node::new(Kind(0), children.into_iter()))
+            let children = children.into_iter().map(Ok);
+            let node = Node::try_new::<_, _, NodeError>(Kind(0).into(), synthesis, children);
+            node.map_err(Seq::Error::custom)
         }
     }
 }
 
+/// Helper to surface node construction errors as deserialization errors.
+struct NodeDeError<E>(E);
+impl<E: Error> From<NodeError> for NodeDeError<E> {
+    fn from(err: NodeError) -> Self {
+        NodeDeError(E::custom(err))
+    }
+}
+
 struct SeqAccessExactSizeIterator<'a, 'de, Seq: SeqAccess<'de>>(
     &'a mut Builder,
     Seq,
+    KindResolver<'a>,
     PhantomData<&'de ()>,
 );
 impl<'de, Seq: SeqAccess<'de>> Iterator for SeqAccessExactSizeIterator<'_, 'de, Seq> {
     type Item = Result<PackedNodeOrToken, Seq::Error>;
     fn next(&mut self) -> Option<Self::Item> {
-        self.1.next_element_seed(ElementSeed(self.0)).transpose()
+        self.1.next_element_seed(ElementSeed(self.0, self.2)).transpose()
     }
 
     #[cfg(not(tarpaulin_ignore))] // `len` is used instead, and this method is obviously correct
@@ -321,7 +713,7 @@ impl<'de, Seq: SeqAccess<'de>> ExactSizeIterator for SeqAccessExactSizeIterator<
     }
 }
 
-struct ElementSeed<'a>(&'a mut Builder);
+struct ElementSeed<'a>(&'a mut Builder, KindResolver<'a>);
 impl<'de> DeserializeSeed<'de> for ElementSeed<'_> {
     type Value = PackedNodeOrToken;
 
@@ -329,7 +721,7 @@ impl<'de> DeserializeSeed<'de> for ElementSeed<'_> {
     where
         D: Deserializer<'de>,
     {
-        const VARIANTS: &[&str] = &["Node", "Token"];
+        const VARIANTS: &[&str] = &["Node", "Token", "BinaryToken"];
         deserializer.deserialize_enum("NodeOrToken", VARIANTS, self)
     }
 }
@@ -348,15 +740,19 @@ impl<'de> Visitor<'de> for ElementSeed<'_> {
         enum Variant {
             Node,
             Token,
+            BinaryToken,
         }
 
         Ok(pack_node_or_token(match data.variant()? {
-            (Variant::Node, variant) => {
-                NodeOrToken::Node(variant.struct_variant(&["kind", "children"], NodeSeed(self.0))?)
-            }
-            (Variant::Token, variant) => {
-                NodeOrToken::Token(variant.struct_variant(&["kind", "text"], TokenSeed(self.0))?)
-            }
+            (Variant::Node, variant) => NodeOrToken::Node(
+                variant.struct_variant(&["kind", "children"], NodeSeed(self.0, self.1))?,
+            ),
+            (Variant::Token, variant) => NodeOrToken::Token(
+                variant.struct_variant(&["kind", "text"], TokenSeed(self.0, false, self.1))?,
+            ),
+            (Variant::BinaryToken, variant) => NodeOrToken::Token(
+                variant.struct_variant(&["kind", "bytes"], TokenSeed(self.0, true, self.1))?,
+            ),
         }))
     }
 }
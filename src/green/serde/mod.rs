@@ -2,3 +2,5 @@
 mod ser;
 #[cfg(feature = "de")]
 mod de;
+#[cfg(feature = "de")]
+mod rowan;
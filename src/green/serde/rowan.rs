@@ -0,0 +1,195 @@
+//! Deserialization of trees serialized by rowan.
+//!
+//! rowan serializes its syntax trees (not its green trees) as nested maps,
+//! with nodes and tokens distinguished by their fields rather than tagged:
+//!
+//! ```json
+//! { "kind": "LIST", "text_range": [0, 3], "children": [
+//!     { "kind": "ATOM", "text_range": [0, 1], "text": "a" },
+//!     { "kind": "WS", "text_range": [1, 2], "text": " " },
+//!     { "kind": "ATOM", "text_range": [2, 3], "text": "b" }
+//! ] }
+//! ```
+//!
+//! Kinds are serialized as the `Debug` name of the language's kind type,
+//! so they have to be mapped back to raw kinds by name. Text ranges are
+//! redundant with the text of the tokens, so they are ignored.
+
+extern crate serde; // this line required to workaround rust-lang/rust#55779
+
+use {
+    crate::{
+        green::{Builder, Node, Token},
+        Kind, NodeOrToken,
+    },
+    serde::de::*,
+    std::{convert::TryFrom, fmt, sync::Arc},
+};
+
+impl Builder {
+    /// Deserialize a tree in the layout rowan serializes its syntax trees in,
+    /// using this cache.
+    ///
+    /// rowan serializes kinds by name, which are mapped to raw kinds by `kinds`.
+    /// Raw numeric kinds are also accepted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+    /// use serde::de::DeserializeSeed;
+    ///
+    /// let json = r#"{ "kind": "LIST", "text_range": [0, 2], "children": [
+    ///     { "kind": "ATOM", "text_range": [0, 1], "text": "a" },
+    ///     { "kind": "WS", "text_range": [1, 2], "text": " " }
+    /// ] }"#;
+    /// let kinds = |name: &str| match name {
+    ///     "WS" => Some(WS),
+    ///     "ATOM" => Some(ATOM),
+    ///     "LIST" => Some(LIST),
+    ///     _ => None,
+    /// };
+    ///
+    /// let mut builder = green::Builder::new();
+    /// let mut deserializer = serde_json::Deserializer::from_str(json);
+    /// let tree = builder.deserialize_rowan(&kinds).deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(tree.children().map(|el| el.kind()).collect::<Vec<_>>(), [ATOM, WS]);
+    /// ```
+    pub fn deserialize_rowan<'a>(
+        &'a mut self,
+        kinds: &'a dyn Fn(&str) -> Option<Kind>,
+    ) -> impl for<'de> DeserializeSeed<'de, Value = Arc<Node>> + 'a {
+        RowanNodeSeed(RowanElementSeed(self, kinds))
+    }
+}
+
+struct RowanNodeSeed<'a>(RowanElementSeed<'a>);
+impl<'de> DeserializeSeed<'de> for RowanNodeSeed<'_> {
+    type Value = Arc<Node>;
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match self.0.deserialize(deserializer)? {
+            NodeOrToken::Node(node) => Ok(node),
+            NodeOrToken::Token(_) => Err(Error::custom("expected a rowan node, found a token")),
+        }
+    }
+}
+
+struct RowanElementSeed<'a>(&'a mut Builder, &'a dyn Fn(&str) -> Option<Kind>);
+impl<'de> DeserializeSeed<'de> for RowanElementSeed<'_> {
+    type Value = NodeOrToken<Arc<Node>, Arc<Token>>;
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+impl<'de> Visitor<'de> for RowanElementSeed<'_> {
+    type Value = NodeOrToken<Arc<Node>, Arc<Token>>;
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a rowan syntax node or token")
+    }
+
+    fn visit_map<Map>(self, mut map: Map) -> Result<Self::Value, Map::Error>
+    where
+        Map: MapAccess<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(field_identifier, rename_all = "snake_case")]
+        enum Field {
+            Kind,
+            TextRange,
+            Children,
+            Text,
+        }
+
+        let mut kind = None;
+        let mut contents = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                Field::Kind if kind.is_some() => Err(Error::duplicate_field("kind"))?,
+                Field::Kind => kind = Some(map.next_value_seed(RowanKindSeed(self.1))?),
+                Field::TextRange => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+                Field::Children | Field::Text if contents.is_some() => {
+                    Err(Error::custom("rowan element has both children and text"))?
+                }
+                Field::Children => {
+                    let children = map.next_value_seed(RowanChildrenSeed(self.0, self.1))?;
+                    contents = Some(NodeOrToken::Node(children));
+                }
+                Field::Text => contents = Some(NodeOrToken::Token(map.next_value::<String>()?)),
+            }
+        }
+
+        let kind = kind.ok_or_else(|| Error::missing_field("kind"))?;
+        match contents.ok_or_else(|| Error::missing_field("children"))? {
+            NodeOrToken::Node(children) => {
+                let node = self.0.try_node(kind, children).map_err(Error::custom)?;
+                Ok(NodeOrToken::Node(node))
+            }
+            NodeOrToken::Token(text) => Ok(NodeOrToken::Token(self.0.token(kind, &text))),
+        }
+    }
+}
+
+struct RowanChildrenSeed<'a>(&'a mut Builder, &'a dyn Fn(&str) -> Option<Kind>);
+impl<'de> DeserializeSeed<'de> for RowanChildrenSeed<'_> {
+    type Value = Vec<NodeOrToken<Arc<Node>, Arc<Token>>>;
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+impl<'de> Visitor<'de> for RowanChildrenSeed<'_> {
+    type Value = Vec<NodeOrToken<Arc<Node>, Arc<Token>>>;
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a sequence of rowan syntax elements")
+    }
+
+    fn visit_seq<Seq>(self, mut seq: Seq) -> Result<Self::Value, Seq::Error>
+    where
+        Seq: SeqAccess<'de>,
+    {
+        let mut children = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(element) = seq.next_element_seed(RowanElementSeed(self.0, self.1))? {
+            children.push(element);
+        }
+        Ok(children)
+    }
+}
+
+/// Deserialize a kind by name, or a raw kind.
+struct RowanKindSeed<'a>(&'a dyn Fn(&str) -> Option<Kind>);
+impl<'de> DeserializeSeed<'de> for RowanKindSeed<'_> {
+    type Value = Kind;
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+impl<'de> Visitor<'de> for RowanKindSeed<'_> {
+    type Value = Kind;
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a kind name or raw kind")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+        (self.0)(v).ok_or_else(|| Error::invalid_value(Unexpected::Str(v), &self))
+    }
+
+    fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> {
+        let raw =
+            u16::try_from(v).map_err(|_| Error::invalid_value(Unexpected::Unsigned(v), &self))?;
+        super::de::checked_kind(raw)
+    }
+}
@@ -1,7 +1,10 @@
 use {
     crate::{
-        green::{Node, Token},
-        Kind, NodeOrToken,
+        green::{
+            analysis::{CorpusStats, Histogram},
+            Document, DocumentSymbol, Metadata, Node, Token,
+        },
+        Kind, KindNames, NodeOrToken,
     },
     serde::ser::*,
 };
@@ -22,26 +25,144 @@ impl Serialize for Token {
     {
         let mut state = serializer.serialize_struct("Token", 2)?;
         state.serialize_field("kind", &self.kind())?;
-        state.serialize_field("text", &self.text())?;
+        match self.try_text() {
+            Some(text) => state.serialize_field("text", text)?,
+            None => state.serialize_field("bytes", &Bytes(self.bytes()))?,
+        }
         state.end()
     }
 }
 
 impl Serialize for Node {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        trace_span!(debug_span, "serialize node", kind = ?self.kind(), len = ?self.len());
+        Named(self, None).serialize(serializer)
+    }
+}
+
+impl Serialize for Metadata {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Metadata", 3)?;
+        state.serialize_field("path", &self.path)?;
+        state.serialize_field("version", &self.version)?;
+        state.serialize_field("language", &self.language)?;
+        state.end()
+    }
+}
+
+impl Serialize for Document {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        trace_span!(debug_span, "serialize document", path = ?self.meta.path);
+        let mut state = serializer.serialize_struct("Document", 2)?;
+        state.serialize_field("meta", &self.meta)?;
+        state.serialize_field("root", &*self.root)?;
+        state.end()
+    }
+}
+
+impl Serialize for CorpusStats {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("CorpusStats", 9)?;
+        state.serialize_field("roots", &self.roots)?;
+        state.serialize_field("node_widths", &self.node_widths)?;
+        state.serialize_field("token_depths", &self.token_depths)?;
+        state.serialize_field("token_lengths", &self.token_lengths)?;
+        state.serialize_field("kinds", &self.kinds)?;
+        state.serialize_field("unique_nodes", &self.unique_nodes)?;
+        state.serialize_field("unique_tokens", &self.unique_tokens)?;
+        state.serialize_field("node_dedup_ratio", &self.node_dedup_ratio())?;
+        state.serialize_field("token_dedup_ratio", &self.token_dedup_ratio())?;
+        state.end()
+    }
+}
+
+/// Serialized as a map from each value to its number of samples.
+impl Serialize for Histogram {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(self.counts())
+    }
+}
+
+impl<K: Serialize> Serialize for DocumentSymbol<K> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("DocumentSymbol", 4)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("range", &self.range)?;
+        state.serialize_field("children", &self.children)?;
+        state.end()
+    }
+}
+
+impl Node {
+    /// Serialize this node with kinds serialized by name.
+    ///
+    /// Kinds without a name in `names` are serialized as their number.
+    /// Deserialize the result with [`Builder::deserialize_node_named`].
+    ///
+    /// [`Builder::deserialize_node_named`]: crate::green::Builder::deserialize_node_named
+    pub fn serialize_named<'a>(&'a self, names: &'a dyn KindNames) -> impl Serialize + 'a {
+        Named(self, Some(names))
+    }
+}
+
+/// Something serialized with kinds serialized by name, if there are names.
+struct Named<'a, T>(T, Option<&'a dyn KindNames>);
+
+impl Serialize for Named<'_, Kind> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.1.and_then(|names| names.kind_name(self.0)) {
+            Some(name) => serializer.serialize_str(name),
+            None => self.0.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for Named<'_, &Node> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
         let mut state = serializer.serialize_struct("Node", 2)?;
-        state.serialize_field("kind", &self.kind())?;
-        state.serialize_field("children", &Children(self))?;
+        state.serialize_field("kind", &Named(self.0.kind(), self.1))?;
+        state.serialize_field("children", &Children(self.0, self.1))?;
         state.end()
     }
 }
 
-struct Wrap<T>(T);
+struct Bytes<'a>(&'a [u8]);
 
-impl Serialize for Wrap<NodeOrToken<&Node, &Token>> {
+impl Serialize for Bytes<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+impl Serialize for Named<'_, NodeOrToken<&Node, &Token>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
@@ -49,22 +170,31 @@ impl Serialize for Wrap<NodeOrToken<&Node, &Token>> {
         match &self.0 {
             NodeOrToken::Node(node) => {
                 let mut state = serializer.serialize_struct_variant("NodeOrToken", 0, "Node", 2)?;
-                state.serialize_field("kind", &node.kind())?;
-                state.serialize_field("children", &Children(node))?;
-                state.end()
-            }
-            NodeOrToken::Token(token) => {
-                let mut state =
-                    serializer.serialize_struct_variant("NodeOrToken", 1, "Token", 2)?;
-                state.serialize_field("kind", &token.kind())?;
-                state.serialize_field("text", &token.text())?;
+                state.serialize_field("kind", &Named(node.kind(), self.1))?;
+                state.serialize_field("children", &Children(node, self.1))?;
                 state.end()
             }
+            NodeOrToken::Token(token) => match token.try_text() {
+                Some(text) => {
+                    let mut state =
+                        serializer.serialize_struct_variant("NodeOrToken", 1, "Token", 2)?;
+                    state.serialize_field("kind", &Named(token.kind(), self.1))?;
+                    state.serialize_field("text", text)?;
+                    state.end()
+                }
+                None => {
+                    let mut state =
+                        serializer.serialize_struct_variant("NodeOrToken", 2, "BinaryToken", 2)?;
+                    state.serialize_field("kind", &Named(token.kind(), self.1))?;
+                    state.serialize_field("bytes", &Bytes(token.bytes()))?;
+                    state.end()
+                }
+            },
         }
     }
 }
 
-struct Children<'a>(&'a Node);
+struct Children<'a>(&'a Node, Option<&'a dyn KindNames>);
 
 impl Serialize for Children<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -74,7 +204,7 @@ impl Serialize for Children<'_> {
         let children = self.0.children();
         let mut state = serializer.serialize_seq(Some(children.len()))?;
         for child in children {
-            state.serialize_element(&Wrap(child.as_deref()))?;
+            state.serialize_element(&Named(child.as_deref(), self.1))?;
         }
         state.end()
     }
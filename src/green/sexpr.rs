@@ -0,0 +1,294 @@
+use {
+    crate::{
+        green::{Builder, Node, NodeError, Token},
+        Kind, KindNames, NodeOrToken,
+    },
+    std::{char, error::Error, fmt, str, sync::Arc},
+};
+
+impl Node {
+    /// Print this tree as an S-expression, which [`from_sexpr`] reads back.
+    ///
+    /// Nodes are printed as `(KIND children...)` and tokens as `KIND "text"`,
+    /// with the text escaped like a Rust string literal. Binary tokens are
+    /// printed like a Rust byte string literal, `KIND b"bytes"`.
+    /// Kinds without a name in `names` are printed as their number.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+    /// let tree = green::TreeBuilder::new()
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "a")
+    ///     .token(WS, " ")
+    ///     .start_node(LIST)
+    ///     .finish_node()
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// let names = |kind| match kind {
+    ///     ATOM => Some("ATOM"),
+    ///     LIST => Some("LIST"),
+    ///     _ => None,
+    /// };
+    /// assert_eq!(tree.to_sexpr(&names), r#"(LIST ATOM "a" 0 " " (LIST))"#);
+    /// ```
+    pub fn to_sexpr(&self, names: &dyn KindNames) -> String {
+        let mut out = String::new();
+        write_node(&mut out, self, names).unwrap();
+        out
+    }
+}
+
+fn write_kind(out: &mut dyn fmt::Write, kind: Kind, names: &dyn KindNames) -> fmt::Result {
+    match names.kind_name(kind) {
+        Some(name) => out.write_str(name),
+        None => write!(out, "{}", kind.0),
+    }
+}
+
+fn write_node(out: &mut dyn fmt::Write, node: &Node, names: &dyn KindNames) -> fmt::Result {
+    out.write_str("(")?;
+    write_kind(out, node.kind(), names)?;
+    for child in node.children() {
+        out.write_str(" ")?;
+        match child {
+            NodeOrToken::Node(node) => write_node(out, &node, names)?,
+            NodeOrToken::Token(token) => write_token(out, &token, names)?,
+        }
+    }
+    out.write_str(")")
+}
+
+fn write_token(out: &mut dyn fmt::Write, token: &Token, names: &dyn KindNames) -> fmt::Result {
+    write_kind(out, token.kind(), names)?;
+    if let Some(text) = token.try_text() {
+        return write!(out, " {:?}", text);
+    }
+    out.write_str(" b\"")?;
+    for &byte in token.bytes() {
+        match byte {
+            b'"' | b'\\' => write!(out, "\\{}", byte as char)?,
+            b'\n' => out.write_str("\\n")?,
+            b'\r' => out.write_str("\\r")?,
+            b'\t' => out.write_str("\\t")?,
+            b' '..=b'~' => out.write_char(byte as char)?,
+            _ => write!(out, "\\x{:02X}", byte)?,
+        }
+    }
+    out.write_str("\"")
+}
+
+/// Read a tree from an S-expression, as printed by [`Node::to_sexpr`].
+///
+/// Kind names are looked up with `kinds`; a kind can also be written as its number.
+/// Whitespace between elements is insignificant.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let kinds = |name: &str| match name {
+///     "ATOM" => Some(ATOM),
+///     "LIST" => Some(LIST),
+///     _ => None,
+/// };
+/// let mut builder = green::Builder::new();
+/// let tree = green::from_sexpr(
+///     r#"
+///     (LIST
+///       ATOM "a"
+///       (LIST ATOM "b\n"))
+///     "#,
+///     kinds,
+///     &mut builder,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(tree.kind(), LIST);
+/// assert_eq!(tree.len(), 3.into());
+/// ```
+pub fn from_sexpr(
+    text: &str,
+    kinds: impl Fn(&str) -> Option<Kind>,
+    builder: &mut Builder,
+) -> Result<Arc<Node>, SexprError> {
+    let mut parser = Parser { text: text.as_bytes(), pos: 0, kinds: &kinds, builder };
+    parser.skip_whitespace();
+    let node = parser.node()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+        None => Ok(node),
+        Some(_) => Err(SexprError::Unexpected { offset: parser.pos }),
+    }
+}
+
+/// Error for reading a malformed S-expression. See [`from_sexpr`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SexprError {
+    /// The text ended in the middle of the tree.
+    UnexpectedEnd,
+    /// An unexpected character at the given byte offset.
+    #[allow(missing_docs)]
+    Unexpected { offset: usize },
+    /// A kind name that is not known, at the given byte offset.
+    #[allow(missing_docs)]
+    UnknownKind { offset: usize, name: String },
+    /// A node could not be built.
+    Node(NodeError),
+}
+
+impl fmt::Display for SexprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SexprError::UnexpectedEnd => write!(f, "unexpected end of S-expression"),
+            SexprError::Unexpected { offset } => {
+                write!(f, "unexpected character in S-expression at offset {}", offset)
+            }
+            SexprError::UnknownKind { offset, name } => {
+                write!(f, "unknown kind {:?} in S-expression at offset {}", name, offset)
+            }
+            SexprError::Node(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for SexprError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SexprError::Node(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+    kinds: &'a dyn Fn(&str) -> Option<Kind>,
+    builder: &'a mut Builder,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Result<u8, SexprError> {
+        let byte = self.peek().ok_or(SexprError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), SexprError> {
+        let offset = self.pos;
+        if self.bump()? == expected {
+            Ok(())
+        } else {
+            Err(SexprError::Unexpected { offset })
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(byte) if byte.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn kind(&mut self) -> Result<Kind, SexprError> {
+        let start = self.pos;
+        while let Some(byte) = self.peek() {
+            if byte.is_ascii_whitespace() || b"()\"".contains(&byte) {
+                break;
+            }
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self
+                .peek()
+                .map_or(SexprError::UnexpectedEnd, |_| SexprError::Unexpected { offset: start }));
+        }
+        // NB: only ASCII bytes end a name, so it is on char boundaries
+        let name = str::from_utf8(&self.text[start..self.pos]).unwrap();
+        (self.kinds)(name)
+            .or_else(|| name.parse().ok().map(Kind))
+            .ok_or_else(|| SexprError::UnknownKind { offset: start, name: name.into() })
+    }
+
+    fn node(&mut self) -> Result<Arc<Node>, SexprError> {
+        self.expect(b'(')?;
+        self.skip_whitespace();
+        let kind = self.kind()?;
+        let mut children: Vec<NodeOrToken<Arc<Node>, Arc<Token>>> = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b')') => break,
+                Some(b'(') => children.push(self.node()?.into()),
+                _ => {
+                    let kind = self.kind()?;
+                    self.skip_whitespace();
+                    children.push(self.token(kind)?.into());
+                }
+            }
+        }
+        self.pos += 1;
+        self.builder.try_node(kind, children).map_err(SexprError::Node)
+    }
+
+    fn token(&mut self, kind: Kind) -> Result<Arc<Token>, SexprError> {
+        let binary = self.peek() == Some(b'b');
+        if binary {
+            self.pos += 1;
+        }
+        self.expect(b'"')?;
+        let mut bytes = vec![];
+        loop {
+            let offset = self.pos;
+            match self.bump()? {
+                b'"' => break,
+                b'\\' => match self.bump()? {
+                    b'n' => bytes.push(b'\n'),
+                    b'r' => bytes.push(b'\r'),
+                    b't' => bytes.push(b'\t'),
+                    b'0' => bytes.push(b'\0'),
+                    escaped @ (b'\\' | b'"' | b'\'') => bytes.push(escaped),
+                    b'x' if binary => bytes.push(self.hex(2, offset)? as u8),
+                    b'u' if !binary => {
+                        self.expect(b'{')?;
+                        let len = self.text[self.pos..]
+                            .iter()
+                            .take_while(|byte| byte.is_ascii_hexdigit())
+                            .count();
+                        let c = char::from_u32(self.hex(len, offset)?)
+                            .ok_or(SexprError::Unexpected { offset })?;
+                        self.expect(b'}')?;
+                        bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                    }
+                    _ => return Err(SexprError::Unexpected { offset }),
+                },
+                byte => bytes.push(byte),
+            }
+        }
+        if binary {
+            Ok(self.builder.binary_token(kind, &bytes))
+        } else {
+            // NB: escapes only produce whole chars, so the text is still UTF-8
+            Ok(self.builder.token(kind, str::from_utf8(&bytes).unwrap()))
+        }
+    }
+
+    /// Parse `len` hex digits, erroring at `offset` if they're invalid.
+    fn hex(&mut self, len: usize, offset: usize) -> Result<u32, SexprError> {
+        let digits = self.text.get(self.pos..self.pos + len).ok_or(SexprError::UnexpectedEnd)?;
+        let digits = str::from_utf8(digits).map_err(|_| SexprError::Unexpected { offset })?;
+        let value =
+            u32::from_str_radix(digits, 16).map_err(|_| SexprError::Unexpected { offset })?;
+        self.pos += len;
+        Ok(value)
+    }
+}
@@ -0,0 +1,159 @@
+use {
+    crate::{green::Node, Kind, KindSet},
+    std::{error::Error, fmt},
+};
+
+/// A description of the children a node of some kind is expected to have.
+///
+/// A node conforms to a shape if it is of the shape's kind, and its children
+/// are, in order, of a kind in each of the shape's child kind sets, followed by
+/// any number of children of a kind in the shape's [`rest`](Shape::rest) set.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, Shape}, Kind, KindSet};
+/// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const PAIR: Kind = Kind(2);
+/// let atom: KindSet = [ATOM].iter().copied().collect();
+/// let ws: KindSet = [WS].iter().copied().collect();
+/// let pair = Shape::new(PAIR).children(vec![atom.clone(), ws, atom]);
+///
+/// let tree = green::TreeBuilder::new()
+///     .start_node(PAIR)
+///     .token(ATOM, "a")
+///     .token(WS, " ")
+///     .token(ATOM, "b")
+///     .finish_node()
+///     .finish();
+/// assert!(tree.conforms(&pair));
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Shape {
+    kind: Kind,
+    children: Vec<KindSet>,
+    rest: KindSet,
+}
+
+impl Shape {
+    /// A shape of nodes of `kind` without any children.
+    pub fn new(kind: Kind) -> Self {
+        Shape { kind, children: vec![], rest: KindSet::new() }
+    }
+
+    /// Expect children of a kind in each set, in order.
+    pub fn children(mut self, children: impl IntoIterator<Item = KindSet>) -> Self {
+        self.children = children.into_iter().collect();
+        self
+    }
+
+    /// Allow any number of trailing children of a kind in `kinds`.
+    pub fn rest(mut self, kinds: KindSet) -> Self {
+        self.rest = kinds;
+        self
+    }
+
+    /// The kind of node this shape describes.
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Check that `node` conforms to this shape, ignoring its descendants.
+    pub fn check(&self, node: &Node) -> Result<(), ShapeError> {
+        self.check_at(node, &[])
+    }
+
+    fn check_at(&self, node: &Node, path: &[usize]) -> Result<(), ShapeError> {
+        let path = || path.to_vec();
+        if node.kind() != self.kind {
+            return Err(ShapeError::Kind { path: path(), expected: self.kind, found: node.kind() });
+        }
+        let found = node.children_len();
+        if found < self.children.len() || found > self.children.len() && self.rest.is_empty() {
+            return Err(ShapeError::Arity { path: path(), expected: self.children.len(), found });
+        }
+        let expected = self.children.iter().chain(std::iter::repeat(&self.rest));
+        for (index, (child, expected)) in node.children().zip(expected).enumerate() {
+            if !expected.contains(child.kind()) {
+                return Err(ShapeError::ChildKind { path: path(), index, found: child.kind() });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Node {
+    /// Whether this node conforms to `shape`, ignoring its descendants.
+    pub fn conforms(&self, shape: &Shape) -> bool {
+        shape.check(self).is_ok()
+    }
+
+    /// Check that this node and all of its descendant nodes conform to the
+    /// first shape in `shapes` of their kind, if any.
+    ///
+    /// Nodes of kinds without a shape are not checked, but their descendants are.
+    /// The error is for the first nonconforming node in preorder.
+    pub fn validate(&self, shapes: &[Shape]) -> Result<(), ShapeError> {
+        validate(self, shapes, &mut vec![])
+    }
+}
+
+fn validate(node: &Node, shapes: &[Shape], path: &mut Vec<usize>) -> Result<(), ShapeError> {
+    if let Some(shape) = shapes.iter().find(|shape| shape.kind == node.kind()) {
+        shape.check_at(node, path)?;
+    }
+    for (index, child) in node.children().enumerate() {
+        if let Some(child) = child.as_node() {
+            path.push(index);
+            validate(child, shapes, path)?;
+            path.pop();
+        }
+    }
+    Ok(())
+}
+
+/// Error for a node not conforming to a [`Shape`].
+///
+/// The path is to the nonconforming node: the index of the child in the root,
+/// then the index of the child within that, and so on.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ShapeError {
+    /// The node is of a different kind than the shape.
+    #[allow(missing_docs)]
+    Kind { path: Vec<usize>, expected: Kind, found: Kind },
+    /// The node has too few or too many children.
+    #[allow(missing_docs)]
+    Arity { path: Vec<usize>, expected: usize, found: usize },
+    /// A child of the node is of an unexpected kind.
+    #[allow(missing_docs)]
+    ChildKind { path: Vec<usize>, index: usize, found: Kind },
+}
+
+impl ShapeError {
+    /// The path to the nonconforming node.
+    pub fn path(&self) -> &[usize] {
+        match self {
+            ShapeError::Kind { path, .. }
+            | ShapeError::Arity { path, .. }
+            | ShapeError::ChildKind { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShapeError::Kind { path, expected, found } => {
+                write!(f, "node at {:?} is {:?}, expected {:?}", path, found, expected)
+            }
+            ShapeError::Arity { path, expected, found } => {
+                write!(f, "node at {:?} has {} children, expected {}", path, found, expected)
+            }
+            ShapeError::ChildKind { path, index, found } => {
+                write!(f, "child {} of node at {:?} is of unexpected kind {:?}", index, path, found)
+            }
+        }
+    }
+}
+
+impl Error for ShapeError {}
@@ -0,0 +1,108 @@
+use {
+    crate::{green::Node, Kind, KindNames, NodeOrToken, TextSize},
+    std::fmt,
+};
+
+/// Truncated printout of a green tree. See [`Node::summary`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let tree = green::TreeBuilder::new()
+///     .start_node(LIST)
+///     .token(ATOM, "a")
+///     .token(ATOM, "b")
+///     .token(ATOM, "c")
+///     .finish_node()
+///     .finish();
+///
+/// let names = |kind| match kind {
+///     ATOM => Some("ATOM"),
+///     LIST => Some("LIST"),
+///     _ => None,
+/// };
+/// assert_eq!(
+///     tree.summary(1, 2).with_kind_names(&names).to_string(),
+///     "LIST@0..3\n  ATOM@0..1 \"a\"\n  ATOM@1..2 \"b\"\n  … 1 more children\n",
+/// );
+/// ```
+#[derive(Clone, Copy)]
+pub struct Summary<'a> {
+    node: &'a Node,
+    max_depth: usize,
+    max_children: usize,
+    names: Option<&'a dyn KindNames>,
+}
+
+impl fmt::Debug for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Summary")
+            .field("node", &format_args!("{:?}@{:?}", self.node.kind(), self.node.len()))
+            .field("max_depth", &self.max_depth)
+            .field("max_children", &self.max_children)
+            .finish()
+    }
+}
+
+impl<'a> Summary<'a> {
+    pub(super) fn new(node: &'a Node, max_depth: usize, max_children: usize) -> Self {
+        Summary { node, max_depth, max_children, names: None }
+    }
+
+    /// Print kinds using the names given by `names`.
+    pub fn with_kind_names(self, names: &'a dyn KindNames) -> Self {
+        Summary { names: Some(names), ..self }
+    }
+
+    fn write_kind(&self, f: &mut fmt::Formatter<'_>, kind: Kind) -> fmt::Result {
+        match self.names.and_then(|names| names.kind_name(kind)) {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{:?}", kind),
+        }
+    }
+
+    fn write_node(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        node: &Node,
+        offset: TextSize,
+        depth: usize,
+    ) -> fmt::Result {
+        write!(f, "{:indent$}", "", indent = depth * 2)?;
+        self.write_kind(f, node.kind())?;
+        write!(f, "@{:?}", offset..offset + node.len())?;
+        if depth >= self.max_depth && node.has_children() {
+            return writeln!(f, " … {} children", node.children_len());
+        }
+        writeln!(f)?;
+
+        for (child_offset, child) in node.children().with_offsets().take(self.max_children) {
+            let offset = offset + child_offset;
+            match child {
+                NodeOrToken::Node(node) => self.write_node(f, &node, offset, depth + 1)?,
+                NodeOrToken::Token(token) => {
+                    write!(f, "{:indent$}", "", indent = (depth + 1) * 2)?;
+                    self.write_kind(f, token.kind())?;
+                    write!(f, "@{:?} ", offset..offset + token.len())?;
+                    match token.try_text() {
+                        Some(text) => writeln!(f, "{:?}", text)?,
+                        None => writeln!(f, "{:?}", token.bytes())?,
+                    }
+                }
+            }
+        }
+        let rest = node.children_len().saturating_sub(self.max_children);
+        if rest != 0 {
+            writeln!(f, "{:indent$}… {} more children", "", rest, indent = (depth + 1) * 2)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_node(f, self.node, 0.into(), 0)
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{green::Node, Kind, NodeOrToken, TextRange, TextSize};
+
+/// A symbol in an outline of a tree. See [`Node::document_symbols`].
+///
+/// This mirrors the Language Server Protocol's `DocumentSymbol`. The range
+/// is in UTF-8 offsets into the text of the tree the outline was made from;
+/// convert it with a [`Utf16Index`] to get LSP positions.
+///
+///   [`Utf16Index`]: crate::green::Utf16Index
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct DocumentSymbol<K> {
+    /// The name of the symbol.
+    pub name: String,
+    /// The kind of the symbol.
+    pub kind: K,
+    /// The range of the node which is the symbol.
+    pub range: TextRange,
+    /// The symbols nested in this one.
+    pub children: Vec<DocumentSymbol<K>>,
+}
+
+impl Node {
+    /// Build a nested outline of the symbols in this tree.
+    ///
+    /// A node is a symbol if `classify` gives a symbol kind for its kind and
+    /// `name_of` gives it a name; `name_of` is only called on nodes which
+    /// `classify` accepts. Symbols are nested under their closest ancestor
+    /// which is a symbol, skipping over nodes which aren't, and are in order.
+    /// Ranges are relative to the start of this node.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind, NodeOrToken, TextRange};
+    /// # const WS: Kind = Kind(0); const NAME: Kind = Kind(1);
+    /// # const FILE: Kind = Kind(2); const FN: Kind = Kind(3); const BLOCK: Kind = Kind(4);
+    /// // fn a { fn b }
+    /// let tree = green::TreeBuilder::new()
+    ///     .start_node(FILE)
+    ///     .start_node(FN)
+    ///     .token(NAME, "a")
+    ///     .token(WS, " ")
+    ///     .start_node(BLOCK)
+    ///     .start_node(FN)
+    ///     .token(NAME, "b")
+    ///     .finish_node()
+    ///     .finish_node()
+    ///     .finish_node()
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// let classify = |kind| if kind == FN { Some("function") } else { None };
+    /// let name_of = |node: &green::Node| {
+    ///     let name = node.children().find(|el| el.kind() == NAME)?;
+    ///     Some(name.into_token()?.text().to_string())
+    /// };
+    /// let symbols = tree.document_symbols(classify, name_of);
+    ///
+    /// assert_eq!(symbols.len(), 1);
+    /// assert_eq!(symbols[0].name, "a");
+    /// assert_eq!(symbols[0].range, TextRange::new(0.into(), 3.into()));
+    /// assert_eq!(symbols[0].children[0].name, "b");
+    /// assert_eq!(symbols[0].children[0].kind, "function");
+    /// assert_eq!(symbols[0].children[0].range, TextRange::new(2.into(), 3.into()));
+    /// ```
+    pub fn document_symbols<K>(
+        &self,
+        classify: impl Fn(Kind) -> Option<K>,
+        name_of: impl Fn(&Node) -> Option<String>,
+    ) -> Vec<DocumentSymbol<K>> {
+        let mut symbols = vec![];
+        visit(self, 0.into(), &classify, &name_of, &mut symbols);
+        symbols
+    }
+}
+
+type Classify<'a, K> = &'a dyn Fn(Kind) -> Option<K>;
+type NameOf<'a> = &'a dyn Fn(&Node) -> Option<String>;
+
+fn visit<K>(
+    node: &Node,
+    offset: TextSize,
+    classify: Classify<'_, K>,
+    name_of: NameOf<'_>,
+    symbols: &mut Vec<DocumentSymbol<K>>,
+) {
+    let symbol = classify(node.kind()).and_then(|kind| Some((kind, name_of(node)?)));
+    match symbol {
+        Some((kind, name)) => {
+            let mut children = vec![];
+            visit_children(node, offset, classify, name_of, &mut children);
+            let range = TextRange::at(offset, node.len());
+            symbols.push(DocumentSymbol { name, kind, range, children });
+        }
+        None => visit_children(node, offset, classify, name_of, symbols),
+    }
+}
+
+fn visit_children<K>(
+    node: &Node,
+    offset: TextSize,
+    classify: Classify<'_, K>,
+    name_of: NameOf<'_>,
+    symbols: &mut Vec<DocumentSymbol<K>>,
+) {
+    for (child_offset, child) in node.children().with_offsets() {
+        if let NodeOrToken::Node(child) = child {
+            visit(&child, offset + child_offset, classify, name_of, symbols);
+        }
+    }
+}
@@ -1,74 +1,291 @@
 use {
-    crate::{Kind, TextSize},
+    crate::{green::ElementRef, Kind, SyntaxKind, TextRange, TextSize},
     erasable::{Erasable, ErasedPtr},
     slice_dst::{AllocSliceDst, SliceDst},
-    std::{alloc::Layout, convert::TryFrom, hash, ptr},
+    std::{alloc::Layout, cmp::Ordering, convert::TryFrom, fmt, hash, ops::Range, ptr, slice, str},
 };
 
 /// A leaf token in the immutable green tree.
 ///
-/// Tokens are crated using [`Builder::token`](crate::green::Builder::token).
+/// Tokens are crated using [`Builder::token`](crate::green::Builder::token),
+/// or [`Builder::binary_token`](crate::green::Builder::binary_token)
+/// for tokens of a byte-oriented source, which may not be UTF-8.
+///
+/// Tokens are ordered structurally: by kind, then text tokens before binary tokens,
+/// then by text, then by [`flags`](Token::flags).
+/// Tokens with different flags are never equal, even if their text is.
 #[repr(C, align(2))] // NB: align >= 2
-#[derive(Debug, Eq)]
+#[derive(Eq)]
 pub struct Token {
-    // NB: This is optimal layout, as the order is (u32, u16, [u8]).
+    // NB: This is optimal layout, as the order is (u32, u16, u8, [u8]).
     // SAFETY: Must be at offset 0 and accurate to trailing array length.
-    text_len: TextSize,
+    text_len: u32,
     kind: Kind,
-    text: str,
+    flags: u8,
+    // SAFETY: Must be UTF-8 unless BINARY_FLAG is set.
+    text: [u8],
 }
 
+/// Set in the `flags` header of a token holding arbitrary bytes.
+const BINARY_FLAG: u8 = 1 << 7;
+
 // Manually impl Eq/Hash so that builder can spoof it
-// Plus we can skip .text_len since it's derived from .text
+// Plus we can skip .text_len since it's derived from .text.
+// The classification flags are compared, so that builders with different
+// classifiers never share a token with the wrong flags.
 impl PartialEq for Token {
     fn eq(&self, other: &Self) -> bool {
-        self.kind == other.kind && self.text == other.text
+        self.kind == other.kind && self.flags == other.flags && self.text == other.text
+    }
+}
+
+// Ordered by kind first, then whether the token is binary, then text, then flags
+impl Ord for Token {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.kind
+            .cmp(&other.kind)
+            .then_with(|| self.is_binary().cmp(&other.is_binary()))
+            .then_with(|| self.text.cmp(&other.text))
+            .then_with(|| self.flags().cmp(&other.flags()))
+    }
+}
+
+impl PartialOrd for Token {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
 impl hash::Hash for Token {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
         self.kind.hash(state);
+        self.flags.hash(state);
         self.text.hash(state);
     }
 }
 
-#[allow(clippy::len_without_is_empty)]
+// Match the derived output for text tokens
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("Token");
+        f.field("text_len", &self.len()).field("kind", &self.kind);
+        match self.try_text() {
+            Some(text) => f.field("text", &text),
+            None => f.field("bytes", &&self.text),
+        };
+        f.finish()
+    }
+}
+
 impl Token {
+    /// Check the invariants of this token's header and text that unsafe code relies on.
+    #[cfg(feature = "paranoid")]
+    fn check_invariants(&self) {
+        assert_eq!(
+            self.text_len as usize,
+            self.text.len(),
+            "token header disagrees with its text length",
+        );
+        assert!(self.is_binary() || str::from_utf8(&self.text).is_ok(), "text token is not UTF-8",);
+    }
+
     /// The kind of this token.
     #[inline]
     pub fn kind(&self) -> Kind {
         self.kind
     }
 
+    /// The kind of this token, as a language's own kind type.
+    ///
+    /// Returns `None` if the raw kind is not a kind of that type.
+    pub fn kind_as<K: SyntaxKind>(&self) -> Option<K> {
+        K::from_raw(self.kind)
+    }
+
     /// The text of this token.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is a binary token. See [`try_text`](Token::try_text).
     #[inline]
     pub fn text(&self) -> &str {
+        self.try_text().expect("called `text` on binary token")
+    }
+
+    /// The text of this token, or `None` if this is a binary token.
+    #[inline]
+    pub fn try_text(&self) -> Option<&str> {
+        #[cfg(feature = "paranoid")]
+        self.check_invariants();
+        if self.is_binary() {
+            None
+        } else {
+            // SAFETY: text tokens are always UTF-8
+            Some(unsafe { str::from_utf8_unchecked(&self.text) })
+        }
+    }
+
+    /// The raw bytes of this token's text.
+    #[inline]
+    pub fn bytes(&self) -> &[u8] {
+        #[cfg(feature = "paranoid")]
+        self.check_invariants();
         &self.text
     }
 
+    /// Whether this token holds arbitrary bytes rather than UTF-8 text.
+    #[inline]
+    pub fn is_binary(&self) -> bool {
+        self.flags & BINARY_FLAG != 0
+    }
+
     /// The length of text at this token.
     #[inline]
     pub fn len(&self) -> TextSize {
-        self.text_len
+        #[cfg(feature = "paranoid")]
+        self.check_invariants();
+        self.text_len.into()
+    }
+
+    /// Whether this token has no text, such as a [missing](crate::green::Builder::missing) token.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0.into()
+    }
+
+    /// The largest value of [`flags`](Token::flags).
+    ///
+    /// Flags share a header byte with the marker for binary tokens,
+    /// which takes the high bit, so seven bits are left for the classifier.
+    pub const MAX_FLAGS: u8 = !BINARY_FLAG;
+
+    /// The classification flags of this token, as computed by the classifier
+    /// of the builder which created it. See [`Builder::set_token_classifier`].
+    ///
+    /// Flags are `0` for tokens created without a classifier, and for binary tokens.
+    ///
+    ///   [`Builder::set_token_classifier`]: crate::green::Builder::set_token_classifier
+    #[inline]
+    pub fn flags(&self) -> u8 {
+        self.flags & Token::MAX_FLAGS
+    }
+
+    /// The text of this token within `range`, which is relative to the start of this token.
+    ///
+    /// Returns `None` if this is a binary token, if the range is past the end
+    /// of the text, or if the range does not lie on char boundaries.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind, TextRange};
+    /// # const STRING: Kind = Kind(0);
+    /// let token = green::Builder::new().token(STRING, r#""a\nb""#);
+    /// let escape = TextRange::at(2.into(), 2.into());
+    /// assert_eq!(token.slice(escape), Some(r"\n"));
+    /// assert_eq!(token.slice(TextRange::at(6.into(), 2.into())), None);
+    /// ```
+    pub fn slice(&self, range: TextRange) -> Option<&str> {
+        self.try_text()?.get(Range::<usize>::from(range))
+    }
+
+    /// The chars of this token's text, with their offsets from the start of this token.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is a binary token.
+    pub fn char_indices(&self) -> impl '_ + DoubleEndedIterator<Item = (TextSize, char)> {
+        self.text().char_indices().map(|(offset, c)| (TextSize::try_from(offset).unwrap(), c))
+    }
+
+    /// Whether the text of this token equals `text`, ignoring ASCII case.
+    ///
+    /// This compares the raw bytes, so it doesn't panic for binary tokens.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// let token = green::Builder::new().token(Kind(0), "SeLeCt");
+    /// assert!(token.text_eq_ignore_ascii_case("select"));
+    /// assert!(!token.text_eq_ignore_ascii_case("selects"));
+    /// ```
+    pub fn text_eq_ignore_ascii_case(&self, text: &str) -> bool {
+        self.bytes().eq_ignore_ascii_case(text.as_bytes())
+    }
+
+    /// Create a new text token from chunks of text, which should add up to `len` bytes,
+    /// returning the token and the actual total length of the chunks.
+    /// The flags of the token are computed by `classify` from the written text.
+    ///
+    /// If the chunks don't add up to `len`, the text is truncated or zero-padded,
+    /// and the token is marked binary, as the text may have been cut mid-char.
+    pub(super) fn from_chunks<'s, A>(
+        kind: Kind,
+        chunks: impl IntoIterator<Item = &'s str>,
+        len: usize,
+        classify: impl FnOnce(&str) -> u8,
+    ) -> (A, usize)
+    where
+        A: AllocSliceDst<Self>,
+    {
+        let text_len = u32::try_from(len).expect("text too long");
+        let (layout, [text_len_offset, kind_offset, flags_offset, text_offset]) = Self::layout(len);
+        let mut written = 0;
+        let mut flags = 0;
+
+        let token = unsafe {
+            // SAFETY: closure fully initializes the place
+            A::new_slice_dst(len, |ptr| {
+                let raw = ptr.as_ptr().cast::<u8>();
+                ptr::write(raw.add(kind_offset).cast(), kind);
+                let text_ptr = raw.add(text_offset);
+                for chunk in chunks {
+                    let start = written.min(len);
+                    let end = (written + chunk.len()).min(len);
+                    ptr::copy_nonoverlapping(chunk.as_ptr(), text_ptr.add(start), end - start);
+                    written += chunk.len();
+                }
+                if written < len {
+                    ptr::write_bytes(text_ptr.add(written), 0, len - written);
+                }
+                let header_flags = if written == len {
+                    // SAFETY: the text is fully written from whole `str` chunks
+                    let text = str::from_utf8_unchecked(slice::from_raw_parts(text_ptr, len));
+                    flags = classify(text);
+                    // NB: checked after initialization, so that the token can be dropped
+                    flags & Token::MAX_FLAGS
+                } else {
+                    BINARY_FLAG
+                };
+                ptr::write(raw.add(text_len_offset).cast(), text_len);
+                ptr::write(raw.add(flags_offset), header_flags);
+                debug_assert_eq!(layout, Layout::for_value(ptr.as_ref()));
+            })
+        };
+        check_flags(flags);
+        (token, written)
     }
 
     // SAFETY: must accurately calculate the layout for length `len`
-    fn layout(len: usize) -> (Layout, [usize; 3]) {
-        let (layout, offset_0) = (Layout::new::<TextSize>(), 0);
+    fn layout(len: usize) -> (Layout, [usize; 4]) {
+        let (layout, offset_0) = (Layout::new::<u32>(), 0);
         let (layout, offset_1) = layout.extend(Layout::new::<Kind>()).unwrap();
-        let (layout, offset_2) = layout.extend(Layout::array::<u8>(len).unwrap()).unwrap();
-        (layout.pad_to_align(), [offset_0, offset_1, offset_2])
+        let (layout, offset_2) = layout.extend(Layout::new::<u8>()).unwrap();
+        let (layout, offset_3) = layout.extend(Layout::array::<u8>(len).unwrap()).unwrap();
+        (layout.pad_to_align(), [offset_0, offset_1, offset_2, offset_3])
     }
 
+    /// Create a new token. `text` must be UTF-8 unless `binary` is set.
     #[allow(clippy::new_ret_no_self)]
-    pub(super) fn new<A>(kind: Kind, text: &str) -> A
+    pub(super) fn new<A>(kind: Kind, text: &[u8], binary: bool, flags: u8) -> A
     where
         A: AllocSliceDst<Self>,
     {
         let len = text.len();
-        let text_len = TextSize::try_from(len).expect("text too long");
-        let (layout, [text_len_offset, kind_offset, text_offset]) = Self::layout(len);
+        let text_len = u32::try_from(len).expect("text too long");
+        let flags = header_flags(binary, flags);
+        let (layout, [text_len_offset, kind_offset, flags_offset, text_offset]) = Self::layout(len);
 
         unsafe {
             // SAFETY: closure fully initializes the place
@@ -76,20 +293,36 @@ impl Token {
                 let raw = ptr.as_ptr().cast::<u8>();
                 ptr::write(raw.add(text_len_offset).cast(), text_len);
                 ptr::write(raw.add(kind_offset).cast(), kind);
+                ptr::write(raw.add(flags_offset), flags);
                 let text_ptr = raw.add(text_offset);
-                ptr::copy_nonoverlapping(text.as_bytes().as_ptr(), text_ptr, len);
+                ptr::copy_nonoverlapping(text.as_ptr(), text_ptr, len);
                 debug_assert_eq!(layout, Layout::for_value(ptr.as_ref()));
             })
         }
     }
 }
 
+/// The `flags` header of a new token, which is hashed as part of its identity.
+pub(super) fn header_flags(binary: bool, flags: u8) -> u8 {
+    if binary {
+        BINARY_FLAG
+    } else {
+        check_flags(flags)
+    }
+}
+
+/// Check that classification flags fit in the `flags` header.
+fn check_flags(flags: u8) -> u8 {
+    assert!(flags <= Token::MAX_FLAGS, "token flags {:#b} don't fit in `Token::MAX_FLAGS`", flags);
+    flags
+}
+
 // SAFETY: un/erase correctly round-trips a pointer
 unsafe impl Erasable for Token {
     unsafe fn unerase(this: ErasedPtr) -> ptr::NonNull<Self> {
         // SAFETY: text_len is at 0 offset
-        let text_len: TextSize = ptr::read(this.cast().as_ptr());
-        let ptr = ptr::slice_from_raw_parts_mut(this.as_ptr().cast(), text_len.into());
+        let text_len: u32 = ptr::read(this.cast().as_ptr());
+        let ptr = ptr::slice_from_raw_parts_mut(this.as_ptr().cast(), text_len as usize);
         // SAFETY: ptr comes from NonNull
         Self::retype(ptr::NonNull::new_unchecked(ptr))
     }
@@ -108,3 +341,47 @@ unsafe impl SliceDst for Token {
         ptr::NonNull::new(ptr.as_ptr() as *mut _).unwrap()
     }
 }
+
+/// Matcher of tokens by kind and text, ignoring ASCII case,
+/// such as for the keywords of case-insensitive languages.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, Keyword}, Kind};
+/// # const WS: Kind = Kind(0); const KW: Kind = Kind(1); const STMT: Kind = Kind(2);
+/// const FROM: Keyword<'_> = Keyword::new(KW, "from");
+///
+/// let tree = green::TreeBuilder::new()
+///     .start_node(STMT)
+///     .token(KW, "SELECT")
+///     .token(WS, " ")
+///     .token(KW, "FROM")
+///     .finish_node()
+///     .finish();
+/// assert_eq!(tree.children().position(|el| FROM.matches(&el)), Some(2));
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Keyword<'a> {
+    /// The kind of token to match.
+    pub kind: Kind,
+    /// The text to match, ignoring ASCII case.
+    pub text: &'a str,
+}
+
+impl<'a> Keyword<'a> {
+    /// Create a matcher of tokens of `kind` with `text`, ignoring ASCII case.
+    pub const fn new(kind: Kind, text: &'a str) -> Self {
+        Keyword { kind, text }
+    }
+
+    /// Whether `token` is matched.
+    pub fn matches_token(&self, token: &Token) -> bool {
+        token.kind() == self.kind && token.text_eq_ignore_ascii_case(self.text)
+    }
+
+    /// Whether `element` is a matched token.
+    pub fn matches(&self, element: &ElementRef<'_>) -> bool {
+        element.as_token().map_or(false, |token| self.matches_token(token))
+    }
+}
@@ -0,0 +1,116 @@
+//! Process-wide caches of tokens.
+//!
+//! Tokens of a fixed alphabet, such as keywords and punctuation, make up most
+//! tokens of a typical source file. Rather than hashing and caching them in
+//! every [`Builder`](crate::green::Builder), they can be created once, up front,
+//! and shared by all builders.
+
+use {
+    crate::{green::Token, Kind},
+    std::{
+        fmt, ptr,
+        sync::{
+            atomic::{AtomicPtr, Ordering},
+            Arc,
+        },
+    },
+};
+
+// NB: set at most once, and never freed, so it can be handed out as `&'static`
+static GLOBAL: AtomicPtr<StaticAlphabet> = AtomicPtr::new(ptr::null_mut());
+
+/// A fixed set of text tokens, created once and shared by all builders.
+///
+/// Once [installed](StaticAlphabet::install), every [`Builder`](crate::green::Builder)
+/// looks up text tokens in the alphabet before hashing them into its own cache,
+/// and returns the alphabet's token if it has one. Lookup is a binary search
+/// by kind and text, so it doesn't hash the token text.
+///
+/// Alphabet tokens don't have any [`flags`](Token::flags), so builders with a
+/// [token classifier](crate::green::Builder::set_token_classifier) don't use the alphabet.
+///
+/// # Examples
+///
+/// ```rust
+/// # use {sorbus::{green::{self, tokens::StaticAlphabet}, Kind}, std::sync::Arc};
+/// # const KW: Kind = Kind(0); const PUNCT: Kind = Kind(1);
+/// StaticAlphabet::new(vec![(KW, "fn"), (KW, "let"), (PUNCT, ";")]).install().unwrap();
+///
+/// let fn_kw = StaticAlphabet::global().unwrap().get(KW, "fn").unwrap();
+/// let mut builder = green::Builder::new();
+/// assert!(Arc::ptr_eq(&builder.token(KW, "fn"), fn_kw));
+/// assert_eq!(builder.size(), 0);
+/// ```
+pub struct StaticAlphabet {
+    tokens: Vec<Arc<Token>>, // sorted by kind, then text
+}
+
+impl fmt::Debug for StaticAlphabet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.tokens.iter().map(|token| (token.kind(), token.text()))).finish()
+    }
+}
+
+impl StaticAlphabet {
+    /// Create the tokens of an alphabet.
+    ///
+    /// Duplicate entries are only created once.
+    pub fn new<'a>(alphabet: impl IntoIterator<Item = (Kind, &'a str)>) -> Self {
+        let mut alphabet: Vec<_> = alphabet.into_iter().collect();
+        alphabet.sort_unstable();
+        alphabet.dedup();
+        let tokens = alphabet
+            .into_iter()
+            .map(|(kind, text)| Token::new(kind, text.as_bytes(), false, 0))
+            .collect();
+        StaticAlphabet { tokens }
+    }
+
+    /// Install this alphabet as the global alphabet consulted by all builders.
+    ///
+    /// Fails, returning this alphabet, if a global alphabet is already installed.
+    /// Tokens already cached by a builder are not replaced, so the alphabet
+    /// should be installed before creating any builders.
+    pub fn install(self) -> Result<(), Self> {
+        let alphabet = Box::into_raw(Box::new(self));
+        match GLOBAL.compare_exchange(
+            ptr::null_mut(),
+            alphabet,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => Ok(()),
+            // SAFETY: the alphabet wasn't installed, so this is still the only pointer to it
+            Err(_) => Err(*unsafe { Box::from_raw(alphabet) }),
+        }
+    }
+
+    /// The installed global alphabet, if any.
+    pub fn global() -> Option<&'static StaticAlphabet> {
+        // SAFETY: an installed alphabet is never freed
+        unsafe { GLOBAL.load(Ordering::Acquire).as_ref() }
+    }
+
+    /// The token of this alphabet with the given kind and text, if any.
+    pub fn get(&self, kind: Kind, text: &str) -> Option<&Arc<Token>> {
+        self.get_bytes(kind, text.as_bytes())
+    }
+
+    pub(super) fn get_bytes(&self, kind: Kind, text: &[u8]) -> Option<&Arc<Token>> {
+        let index = self
+            .tokens
+            .binary_search_by(|token| token.kind().cmp(&kind).then(token.bytes().cmp(text)))
+            .ok()?;
+        Some(&self.tokens[index])
+    }
+
+    /// The number of tokens in this alphabet.
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Whether this alphabet has no tokens.
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+}
@@ -1,7 +1,8 @@
 use {
     crate::{
         green::{
-            pack_node_or_token, unpack_node_or_token, Builder, Node, PackedNodeOrToken, Token,
+            pack_node_or_token, unpack_node_or_token, Builder, Node, PackedNodeOrToken, SliceIter,
+            Token,
         },
         Kind, NodeOrToken,
     },
@@ -12,12 +13,53 @@ use {
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub struct Checkpoint(usize);
 
+/// Policy for splitting whitespace tokens into canonical pieces.
+///
+/// Real-world whitespace is rarely identical (think indentation after a newline),
+/// so whitespace tokens defeat deduplication. With this policy, each whitespace
+/// token is instead added as a sequence of tokens drawn from a small set: a line
+/// break (`"\n"` or `"\r\n"`), or a run of up to `max_run` of the same character.
+/// The text of the tree is unchanged; only the whitespace tokens are split.
+/// See [`TreeBuilder::set_whitespace_policy`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct WhitespacePolicy {
+    /// The kind of whitespace tokens to split.
+    pub kind: Kind,
+    /// The maximum length of a run of the same character in one token.
+    pub max_run: usize,
+}
+
+impl WhitespacePolicy {
+    /// Split `text` into canonical pieces.
+    fn split<'a>(self, mut text: &'a str) -> impl 'a + Iterator<Item = &'a str> {
+        let max_run = self.max_run.max(1);
+        std::iter::from_fn(move || {
+            let first = text.chars().next()?;
+            let len = if text.starts_with("\r\n") {
+                2
+            } else {
+                text.char_indices().take(max_run).find(|&(_, c)| c != first).map_or_else(
+                    || text.chars().take(max_run).map(char::len_utf8).sum(),
+                    |(i, _)| i,
+                )
+            };
+            let (piece, rest) = text.split_at(len);
+            text = rest;
+            Some(piece)
+        })
+    }
+}
+
 /// Top-down builder context for a green tree.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct TreeBuilder {
     cache: Builder,
     stack: Vec<(Kind, usize)>,
     children: Vec<PackedNodeOrToken>,
+    whitespace: Option<WhitespacePolicy>,
+    max_depth: Option<usize>,
+    flattened: usize, // open nodes past the depth limit
+    depth_exceeded: bool,
 }
 
 impl TreeBuilder {
@@ -42,9 +84,88 @@ impl TreeBuilder {
         self
     }
 
+    /// Set the policy for splitting whitespace tokens added with [`token`].
+    ///
+    ///   [`token`]: TreeBuilder::token
+    pub fn set_whitespace_policy(&mut self, policy: Option<WhitespacePolicy>) -> &mut Self {
+        self.whitespace = policy;
+        self
+    }
+
+    /// Set the maximum depth of nested nodes, or `None` for no limit (the default).
+    ///
+    /// Deeply nested input, such as thousands of open parentheses, builds trees
+    /// which are too deep to be useful and which recursive consumers may overflow
+    /// the stack on. Nodes started past the limit are not created; their children
+    /// are added to the deepest node within the limit instead, so the text of the
+    /// tree is unchanged. [`depth_exceeded`](TreeBuilder::depth_exceeded) reports
+    /// whether this happened. The root node is always created, even with a limit of 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+    /// let mut builder = green::TreeBuilder::new();
+    /// builder.set_max_depth(Some(2));
+    /// for _ in 0..100 {
+    ///     builder.start_node(LIST).token(ATOM, "(");
+    /// }
+    /// for _ in 0..100 {
+    ///     builder.token(ATOM, ")").finish_node();
+    /// }
+    /// assert!(builder.depth_exceeded());
+    /// let tree = builder.finish();
+    /// assert_eq!(tree.len(), 200.into());
+    /// assert_eq!(tree.children().filter(|el| el.is_node()).count(), 1);
+    /// ```
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) -> &mut Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Whether a node was started past the [maximum depth](TreeBuilder::set_max_depth)
+    /// in the tree currently being built.
+    ///
+    /// This is reset by [`finish`](TreeBuilder::finish), so check it before finishing.
+    pub fn depth_exceeded(&self) -> bool {
+        self.depth_exceeded
+    }
+
+    /// Whether to flatten a node started now into the current branch.
+    fn flatten(&mut self) -> bool {
+        let flatten = self.flattened > 0
+            || self.max_depth.map_or(false, |max| self.stack.len() >= max.max(1));
+        if flatten {
+            self.flattened += 1;
+            self.depth_exceeded = true;
+        }
+        flatten
+    }
+
     /// Add a new token to the current branch.
+    ///
+    /// If a [`WhitespacePolicy`] is set and applies to this token,
+    /// this may add multiple tokens instead.
     pub fn token(&mut self, kind: Kind, text: &str) -> &mut Self {
-        let token = self.cache.token(kind, text);
+        match self.whitespace {
+            Some(policy) if policy.kind == kind && !text.is_empty() => {
+                for piece in policy.split(text) {
+                    let token = self.cache.token(kind, piece);
+                    self.add(token);
+                }
+                self
+            }
+            _ => {
+                let token = self.cache.token(kind, text);
+                self.add(token)
+            }
+        }
+    }
+
+    /// Add a new binary token to the current branch.
+    pub fn binary_token(&mut self, kind: Kind, bytes: &[u8]) -> &mut Self {
+        let token = self.cache.binary_token(kind, bytes);
         self.add(token)
     }
 
@@ -53,7 +174,7 @@ impl TreeBuilder {
     where
         I: IntoIterator,
         I::Item: Into<NodeOrToken<Arc<Node>, Arc<Token>>>,
-        I::IntoIter: ExactSizeIterator + AsRef<[R]>,
+        I::IntoIter: SliceIter<Elem = R>,
         for<'a> &'a R: Into<NodeOrToken<&'a Node, &'a Token>>,
     {
         let node = self.cache.node(kind, children);
@@ -62,15 +183,58 @@ impl TreeBuilder {
 
     /// Start a new child node and make it the current branch.
     pub fn start_node(&mut self, kind: Kind) -> &mut Self {
-        self.stack.push((kind, self.children.len()));
+        if !self.flatten() {
+            self.stack.push((kind, self.children.len()));
+        }
+        self
+    }
+
+    /// Start a new child node with room for `additional` children,
+    /// and make it the current branch.
+    ///
+    /// This is [`start_node`](TreeBuilder::start_node) followed by
+    /// [`reserve_children`](TreeBuilder::reserve_children).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const ATOM: Kind = Kind(0); const OP: Kind = Kind(1); const BINARY: Kind = Kind(2);
+    /// // a binary expression always has three children
+    /// let tree = green::TreeBuilder::new()
+    ///     .start_node_with_capacity(BINARY, 3)
+    ///     .token(ATOM, "a")
+    ///     .token(OP, "+")
+    ///     .token(ATOM, "b")
+    ///     .finish_node()
+    ///     .finish();
+    /// assert_eq!(tree.children_len(), 3);
+    /// ```
+    pub fn start_node_with_capacity(&mut self, kind: Kind, additional: usize) -> &mut Self {
+        self.start_node(kind).reserve_children(additional)
+    }
+
+    /// Reserve room for at least `additional` more elements to be added
+    /// before the current branch is finished.
+    ///
+    /// Children of all open branches share one buffer, so this avoids
+    /// reallocating it while adding a known number of elements.
+    pub fn reserve_children(&mut self, additional: usize) -> &mut Self {
+        self.children.reserve(additional);
         self
     }
 
     /// Finish the current branch and restore its parent as current.
     pub fn finish_node(&mut self) -> &mut Self {
+        if self.flattened > 0 {
+            self.flattened -= 1;
+            return self;
+        }
         let (kind, first_child) = self.stack.pop().unwrap_or_else(|| {
             panic!("called `TreeBuilder::finish_node` without paired `start_node`")
         });
+        // NB: the current branch is always at the end of the buffer,
+        // so draining it doesn't shift any other elements.
         let children = self.children.drain(first_child..);
         // NB: inline Self::node here because of borrow on `self.children`
         let node = self.cache.node_packed(kind, children);
@@ -158,7 +322,9 @@ impl TreeBuilder {
             )
         };
 
-        self.stack.push((kind, checkpoint));
+        if !self.flatten() {
+            self.stack.push((kind, checkpoint));
+        }
         self
     }
 
@@ -176,6 +342,10 @@ impl TreeBuilder {
             checkpoint <= self.children.len(),
             "checkpoint no longer valid; was `finish_node` called early?",
         );
+        if self.flattened > 0 {
+            self.flattened -= 1;
+            return self;
+        }
 
         let (kind, first_child) = self.stack.pop().unwrap_or_else(|| {
             panic!("called `TreeBuilder::finish_node_at` without paired `start_node`")
@@ -184,10 +354,15 @@ impl TreeBuilder {
             checkpoint >= first_child,
             "checkpoint no longer valid; was an unmatched `start_node` called?",
         );
+        // NB: this shifts the elements after the checkpoint twice, but those are
+        // usually a few trivia tokens; benches/tree_builder.rs (finish_at) shows
+        // no measurable difference from finishing before adding them.
         let children = self.children.drain(first_child..checkpoint);
         // NB: inline Self::node here because of borrow on `self.children`
         let node = self.cache.node_packed(kind, children);
-        self.add(node)
+        // the node takes the place of its children, before the shifted elements
+        self.children.insert(first_child, pack_node_or_token(NodeOrToken::Node(node)));
+        self
     }
 
     /// Complete the current tree building.
@@ -199,7 +374,8 @@ impl TreeBuilder {
     /// Panics if more nodes have been started than finished,
     /// or the current branch has more than one element.
     pub fn finish(&mut self) -> Arc<Node> {
-        assert!(self.stack.is_empty());
+        assert!(self.stack.is_empty() && self.flattened == 0);
+        self.depth_exceeded = false;
         assert_eq!(self.children.len(), 1);
         unpack_node_or_token(self.children.pop().unwrap()).into_node().unwrap()
     }
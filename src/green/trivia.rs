@@ -0,0 +1,190 @@
+use {
+    crate::{
+        green::{ChildrenWithOffsets, ElementRef, Node, Token},
+        ArcBorrow, Kind, KindSet, NodeOrToken, TextRange, TextSize,
+    },
+    std::iter,
+};
+
+impl Node {
+    /// The run of trivia tokens directly before the child at `index`.
+    ///
+    /// Trivia are the tokens whose kinds match `is_trivia`, such as whitespace
+    /// and comments. This is the basis of attaching comments to the items they
+    /// document: the run ends at the nearest non-trivia sibling before the child.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not the index of a child of this node.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind};
+    /// # const WS: Kind = Kind(0); const COMMENT: Kind = Kind(1);
+    /// # const ATOM: Kind = Kind(2); const LIST: Kind = Kind(3);
+    /// let tree = green::TreeBuilder::new()
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "a")
+    ///     .token(WS, " ")
+    ///     .token(COMMENT, "/* b */")
+    ///     .token(WS, " ")
+    ///     .token(ATOM, "b")
+    ///     .token(WS, " ")
+    ///     .token(COMMENT, "// b")
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// let is_trivia = |kind| kind == WS || kind == COMMENT;
+    /// let comments = |trivia: green::ChildrenWithOffsets<'_>| {
+    ///     let comments = trivia.filter(|(_, el)| el.kind() == COMMENT);
+    ///     comments.map(|(_, el)| el.unwrap_token().text().to_owned()).collect::<Vec<_>>()
+    /// };
+    /// assert_eq!(comments(tree.leading_trivia_of(4, is_trivia)), ["/* b */"]);
+    /// assert_eq!(comments(tree.trailing_trivia_of(4, is_trivia)), ["// b"]);
+    /// assert_eq!(tree.leading_trivia_of(0, is_trivia).len(), 0);
+    /// ```
+    pub fn leading_trivia_of(
+        &self,
+        index: usize,
+        is_trivia: impl Fn(Kind) -> bool,
+    ) -> ChildrenWithOffsets<'_> {
+        assert!(index < self.children_len(), "child index {} out of range", index);
+        let (before, _) = self.children().with_offsets().split_at(index);
+        let start = before
+            .clone()
+            .rposition(|(_, el)| !is_trivia_token(&el, &is_trivia))
+            .map_or(0, |i| i + 1);
+        before.split_at(start).1
+    }
+
+    /// The run of trivia tokens directly after the child at `index`.
+    /// See [`leading_trivia_of`](Node::leading_trivia_of).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not the index of a child of this node.
+    pub fn trailing_trivia_of(
+        &self,
+        index: usize,
+        is_trivia: impl Fn(Kind) -> bool,
+    ) -> ChildrenWithOffsets<'_> {
+        assert!(index < self.children_len(), "child index {} out of range", index);
+        let (_, after) = self.children().with_offsets().split_at(index + 1);
+        let end = after
+            .clone()
+            .position(|(_, el)| !is_trivia_token(&el, &is_trivia))
+            .unwrap_or_else(|| after.len());
+        after.split_at(end).0
+    }
+}
+
+impl Node {
+    /// The comment tokens of this tree, in order, with their ranges from the start of this node.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind, KindSet, TextRange};
+    /// # const WS: Kind = Kind(0); const COMMENT: Kind = Kind(1);
+    /// # const ATOM: Kind = Kind(2); const LIST: Kind = Kind(3);
+    /// let tree = green::TreeBuilder::new()
+    ///     .start_node(LIST)
+    ///     .token(COMMENT, "//a")
+    ///     .start_node(LIST)
+    ///     .token(ATOM, "a")
+    ///     .token(COMMENT, "//b")
+    ///     .finish_node()
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// let comments: Vec<_> = tree.comments(Some(COMMENT).into_iter().collect()).collect();
+    /// assert_eq!(comments[1].0, TextRange::new(4.into(), 7.into()));
+    /// assert_eq!(comments[1].1.text(), "//b");
+    /// ```
+    pub fn comments(
+        &self,
+        comment_kinds: KindSet,
+    ) -> impl '_ + Iterator<Item = (TextRange, ArcBorrow<'_, Token>)> {
+        self.token_ranges().filter(move |(_, token)| comment_kinds.contains(token.kind()))
+    }
+
+    /// The comment tokens of this tree grouped into blocks of consecutive comments,
+    /// with the range of each block from the start of this node.
+    ///
+    /// Comments are consecutive if only tokens of `gap_kinds`, such as whitespace,
+    /// separate them. To not treat comments separated by blank lines as
+    /// consecutive, lex blank lines as a separate kind not in `gap_kinds`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::{green, Kind, KindSet};
+    /// # const WS: Kind = Kind(0); const COMMENT: Kind = Kind(1);
+    /// # const ATOM: Kind = Kind(2); const LIST: Kind = Kind(3);
+    /// let tree = green::TreeBuilder::new()
+    ///     .start_node(LIST)
+    ///     .token(COMMENT, "// a")
+    ///     .token(WS, "\n")
+    ///     .token(COMMENT, "// b")
+    ///     .token(WS, "\n")
+    ///     .token(ATOM, "x")
+    ///     .token(COMMENT, "// c")
+    ///     .finish_node()
+    ///     .finish();
+    ///
+    /// let kinds = |kind: Kind| Some(kind).into_iter().collect::<KindSet>();
+    /// let blocks: Vec<Vec<String>> = tree
+    ///     .comment_blocks(kinds(COMMENT), kinds(WS))
+    ///     .map(|(_, block)| block.iter().map(|comment| comment.text().to_owned()).collect())
+    ///     .collect();
+    /// assert_eq!(blocks, [vec!["// a", "// b"], vec!["// c"]]);
+    /// ```
+    pub fn comment_blocks(
+        &self,
+        comment_kinds: KindSet,
+        gap_kinds: KindSet,
+    ) -> impl '_ + Iterator<Item = (TextRange, Vec<ArcBorrow<'_, Token>>)> {
+        let mut tokens = self.token_ranges().peekable();
+        iter::from_fn(move || {
+            let (first, comment) =
+                tokens.find(|(_, token)| comment_kinds.contains(token.kind()))?;
+            let (mut end, mut block) = (first.end(), vec![comment]);
+            while let Some(&(range, token)) = tokens.peek() {
+                if comment_kinds.contains(token.kind()) {
+                    end = range.end();
+                    block.push(token);
+                } else if !gap_kinds.contains(token.kind()) {
+                    break;
+                }
+                tokens.next();
+            }
+            Some((TextRange::new(first.start(), end), block))
+        })
+    }
+
+    /// The tokens of this tree, in order, with their ranges from the start of this node.
+    fn token_ranges(&self) -> impl '_ + Iterator<Item = (TextRange, ArcBorrow<'_, Token>)> {
+        let mut stack = vec![(TextSize::from(0), self.children().with_offsets())];
+        iter::from_fn(move || loop {
+            let (start, children) = stack.last_mut()?;
+            let start = *start;
+            match children.next() {
+                None => {
+                    stack.pop();
+                }
+                Some((offset, NodeOrToken::Node(node))) => {
+                    let node = ArcBorrow::downgrade(node);
+                    stack.push((start + offset, node.children().with_offsets()));
+                }
+                Some((offset, NodeOrToken::Token(token))) => {
+                    return Some((TextRange::at(start + offset, token.len()), token));
+                }
+            }
+        })
+    }
+}
+
+fn is_trivia_token(element: &ElementRef<'_>, is_trivia: &impl Fn(Kind) -> bool) -> bool {
+    element.is_token() && is_trivia(element.kind())
+}
@@ -0,0 +1,121 @@
+use {
+    crate::{
+        green::{Node, Token},
+        NodeOrToken, TextSize,
+    },
+    std::convert::TryFrom,
+};
+
+impl Token {
+    /// The length of this token's text in UTF-16 code units.
+    ///
+    /// This is computed on demand. Each byte of a binary token counts as one unit.
+    pub fn len_utf16(&self) -> u32 {
+        match self.try_text() {
+            Some(text) => text.chars().map(|c| c.len_utf16() as u32).sum(),
+            None => self.len().into(),
+        }
+    }
+}
+
+impl Node {
+    /// The length of this node's text in UTF-16 code units.
+    ///
+    /// This is computed on demand by visiting every token in the tree;
+    /// use a [`Utf16Index`] to convert many offsets.
+    pub fn len_utf16(&self) -> u32 {
+        self.children()
+            .map(|el| el.either(|node| node.len_utf16(), |token| token.len_utf16()))
+            .sum()
+    }
+}
+
+/// Conversion table between UTF-8 and UTF-16 offsets in the text of a tree.
+///
+/// Language servers speak in UTF-16 positions; building this index once
+/// makes each conversion a binary search over the non-ASCII characters.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, Utf16Index}, Kind};
+/// # const ATOM: Kind = Kind(0); const LIST: Kind = Kind(1);
+/// let tree = green::TreeBuilder::new()
+///     .start_node(LIST)
+///     .token(ATOM, "ß")
+///     .token(ATOM, "🦀x")
+///     .finish_node()
+///     .finish();
+/// let index = Utf16Index::new(&tree);
+/// assert_eq!(index.len_utf16(), 4);
+/// assert_eq!(index.to_utf16(6.into()), 3);
+/// assert_eq!(index.from_utf16(3), 6.into());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Utf16Index {
+    /// For each non-ASCII char: its UTF-8 end offset, and the total
+    /// UTF-8 minus UTF-16 length of all chars up to and including it.
+    wide: Vec<(TextSize, u32)>,
+    len: TextSize,
+}
+
+impl Utf16Index {
+    /// Index the text of a tree.
+    pub fn new(node: &Node) -> Self {
+        fn visit(wide: &mut Vec<(TextSize, u32)>, node: &Node, offset: TextSize) {
+            for (child_offset, child) in node.children().with_offsets() {
+                let offset = offset + child_offset;
+                let token = match child {
+                    NodeOrToken::Node(node) => {
+                        visit(wide, &node, offset);
+                        continue;
+                    }
+                    NodeOrToken::Token(token) => token,
+                };
+                let text = match token.try_text() {
+                    Some(text) => text,
+                    None => continue,
+                };
+                for (i, c) in text.char_indices().filter(|(_, c)| !c.is_ascii()) {
+                    let shrink = (c.len_utf8() - c.len_utf16()) as u32;
+                    let total = wide.last().map_or(0, |&(_, total)| total) + shrink;
+                    let end = TextSize::try_from(i + c.len_utf8()).unwrap();
+                    wide.push((offset + end, total));
+                }
+            }
+        }
+
+        let mut wide = vec![];
+        visit(&mut wide, node, 0.into());
+        Utf16Index { wide, len: node.len() }
+    }
+
+    /// The length of the indexed text in UTF-16 code units.
+    pub fn len_utf16(&self) -> u32 {
+        self.to_utf16(self.len)
+    }
+
+    /// Convert a UTF-8 offset to a UTF-16 offset.
+    ///
+    /// The offset must be on a char boundary.
+    pub fn to_utf16(&self, offset: TextSize) -> u32 {
+        let shrink = match self.wide.binary_search_by_key(&offset, |&(end, _)| end) {
+            Ok(index) => self.wide[index].1,
+            Err(0) => 0,
+            Err(index) => self.wide[index - 1].1,
+        };
+        u32::from(offset) - shrink
+    }
+
+    /// Convert a UTF-16 offset to a UTF-8 offset.
+    ///
+    /// The offset must not be in the middle of a surrogate pair.
+    pub fn from_utf16(&self, offset: u32) -> TextSize {
+        let shrink = match self.wide.binary_search_by_key(&offset, |&(end, s)| u32::from(end) - s) {
+            Ok(index) => self.wide[index].1,
+            Err(0) => 0,
+            Err(index) => self.wide[index - 1].1,
+        };
+        TextSize::from(offset + shrink)
+    }
+}
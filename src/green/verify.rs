@@ -0,0 +1,89 @@
+use {
+    crate::{green::Node, ArcBorrow, NodeOrToken, TextSize},
+    std::{convert::TryFrom, error::Error, fmt},
+};
+
+/// Where a tree's text first differs from its source. See [`verify_text`].
+///
+/// The slices are bytes, as the mismatch may not fall on a char boundary.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Mismatch<'a> {
+    /// The offset of the first differing byte.
+    pub offset: TextSize,
+    /// The source text from the mismatch, as long as the found slice if there is that much.
+    pub expected: &'a [u8],
+    /// The rest of the token containing the mismatch, or empty if the tree ended early.
+    pub found: &'a [u8],
+}
+
+impl fmt::Display for Mismatch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "tree text differs from source at offset {}: expected {:?}, found {:?}",
+            u32::from(self.offset),
+            String::from_utf8_lossy(self.expected),
+            String::from_utf8_lossy(self.found),
+        )
+    }
+}
+
+impl Error for Mismatch<'_> {}
+
+/// Check that a tree losslessly represents its source text.
+///
+/// This compares the text of each token against the source in place,
+/// without building the tree's full text, and reports the first difference.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, Kind};
+/// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+/// let tree = green::TreeBuilder::new()
+///     .start_node(LIST)
+///     .token(ATOM, "a")
+///     .token(WS, " ")
+///     .token(ATOM, "bc")
+///     .finish_node()
+///     .finish();
+///
+/// assert!(green::verify_text(&tree, "a bc").is_ok());
+/// let mismatch = green::verify_text(&tree, "a bd").unwrap_err();
+/// assert_eq!(mismatch.offset, 3.into());
+/// assert_eq!((mismatch.expected, mismatch.found), (&b"d"[..], &b"c"[..]));
+/// ```
+pub fn verify_text<'a>(root: &'a Node, source: &'a str) -> Result<(), Mismatch<'a>> {
+    let source = source.as_bytes();
+    let mut pos = 0;
+    verify_node(root, source, &mut pos)?;
+    if pos < source.len() {
+        return Err(Mismatch { offset: offset(pos), expected: &source[pos..], found: &[] });
+    }
+    Ok(())
+}
+
+fn verify_node<'a>(node: &'a Node, source: &'a [u8], pos: &mut usize) -> Result<(), Mismatch<'a>> {
+    for child in node.children() {
+        match child {
+            NodeOrToken::Node(node) => verify_node(ArcBorrow::downgrade(node), source, pos)?,
+            NodeOrToken::Token(token) => {
+                let bytes = ArcBorrow::downgrade(token).bytes();
+                let rest = &source[*pos..];
+                let same = Iterator::zip(bytes.iter(), rest).take_while(|(a, b)| a == b).count();
+                if same < bytes.len() {
+                    let found = &bytes[same..];
+                    let expected = &rest[same..rest.len().min(same + found.len())];
+                    return Err(Mismatch { offset: offset(*pos + same), expected, found });
+                }
+                *pos += bytes.len();
+            }
+        }
+    }
+    Ok(())
+}
+
+fn offset(pos: usize) -> TextSize {
+    TextSize::try_from(pos).expect("text offset should fit in TextSize")
+}
@@ -0,0 +1,135 @@
+use {
+    crate::{
+        green::{Bias, ElementRef, Node, Token},
+        ArcBorrow, Kind, NodeOrToken, TextRange, TextSize,
+    },
+    std::sync::Arc,
+};
+
+/// A green element paired with its absolute offset in the text of a root.
+///
+/// Navigating to children keeps accumulating offsets, so the elements reached
+/// know their absolute ranges. This is a lighter alternative to a full red tree
+/// for read-only analyses which only walk down the tree; there are no parent
+/// pointers, and nothing is allocated.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green::{self, WithOffset}, Kind, TextRange};
+/// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+/// let tree = green::TreeBuilder::new()
+///     .start_node(LIST)
+///     .token(ATOM, "a")
+///     .token(WS, " ")
+///     .start_node(LIST)
+///     .token(ATOM, "b")
+///     .finish_node()
+///     .finish_node()
+///     .finish();
+///
+/// let root = WithOffset::root(&tree);
+/// let b = root.token_at_offset(2.into()).unwrap();
+/// assert_eq!(b.range(), TextRange::new(2.into(), 3.into()));
+/// assert_eq!(b.text(), Some("b"));
+///
+/// let ranges: Vec<_> = root.children().map(|child| child.range()).collect();
+/// assert_eq!(ranges[2], TextRange::new(2.into(), 3.into()));
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct WithOffset<'a> {
+    offset: TextSize,
+    element: ElementRef<'a>,
+}
+
+impl<'a> WithOffset<'a> {
+    /// Pair an element with its absolute offset.
+    pub fn new(offset: TextSize, element: ElementRef<'a>) -> Self {
+        WithOffset { offset, element }
+    }
+
+    /// The root of a tree, at offset 0.
+    pub fn root(node: &'a Arc<Node>) -> Self {
+        WithOffset::new(0.into(), NodeOrToken::Node(ArcBorrow::from(node)))
+    }
+
+    /// The absolute offset of the start of this element.
+    pub fn offset(&self) -> TextSize {
+        self.offset
+    }
+
+    /// The absolute range of this element.
+    pub fn range(&self) -> TextRange {
+        TextRange::at(self.offset, self.element.len())
+    }
+
+    /// The length of text at this element.
+    pub fn len(&self) -> TextSize {
+        self.element.len()
+    }
+
+    /// Whether this element has no text.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0.into()
+    }
+
+    /// The kind of this element.
+    pub fn kind(&self) -> Kind {
+        self.element.kind()
+    }
+
+    /// The element, without its offset.
+    pub fn element(&self) -> ElementRef<'a> {
+        self.element
+    }
+
+    /// This element, if it is a node.
+    pub fn as_node(&self) -> Option<&'a Node> {
+        self.element.into_node().map(ArcBorrow::downgrade)
+    }
+
+    /// This element, if it is a token.
+    pub fn as_token(&self) -> Option<&'a Token> {
+        self.element.into_token().map(ArcBorrow::downgrade)
+    }
+
+    /// The text of this element, if it is a text token.
+    pub fn text(&self) -> Option<&'a str> {
+        self.as_token()?.try_text()
+    }
+
+    /// The children of this element, if it is a node, with their absolute offsets.
+    pub fn children(&self) -> impl 'a + DoubleEndedIterator<Item = WithOffset<'a>> {
+        let offset = self.offset;
+        let children = self.as_node().map(|node| node.children().with_offsets());
+        children
+            .into_iter()
+            .flatten()
+            .map(move |(child_offset, child)| WithOffset::new(offset + child_offset, child))
+    }
+
+    /// The child of this node at the absolute `offset`. See [`Node::try_index_of_offset`].
+    ///
+    /// Returns `None` if this is a token, or the offset is not within this node.
+    pub fn child_at_offset(&self, offset: TextSize, bias: Bias) -> Option<WithOffset<'a>> {
+        let node = self.as_node()?;
+        let relative = offset.checked_sub(self.offset)?;
+        let index = node.try_index_of_offset(relative, bias)?;
+        let (child_offset, child) = node.children().with_offsets().get(index)?;
+        Some(WithOffset::new(self.offset + child_offset, child))
+    }
+
+    /// The token containing the absolute `offset`. See [`Node::token_at_offset`].
+    ///
+    /// Returns `None` if the offset is not within this element.
+    pub fn token_at_offset(&self, offset: TextSize) -> Option<WithOffset<'a>> {
+        let relative = offset.checked_sub(self.offset)?;
+        match self.element {
+            NodeOrToken::Node(node) => {
+                let (start, token) = ArcBorrow::downgrade(node).token_at_offset(relative)?;
+                Some(WithOffset::new(self.offset + start, NodeOrToken::Token(token)))
+            }
+            NodeOrToken::Token(token) => Some(*self).filter(|_| relative < token.len()),
+        }
+    }
+}
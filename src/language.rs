@@ -0,0 +1,450 @@
+//! A typed façade over green trees, parameterized by a [`Language`].
+//!
+//! The green layer only knows raw [`Kind`]s, so that one cache can be shared
+//! by trees of any language. A [`Language`] ties the raw kinds to a language's
+//! own kind type, along with which kinds are trivia and what they are called,
+//! so that consumers can work in terms of their own kinds.
+//!
+//! [`SyntaxNode`] and [`SyntaxToken`] are a thin red layer over the green tree:
+//! they know their parent and their absolute offset, and are created lazily
+//! while navigating. [`AstNode`] is implemented by typed wrappers of nodes.
+
+use {
+    crate::{
+        green::{ElementRef, Node, Token},
+        Kind, KindNames, NodeOrToken, SyntaxKind, TextRange, TextSize,
+    },
+    std::{fmt, fmt::Write, hash, marker::PhantomData, sync::Arc},
+};
+
+/// A language of syntax trees.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, language::{Language, SyntaxNode}, Kind};
+/// sorbus::syntax_kind! {
+///     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///     enum LispKind { Whitespace, Atom, List }
+/// }
+///
+/// enum Lisp {}
+/// impl Language for Lisp {
+///     type Kind = LispKind;
+///     fn is_trivia(kind: LispKind) -> bool {
+///         kind == LispKind::Whitespace
+///     }
+///     fn kind_name(kind: LispKind) -> Option<&'static str> {
+///         Some(match kind {
+///             LispKind::Whitespace => "WS",
+///             LispKind::Atom => "ATOM",
+///             LispKind::List => "LIST",
+///         })
+///     }
+/// }
+///
+/// let tree = green::TreeBuilder::new()
+///     .start_node(Kind(2))
+///     .token(Kind(1), "a")
+///     .token(Kind(0), " ")
+///     .token(Kind(1), "b")
+///     .finish_node()
+///     .finish();
+///
+/// let root = SyntaxNode::<Lisp>::new_root(tree);
+/// assert_eq!(root.kind(), LispKind::List);
+/// assert_eq!(root.debug_tree(), "LIST@0..3\n  ATOM@0..1 \"a\"\n  WS@1..2 \" \"\n  ATOM@2..3 \"b\"\n");
+/// ```
+pub trait Language: Sized + 'static {
+    /// The kind type of this language.
+    type Kind: SyntaxKind + Copy + Eq + fmt::Debug;
+
+    /// Whether tokens of `kind` are trivia, such as whitespace and comments.
+    fn is_trivia(kind: Self::Kind) -> bool;
+
+    /// The human-readable name of `kind`, used when printing trees.
+    fn kind_name(kind: Self::Kind) -> Option<&'static str> {
+        let _ = kind;
+        None
+    }
+
+    /// Whether tokens of the raw `kind` are trivia.
+    /// Raw kinds which aren't kinds of this language are never trivia.
+    fn is_raw_trivia(kind: Kind) -> bool {
+        Self::Kind::from_raw(kind).map_or(false, Self::is_trivia)
+    }
+}
+
+/// The kind names of a language, for use with APIs taking [`KindNames`],
+/// such as [`Node::to_sexpr`].
+pub struct Names<L>(PhantomData<fn() -> L>);
+
+impl<L: Language> Names<L> {
+    /// The kind names of `L`.
+    pub fn new() -> Self {
+        Names(PhantomData)
+    }
+}
+
+impl<L: Language> Default for Names<L> {
+    fn default() -> Self {
+        Names::new()
+    }
+}
+
+impl<L> fmt::Debug for Names<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Names")
+    }
+}
+
+impl<L: Language> KindNames for Names<L> {
+    fn kind_name(&self, kind: Kind) -> Option<&str> {
+        L::Kind::from_raw(kind).and_then(L::kind_name)
+    }
+}
+
+/// Name a raw kind of `L` for printing, falling back to its `Debug` representation.
+fn display_kind<L: Language>(kind: Kind) -> String {
+    match L::Kind::from_raw(kind) {
+        Some(kind) => L::kind_name(kind).map_or_else(|| format!("{:?}", kind), Into::into),
+        None => format!("{:?}", kind),
+    }
+}
+
+/// A node or token of a typed syntax tree.
+pub type SyntaxElement<L> = NodeOrToken<SyntaxNode<L>, SyntaxToken<L>>;
+
+/// A node of a typed syntax tree, which knows its parent and absolute offset.
+pub struct SyntaxNode<L> {
+    data: Arc<NodeData>,
+    language: PhantomData<fn() -> L>,
+}
+
+struct NodeData {
+    green: Arc<Node>,
+    parent: Option<(Arc<NodeData>, usize)>, // parent and index in it
+    offset: TextSize,
+}
+
+impl<L> Clone for SyntaxNode<L> {
+    fn clone(&self) -> Self {
+        SyntaxNode { data: Arc::clone(&self.data), language: PhantomData }
+    }
+}
+
+/// Nodes are equal when they are the same node at the same place in the same tree.
+impl<L> PartialEq for SyntaxNode<L> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.data, &other.data) || self.data == other.data
+    }
+}
+
+impl<L> Eq for SyntaxNode<L> {}
+
+impl<L> hash::Hash for SyntaxNode<L> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.data.green).hash(state);
+        self.data.offset.hash(state);
+    }
+}
+
+impl PartialEq for NodeData {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.green, &other.green)
+            && self.offset == other.offset
+            && match (&self.parent, &other.parent) {
+                (Some((parent, index)), Some((other_parent, other_index))) => {
+                    index == other_index
+                        && (Arc::ptr_eq(parent, other_parent) || parent == other_parent)
+                }
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+impl<L: Language> fmt::Debug for SyntaxNode<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{:?}", display_kind::<L>(self.raw_kind()), self.text_range())
+    }
+}
+
+impl<L> SyntaxNode<L> {
+    fn parent_index(&self) -> Option<usize> {
+        self.data.parent.as_ref().map(|&(_, index)| index)
+    }
+}
+
+impl<L: Language> SyntaxNode<L> {
+    /// The root of a typed tree over the green tree `green`.
+    pub fn new_root(green: Arc<Node>) -> Self {
+        SyntaxNode::new(NodeData { green, parent: None, offset: 0.into() })
+    }
+
+    fn new(data: NodeData) -> Self {
+        SyntaxNode { data: Arc::new(data), language: PhantomData }
+    }
+
+    /// The green node underlying this node.
+    pub fn green(&self) -> &Arc<Node> {
+        &self.data.green
+    }
+
+    /// The raw kind of this node.
+    pub fn raw_kind(&self) -> Kind {
+        self.data.green.kind()
+    }
+
+    /// The kind of this node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the raw kind of this node isn't a kind of `L`.
+    pub fn kind(&self) -> L::Kind {
+        let kind = self.raw_kind();
+        L::Kind::from_raw(kind)
+            .unwrap_or_else(|| panic!("{:?} is not a kind of the language", kind))
+    }
+
+    /// The range of this node in the text of the root.
+    pub fn text_range(&self) -> TextRange {
+        TextRange::at(self.data.offset, self.data.green.len())
+    }
+
+    /// The parent of this node, or `None` if this is the root.
+    pub fn parent(&self) -> Option<SyntaxNode<L>> {
+        let (parent, _) = self.data.parent.as_ref()?;
+        Some(SyntaxNode { data: Arc::clone(parent), language: PhantomData })
+    }
+
+    /// This node, then its parent, and so on up to the root.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode<L>> {
+        std::iter::successors(Some(self.clone()), SyntaxNode::parent)
+    }
+
+    /// The child element at `index`, if any.
+    pub fn child(&self, index: usize) -> Option<SyntaxElement<L>> {
+        let (offset, element) = self.data.green.children().with_offsets().get(index)?;
+        Some(self.make_child(index, self.data.offset + offset, element))
+    }
+
+    fn make_child(
+        &self,
+        index: usize,
+        offset: TextSize,
+        element: ElementRef<'_>,
+    ) -> SyntaxElement<L> {
+        match element.upgrade() {
+            NodeOrToken::Node(green) => NodeOrToken::Node(SyntaxNode::new(NodeData {
+                green,
+                parent: Some((Arc::clone(&self.data), index)),
+                offset,
+            })),
+            NodeOrToken::Token(green) => {
+                NodeOrToken::Token(SyntaxToken { parent: self.clone(), index, offset, green })
+            }
+        }
+    }
+
+    /// The child nodes and tokens of this node.
+    pub fn children_with_tokens(&self) -> impl '_ + Iterator<Item = SyntaxElement<L>> {
+        let children = self.data.green.children().with_offsets().enumerate();
+        children.map(move |(index, (offset, element))| {
+            self.make_child(index, self.data.offset + offset, element)
+        })
+    }
+
+    /// The child nodes of this node.
+    pub fn children(&self) -> impl '_ + Iterator<Item = SyntaxNode<L>> {
+        self.children_with_tokens().filter_map(NodeOrToken::into_node)
+    }
+
+    /// The run of trivia tokens directly before this node in its parent.
+    /// See [`Node::leading_trivia_of`].
+    pub fn leading_trivia(&self) -> impl Iterator<Item = SyntaxToken<L>> {
+        let trivia = self.parent().zip(self.parent_index()).map(|(parent, index)| {
+            let len = parent.green().leading_trivia_of(index, L::is_raw_trivia).len();
+            (parent, index - len..index)
+        });
+        trivia_tokens(trivia)
+    }
+
+    /// The run of trivia tokens directly after this node in its parent.
+    /// See [`Node::trailing_trivia_of`].
+    pub fn trailing_trivia(&self) -> impl Iterator<Item = SyntaxToken<L>> {
+        let trivia = self.parent().zip(self.parent_index()).map(|(parent, index)| {
+            let len = parent.green().trailing_trivia_of(index, L::is_raw_trivia).len();
+            (parent, index + 1..index + 1 + len)
+        });
+        trivia_tokens(trivia)
+    }
+
+    /// Cast this node to a typed AST node, if it has the right kind.
+    pub fn cast<N: AstNode<L>>(self) -> Option<N> {
+        N::cast(self)
+    }
+
+    /// Print this subtree, one element per line, with kind names and ranges.
+    pub fn debug_tree(&self) -> String {
+        let mut out = String::new();
+        self.write_tree(&mut out, 0).unwrap();
+        out
+    }
+
+    fn write_tree(&self, out: &mut String, depth: usize) -> fmt::Result {
+        writeln!(out, "{:indent$}{:?}", "", self, indent = depth * 2)?;
+        for child in self.children_with_tokens() {
+            match child {
+                NodeOrToken::Node(node) => node.write_tree(out, depth + 1)?,
+                NodeOrToken::Token(token) => {
+                    writeln!(out, "{:indent$}{:?}", "", token, indent = (depth + 1) * 2)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn trivia_tokens<L: Language>(
+    trivia: Option<(SyntaxNode<L>, std::ops::Range<usize>)>,
+) -> impl Iterator<Item = SyntaxToken<L>> {
+    trivia.into_iter().flat_map(|(parent, indices)| {
+        indices.map(move |index| parent.child(index).unwrap().unwrap_token())
+    })
+}
+
+/// A token of a typed syntax tree, which knows its parent and absolute offset.
+pub struct SyntaxToken<L> {
+    parent: SyntaxNode<L>,
+    index: usize,
+    offset: TextSize,
+    green: Arc<Token>,
+}
+
+impl<L> Clone for SyntaxToken<L> {
+    fn clone(&self) -> Self {
+        SyntaxToken {
+            parent: self.parent.clone(),
+            index: self.index,
+            offset: self.offset,
+            green: Arc::clone(&self.green),
+        }
+    }
+}
+
+/// Tokens are equal when they are the same child of the same node.
+impl<L> PartialEq for SyntaxToken<L> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.parent == other.parent
+    }
+}
+
+impl<L> Eq for SyntaxToken<L> {}
+
+impl<L> hash::Hash for SyntaxToken<L> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.parent.hash(state);
+        self.index.hash(state);
+    }
+}
+
+impl<L: Language> fmt::Debug for SyntaxToken<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{:?} ", display_kind::<L>(self.raw_kind()), self.text_range())?;
+        match self.green.try_text() {
+            Some(text) => write!(f, "{:?}", text),
+            None => write!(f, "{:?}", self.green.bytes()),
+        }
+    }
+}
+
+impl<L: Language> SyntaxToken<L> {
+    /// The green token underlying this token.
+    pub fn green(&self) -> &Arc<Token> {
+        &self.green
+    }
+
+    /// The raw kind of this token.
+    pub fn raw_kind(&self) -> Kind {
+        self.green.kind()
+    }
+
+    /// The kind of this token.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the raw kind of this token isn't a kind of `L`.
+    pub fn kind(&self) -> L::Kind {
+        let kind = self.raw_kind();
+        L::Kind::from_raw(kind)
+            .unwrap_or_else(|| panic!("{:?} is not a kind of the language", kind))
+    }
+
+    /// The text of this token.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is a binary token.
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    /// The range of this token in the text of the root.
+    pub fn text_range(&self) -> TextRange {
+        TextRange::at(self.offset, self.green.len())
+    }
+
+    /// The parent node of this token.
+    pub fn parent(&self) -> &SyntaxNode<L> {
+        &self.parent
+    }
+
+    /// Whether this token is trivia.
+    pub fn is_trivia(&self) -> bool {
+        L::is_raw_trivia(self.raw_kind())
+    }
+}
+
+/// A typed wrapper of nodes of some kinds of a language.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, language::{AstNode, Language, SyntaxNode}, Kind};
+/// # sorbus::syntax_kind! {
+/// #     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// #     enum LispKind { Whitespace, Atom, List }
+/// # }
+/// # enum Lisp {}
+/// # impl Language for Lisp {
+/// #     type Kind = LispKind;
+/// #     fn is_trivia(kind: LispKind) -> bool { kind == LispKind::Whitespace }
+/// # }
+/// struct List(SyntaxNode<Lisp>);
+/// impl AstNode<Lisp> for List {
+///     fn can_cast(kind: LispKind) -> bool {
+///         kind == LispKind::List
+///     }
+///     fn cast(syntax: SyntaxNode<Lisp>) -> Option<Self> {
+///         if Self::can_cast(syntax.kind()) { Some(List(syntax)) } else { None }
+///     }
+///     fn syntax(&self) -> &SyntaxNode<Lisp> {
+///         &self.0
+///     }
+/// }
+///
+/// let tree = green::TreeBuilder::new().start_node(Kind(2)).finish_node().finish();
+/// let list: List = SyntaxNode::new_root(tree).cast().unwrap();
+/// assert_eq!(list.syntax().kind(), LispKind::List);
+/// ```
+pub trait AstNode<L: Language>: Sized {
+    /// Whether nodes of `kind` can be cast to this type.
+    fn can_cast(kind: L::Kind) -> bool;
+
+    /// Cast a node to this type, or `None` if it has the wrong kind.
+    fn cast(syntax: SyntaxNode<L>) -> Option<Self>;
+
+    /// The untyped node.
+    fn syntax(&self) -> &SyntaxNode<L>;
+}
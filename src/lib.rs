@@ -19,11 +19,43 @@ const ASSERT_TEXTSIZE_IS_U32: fn() = || {
     let _ = std::mem::transmute::<u32, text_size::TextSize>;
 };
 
+/// Assert an internal invariant that unsafe code relies on.
+///
+/// This is checked in debug builds, or in any build with the `paranoid` feature.
+macro_rules! paranoid_assert {
+    ($($arg:tt)*) => {
+        if cfg!(any(debug_assertions, feature = "paranoid")) {
+            assert!($($arg)*);
+        }
+    };
+}
+
+/// Emit a `tracing` event, if the `tracing` feature is enabled.
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)*);
+    };
+}
+
+/// Enter a `tracing` span until the end of the current scope,
+/// if the `tracing` feature is enabled.
+macro_rules! trace_span {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::$level!($($arg)*).entered();
+    };
+}
+
 pub mod green;
+pub mod language;
+pub mod parse;
+pub mod recipes;
+pub mod testing;
 mod utils;
 
 #[doc(inline)]
-pub use crate::utils::{Kind, NodeOrToken};
+pub use crate::utils::{Kind, KindNames, KindSet, NodeOrToken, SyntaxKind};
 #[doc(no_inline)]
 pub use {
     rc_borrow::ArcBorrow,
@@ -0,0 +1,218 @@
+//! Abstract interfaces between lexers, parsers, and tree builders.
+//!
+//! Writing a parser against [`TokenSource`] and [`TreeSink`] rather than
+//! a concrete lexer and [`TreeBuilder`] lets it be tested in isolation,
+//! such as by parsing into an [`EventAssert`] recording sink.
+//!
+//!   [`TreeBuilder`]: crate::green::TreeBuilder
+//!   [`EventAssert`]: crate::testing::EventAssert
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use sorbus::{parse::{LexerSource, TokenSource, TreeSink}, testing::EventAssert, Kind};
+//! const ATOM: Kind = Kind(0);
+//! const OPEN: Kind = Kind(1);
+//! const CLOSE: Kind = Kind(2);
+//! const LIST: Kind = Kind(3);
+//!
+//! fn parse_list(source: &mut impl TokenSource, sink: &mut impl TreeSink) {
+//!     sink.start_node(LIST);
+//!     while let Some((kind, text)) = source.current() {
+//!         match kind {
+//!             OPEN => {
+//!                 sink.token(kind, text);
+//!                 source.bump();
+//!                 parse_list(source, sink);
+//!             }
+//!             CLOSE => {
+//!                 sink.token(kind, text);
+//!                 source.bump();
+//!                 break;
+//!             }
+//!             _ => {
+//!                 sink.token(kind, text);
+//!                 source.bump();
+//!             }
+//!         }
+//!     }
+//!     sink.finish_node();
+//! }
+//!
+//! let tokens = vec![(ATOM, "a"), (OPEN, "("), (ATOM, "b"), (CLOSE, ")")];
+//! let mut events = EventAssert::new();
+//! parse_list(&mut LexerSource::new(tokens), &mut events);
+//!
+//! let mut expected = EventAssert::new();
+//! expected
+//!     .start_node(LIST)
+//!     .token(ATOM, "a")
+//!     .token(OPEN, "(")
+//!     .start_node(LIST)
+//!     .token(ATOM, "b")
+//!     .token(CLOSE, ")")
+//!     .finish_node()
+//!     .finish_node();
+//! events.assert_events(expected.events());
+//! ```
+
+use {
+    crate::{green, testing, Kind},
+    std::{collections::VecDeque, fmt},
+};
+
+/// A stream of tokens for a parser to pull from.
+pub trait TokenSource {
+    /// The kind and text of the token `n` tokens ahead of the current one,
+    /// or `None` if there are not that many tokens left.
+    fn lookahead_nth(&mut self, n: usize) -> Option<(Kind, &str)>;
+
+    /// The kind and text of the current token, or `None` at the end of the tokens.
+    fn current(&mut self) -> Option<(Kind, &str)> {
+        self.lookahead_nth(0)
+    }
+
+    /// Advance past the current token.
+    fn bump(&mut self);
+}
+
+/// A consumer of the tree building events produced by a parser.
+///
+/// The methods mirror those of [`TreeBuilder`](green::TreeBuilder).
+pub trait TreeSink {
+    /// Checkpoint for maybe wrapping a node.
+    type Checkpoint: Copy;
+
+    /// Add a new token to the current branch.
+    fn token(&mut self, kind: Kind, text: &str);
+
+    /// Start a new child node and make it the current branch.
+    fn start_node(&mut self, kind: Kind);
+
+    /// Finish the current branch and restore its parent as current.
+    fn finish_node(&mut self);
+
+    /// Prepare for maybe wrapping the next node.
+    fn checkpoint(&self) -> Self::Checkpoint;
+
+    /// Wrap the elements added after `checkpoint` in a new node,
+    /// and make the new node the current branch.
+    fn start_node_at(&mut self, checkpoint: Self::Checkpoint, kind: Kind);
+}
+
+impl TreeSink for green::TreeBuilder {
+    type Checkpoint = green::Checkpoint;
+
+    fn token(&mut self, kind: Kind, text: &str) {
+        green::TreeBuilder::token(self, kind, text);
+    }
+
+    fn start_node(&mut self, kind: Kind) {
+        green::TreeBuilder::start_node(self, kind);
+    }
+
+    fn finish_node(&mut self) {
+        green::TreeBuilder::finish_node(self);
+    }
+
+    fn checkpoint(&self) -> Self::Checkpoint {
+        green::TreeBuilder::checkpoint(self)
+    }
+
+    fn start_node_at(&mut self, checkpoint: Self::Checkpoint, kind: Kind) {
+        green::TreeBuilder::start_node_at(self, checkpoint, kind);
+    }
+}
+
+impl TreeSink for testing::EventAssert {
+    type Checkpoint = testing::Checkpoint;
+
+    fn token(&mut self, kind: Kind, text: &str) {
+        testing::EventAssert::token(self, kind, text);
+    }
+
+    fn start_node(&mut self, kind: Kind) {
+        testing::EventAssert::start_node(self, kind);
+    }
+
+    fn finish_node(&mut self) {
+        testing::EventAssert::finish_node(self);
+    }
+
+    fn checkpoint(&self) -> Self::Checkpoint {
+        testing::EventAssert::checkpoint(self)
+    }
+
+    fn start_node_at(&mut self, checkpoint: Self::Checkpoint, kind: Kind) {
+        testing::EventAssert::start_node_at(self, checkpoint, kind);
+    }
+}
+
+impl<S: ?Sized + TreeSink> TreeSink for &mut S {
+    type Checkpoint = S::Checkpoint;
+
+    fn token(&mut self, kind: Kind, text: &str) {
+        (**self).token(kind, text)
+    }
+
+    fn start_node(&mut self, kind: Kind) {
+        (**self).start_node(kind)
+    }
+
+    fn finish_node(&mut self) {
+        (**self).finish_node()
+    }
+
+    fn checkpoint(&self) -> Self::Checkpoint {
+        (**self).checkpoint()
+    }
+
+    fn start_node_at(&mut self, checkpoint: Self::Checkpoint, kind: Kind) {
+        (**self).start_node_at(checkpoint, kind)
+    }
+}
+
+/// A [`TokenSource`] pulling tokens lazily from a lexer iterator.
+///
+/// Tokens are only lexed as far ahead as the parser has looked.
+pub struct LexerSource<I, S> {
+    lexer: I,
+    lookahead: VecDeque<(Kind, S)>,
+}
+
+impl<I, S: fmt::Debug> fmt::Debug for LexerSource<I, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LexerSource").field("lookahead", &self.lookahead).finish()
+    }
+}
+
+impl<I, S> LexerSource<I, S>
+where
+    I: Iterator<Item = (Kind, S)>,
+    S: AsRef<str>,
+{
+    /// Pull tokens from a lexer.
+    pub fn new(lexer: impl IntoIterator<IntoIter = I>) -> Self {
+        LexerSource { lexer: lexer.into_iter(), lookahead: VecDeque::new() }
+    }
+}
+
+impl<I, S> TokenSource for LexerSource<I, S>
+where
+    I: Iterator<Item = (Kind, S)>,
+    S: AsRef<str>,
+{
+    fn lookahead_nth(&mut self, n: usize) -> Option<(Kind, &str)> {
+        while self.lookahead.len() <= n {
+            self.lookahead.push_back(self.lexer.next()?);
+        }
+        let (kind, text) = &self.lookahead[n];
+        Some((*kind, text.as_ref()))
+    }
+
+    fn bump(&mut self) {
+        if self.lookahead.pop_front().is_none() {
+            self.lexer.next();
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Small, composable helpers for common tasks on green trees.
+//!
+//! These started out as code in the examples, and are kept here as supported
+//! API because nearly every consumer ends up writing them.
+
+use {
+    crate::{
+        green::{ElementRef, Node, Token},
+        ArcBorrow, Kind, NodeOrToken,
+    },
+    std::fmt::{self, Write},
+};
+
+/// The children of `node` which aren't trivia tokens.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, recipes, Kind};
+/// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+/// let tree = green::TreeBuilder::new()
+///     .start_node(LIST)
+///     .token(ATOM, "a")
+///     .token(WS, " ")
+///     .token(ATOM, "b")
+///     .finish_node()
+///     .finish();
+///
+/// let kinds: Vec<_> = recipes::skip_trivia(&tree, |kind| kind == WS).map(|el| el.kind()).collect();
+/// assert_eq!(kinds, [ATOM, ATOM]);
+/// ```
+pub fn skip_trivia<'a>(
+    node: &'a Node,
+    is_trivia: impl 'a + Fn(Kind) -> bool,
+) -> impl 'a + DoubleEndedIterator<Item = ElementRef<'a>> {
+    node.children().filter(move |el| !(el.is_token() && is_trivia(el.kind())))
+}
+
+/// The full source text of a tree, rebuilt from its tokens.
+///
+/// Binary tokens which aren't UTF-8 are converted lossily.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, recipes, Kind};
+/// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const LIST: Kind = Kind(2);
+/// let tree = green::TreeBuilder::new()
+///     .start_node(LIST)
+///     .token(ATOM, "a")
+///     .token(WS, " ")
+///     .start_node(LIST)
+///     .token(ATOM, "b")
+///     .finish_node()
+///     .finish_node()
+///     .finish();
+///
+/// assert_eq!(recipes::reconstruct_text(&tree), "a b");
+/// ```
+pub fn reconstruct_text(node: &Node) -> String {
+    let mut text = String::with_capacity(u32::from(node.len()) as usize);
+    let mut stack = vec![node.children()];
+    while let Some(children) = stack.last_mut() {
+        match children.next() {
+            Some(NodeOrToken::Node(node)) => stack.push(ArcBorrow::downgrade(node).children()),
+            Some(NodeOrToken::Token(token)) => match token.try_text() {
+                Some(token) => text.push_str(token),
+                None => text.push_str(&String::from_utf8_lossy(token.bytes())),
+            },
+            None => {
+                stack.pop();
+            }
+        }
+    }
+    text
+}
+
+/// An S-expression of the structure of a tree, ignoring trivia.
+///
+/// Each node is written as its tokens, then its child nodes, separated by
+/// spaces and in parentheses; a node with only one non-trivia child is written
+/// as just that child. This suits expression trees where operators are tokens
+/// and operands are nodes, as produced by a Pratt parser.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, recipes, Kind};
+/// # const WS: Kind = Kind(0); const ATOM: Kind = Kind(1); const OP: Kind = Kind(2);
+/// # const EXPR: Kind = Kind(3);
+/// // 1 + 2 * 3
+/// let tree = green::TreeBuilder::new()
+///     .start_node(EXPR)
+///     .start_node(EXPR).token(ATOM, "1").finish_node()
+///     .token(WS, " ")
+///     .token(OP, "+")
+///     .token(WS, " ")
+///     .start_node(EXPR)
+///     .start_node(EXPR).token(ATOM, "2").finish_node()
+///     .token(OP, "*")
+///     .start_node(EXPR).token(ATOM, "3").finish_node()
+///     .finish_node()
+///     .finish_node()
+///     .finish();
+///
+/// assert_eq!(recipes::collect_sexpr(&tree, |kind| kind == WS), "(+ 1 (* 2 3))");
+/// ```
+pub fn collect_sexpr(node: &Node, is_trivia: impl Fn(Kind) -> bool) -> String {
+    fn write_sexpr(
+        el: NodeOrToken<&Node, &Token>,
+        is_trivia: &dyn Fn(Kind) -> bool,
+        out: &mut String,
+    ) -> fmt::Result {
+        match el {
+            NodeOrToken::Token(token) => match token.try_text() {
+                Some(text) => out.write_str(text),
+                None => write!(out, "{:?}", token.bytes()),
+            },
+            NodeOrToken::Node(node) => {
+                let children: Vec<_> = skip_trivia(node, is_trivia).collect();
+                if let [child] = &children[..] {
+                    return write_sexpr(child.as_deref(), is_trivia, out);
+                }
+                out.write_str("(")?;
+                for token in children.iter().filter(|el| el.is_token()) {
+                    write_sexpr(token.as_deref(), is_trivia, out)?;
+                }
+                for node in children.iter().filter(|el| el.is_node()) {
+                    out.write_str(" ")?;
+                    write_sexpr(node.as_deref(), is_trivia, out)?;
+                }
+                out.write_str(")")
+            }
+        }
+    }
+
+    let mut out = String::new();
+    write_sexpr(NodeOrToken::Node(node), &is_trivia, &mut out).unwrap();
+    out
+}
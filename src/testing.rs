@@ -0,0 +1,359 @@
+//! Utilities for testing parsers built on sorbus.
+//!
+//! Comparing whole trees (or their debug snapshots) makes for brittle tests
+//! with unhelpful failures. [`EventAssert`] instead records the calls a parser
+//! makes to build a tree as a flat list of events, and reports a line diff
+//! of the events when they don't match the expected list.
+//!
+//! For performance tests, [`gen_tree`] generates reproducible trees of
+//! various shapes.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use sorbus::{testing::{Event, EventAssert}, Kind};
+//! const ATOM: Kind = Kind(0);
+//! const LIST: Kind = Kind(1);
+//!
+//! let mut events = EventAssert::new();
+//! events.start_node(LIST).token(ATOM, "a").token(ATOM, "b").finish_node();
+//!
+//! events.assert_events(&[
+//!     Event::Start(LIST),
+//!     Event::Token(ATOM, "a".into()),
+//!     Event::Token(ATOM, "b".into()),
+//!     Event::Finish,
+//! ]);
+//! ```
+
+use {
+    crate::{
+        green::{Node, TreeBuilder},
+        Kind, NodeOrToken,
+    },
+    std::{fmt, sync::Arc},
+};
+
+/// A single tree building event.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Event {
+    Start(Kind),
+    Token(Kind, String),
+    Finish,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Start(kind) => write!(f, "START {:?}", kind),
+            Event::Token(kind, text) => write!(f, "TOKEN {:?} {:?}", kind, text),
+            Event::Finish => write!(f, "FINISH"),
+        }
+    }
+}
+
+/// Checkpoint for maybe wrapping a node. See [`EventAssert::checkpoint`].
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct Checkpoint(usize);
+
+/// Recorder of tree building events, mirroring the [`TreeBuilder`] API.
+///
+///   [`TreeBuilder`]: crate::green::TreeBuilder
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct EventAssert {
+    events: Vec<Event>,
+}
+
+impl EventAssert {
+    /// Create a new, empty recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the events that build an existing tree.
+    ///
+    /// The bytes of binary tokens are recorded as (lossy) UTF-8 text.
+    pub fn from_tree(node: &Node) -> Self {
+        fn visit(events: &mut Vec<Event>, node: &Node) {
+            events.push(Event::Start(node.kind()));
+            for child in node.children() {
+                match child {
+                    NodeOrToken::Node(node) => visit(events, &node),
+                    NodeOrToken::Token(token) => {
+                        let text = String::from_utf8_lossy(token.bytes()).into_owned();
+                        events.push(Event::Token(token.kind(), text))
+                    }
+                }
+            }
+            events.push(Event::Finish);
+        }
+
+        let mut events = vec![];
+        visit(&mut events, node);
+        EventAssert { events }
+    }
+
+    /// The recorded events.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Record adding a new token to the current branch.
+    pub fn token(&mut self, kind: Kind, text: &str) -> &mut Self {
+        self.events.push(Event::Token(kind, text.into()));
+        self
+    }
+
+    /// Record starting a new child node.
+    pub fn start_node(&mut self, kind: Kind) -> &mut Self {
+        self.events.push(Event::Start(kind));
+        self
+    }
+
+    /// Record finishing the current branch.
+    pub fn finish_node(&mut self) -> &mut Self {
+        self.events.push(Event::Finish);
+        self
+    }
+
+    /// Prepare for maybe wrapping the next node.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.events.len())
+    }
+
+    /// Record wrapping the elements added after `checkpoint` in a new node.
+    pub fn start_node_at(&mut self, Checkpoint(checkpoint): Checkpoint, kind: Kind) -> &mut Self {
+        assert!(
+            checkpoint <= self.events.len(),
+            "checkpoint no longer valid; was it taken from a different `EventAssert`?",
+        );
+        self.events.insert(checkpoint, Event::Start(kind));
+        self
+    }
+
+    /// Assert that the recorded events match the expected events.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a line diff of the events if they don't match.
+    #[track_caller]
+    pub fn assert_events(&self, expected: &[Event]) {
+        if self.events != expected {
+            panic!(
+                "events do not match (-expected +actual):\n{}",
+                EventDiff { expected, actual: &self.events },
+            );
+        }
+    }
+
+    /// Assert that the recorded events build the same tree as `node`.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a line diff of the events if they don't match.
+    #[track_caller]
+    pub fn assert_tree(&self, node: &Node) {
+        self.assert_events(EventAssert::from_tree(node).events())
+    }
+}
+
+/// Line diff of two event lists, indented by nesting depth.
+struct EventDiff<'a> {
+    expected: &'a [Event],
+    actual: &'a [Event],
+}
+
+impl fmt::Display for EventDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (expected, actual) = (self.expected, self.actual);
+
+        // longest common subsequence table, suffix-wise
+        let width = actual.len() + 1;
+        let mut lcs = vec![0_usize; (expected.len() + 1) * width];
+        for i in (0..expected.len()).rev() {
+            for j in (0..actual.len()).rev() {
+                lcs[i * width + j] = if expected[i] == actual[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let mut depth = 0_usize;
+        let mut line = |f: &mut fmt::Formatter<'_>, sign: char, event: &Event| {
+            if *event == Event::Finish {
+                depth = depth.saturating_sub(1);
+            }
+            writeln!(f, "{} {:indent$}{}", sign, "", event, indent = depth * 2)?;
+            if let Event::Start(_) = event {
+                depth += 1;
+            }
+            Ok(())
+        };
+
+        let (mut i, mut j) = (0, 0);
+        while i < expected.len() || j < actual.len() {
+            if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+                line(f, ' ', &expected[i])?;
+                i += 1;
+                j += 1;
+            } else if j == actual.len()
+                || i < expected.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1]
+            {
+                line(f, '-', &expected[i])?;
+                i += 1;
+            } else {
+                line(f, '+', &actual[j])?;
+                j += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The shape of trees generated by [`gen_tree`].
+///
+/// Start from one of the preset profiles and adjust the fields as needed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Profile {
+    /// The number of tokens to generate, not counting whitespace.
+    pub tokens: usize,
+    /// The maximum depth of nodes below the root.
+    pub max_depth: usize,
+    /// The maximum number of children of each node other than the root,
+    /// not counting whitespace. The root takes children until it has enough tokens.
+    pub max_children: usize,
+    /// The chance, in percent, that a child is a node rather than a token.
+    pub node_percent: u32,
+    /// The number of distinct token texts, which decides how much tokens are shared.
+    pub vocabulary: u32,
+    /// Whether to put whitespace tokens between siblings.
+    pub whitespace: bool,
+}
+
+impl Profile {
+    /// A few wide nodes directly below the root, as in a data file.
+    pub fn wide_flat() -> Self {
+        Profile {
+            tokens: 10_000,
+            max_depth: 1,
+            max_children: 512,
+            node_percent: 5,
+            vocabulary: 1024,
+            whitespace: false,
+        }
+    }
+
+    /// Long chains of narrow nodes, as in deeply nested expressions.
+    pub fn deep_narrow() -> Self {
+        Profile {
+            tokens: 10_000,
+            max_depth: 512,
+            max_children: 2,
+            node_percent: 90,
+            vocabulary: 16,
+            whitespace: false,
+        }
+    }
+
+    /// Moderately nested nodes with whitespace and repeated tokens, as in source code.
+    pub fn mixed() -> Self {
+        Profile {
+            tokens: 10_000,
+            max_depth: 32,
+            max_children: 8,
+            node_percent: 30,
+            vocabulary: 64,
+            whitespace: true,
+        }
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile::mixed()
+    }
+}
+
+/// A small, fast, deterministic pseudo-random number generator (SplitMix64).
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`.
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+/// Generate a pseudo-random tree with the given shape.
+///
+/// The same seed and profile always generate the same tree, on every platform
+/// and in every version of this crate with the same major version.
+///
+/// Whitespace tokens have the kind `Kind(0)`, and other tokens kinds `1..=4`,
+/// with each text always having the same kind. Nodes have kinds `8..=11`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::testing::{gen_tree, Profile};
+/// let tree = gen_tree(97, Profile { tokens: 100, ..Profile::mixed() });
+/// assert_eq!(tree, gen_tree(97, Profile { tokens: 100, ..Profile::mixed() }));
+/// assert_ne!(tree, gen_tree(98, Profile { tokens: 100, ..Profile::mixed() }));
+/// ```
+pub fn gen_tree(seed: u64, profile: Profile) -> Arc<Node> {
+    let mut rng = Rng(seed);
+    let mut builder = TreeBuilder::new();
+    let mut word = String::new();
+
+    builder.start_node(Kind(8));
+    // for each open node, the number of children left to add and whether it has any yet
+    let mut open = vec![(usize::MAX, false)];
+    let mut tokens = 0;
+    while tokens < profile.tokens {
+        while open.last().unwrap().0 == 0 {
+            open.pop();
+            builder.finish_node();
+        }
+        let depth = open.len() - 1;
+        let (remaining, has_children) = open.last_mut().unwrap();
+        *remaining -= 1;
+        if profile.whitespace && std::mem::replace(has_children, true) {
+            builder.token(Kind(0), if rng.below(8) == 0 { "\n" } else { " " });
+        }
+
+        if depth < profile.max_depth && rng.below(100) < u64::from(profile.node_percent) {
+            builder.start_node(Kind(8 + rng.below(4) as u16));
+            open.push((1 + rng.below(profile.max_children.max(1) as u64) as usize, false));
+        } else {
+            let mut n = rng.below(u64::from(profile.vocabulary.max(1)));
+            let kind = Kind(1 + (n % 4) as u16);
+            // bijective base 26, so texts are "a" to "z", then "aa" and so on
+            word.clear();
+            loop {
+                word.insert(0, (b'a' + (n % 26) as u8) as char);
+                n /= 26;
+                if n == 0 {
+                    break;
+                }
+                n -= 1;
+            }
+            builder.token(kind, &word);
+            tokens += 1;
+        }
+    }
+    for _ in open {
+        builder.finish_node();
+    }
+    builder.finish()
+}
@@ -2,16 +2,47 @@ use {
     crate::{green, ArcBorrow, TextSize},
     std::{
         fmt::{self, Debug},
-        ops::Deref,
+        iter::FromIterator,
+        ops::{Deref, DerefMut},
         sync::Arc,
     },
 };
 
 /// Raw kind tag for each element in the tree.
 #[repr(transparent)]
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Default, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub struct Kind(pub u16);
 
+impl Kind {
+    /// The reserved kind, `Kind(u16::MAX)`.
+    ///
+    /// This is never produced by [`Kind::new`], so it can be used as a sentinel,
+    /// such as for a "tombstone" event marking an abandoned node in a parser's
+    /// event list. Deserialization rejects this kind unless the `tombstone-kind`
+    /// feature is enabled.
+    ///
+    /// Note that as the raw kind is public, `Kind(u16::MAX)` can still be
+    /// constructed directly, so `Option<Kind>` does not use it as a niche.
+    pub const TOMBSTONE: Kind = Kind(u16::MAX);
+
+    /// Create a kind, or `None` if `raw` is the reserved [`TOMBSTONE`](Kind::TOMBSTONE) kind.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorbus::Kind;
+    /// assert_eq!(Kind::new(0), Some(Kind(0)));
+    /// assert_eq!(Kind::new(u16::MAX), None);
+    /// ```
+    pub const fn new(raw: u16) -> Option<Kind> {
+        if raw == Kind::TOMBSTONE.0 {
+            None
+        } else {
+            Some(Kind(raw))
+        }
+    }
+}
+
 /// Skip multiline, just do it inline
 impl Debug for Kind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -19,9 +50,199 @@ impl Debug for Kind {
     }
 }
 
+/// A set of kinds, such as the trivia or comment kinds of a language.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{Kind, KindSet};
+/// # const WS: Kind = Kind(0); const COMMENT: Kind = Kind(1); const ATOM: Kind = Kind(2);
+/// let trivia: KindSet = [WS, COMMENT].iter().copied().collect();
+/// assert!(trivia.contains(COMMENT));
+/// assert!(!trivia.contains(ATOM));
+/// ```
+#[derive(Clone, Default, Eq, PartialEq, Hash)]
+pub struct KindSet {
+    // NB: no trailing zero words, so that derived Eq is set equality
+    bits: Vec<u64>,
+}
+
+impl KindSet {
+    /// Create a new, empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the set contains `kind`.
+    pub fn contains(&self, kind: Kind) -> bool {
+        let (word, bit) = Self::index(kind);
+        matches!(self.bits.get(word), Some(&bits) if bits & bit != 0)
+    }
+
+    /// Add `kind` to the set. Returns whether it was newly added.
+    pub fn insert(&mut self, kind: Kind) -> bool {
+        let (word, bit) = Self::index(kind);
+        if self.bits.len() <= word {
+            self.bits.resize(word + 1, 0);
+        }
+        let added = self.bits[word] & bit == 0;
+        self.bits[word] |= bit;
+        added
+    }
+
+    /// Remove `kind` from the set. Returns whether it was present.
+    pub fn remove(&mut self, kind: Kind) -> bool {
+        let present = self.contains(kind);
+        if present {
+            let (word, bit) = Self::index(kind);
+            self.bits[word] &= !bit;
+            while self.bits.last() == Some(&0) {
+                self.bits.pop();
+            }
+        }
+        present
+    }
+
+    /// Whether the set contains no kinds.
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    /// The kinds in the set, in ascending order.
+    pub fn iter(&self) -> impl '_ + Iterator<Item = Kind> {
+        (0..self.bits.len() * 64)
+            .map(|raw| Kind(raw as u16))
+            .filter(move |&kind| self.contains(kind))
+    }
+
+    fn index(kind: Kind) -> (usize, u64) {
+        (usize::from(kind.0) / 64, 1 << (kind.0 % 64))
+    }
+}
+
+impl Debug for KindSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<Kind> for KindSet {
+    fn from_iter<I: IntoIterator<Item = Kind>>(iter: I) -> Self {
+        let mut set = KindSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<Kind> for KindSet {
+    fn extend<I: IntoIterator<Item = Kind>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|kind| {
+            self.insert(kind);
+        })
+    }
+}
+
+/// Resolver of human-readable names for kinds, used when printing trees.
+///
+/// This is implemented for closures, so a language's `fn(Kind) -> Option<&str>`
+/// name lookup can be used directly.
+pub trait KindNames {
+    /// The name of `kind`, if it has one.
+    fn kind_name(&self, kind: Kind) -> Option<&str>;
+}
+
+impl<F> KindNames for F
+where
+    F: Fn(Kind) -> Option<&'static str>,
+{
+    fn kind_name(&self, kind: Kind) -> Option<&str> {
+        self(kind)
+    }
+}
+
+/// A language's own kind type, convertible to and from raw [`Kind`]s.
+///
+/// This is usually a `#[repr(u16)]` enum of the kinds of a grammar; the
+/// [`syntax_kind!`](crate::syntax_kind) macro declares such an enum and implements this trait.
+pub trait SyntaxKind: Sized {
+    /// Convert a raw kind, or `None` if it is not a kind of this type.
+    fn from_raw(kind: Kind) -> Option<Self>;
+    /// Convert to a raw kind.
+    fn into_raw(self) -> Kind;
+}
+
+impl SyntaxKind for Kind {
+    fn from_raw(kind: Kind) -> Option<Self> {
+        Some(kind)
+    }
+
+    fn into_raw(self) -> Kind {
+        self
+    }
+}
+
+/// Declare a `#[repr(u16)]` enum of kinds and implement [`SyntaxKind`] for it.
+///
+/// Variants are fieldless, and may have explicit discriminants.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorbus::{green, Kind, SyntaxKind};
+/// sorbus::syntax_kind! {
+///     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///     pub enum MyKind {
+///         Atom,
+///         List,
+///         Error = 100,
+///     }
+/// }
+///
+/// assert_eq!(MyKind::List.into_raw(), Kind(1));
+/// assert_eq!(MyKind::from_raw(Kind(100)), Some(MyKind::Error));
+/// assert_eq!(MyKind::from_raw(Kind(2)), None);
+///
+/// let tree = green::TreeBuilder::new()
+///     .start_node(MyKind::List.into_raw())
+///     .token(MyKind::Atom.into_raw(), "a")
+///     .finish_node()
+///     .finish();
+/// assert_eq!(tree.kind_as(), Some(MyKind::List));
+/// ```
+#[macro_export]
+macro_rules! syntax_kind {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident $(= $discriminant:expr)?),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr(u16)]
+        $vis enum $name {
+            $($(#[$variant_meta])* $variant $(= $discriminant)?),*
+        }
+
+        impl $crate::SyntaxKind for $name {
+            fn from_raw(kind: $crate::Kind) -> ::std::option::Option<Self> {
+                $(if kind.0 == $name::$variant as u16 {
+                    return ::std::option::Option::Some($name::$variant);
+                })*
+                ::std::option::Option::None
+            }
+
+            fn into_raw(self) -> $crate::Kind {
+                $crate::Kind(self as u16)
+            }
+        }
+    };
+}
+
 /// Enum wrapping either a node or a token.
+///
+/// When ordered, all nodes sort before all tokens.
 #[allow(missing_docs)]
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub enum NodeOrToken<Node, Token> {
     Node(Node),
     Token(Token),
@@ -71,7 +292,15 @@ impl<Node, Token> NodeOrToken<Node, Token> {
         }
     }
 
-    pub(crate) fn map<N, T>(
+    pub fn as_mut(&mut self) -> NodeOrToken<&mut Node, &mut Token> {
+        match *self {
+            NodeOrToken::Node(ref mut node) => NodeOrToken::Node(node),
+            NodeOrToken::Token(ref mut token) => NodeOrToken::Token(token),
+        }
+    }
+
+    /// Map the node with `n` or the token with `t`.
+    pub fn map<N, T>(
         self,
         n: impl FnOnce(Node) -> N,
         t: impl FnOnce(Token) -> T,
@@ -82,6 +311,47 @@ impl<Node, Token> NodeOrToken<Node, Token> {
         }
     }
 
+    /// Map the node with `f`, leaving a token untouched.
+    pub fn map_node<N>(self, f: impl FnOnce(Node) -> N) -> NodeOrToken<N, Token> {
+        self.map(f, |token| token)
+    }
+
+    /// Map the token with `f`, leaving a node untouched.
+    pub fn map_token<T>(self, f: impl FnOnce(Token) -> T) -> NodeOrToken<Node, T> {
+        self.map(|node| node, f)
+    }
+
+    /// Handle both cases, calling `n` with the node or `t` with the token.
+    pub fn either<R>(self, n: impl FnOnce(Node) -> R, t: impl FnOnce(Token) -> R) -> R {
+        match self {
+            NodeOrToken::Node(node) => n(node),
+            NodeOrToken::Token(token) => t(token),
+        }
+    }
+
+    /// Pair up two elements, if they are both nodes or both tokens.
+    pub fn zip<N, T>(self, other: NodeOrToken<N, T>) -> Option<NodeOrToken<(Node, N), (Token, T)>> {
+        match (self, other) {
+            (NodeOrToken::Node(this), NodeOrToken::Node(that)) => {
+                Some(NodeOrToken::Node((this, that)))
+            }
+            (NodeOrToken::Token(this), NodeOrToken::Token(that)) => {
+                Some(NodeOrToken::Token((this, that)))
+            }
+            _ => None,
+        }
+    }
+
+    /// The node, or `err` if this is a token, like `Option::ok_or`.
+    pub fn node_ok_or<E>(self, err: E) -> Result<Node, E> {
+        self.into_node().ok_or(err)
+    }
+
+    /// The token, or `err` if this is a node, like `Option::ok_or`.
+    pub fn token_ok_or<E>(self, err: E) -> Result<Token, E> {
+        self.into_token().ok_or(err)
+    }
+
     pub fn as_deref(&self) -> NodeOrToken<&Node::Target, &Token::Target>
     where
         Node: Deref,
@@ -90,6 +360,15 @@ impl<Node, Token> NodeOrToken<Node, Token> {
         self.as_ref().map(Deref::deref, Deref::deref)
     }
 
+    pub fn as_deref_mut(&mut self) -> NodeOrToken<&mut Node::Target, &mut Token::Target>
+    where
+        Node: DerefMut,
+        Token: DerefMut,
+    {
+        self.as_mut().map(DerefMut::deref_mut, DerefMut::deref_mut)
+    }
+
+    /// The kind of this element, without matching on whether it is a node or token.
     pub fn kind(&self) -> Kind
     where
         Node: Deref<Target = green::Node>,
@@ -106,11 +385,26 @@ where
     Node: Deref<Target = green::Node>,
     Token: Deref<Target = green::Token>,
 {
+    /// The length of text of this element, without matching on whether it is a node or token.
     pub fn len(&self) -> TextSize {
         self.as_deref().map(green::Node::len, green::Token::len).flatten()
     }
 }
 
+impl<Node: Clone, Token: Clone> NodeOrToken<&'_ Node, &'_ Token> {
+    /// Clone the referenced node or token, like `Option::cloned`.
+    pub fn cloned(self) -> NodeOrToken<Node, Token> {
+        self.map(Node::clone, Token::clone)
+    }
+}
+
+impl NodeOrToken<ArcBorrow<'_, green::Node>, ArcBorrow<'_, green::Token>> {
+    /// Upgrade the borrowed node or token into an owned, reference counted handle.
+    pub fn upgrade(self) -> NodeOrToken<Arc<green::Node>, Arc<green::Token>> {
+        self.map(ArcBorrow::upgrade, ArcBorrow::upgrade)
+    }
+}
+
 impl<T> NodeOrToken<T, T> {
     pub(crate) fn flatten(self) -> T {
         match self {
@@ -132,6 +426,36 @@ impl From<Arc<green::Token>> for NodeOrToken<Arc<green::Node>, Arc<green::Token>
     }
 }
 
+impl From<&'_ Arc<green::Node>> for NodeOrToken<Arc<green::Node>, Arc<green::Token>> {
+    fn from(this: &Arc<green::Node>) -> Self {
+        NodeOrToken::Node(Arc::clone(this))
+    }
+}
+
+impl From<&'_ Arc<green::Token>> for NodeOrToken<Arc<green::Node>, Arc<green::Token>> {
+    fn from(this: &Arc<green::Token>) -> Self {
+        NodeOrToken::Token(Arc::clone(this))
+    }
+}
+
+impl From<ArcBorrow<'_, green::Node>> for NodeOrToken<Arc<green::Node>, Arc<green::Token>> {
+    fn from(this: ArcBorrow<'_, green::Node>) -> Self {
+        NodeOrToken::Node(ArcBorrow::upgrade(this))
+    }
+}
+
+impl From<ArcBorrow<'_, green::Token>> for NodeOrToken<Arc<green::Node>, Arc<green::Token>> {
+    fn from(this: ArcBorrow<'_, green::Token>) -> Self {
+        NodeOrToken::Token(ArcBorrow::upgrade(this))
+    }
+}
+
+impl From<green::ElementRef<'_>> for NodeOrToken<Arc<green::Node>, Arc<green::Token>> {
+    fn from(this: green::ElementRef<'_>) -> Self {
+        this.upgrade()
+    }
+}
+
 impl<'a> From<&'a green::Node> for NodeOrToken<&'a green::Node, &'a green::Token> {
     fn from(this: &'a green::Node) -> Self {
         NodeOrToken::Node(this)
@@ -0,0 +1,60 @@
+use sorbus::{
+    green::{self, analysis::sharing_report},
+    Kind,
+};
+
+const ATOM: Kind = Kind(0);
+const LIST: Kind = Kind(1);
+
+#[test]
+fn nested_sharing() {
+    let mut builder = green::TreeBuilder::new();
+    let leaf = builder.start_node(LIST).token(ATOM, "a").token(ATOM, "b").finish_node().finish();
+    let pair = builder.start_node(LIST).add(leaf.clone()).add(leaf.clone()).finish_node().finish();
+    let tree = builder.start_node(LIST).add(pair.clone()).add(pair.clone()).finish_node().finish();
+
+    let report = sharing_report(&tree);
+    assert_eq!((report.logical_nodes, report.unique_nodes), (7, 3));
+    assert_eq!((report.logical_tokens, report.unique_tokens), (8, 2));
+
+    let bytes = |el: green::ElementRef<'_>| {
+        el.either(|node| std::mem::size_of_val(&*node), |token| std::mem::size_of_val(&*token))
+    };
+    let (leaf_bytes, pair_bytes) = (std::mem::size_of_val(&*leaf), std::mem::size_of_val(&*pair));
+    let tokens_bytes: usize = leaf.children().map(bytes).sum();
+    assert_eq!(report.bytes_saved, 3 * leaf_bytes + pair_bytes + 3 * tokens_bytes);
+
+    let leaf_tree = leaf_bytes + tokens_bytes;
+    let mut expected = vec![(4, 3 * leaf_tree), (2, pair_bytes + 2 * leaf_tree)];
+    expected.sort_by_key(|&(_, saved)| std::cmp::Reverse(saved));
+    let top: Vec<_> =
+        report.top_shared.iter().map(|shared| (shared.occurrences, shared.bytes_saved)).collect();
+    assert_eq!(top, expected);
+}
+
+#[test]
+fn corpus_histograms() {
+    use sorbus::green::analysis::{corpus_stats, Histogram};
+    let mut histogram = Histogram::new();
+    assert_eq!((histogram.quantile(0.5), histogram.mean()), (None, None));
+    for value in [1, 2, 2, 3, 10] {
+        histogram.record(value);
+    }
+    assert_eq!((histogram.min(), histogram.max()), (Some(1), Some(10)));
+    assert_eq!(histogram.quantile(0.0), Some(1));
+    assert_eq!(histogram.quantile(0.5), Some(2));
+    assert_eq!(histogram.quantile(0.8), Some(3));
+    assert_eq!(histogram.mean(), Some(3.6));
+
+    let mut builder = green::TreeBuilder::new();
+    let leaf = builder.start_node(LIST).token(ATOM, "a").token(ATOM, "b").finish_node().finish();
+    let tree = builder.start_node(LIST).add(leaf.clone()).add(leaf.clone()).finish_node().finish();
+    let stats = corpus_stats(vec![&*tree, &*tree]);
+    assert_eq!(stats.roots, 2);
+    assert_eq!(stats.node_widths.counts().collect::<Vec<_>>(), [(2, 6)]);
+    assert_eq!(stats.token_depths.counts().collect::<Vec<_>>(), [(2, 8)]);
+    assert_eq!((stats.unique_nodes, stats.unique_tokens), (2, 2));
+    assert_eq!(stats.node_dedup_ratio(), 2.0 / 6.0);
+    assert_eq!(stats.token_dedup_ratio(), 2.0 / 8.0);
+    assert_eq!(corpus_stats(vec![]).node_dedup_ratio(), 1.0);
+}
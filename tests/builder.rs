@@ -0,0 +1,306 @@
+use {
+    sorbus::{green, Kind},
+    std::{sync::Arc, vec},
+};
+
+const ATOM: Kind = Kind(0);
+const LIST: Kind = Kind(1);
+
+/// Iterator over children which claims to have one more child than it does.
+struct Liar(vec::IntoIter<Arc<green::Token>>);
+
+impl Iterator for Liar {
+    type Item = Arc<green::Token>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl ExactSizeIterator for Liar {
+    fn len(&self) -> usize {
+        self.0.len() + 1
+    }
+}
+
+impl green::SliceIter for Liar {
+    type Elem = Arc<green::Token>;
+    fn as_slice(&self) -> &[Arc<green::Token>] {
+        self.0.as_slice()
+    }
+}
+
+#[test]
+fn try_node_errors() {
+    let mut builder = green::Builder::new();
+    let a = builder.token(ATOM, "a");
+
+    let liar = Liar(vec![a.clone(), a.clone()].into_iter());
+    assert_eq!(builder.try_node(LIST, liar), Err(green::NodeError::IteratorLied));
+    // the children written before the error are released
+    assert_eq!(Arc::strong_count(&a), 2);
+    assert_eq!(builder.size(), 1);
+
+    let too_many = vec![a.clone(); usize::from(u16::MAX) + 1];
+    assert_eq!(
+        builder.try_node(LIST, too_many),
+        Err(green::NodeError::TooManyChildren { len: usize::from(u16::MAX) + 1 }),
+    );
+
+    let node = builder.try_node(LIST, vec![a.clone()]).unwrap();
+    assert!(Arc::ptr_eq(&node, &builder.node(LIST, vec![a])));
+}
+
+#[test]
+fn fragment_oversized_nodes() {
+    const FRAGMENT: Kind = Kind(2);
+
+    let mut builder = green::Builder::new();
+    builder.set_fragment_kind(Some(FRAGMENT));
+    let a = builder.token(ATOM, "a");
+
+    let len = usize::from(u16::MAX) * 2 + 2;
+    let node = builder.node(LIST, vec![a; len]);
+    assert_eq!(node.kind(), LIST);
+    assert_eq!(node.len(), (len as u32).into());
+    let fragments: Vec<_> = node.children().map(|child| child.unwrap_node()).collect();
+    assert_eq!(fragments.len(), 3);
+    assert!(fragments.iter().all(|fragment| fragment.kind() == FRAGMENT));
+    assert_eq!(fragments[2].children_len(), 2);
+    // full fragments are deduplicated
+    assert!(std::ptr::eq(&*fragments[0], &*fragments[1]));
+}
+
+#[test]
+#[cfg(feature = "provenance")]
+fn provenance_separates_nodes() {
+    let mut builder = green::Builder::new();
+    let a = builder.token(ATOM, "a");
+    let written = builder.node(LIST, vec![a.clone()]);
+    let expanded = builder.node_with_provenance(LIST, 7, vec![a.clone()]);
+    let again = builder.node_with_provenance(LIST, 7, vec![a]);
+
+    assert_eq!(written.provenance(), 0);
+    assert_eq!(expanded.provenance(), 7);
+    assert!(!Arc::ptr_eq(&written, &expanded));
+    assert!(Arc::ptr_eq(&expanded, &again));
+    assert!(written < expanded);
+}
+
+#[test]
+fn token_from_chunks_deduplicates() {
+    let mut builder = green::Builder::new();
+    let whole = builder.token(ATOM, "héllo");
+    let chunked = builder.token_from_chunks(ATOM, vec!["h\u{e9}", "", "llo"], 6.into());
+    assert!(Arc::ptr_eq(&whole, &chunked));
+    let new = builder.token_from_chunks(ATOM, vec!["wor", "ld"], 5.into());
+    assert_eq!(new.text(), "world");
+    assert_eq!(builder.size(), 2);
+}
+
+#[test]
+#[should_panic(expected = "token chunks don't add up to `total_len`")]
+fn token_from_chunks_too_long() {
+    green::Builder::new().token_from_chunks(ATOM, vec!["ab", "cd"], 3.into());
+}
+
+#[test]
+#[should_panic(expected = "token chunks don't add up to `total_len`")]
+fn token_from_chunks_too_short() {
+    green::Builder::new().token_from_chunks(ATOM, vec!["ab"], 3.into());
+}
+
+#[test]
+fn token_classifier_flags() {
+    const NEWLINE: u8 = 0b001;
+    let mut builder = green::Builder::new();
+    builder.set_token_classifier(|_, text| if text.contains('\n') { NEWLINE } else { 0 });
+
+    assert_eq!(builder.token(ATOM, "a\nb").flags(), NEWLINE);
+    assert_eq!(builder.token(ATOM, "ab").flags(), 0);
+    assert_eq!(builder.token_from_chunks(ATOM, vec!["a", "\n", "c"], 3.into()).flags(), NEWLINE);
+    assert_eq!(builder.binary_token(ATOM, b"a\nb").flags(), 0);
+
+    let (a, b) = (builder.token(ATOM, "a\n"), builder.token(ATOM, "b"));
+    let joined = builder.join_tokens(&a, &b, ATOM);
+    assert_eq!(joined.flags(), NEWLINE);
+    let (left, right) = builder.split_token(&joined, 2.into(), (ATOM, ATOM));
+    assert_eq!((left.flags(), right.flags()), (NEWLINE, 0));
+    assert_eq!(left.len(), 2.into());
+
+    // tokens with different flags are different tokens, even from a shared cache
+    let classified = builder.token(ATOM, "a\nb");
+    let mut unclassified = builder.clone();
+    unclassified.set_token_classifier(|_, _| 0);
+    let token = unclassified.token(ATOM, "a\nb");
+    assert_eq!((token.flags(), classified.flags()), (0, NEWLINE));
+    assert_ne!(*token, *classified);
+    assert!(Arc::ptr_eq(&unclassified.token(ATOM, "a\nb"), &token));
+    assert!(Arc::ptr_eq(&builder.token(ATOM, "a\nb"), &classified));
+}
+
+#[test]
+#[should_panic(expected = "don't fit in `Token::MAX_FLAGS`")]
+fn token_classifier_too_many_flags() {
+    let mut builder = green::Builder::new();
+    builder.set_token_classifier(|_, _| green::Token::MAX_FLAGS + 1);
+    builder.token_from_chunks(ATOM, vec!["a"], 1.into());
+}
+
+#[test]
+#[cfg(feature = "node-flags")]
+fn multiline_tracking_is_opt_in() {
+    let mut builder = green::TreeBuilder::new();
+    let untracked = builder.start_node(LIST).token(ATOM, "a\nb").finish_node().finish();
+    assert!(!untracked.is_multiline());
+
+    builder.builder().set_track_multiline(true);
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .start_node(LIST)
+                .start_node(LIST)
+                    .token(ATOM, "b\nc")
+                .finish_node()
+            .finish_node()
+            .start_node(LIST)
+                .token(ATOM, "c")
+            .finish_node()
+        .finish_node()
+        .finish();
+    assert!(tree.is_multiline());
+    let children: Vec<_> = tree.children().map(|el| el.unwrap_node().is_multiline()).collect();
+    assert_eq!(children, [true, false]);
+
+    // nodes cached before tracking was enabled are reused as-is
+    let retracked = builder.start_node(LIST).token(ATOM, "a\nb").finish_node().finish();
+    assert!(Arc::ptr_eq(&retracked, &untracked));
+    assert!(!retracked.is_multiline());
+}
+
+#[test]
+#[cfg(feature = "node-flags")]
+fn multiline_nodes_keep_their_length() {
+    let mut builder = green::Builder::new();
+    builder.set_track_multiline(true);
+
+    // one shared mebibyte token, so this doesn't allocate all the text it holds
+    let token = builder.token(ATOM, &format!("\n{}", "a".repeat((1 << 20) - 1)));
+    let long = builder.node(LIST, vec![token; 2100]);
+    let outer = builder.node(LIST, vec![long.clone()]);
+    assert_eq!(u32::from(long.len()), 2100 << 20);
+    assert_eq!(outer.len(), long.len());
+    for node in &[long, outer] {
+        assert!(node.is_multiline());
+    }
+}
+
+#[test]
+#[cfg(feature = "node-flags")]
+fn synthesized_flags_propagate_masked_token_flags() {
+    let mut builder = green::Builder::new();
+    builder.set_token_classifier(|_, text| text.len().min(3) as u8).set_flag_propagation(0b010);
+
+    let a = builder.token(ATOM, "a");
+    let bb = builder.token(ATOM, "bb");
+    let ccc = builder.token(ATOM, "ccc");
+    let inner = builder.node(LIST, vec![bb.clone(), a.clone()]);
+    let outer =
+        builder.node(LIST, vec![sorbus::NodeOrToken::from(inner.clone()), a.clone().into()]);
+    assert_eq!(inner.synthesized_flags(), 0b010);
+    assert_eq!(outer.synthesized_flags(), 0b010);
+
+    // only the propagated flags are synthesized
+    let odd = builder.node(LIST, vec![a, ccc]);
+    assert_eq!(odd.synthesized_flags(), 0b010);
+    let empty = builder.node(LIST, Vec::<Arc<green::Token>>::new());
+    assert_eq!(empty.synthesized_flags(), 0);
+}
+
+#[test]
+#[cfg(feature = "node-flags")]
+fn node_flags_leave_room_for_long_text() {
+    let mut builder = green::Builder::new();
+    builder
+        .set_track_multiline(true)
+        .set_token_classifier(|_, _| green::Token::MAX_FLAGS)
+        .set_flag_propagation(green::Token::MAX_FLAGS);
+
+    // one shared mebibyte token, so this doesn't allocate all the text it holds
+    let token = builder.token(ATOM, &format!("\n{}", "a".repeat((1 << 20) - 1)));
+    let long = builder.node(LIST, vec![token.clone(); 2100]);
+    let longer = builder.node(LIST, vec![sorbus::NodeOrToken::from(long.clone()), token.into()]);
+    assert_eq!(u32::from(long.len()), 2100 << 20);
+    assert_eq!(u32::from(longer.len()), 2101 << 20);
+    for node in &[long, longer] {
+        assert!(node.is_multiline());
+        assert_eq!(node.synthesized_flags(), green::Token::MAX_FLAGS);
+    }
+}
+
+#[test]
+fn layered_builder_shares_base() {
+    let mut base = green::Builder::new();
+    let a = base.token_with_value(ATOM, "a", 1_u32);
+    let list = base.node(LIST, vec![a.clone()]);
+
+    let mut layer = green::Builder::layered(&base);
+    assert!(Arc::ptr_eq(&layer.token(ATOM, "a"), &a));
+    assert!(Arc::ptr_eq(&layer.node(LIST, vec![a.clone()]), &list));
+    assert_eq!(layer.token_value::<u32>(&a), Some(&1));
+    assert_eq!(layer.size(), 0);
+
+    // new elements are cached in the layer, not the base
+    let b = layer.token(ATOM, "b");
+    let both = layer.node(LIST, vec![a.clone(), b.clone()]);
+    assert_eq!((layer.size(), base.size()), (2, 2));
+    assert!(Arc::ptr_eq(&layer.node(LIST, vec![a.clone(), b]), &both));
+    let both = layer.node(LIST, vec![both, list.clone()]);
+    assert_eq!(layer.size(), 3);
+
+    // collecting the layer leaves the base intact
+    drop(both);
+    layer.gc();
+    assert_eq!(layer.size(), 0);
+    drop((a, list, base));
+    layer.gc();
+    let a = layer.token(ATOM, "a");
+    assert_eq!(layer.token_value::<u32>(&a), Some(&1));
+    assert_eq!(layer.size(), 0);
+}
+
+#[test]
+fn snapshot_keys_are_stable() {
+    let build = |texts: &[&str]| {
+        let mut builder = green::Builder::new();
+        let tokens: Vec<_> = texts.iter().map(|text| builder.token(ATOM, text)).collect();
+        let children = vec![builder.token(ATOM, "a"), builder.token(ATOM, "b")];
+        let list = builder.node(LIST, children);
+        (builder, list, tokens)
+    };
+    let (mut ab, list, tokens) = build(&["a", "b", "c"]);
+    let (ba, _list, _tokens) = build(&["c", "b", "a"]);
+    assert_eq!(ab.snapshot_keys(), ba.snapshot_keys());
+    assert_eq!(ab.snapshot_keys().lines().count(), 4);
+
+    drop((list, tokens));
+    ab.gc();
+    assert_eq!(ab.snapshot_keys(), "");
+}
+
+#[test]
+fn missing_tokens_are_shared_and_take_no_text() {
+    let mut builder = green::Builder::new();
+    let missing = builder.missing(ATOM);
+    assert!(missing.is_empty());
+    assert_eq!(missing.text(), "");
+    assert!(Arc::ptr_eq(&missing, &builder.missing(ATOM)));
+    assert!(!Arc::ptr_eq(&missing, &builder.missing(LIST)));
+
+    let a = builder.token(ATOM, "a");
+    let list = builder.node(LIST, vec![missing.clone(), a, missing.clone()]);
+    assert_eq!(list.len(), 1.into());
+    assert_eq!(list.index_of_offset(0.into()), 1);
+    assert_eq!(list.token_at_offset(0.into()).unwrap().1.text(), "a");
+    assert_eq!(green::verify_text(&list, "a"), Ok(()));
+    assert_eq!(builder.size(), 4);
+}
@@ -0,0 +1,130 @@
+use sorbus::{
+    green::{self, CanonicalPolicy, TriviaPlacement},
+    recipes, Kind, KindSet,
+};
+
+const WS: Kind = Kind(0);
+const ATOM: Kind = Kind(1);
+const LIST: Kind = Kind(2);
+const ERROR: Kind = Kind(3);
+
+fn kinds(kinds: &[Kind]) -> KindSet {
+    kinds.iter().copied().collect()
+}
+
+#[test]
+fn trivia_is_hoisted_to_the_nearest_node_with_content() {
+    let mut builder = green::TreeBuilder::new();
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .start_node(LIST)
+                .token(WS, " ")
+                .start_node(LIST)
+                    .token(WS, " ")
+                    .token(ATOM, "a")
+                    .token(WS, "  ")
+                .finish_node()
+            .finish_node()
+            .start_node(LIST)
+                .token(WS, "\n")
+            .finish_node()
+        .finish_node()
+        .finish();
+    #[rustfmt::skip]
+    let expected = builder
+        .start_node(LIST)
+            .token(WS, " ")
+            .token(WS, " ")
+            .start_node(LIST)
+                .start_node(LIST)
+                    .token(ATOM, "a")
+                .finish_node()
+            .finish_node()
+            .token(WS, "  ")
+            .token(WS, "\n")
+        .finish_node()
+        .finish();
+
+    let policy = CanonicalPolicy {
+        trivia: kinds(&[WS]),
+        placement: TriviaPlacement::Outside,
+        keep_empty: KindSet::default(),
+    };
+    let canonical = builder.builder().canonicalize(&tree, &policy);
+    assert_eq!(canonical, expected);
+    assert_eq!(recipes::reconstruct_text(&canonical), recipes::reconstruct_text(&tree));
+    // canonicalizing is idempotent
+    assert_eq!(builder.builder().canonicalize(&canonical, &policy), canonical);
+}
+
+#[test]
+fn empty_nodes_are_removed_unless_kept() {
+    let mut builder = green::TreeBuilder::new();
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .start_node(LIST).finish_node()
+            .start_node(ERROR).finish_node()
+            .start_node(LIST)
+                .start_node(LIST).finish_node()
+            .finish_node()
+            .token(ATOM, "a")
+        .finish_node()
+        .finish();
+    #[rustfmt::skip]
+    let expected = builder
+        .start_node(LIST)
+            .start_node(ERROR).finish_node()
+            .token(ATOM, "a")
+        .finish_node()
+        .finish();
+
+    let policy = CanonicalPolicy { keep_empty: kinds(&[ERROR]), ..CanonicalPolicy::default() };
+    assert_eq!(builder.builder().canonicalize(&tree, &policy), expected);
+
+    // the root is never removed
+    let empty = builder.start_node(LIST).finish_node().finish();
+    assert_eq!(builder.builder().canonicalize(&empty, &policy), empty);
+}
+
+#[test]
+fn keep_placement_leaves_trivia_alone() {
+    let mut builder = green::TreeBuilder::new();
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .start_node(LIST)
+                .token(WS, " ")
+                .token(ATOM, "a")
+            .finish_node()
+            .token(WS, " ")
+        .finish_node()
+        .finish();
+
+    let policy = CanonicalPolicy { trivia: kinds(&[WS]), ..CanonicalPolicy::default() };
+    let canonical = builder.builder().canonicalize(&tree, &policy);
+    // unchanged trees are deduplicated back to the same allocation
+    assert!(std::sync::Arc::ptr_eq(&canonical, &tree));
+}
+
+#[test]
+#[cfg(feature = "provenance")]
+fn canonical_nodes_keep_their_provenance() {
+    let mut builder = green::Builder::new();
+    let ws = builder.token(WS, " ");
+    let a = builder.token(ATOM, "a");
+    let inner = builder.node_with_provenance(LIST, 2, vec![a, ws]);
+    let tree = builder.node_with_provenance(LIST, 1, vec![inner]);
+
+    let policy = CanonicalPolicy {
+        trivia: kinds(&[WS]),
+        placement: TriviaPlacement::Outside,
+        ..CanonicalPolicy::default()
+    };
+    let canonical = builder.canonicalize(&tree, &policy);
+    assert_eq!(canonical.provenance(), 1);
+    let inner = canonical.children().next().unwrap().unwrap_node();
+    assert_eq!(inner.provenance(), 2);
+    assert_eq!(inner.children_len(), 1);
+}
@@ -0,0 +1,497 @@
+use {
+    sorbus::{
+        green::{self, ElementRef},
+        Kind, TextRange,
+    },
+    std::sync::Arc,
+};
+
+/// A flat node with tokens `"0"` through `"{len-1}"`.
+fn make_flat(len: usize) -> Arc<green::Node> {
+    let mut builder = green::TreeBuilder::new();
+    builder.start_node(Kind(1));
+    for i in 0..len {
+        builder.token(Kind(0), &i.to_string());
+    }
+    builder.finish_node().finish()
+}
+
+fn text(el: ElementRef<'_>) -> String {
+    el.unwrap_token().text().to_owned()
+}
+
+#[test]
+fn reverse_iteration_matches_next_back() {
+    for len in 0..6 {
+        let node = make_flat(len);
+        // split to also start iteration on a half-aligned element
+        for mid in 0..=len {
+            let (_, children) = node.children().split_at(mid);
+            let expected: Vec<String> = children.clone().rev().map(text).collect();
+
+            let folded = children.clone().rfold(vec![], |mut acc, el| {
+                acc.push(text(el));
+                acc
+            });
+            assert_eq!(folded, expected);
+
+            let with_offsets = children.with_offsets().rfold(vec![], |mut acc, (_, el)| {
+                acc.push(text(el));
+                acc
+            });
+            assert_eq!(with_offsets, expected);
+        }
+    }
+}
+
+#[test]
+fn rfind_leaves_iterator_before_match() {
+    let node = make_flat(7);
+    let mut children = node.children();
+    let found = children.rfind(|el| el.as_token().unwrap().text() == "4").map(text);
+    assert_eq!(found.as_deref(), Some("4"));
+    assert_eq!(children.map(text).collect::<Vec<_>>(), ["0", "1", "2", "3"]);
+
+    let mut children = node.children().split_at(3).1;
+    assert!(children.rfind(|_| false).is_none());
+    assert_eq!(children.len(), 0);
+}
+
+#[test]
+fn searches_leave_iterator_after_match() {
+    let node = make_flat(7);
+    let is = |text: &'static str| move |el: &ElementRef<'_>| el.as_token().unwrap().text() == text;
+
+    for mid in 0..3 {
+        let mut children = node.children().split_at(mid).1;
+        assert_eq!(children.find(is("4")).map(text).as_deref(), Some("4"));
+        assert_eq!(children.map(text).collect::<Vec<_>>(), ["5", "6"]);
+
+        let mut children = node.children().split_at(mid).1;
+        assert_eq!(children.position(|el| is("3")(&el)), Some(3 - mid));
+        assert_eq!(children.len(), 3);
+
+        let mut children = node.children().split_at(mid).1;
+        assert!(children.any(|el| is("5")(&el)));
+        assert!(!children.all(|el| is("5")(&el)));
+        assert_eq!(children.len(), 0);
+        assert!(children.find(|_| true).is_none());
+    }
+
+    let mut children = node.children().with_offsets();
+    let found = children.find_map(|(offset, el)| if is("2")(&el) { Some(offset) } else { None });
+    assert_eq!(found, Some(2.into()));
+    assert!(children.all(|(_, el)| el.is_token()));
+}
+
+#[test]
+fn node_children_len() {
+    for len in 0..4 {
+        let node = make_flat(len);
+        assert_eq!(node.children_len(), node.children().len());
+        assert_eq!(node.has_children(), len != 0);
+        assert_eq!(node.is_empty(), len == 0);
+    }
+
+    let empty_token = green::Builder::new().token(Kind(0), "");
+    let node = green::Builder::new().node(Kind(1), vec![empty_token]);
+    assert!(node.is_empty() && node.has_children());
+}
+
+#[test]
+fn children_compare_across_alignments() {
+    let mut builder = green::Builder::new();
+    let a = builder.token(Kind(0), "a");
+    let empty = builder.token(Kind(0), "");
+    // `a` at offset 0, once as the first (full aligned) child, once as the second (half aligned)
+    let full = builder.node(Kind(1), vec![a.clone()]);
+    let half = builder.node(Kind(1), vec![empty, a]);
+
+    assert_eq!(full[0], half[1]);
+    assert_eq!(half[1], full[0]);
+    assert_ne!(full[0], half[0]);
+    assert_ne!(half[0], full[0]);
+}
+
+#[test]
+fn visitor_control_flow() {
+    use sorbus::{
+        green::{VisitControl, Visitor},
+        TextSize,
+    };
+
+    const ATOM: Kind = Kind(0);
+    const LIST: Kind = Kind(1);
+    const SKIP: Kind = Kind(2);
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+    impl Visitor for Log {
+        fn visit(&mut self, kind: Kind, el: ElementRef<'_>, offset: TextSize) -> VisitControl {
+            self.0.push(format!("visit {:?}@{:?}", kind, offset));
+            match kind {
+                SKIP => VisitControl::SkipChildren,
+                ATOM if el.unwrap_token().text() == "stop" => VisitControl::Stop,
+                _ => VisitControl::Continue,
+            }
+        }
+
+        fn leave(&mut self, node: &green::Node, offset: TextSize) {
+            self.0.push(format!("leave {:?}@{:?}", node.kind(), offset));
+        }
+    }
+
+    #[rustfmt::skip]
+    let tree = green::TreeBuilder::new()
+        .start_node(LIST)
+            .start_node(SKIP)
+                .token(ATOM, "a")
+            .finish_node()
+            .start_node(LIST)
+                .token(ATOM, "b")
+            .finish_node()
+            .start_node(LIST)
+                .token(ATOM, "stop")
+                .token(ATOM, "c")
+            .finish_node()
+            .token(ATOM, "d")
+        .finish_node()
+        .finish();
+
+    let mut log = Log::default();
+    tree.accept(&mut log);
+    assert_eq!(
+        log.0,
+        [
+            "visit Kind(2)@0",
+            "visit Kind(1)@1",
+            "visit Kind(0)@1",
+            "leave Kind(1)@1",
+            "visit Kind(1)@2",
+            "visit Kind(0)@2",
+        ],
+    );
+}
+
+#[test]
+fn offset_restricted_iteration() {
+    let tree = green::TreeBuilder::new()
+        .start_node(Kind(1))
+        .token(Kind(0), "ab")
+        .token(Kind(0), "")
+        .token(Kind(0), "")
+        .token(Kind(0), "cd")
+        .finish_node()
+        .finish();
+    let children = tree.children().with_offsets();
+    let from = |offset: u32| tree.children_len() - children.from_offset(offset.into()).len();
+    let until = |offset: u32| children.until_offset(offset.into()).len();
+
+    assert_eq!((from(0), from(1), from(2), from(3), from(4)), (0, 0, 1, 3, 4));
+    assert_eq!((until(0), until(1), until(2), until(3), until(4)), (0, 1, 1, 4, 4));
+}
+
+#[test]
+fn biased_index_of_offset() {
+    use sorbus::green::Bias::{Left, Right};
+
+    let empty = green::TreeBuilder::new().start_node(Kind(1)).finish_node().finish();
+    assert_eq!(empty.try_index_of_offset(0.into(), Left), None);
+    assert_eq!(empty.try_index_of_offset(0.into(), Right), None);
+
+    let tree = green::TreeBuilder::new()
+        .start_node(Kind(1))
+        .token(Kind(0), "ab")
+        .token(Kind(0), "")
+        .token(Kind(0), "cd")
+        .finish_node()
+        .finish();
+    let index = |offset: u32| {
+        (
+            tree.try_index_of_offset(offset.into(), Left),
+            tree.try_index_of_offset(offset.into(), Right),
+        )
+    };
+    assert_eq!(index(0), (Some(0), Some(0)));
+    assert_eq!(index(1), (Some(0), Some(0)));
+    assert_eq!(index(2), (Some(0), Some(2)));
+    assert_eq!(index(3), (Some(2), Some(2)));
+    assert_eq!(index(4), (Some(2), None));
+    assert_eq!(index(5), (None, None));
+}
+
+#[test]
+fn index_of_offset_small_and_large_nodes() {
+    // straddle the cutoff between linear and binary search
+    for len in 1..=20 {
+        let tree = make_flat(len);
+        let mut expected = vec![];
+        for (index, child) in tree.children().enumerate() {
+            expected.extend(std::iter::repeat(index).take(child.len().into()));
+        }
+        let actual: Vec<_> =
+            (0..u32::from(tree.len())).map(|offset| tree.index_of_offset(offset.into())).collect();
+        assert_eq!(actual, expected, "len {}", len);
+    }
+}
+
+#[test]
+fn offset_of_shared_and_empty_descendants() {
+    let mut builder = green::TreeBuilder::new();
+    let empty = builder.start_node(Kind(2)).finish_node().finish();
+    let word =
+        builder.start_node(Kind(1)).token(Kind(0), "ab").add(empty.clone()).finish_node().finish();
+    let tree = builder
+        .start_node(Kind(1))
+        .token(Kind(0), "c")
+        .add(word.clone())
+        .add(word.clone())
+        .finish_node()
+        .finish();
+
+    assert_eq!(tree.offset_of_descendant(&tree), Some(0.into()));
+    assert_eq!(tree.offset_of_descendant(&word), Some(1.into()));
+    assert_eq!(tree.offset_of_descendant(&empty), Some(3.into()));
+    let unrelated = green::TreeBuilder::new().start_node(Kind(2)).finish_node().finish();
+    assert_eq!(tree.offset_of_descendant(&unrelated), None);
+}
+
+#[test]
+fn trivia_runs_stop_at_nodes() {
+    const WS: Kind = Kind(0);
+    let mut builder = green::TreeBuilder::new();
+    let tree = builder
+        .start_node(Kind(1))
+        .token(WS, " ")
+        .start_node(WS) // a node of a trivia kind is not trivia
+        .finish_node()
+        .token(WS, " ")
+        .token(WS, "\n")
+        .token(Kind(2), "a")
+        .token(WS, " ")
+        .finish_node()
+        .finish();
+    let is_trivia = |kind| kind == WS;
+    let offsets = |trivia: green::ChildrenWithOffsets<'_>| {
+        trivia.map(|(offset, _)| u32::from(offset)).collect::<Vec<_>>()
+    };
+
+    assert_eq!(offsets(tree.leading_trivia_of(4, is_trivia)), [1, 2]);
+    assert_eq!(offsets(tree.trailing_trivia_of(4, is_trivia)), [4]);
+    assert_eq!(offsets(tree.leading_trivia_of(1, is_trivia)), [0]);
+    assert_eq!(offsets(tree.trailing_trivia_of(1, is_trivia)), [1, 2]);
+    assert!(offsets(tree.trailing_trivia_of(5, is_trivia)).is_empty());
+}
+
+#[test]
+fn comment_blocks_across_nodes() {
+    use sorbus::{KindSet, TextRange};
+
+    const WS: Kind = Kind(0);
+    const COMMENT: Kind = Kind(3);
+    let mut comments = KindSet::new();
+    assert!(comments.insert(COMMENT) && comments.insert(Kind(300)));
+    assert!(!comments.insert(COMMENT));
+    assert!(comments.remove(Kind(300)) && !comments.remove(Kind(300)));
+    assert_eq!(comments, Some(COMMENT).into_iter().collect());
+    assert_eq!(format!("{:?}", comments), "{Kind(3)}");
+
+    let tree = green::TreeBuilder::new()
+        .start_node(Kind(1))
+        .token(COMMENT, "#a")
+        .start_node(Kind(1))
+        .token(WS, " ")
+        .token(COMMENT, "#b")
+        .finish_node()
+        .token(Kind(2), "x")
+        .token(COMMENT, "#c")
+        .finish_node()
+        .finish();
+    let range = |start: u32, end: u32| TextRange::new(start.into(), end.into());
+
+    let ranges: Vec<_> = tree.comments(comments.clone()).map(|(range, _)| range).collect();
+    assert_eq!(ranges, [range(0, 2), range(3, 5), range(6, 8)]);
+    let blocks: Vec<_> = tree
+        .comment_blocks(comments, Some(WS).into_iter().collect())
+        .map(|(range, block)| (range, block.len()))
+        .collect();
+    assert_eq!(blocks, [(range(0, 5), 2), (range(6, 8), 1)]);
+}
+
+#[test]
+fn with_offset_navigation() {
+    use sorbus::{
+        green::{Bias, WithOffset},
+        TextRange,
+    };
+
+    #[rustfmt::skip]
+    let tree = green::TreeBuilder::new()
+        .start_node(Kind(1))
+            .token(Kind(0), "ab")
+            .start_node(Kind(1))
+                .token(Kind(0), "cd")
+                .token(Kind(0), "")
+                .token(Kind(0), "ef")
+            .finish_node()
+        .finish_node()
+        .finish();
+
+    let root = WithOffset::root(&tree);
+    let inner = root.child_at_offset(2.into(), Bias::Right).unwrap();
+    assert_eq!(inner.range(), TextRange::new(2.into(), 6.into()));
+    assert_eq!(root.child_at_offset(2.into(), Bias::Left).unwrap().text(), Some("ab"));
+
+    let offsets: Vec<_> = inner.children().map(|child| child.offset()).collect();
+    assert_eq!(offsets, [2.into(), 4.into(), 4.into()]);
+    assert_eq!(inner.child_at_offset(1.into(), Bias::Right), None);
+
+    let ef = inner.token_at_offset(5.into()).unwrap();
+    assert_eq!((ef.range(), ef.text()), (TextRange::new(4.into(), 6.into()), Some("ef")));
+    assert_eq!(ef.token_at_offset(4.into()), Some(ef));
+    assert_eq!(ef.token_at_offset(6.into()), None);
+    assert_eq!(ef.children().count(), 0);
+}
+
+#[test]
+fn token_boundaries_with_empty_elements() {
+    #[rustfmt::skip]
+    let tree = green::TreeBuilder::new()
+        .start_node(Kind(1))
+            .token(Kind(0), "ab")
+            .start_node(Kind(1))
+            .finish_node()
+            .token(Kind(0), "")
+            .start_node(Kind(1))
+                .token(Kind(0), "")
+                .token(Kind(0), "cde")
+            .finish_node()
+            .token(Kind(0), "f")
+        .finish_node()
+        .finish();
+
+    let boundaries: Vec<u32> = tree.token_boundaries().map(u32::from).collect();
+    assert_eq!(boundaries, [0, 2, 5, 6]);
+    for offset in 0..=7 {
+        let expected = boundaries.contains(&offset);
+        assert_eq!(tree.is_token_boundary(offset.into()), expected, "at {}", offset);
+    }
+
+    let empty = green::TreeBuilder::new().start_node(Kind(1)).finish_node().finish();
+    assert_eq!(empty.token_boundaries().collect::<Vec<_>>(), [0.into()]);
+    assert!(empty.is_token_boundary(0.into()));
+}
+
+#[test]
+fn index_and_iterate_node() {
+    let tree = make_flat(4);
+    let mut texts = vec![];
+    for child in &*tree {
+        texts.push(text(child));
+    }
+    assert_eq!(texts, ["0", "1", "2", "3"]);
+
+    assert_eq!(tree[2].offset(), 2.into());
+    assert_eq!(tree[2].get(), tree.children().nth(2).unwrap());
+    assert_eq!(&tree[1], &tree[1]);
+    assert_ne!(&tree[1], &tree[2]);
+}
+
+#[test]
+#[should_panic]
+fn index_out_of_bounds() {
+    let tree = make_flat(4);
+    let _ = &tree[4];
+}
+
+#[test]
+fn offset_aware_folds_and_remaining_range() {
+    use sorbus::{TextRange, TextSize};
+    let node = make_flat(12);
+    for mid in 0..=12 {
+        // split to also start iteration on a half-aligned element
+        let (_, children) = node.children().with_offsets().split_at(mid);
+        let expected: Vec<TextSize> = children.clone().map(|(offset, _)| offset).collect();
+
+        let folded = children.clone().fold(vec![], |mut acc, (offset, _)| {
+            acc.push(offset);
+            acc
+        });
+        assert_eq!(folded, expected);
+        let mut rfolded = children.clone().rfold(vec![], |mut acc, (offset, _)| {
+            acc.push(offset);
+            acc
+        });
+        rfolded.reverse();
+        assert_eq!(rfolded, expected);
+
+        let mut children = children;
+        while let Some(range) = children.remaining_range() {
+            let (start, _) = children.peek().unwrap();
+            assert_eq!(range, TextRange::new(start, node.len()));
+            children.next();
+        }
+        assert_eq!(children.len(), 0);
+    }
+}
+
+#[test]
+fn direct_tokens_skip_nodes() {
+    let mut builder = green::TreeBuilder::new();
+    builder.start_node(Kind(1));
+    for i in 0..10 {
+        if i % 3 == 0 {
+            builder.start_node(Kind(2)).token(Kind(0), "node").finish_node();
+        } else {
+            builder.token(Kind(0), &i.to_string());
+        }
+    }
+    let tree = builder.finish_node().finish();
+
+    let expected: Vec<_> = tree
+        .children()
+        .with_offsets()
+        .filter_map(|(offset, el)| Some((TextRange::at(offset, el.len()), el.into_token()?)))
+        .collect();
+    let actual: Vec<_> = tree.direct_tokens().collect();
+    assert_eq!(actual.len(), 6);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn get_child_matches_with_offsets() {
+    let node = make_flat(12);
+    for mid in 0..=12 {
+        let (_, children) = node.children().split_at(mid);
+        let expected: Vec<_> = children.with_offsets().collect();
+        for (i, &(offset, el)) in expected.iter().enumerate() {
+            // offsets are from the parent node, not from the start of the iterator
+            let child = children.get_child(i).unwrap();
+            assert_eq!((child.offset(), child.get()), (offset, el));
+            assert!(std::ptr::eq(child, &node[mid + i]));
+            assert!(std::ptr::eq(children.with_offsets().get_child(i).unwrap(), child));
+        }
+        assert!(children.get_child(expected.len()).is_none());
+    }
+}
+
+#[test]
+fn nth_of_kind_counts_only_that_kind() {
+    let mut builder = green::TreeBuilder::new();
+    builder.start_node(Kind(1));
+    for i in 0..10 {
+        builder.token(Kind(i % 3), &i.to_string());
+    }
+    let tree = builder.finish_node().finish();
+
+    for kind in 0..3 {
+        let expected: Vec<_> =
+            tree.children().with_offsets().filter(|(_, el)| el.kind() == Kind(kind)).collect();
+        for (n, &(offset, el)) in expected.iter().enumerate() {
+            let child = tree.nth_of_kind(Kind(kind), n).unwrap();
+            assert_eq!((child.offset(), child.get()), (offset, el));
+        }
+        assert!(tree.nth_of_kind(Kind(kind), expected.len()).is_none());
+    }
+    assert!(tree.nth_of_kind(Kind(3), 0).is_none());
+}
@@ -0,0 +1,514 @@
+use {
+    sorbus::{green, Kind, NodeOrToken, TextRange},
+    std::sync::Arc,
+};
+
+const WS: Kind = Kind(0);
+const ATOM: Kind = Kind(1);
+const LIST: Kind = Kind(2);
+
+#[test]
+fn coalesce_adjacent_tokens() {
+    let mut builder = green::TreeBuilder::new();
+
+    #[rustfmt::skip]
+    let split = builder
+        .start_node(LIST)
+            .token(ATOM, "fo")
+            .token(ATOM, "o")
+            .token(WS, " ")
+            .token(ATOM, "b")
+            .token(ATOM, "a")
+            .token(ATOM, "r")
+        .finish_node()
+        .finish();
+
+    #[rustfmt::skip]
+    let joined = builder
+        .start_node(LIST)
+            .token(ATOM, "foo")
+            .token(WS, " ")
+            .token(ATOM, "bar")
+        .finish_node()
+        .finish();
+
+    let coalesced = builder.builder().coalesce_tokens(&split, ATOM);
+    assert!(Arc::ptr_eq(&coalesced, &joined));
+
+    // nothing to coalesce returns the same node
+    let unchanged = builder.builder().coalesce_tokens(&joined, ATOM);
+    assert!(Arc::ptr_eq(&unchanged, &joined));
+    assert_eq!(unchanged.children().filter(NodeOrToken::is_token).count(), 3);
+}
+
+#[test]
+fn respace_across_node_edges() {
+    use sorbus::green::format::{respace, Spacer};
+
+    const PLUS: Kind = Kind(3);
+
+    struct OneSpace;
+    impl Spacer for OneSpace {
+        fn whitespace(&self) -> Kind {
+            WS
+        }
+
+        fn spacing(&self, left: Kind, right: Kind) -> Option<&str> {
+            if left == PLUS || right == PLUS {
+                Some(" ")
+            } else {
+                None
+            }
+        }
+    }
+
+    let mut builder = green::TreeBuilder::new();
+
+    #[rustfmt::skip]
+    let messy = builder
+        .start_node(LIST)
+            .start_node(LIST)
+                .token(ATOM, "a")
+                .token(WS, "  ")
+            .finish_node()
+            .token(WS, "\t")
+            .token(PLUS, "+")
+            .start_node(LIST)
+                .token(ATOM, "b")
+                .token(WS, " ")
+                .token(ATOM, "c")
+            .finish_node()
+        .finish_node()
+        .finish();
+
+    #[rustfmt::skip]
+    let tidy = builder
+        .start_node(LIST)
+            .start_node(LIST)
+                .token(ATOM, "a")
+            .finish_node()
+            .token(WS, " ")
+            .token(PLUS, "+")
+            .token(WS, " ")
+            .start_node(LIST)
+                .token(ATOM, "b")
+                .token(WS, " ")
+                .token(ATOM, "c")
+            .finish_node()
+        .finish_node()
+        .finish();
+
+    let respaced = respace(builder.builder(), &messy, &OneSpace);
+    assert!(Arc::ptr_eq(&respaced, &tidy));
+}
+
+#[test]
+fn editor_nested_edits() {
+    use sorbus::green::edit::Editor;
+
+    let mut builder = green::TreeBuilder::new();
+
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .token(ATOM, "a")
+            .start_node(LIST)
+                .token(ATOM, "b")
+                .token(ATOM, "c")
+            .finish_node()
+            .token(ATOM, "d")
+        .finish_node()
+        .finish();
+
+    let x = builder.builder().token(ATOM, "x");
+    let y = builder.builder().token(ATOM, "y");
+
+    let mut editor = Editor::new(tree.clone());
+    editor
+        .replace_with(&[1, 1], x.clone())
+        .insert_before(&[1, 0], y.clone())
+        .detach(&[2])
+        .insert_after(&[0], y);
+    let edited = editor.commit(builder.builder()).into_root();
+
+    #[rustfmt::skip]
+    let expected = builder
+        .start_node(LIST)
+            .token(ATOM, "a")
+            .token(ATOM, "y")
+            .start_node(LIST)
+                .token(ATOM, "y")
+                .token(ATOM, "b")
+                .token(ATOM, "x")
+            .finish_node()
+        .finish_node()
+        .finish();
+    assert!(Arc::ptr_eq(&edited, &expected));
+
+    // edits inside a replaced element are discarded
+    let mut editor = Editor::new(tree);
+    editor.replace_with(&[1, 0], x.clone()).detach(&[1]);
+    let edited = editor.commit(builder.builder()).into_root();
+    assert_eq!(edited.children_len(), 2);
+}
+
+#[test]
+#[cfg(feature = "provenance")]
+fn editor_keeps_provenance_of_ancestors() {
+    use sorbus::green::edit::Editor;
+
+    let mut builder = green::Builder::new();
+    let a = builder.token(ATOM, "a");
+    let b = builder.token(ATOM, "b");
+    let inner = builder.node_with_provenance(LIST, 2, vec![a.clone(), b.clone()]);
+    let tree =
+        builder.node_with_provenance(LIST, 1, vec![NodeOrToken::Node(inner), a.clone().into()]);
+
+    let mut editor = Editor::new(tree);
+    editor.replace_with(&[0, 1], a);
+    let edited = editor.commit(&mut builder).into_root();
+    assert_eq!(edited.provenance(), 1);
+    let inner = edited.children().next().unwrap().unwrap_node();
+    assert_eq!(inner.provenance(), 2);
+    assert_eq!(inner.children().nth(1).unwrap().unwrap_token().text(), "a");
+}
+
+#[test]
+fn edit_result_maps_offsets() {
+    use sorbus::green::edit::Editor;
+
+    let mut builder = green::TreeBuilder::new();
+
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .token(ATOM, "aaa")
+            .token(WS, " ")
+            .token(ATOM, "bbb")
+            .token(WS, " ")
+            .token(ATOM, "ccc")
+        .finish_node()
+        .finish();
+
+    let x = builder.builder().token(ATOM, "x");
+    let yyyy = builder.builder().token(ATOM, "yyyy");
+
+    let mut editor = Editor::new(tree);
+    editor.replace_with(&[2], x).insert_before(&[4], yyyy);
+    let result = editor.commit(builder.builder());
+    // "aaa bbb ccc" => "aaa x yyyyccc"
+
+    assert_eq!(result.map_offset(2.into()), 2.into());
+    assert_eq!(result.map_offset(4.into()), 4.into());
+    assert_eq!(result.map_offset(5.into()), 4.into());
+    assert_eq!(result.map_offset(7.into()), 5.into());
+    assert_eq!(result.map_offset(8.into()), 6.into());
+    assert_eq!(result.map_offset(9.into()), 11.into());
+    assert_eq!(result.map_offset(11.into()), 13.into());
+
+    let map = |start: u32, end: u32| result.map_range(TextRange::new(start.into(), end.into()));
+    assert_eq!(map(0, 3), TextRange::new(0.into(), 3.into()));
+    assert_eq!(map(5, 6), TextRange::new(4.into(), 5.into()));
+    assert_eq!(map(8, 11), TextRange::new(10.into(), 13.into()));
+    assert_eq!(map(4, 8), TextRange::new(4.into(), 6.into()));
+    assert_eq!(map(9, 10), TextRange::new(11.into(), 12.into()));
+    assert_eq!(result.root().len(), 13.into());
+}
+
+#[test]
+fn edit_ranges_on_char_boundaries() {
+    use sorbus::{
+        green::edit::{EditError, Editor},
+        TextRange,
+    };
+
+    let mut builder = green::TreeBuilder::new();
+
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .token(ATOM, "a")
+            .start_node(LIST)
+                .token(ATOM, "ß")
+            .finish_node()
+            .binary_token(ATOM, b"\xFF\xFF")
+        .finish_node()
+        .finish();
+
+    let boundaries: Vec<bool> = (0..7).map(|offset| tree.is_char_boundary(offset.into())).collect();
+    assert_eq!(boundaries, [true, true, false, true, true, true, false]);
+
+    let editor = Editor::new(tree);
+    let range = |start: u32, end: u32| TextRange::new(start.into(), end.into());
+    assert_eq!(editor.check_range(range(1, 4)), Ok(()));
+    assert_eq!(
+        editor.check_range(range(0, 2)),
+        Err(EditError::NotCharBoundary { offset: 2.into() })
+    );
+    assert_eq!(
+        editor.check_range(range(3, 6)),
+        Err(EditError::OutOfBounds { range: range(3, 6), len: 5.into() }),
+    );
+}
+
+#[test]
+fn offset_index_follows_edits() {
+    use sorbus::green::{edit::Editor, OffsetIndex};
+
+    let mut builder = green::TreeBuilder::new();
+
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .token(ATOM, "aaa")
+            .token(WS, " ")
+            .start_node(LIST)
+                .token(ATOM, "bbb")
+                .token(WS, "")
+                .token(ATOM, "ccc")
+            .finish_node()
+            .token(WS, " ")
+            .token(ATOM, "ddd")
+        .finish_node()
+        .finish();
+
+    let mut index = OffsetIndex::new(tree.clone());
+    let x = builder.builder().token(ATOM, "x");
+    let yyyy = builder.builder().token(ATOM, "yyyy");
+
+    let mut editor = Editor::new(tree);
+    editor.replace_with(&[2, 0], x).insert_before(&[4], yyyy).detach(&[1]);
+    let result = editor.commit(builder.builder());
+    // "aaa bbbccc ddd" => "aaaxccc yyyyddd"
+    index.update(&result);
+
+    let fresh = OffsetIndex::new(result.into_root());
+    for offset in 0..16 {
+        let lookup = |index: &OffsetIndex| {
+            index.token_at_offset(offset.into()).map(|(start, token)| (start, Arc::clone(token)))
+        };
+        assert_eq!(lookup(&index), lookup(&fresh), "at offset {}", offset);
+    }
+    let (start, token) = index.token_at_offset(9.into()).unwrap();
+    assert_eq!((start, token.text()), (8.into(), "yyyy"));
+}
+
+#[test]
+fn split_and_join_tokens() {
+    use sorbus::green::edit::{join_tokens_at, split_token_at};
+
+    let mut builder = green::TreeBuilder::new();
+
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .token(WS, "")
+            .start_node(LIST)
+                .token(ATOM, "a+b")
+            .finish_node()
+        .finish_node()
+        .finish();
+
+    // not strictly within a token
+    assert!(split_token_at(builder.builder(), &tree, 0.into(), (ATOM, ATOM)).is_none());
+    assert!(split_token_at(builder.builder(), &tree, 3.into(), (ATOM, ATOM)).is_none());
+
+    let split = split_token_at(builder.builder(), &tree, 1.into(), (ATOM, WS)).unwrap();
+    let split = split_token_at(builder.builder(), split.root(), 2.into(), (WS, ATOM)).unwrap();
+    #[rustfmt::skip]
+    let expected = builder
+        .start_node(LIST)
+            .token(WS, "")
+            .start_node(LIST)
+                .token(ATOM, "a")
+                .token(WS, "+")
+                .token(ATOM, "b")
+            .finish_node()
+        .finish_node()
+        .finish();
+    assert_eq!(split.root(), &expected);
+
+    // no token ends at the start of the first child
+    assert!(join_tokens_at(builder.builder(), split.root(), 0.into(), ATOM).is_none());
+    let joined = join_tokens_at(builder.builder(), split.root(), 1.into(), ATOM).unwrap();
+    let joined = join_tokens_at(builder.builder(), joined.root(), 2.into(), ATOM).unwrap();
+    assert_eq!(joined.root(), &tree);
+
+    let binary = builder.builder().binary_token(ATOM, b"\xFF");
+    let text = builder.builder().token(ATOM, "a");
+    assert!(builder.builder().join_tokens(&text, &binary, ATOM).is_binary());
+    let (left, right) = builder.builder().split_token(&text, 0.into(), (WS, ATOM));
+    assert_eq!((left.text(), right.text()), ("", "a"));
+}
+
+#[test]
+fn apply_all_edits() {
+    use sorbus::green::edit;
+    let range = |start: u32, end: u32| TextRange::new(start.into(), end.into());
+    let mut builder = green::TreeBuilder::new();
+
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .start_node(LIST)
+                .token(ATOM, "foo")
+                .token(WS, " ")
+            .finish_node()
+            .token(ATOM, "bar")
+            .start_node(LIST)
+                .token(WS, " ")
+                .token(ATOM, "baz")
+            .finish_node()
+        .finish_node()
+        .finish();
+
+    // an edit across tokens and nodes, two edits in one token, and an append
+    let edits = [(range(11, 11), "!"), (range(2, 5), "x"), (range(9, 10), "Z"), (range(7, 8), "")];
+    let edited = edit::apply_all(builder.builder(), &tree, &edits).unwrap();
+    #[rustfmt::skip]
+    let expected = builder
+        .start_node(LIST)
+            .start_node(LIST)
+                .token(ATOM, "foxar")
+            .finish_node()
+            .start_node(LIST)
+                .token(ATOM, "bZz!")
+            .finish_node()
+        .finish_node()
+        .finish();
+    assert_eq!(edited.root(), &expected);
+    assert_eq!(edited.map_offset(6.into()), 4.into());
+    assert_eq!(edited.map_offset(10.into()), 7.into());
+    assert_eq!(edited.map_range(range(11, 11)), range(8, 8));
+
+    // deleting all of a token removes it, and untouched subtrees are shared
+    let edited = edit::apply_all(builder.builder(), &tree, &[(range(3, 4), "")]).unwrap();
+    fn child(tree: &green::Node, index: usize) -> &green::Node {
+        sorbus::ArcBorrow::downgrade(tree.children().get(index).unwrap().into_node().unwrap())
+    }
+    assert_eq!(child(edited.root(), 0).children().len(), 1);
+    assert!(std::ptr::eq(child(edited.root(), 2), child(&tree, 2)));
+
+    assert_eq!(
+        edit::apply_all(builder.builder(), &tree, &[(range(0, 2), ""), (range(1, 3), "")])
+            .unwrap_err(),
+        edit::EditError::Overlapping { first: range(0, 2), second: range(1, 3) },
+    );
+    assert_eq!(
+        edit::apply_all(builder.builder(), &tree, &[(range(0, 12), "")]).unwrap_err(),
+        edit::EditError::OutOfBounds { range: range(0, 12), len: 11.into() },
+    );
+    let empty = builder.start_node(LIST).finish_node().finish();
+    assert_eq!(
+        edit::apply_all(builder.builder(), &empty, &[(range(0, 0), "a")]).unwrap_err(),
+        edit::EditError::NoToken { offset: 0.into() },
+    );
+}
+
+#[test]
+fn wrap_and_unwrap() {
+    let mut builder = green::TreeBuilder::new();
+
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .start_node(LIST)
+                .token(ATOM, "a")
+                .token(WS, " ")
+                .token(ATOM, "b")
+            .finish_node()
+        .finish_node()
+        .finish();
+
+    // wrapping a nested element and unwrapping it again round-trips
+    let (l, r) = (builder.builder().token(WS, "<"), builder.builder().token(WS, ">"));
+    let wrapped = builder.builder().wrap(&tree, &[0, 2], LIST, &[l], &[r]);
+    assert_eq!(wrapped.root().len(), 5.into());
+    assert_eq!(wrapped.map_offset(2.into()), 2.into());
+    assert_eq!(
+        wrapped.map_range(TextRange::new(2.into(), 3.into())),
+        TextRange::new(3.into(), 4.into())
+    );
+    let wrapped = builder.builder().wrap(&tree, &[0, 2], LIST, &[], &[]);
+    let unwrapped = builder.builder().unwrap(wrapped.root(), &[0, 2]);
+    assert!(Arc::ptr_eq(unwrapped.root(), &tree));
+
+    // the root itself can be wrapped
+    let wrapped = builder.builder().wrap(&tree, &[], LIST, &[], &[]);
+    assert_eq!(wrapped.root().children().len(), 1);
+    let unwrapped = builder.builder().unwrap(wrapped.root(), &[0]);
+    assert!(Arc::ptr_eq(unwrapped.root(), &tree));
+}
+
+#[test]
+fn bounded_history() {
+    use sorbus::green::{edit, History};
+    let mut builder = green::TreeBuilder::new();
+    let tree = builder.start_node(LIST).token(ATOM, "a").finish_node().finish();
+    let mut history = History::new(tree, 2);
+
+    let append = |builder: &mut green::TreeBuilder, history: &History| {
+        let end = TextRange::empty(history.root().len());
+        edit::apply_all(builder.builder(), history.root(), &[(end, "a")]).unwrap()
+    };
+    for version in 1..=3 {
+        let edited = append(&mut builder, &history);
+        assert_eq!(history.push(edited), version);
+    }
+    assert_eq!(history.root().len(), 4.into());
+
+    // only the last two edits are retained
+    assert!(history.root_at(0).is_none());
+    assert_eq!(history.root_at(1).unwrap().len(), 2.into());
+    assert_eq!(history.map_offset(0.into(), 0, 3), None);
+    assert_eq!(history.map_offset(2.into(), 1, 3), Some(2.into()));
+    assert_eq!(history.map_offset(4.into(), 3, 1), Some(2.into()));
+
+    assert!(history.undo().is_some());
+    assert!(history.undo().is_some());
+    assert!(history.undo().is_none());
+    assert_eq!(history.version(), 1);
+
+    // pushing after an undo discards the undone versions
+    let edited = append(&mut builder, &history);
+    assert_eq!(history.push(edited), 2);
+    assert!(!history.can_redo());
+    assert_eq!(history.root().len(), 3.into());
+}
+
+#[test]
+fn damaged_range_of_nested_blocks() {
+    use sorbus::green::reuse::damaged_range;
+    // (a (b c) (d))
+    #[rustfmt::skip]
+    let tree = green::TreeBuilder::new()
+        .start_node(LIST)
+            .token(ATOM, "(")
+            .token(ATOM, "a")
+            .start_node(LIST)
+                .token(ATOM, "(")
+                .token(ATOM, "b")
+                .token(WS, " ")
+                .token(ATOM, "c")
+                .token(ATOM, ")")
+            .finish_node()
+            .start_node(LIST)
+                .token(ATOM, "(")
+                .token(ATOM, "d")
+                .token(ATOM, ")")
+            .finish_node()
+            .token(ATOM, ")")
+        .finish_node()
+        .finish();
+    let is_list = |node: &green::Node| node.kind() == LIST;
+    let range = |start: u32, end: u32| TextRange::new(start.into(), end.into());
+
+    assert_eq!(damaged_range(&tree, range(3, 4), is_list).0, range(2, 7));
+    assert_eq!(damaged_range(&tree, range(4, 5), is_list).0, range(2, 7));
+    assert_eq!(damaged_range(&tree, range(8, 9), is_list).0, range(7, 10));
+    // spanning two lists, or touching the end of one
+    assert_eq!(damaged_range(&tree, range(5, 8), is_list).0, range(0, 11));
+    assert_eq!(damaged_range(&tree, range(10, 10), is_list).0, range(0, 11));
+    // nothing but the root is reparseable
+    assert_eq!(damaged_range(&tree, range(3, 4), |_| false).0, range(0, 11));
+}
@@ -0,0 +1,112 @@
+use sorbus::{
+    green::{self, edit::Editor, flat::FlatTree},
+    Kind, TextRange,
+};
+
+const ATOM: Kind = Kind(0);
+const LIST: Kind = Kind(1);
+
+#[test]
+fn flat_tree_links_match_tree() {
+    let mut builder = green::TreeBuilder::new();
+    let inner = builder.start_node(LIST).token(ATOM, "a").token(ATOM, "b").finish_node().finish();
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .add(inner.clone())
+            .start_node(LIST)
+            .finish_node()
+            .add(inner)
+        .finish_node()
+        .finish();
+
+    let flat = FlatTree::new(tree.clone());
+    assert_eq!(flat.len(), 8);
+    assert_eq!(flat.children(0).collect::<Vec<_>>(), [1, 4, 5]);
+    assert_eq!(flat.first_child(4), None);
+    assert_eq!(flat.next_sibling(5), None);
+    assert!(flat.is_token(7) && !flat.is_token(4));
+
+    for id in 0..flat.len() as u32 {
+        let path = flat.path(id);
+        assert_eq!(flat.id_at_path(&path), Some(id));
+        let element = flat.element(id);
+        assert_eq!(element.kind(), flat.kinds()[id as usize]);
+        assert_eq!(element.len(), flat.ranges()[id as usize].len());
+        if let Some(parent) = flat.parent(id) {
+            assert!(flat.ranges()[parent as usize].contains_range(flat.ranges()[id as usize]));
+            assert!(flat.children(parent).any(|child| child == id));
+        }
+    }
+    assert_eq!(flat.ranges()[7], TextRange::new(3.into(), 4.into()));
+    assert_eq!(flat.id_at_path(&[3]), None);
+}
+
+/// Check that `flat` matches a freshly flattened version of its tree.
+fn assert_consistent(flat: &FlatTree) {
+    let fresh = FlatTree::new(flat.root().clone());
+    let mut stack = vec![(0, 0)];
+    let mut live = 0;
+    while let Some((id, fresh_id)) = stack.pop() {
+        live += 1;
+        assert!(flat.is_live(id));
+        assert_eq!(flat.kinds()[id as usize], fresh.kinds()[fresh_id as usize]);
+        assert_eq!(flat.ranges()[id as usize], fresh.ranges()[fresh_id as usize]);
+        assert_eq!(flat.is_token(id), fresh.is_token(fresh_id));
+        assert_eq!(flat.path(id), fresh.path(fresh_id));
+        let children: Vec<_> = flat.children(id).collect();
+        let fresh_children: Vec<_> = fresh.children(fresh_id).collect();
+        assert_eq!(children.len(), fresh_children.len());
+        for &child in &children {
+            assert_eq!(flat.parent(child), Some(id));
+        }
+        stack.extend(children.into_iter().zip(fresh_children));
+    }
+    assert_eq!((0..flat.len() as u32).filter(|&id| flat.is_live(id)).count(), live);
+}
+
+#[test]
+fn flat_tree_update_keeps_unchanged_ids() {
+    let mut builder = green::TreeBuilder::new();
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .start_node(LIST)
+                .token(ATOM, "a")
+                .token(ATOM, "b")
+            .finish_node()
+            .start_node(LIST)
+                .token(ATOM, "c")
+            .finish_node()
+            .token(ATOM, "d")
+        .finish_node()
+        .finish();
+    let mut flat = FlatTree::new(tree.clone());
+    let c_list = flat.id_at_path(&[1]).unwrap();
+
+    // edit a token deep in the tree: its ancestors are kept but changed
+    let mut editor = Editor::new(tree);
+    editor.replace_with(&[0, 1], builder.builder().token(ATOM, "bb"));
+    let tree = editor.commit(builder.builder()).into_root();
+    let patch = flat.update(tree.clone());
+    assert_eq!(patch.changed(), [0, 1, 3]);
+    assert!(patch.added().is_empty() && patch.removed().is_empty());
+    assert_eq!(flat.id_at_path(&[1]), Some(c_list));
+    assert_eq!(flat.ranges()[c_list as usize], TextRange::new(3.into(), 4.into()));
+    assert_consistent(&flat);
+
+    // replace a node with a token, and insert a node
+    let mut editor = Editor::new(tree);
+    editor.replace_with(&[0], builder.builder().token(ATOM, "e"));
+    editor.insert_before(&[2], builder.start_node(LIST).token(ATOM, "f").finish_node().finish());
+    let tree = editor.commit(builder.builder()).into_root();
+    let patch = flat.update(tree.clone());
+    assert_eq!(patch.removed(), [1, 3, 2]);
+    assert_eq!(patch.added(), [7, 8, 9]);
+    assert_eq!(flat.id_at_path(&[1]), Some(c_list));
+    assert_consistent(&flat);
+
+    // updating to the same tree changes nothing
+    assert_eq!(flat.update(tree), Default::default());
+    assert_consistent(&flat);
+}
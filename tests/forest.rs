@@ -0,0 +1,45 @@
+use sorbus::{green, Kind};
+
+const WS: Kind = Kind(0);
+const ATOM: Kind = Kind(1);
+const LIST: Kind = Kind(2);
+
+#[test]
+fn offsets_across_chunks() {
+    let mut builder = green::TreeBuilder::new();
+
+    #[rustfmt::skip]
+    let first = builder
+        .start_node(LIST)
+            .token(ATOM, "a")
+            .start_node(LIST)
+            .finish_node()
+            .start_node(LIST)
+                .token(WS, "")
+                .token(ATOM, "bc")
+            .finish_node()
+        .finish_node()
+        .finish();
+    let empty = builder.start_node(LIST).finish_node().finish();
+    let second = builder.start_node(LIST).token(ATOM, "de").finish_node().finish();
+
+    let mut forest: green::Forest = vec![first.clone(), empty, second].into_iter().collect();
+    assert_eq!(forest.len(), 5.into());
+    assert_eq!(forest.chunks().map(|(offset, _)| u32::from(offset)).collect::<Vec<_>>(), [0, 3, 3]);
+
+    let token_at = |forest: &green::Forest, offset: u32| {
+        forest.token_at_offset(offset.into()).map(|(start, token)| (start, token.text().to_owned()))
+    };
+    assert_eq!(token_at(&forest, 1), Some((1.into(), "bc".into())));
+    assert_eq!(token_at(&forest, 3), Some((3.into(), "de".into())));
+    assert_eq!(token_at(&forest, 5), None);
+
+    let ab = builder.start_node(LIST).token(ATOM, "ab").finish_node().finish();
+    assert_eq!(forest.replace_chunk(0, ab), first);
+    assert_eq!(forest.chunk_at_offset(2.into()), Some(2));
+    assert_eq!(token_at(&forest, 2), Some((2.into(), "de".into())));
+
+    forest.remove_chunk(0);
+    assert_eq!(forest.chunks().map(|(offset, _)| u32::from(offset)).collect::<Vec<_>>(), [0, 0]);
+    assert_eq!(token_at(&forest, 0), Some((0.into(), "de".into())));
+}
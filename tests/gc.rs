@@ -33,3 +33,119 @@ fn works_properly() {
     builder.builder().gc();
     assert_eq!(builder.builder().size(), 0);
 }
+
+#[test]
+fn token_values_collected_with_tokens() {
+    let mut builder = green::Builder::new();
+    let kept = builder.token_with_value(Kind(0), "1", 1_u32);
+    let dropped = builder.token_with_value(Kind(0), "2", 2_u32);
+    assert_eq!(builder.token_value::<u32>(&dropped), Some(&2));
+
+    drop(dropped);
+    builder.gc();
+    assert_eq!(builder.token_value::<u32>(&kept), Some(&1));
+    let recreated = builder.token(Kind(0), "2");
+    assert_eq!(builder.token_value::<u32>(&recreated), None);
+}
+
+#[test]
+fn kind_histogram_counts_cached_elements() {
+    let mut builder = green::TreeBuilder::new();
+
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(Kind(2))
+            .token(Kind(0), " ")
+            .token(Kind(0), "  ")
+            .token(Kind(0), " ")
+            .token(Kind(1), "x")
+        .finish_node()
+        .finish();
+
+    let histogram = builder.builder().kind_histogram();
+    let counts: Vec<_> =
+        histogram.iter().map(|(kind, stats)| (kind.0, stats.nodes, stats.tokens)).collect();
+    assert_eq!(counts, [(0, 0, 2), (1, 0, 1), (2, 1, 0)]);
+
+    drop(tree);
+    builder.builder().gc();
+    assert!(builder.builder().kind_histogram().is_empty());
+}
+
+#[test]
+fn pool_keeps_frozen_trees_alive() {
+    let mut builder = green::TreeBuilder::new();
+    let tree = builder.start_node(Kind(1)).token(Kind(0), "kind").finish_node().finish();
+
+    let pool = green::Pool::new();
+    let frozen = pool.freeze(tree);
+    assert_eq!(pool.len(), 1);
+    builder.builder().gc();
+    assert_eq!(builder.builder().size(), 2);
+    assert_eq!(frozen.children_len(), 1);
+
+    drop(pool);
+    builder.builder().gc();
+    assert_eq!(builder.builder().size(), 0);
+}
+
+#[test]
+fn evict_hooks_see_each_element_once() {
+    use std::sync::{Arc, Mutex};
+
+    let evicted = Arc::new(Mutex::new(vec![]));
+    let mut builder = green::TreeBuilder::new();
+    builder.builder().on_evict({
+        let evicted = Arc::clone(&evicted);
+        move |element| evicted.lock().unwrap().push((element.is_node(), element.len()))
+    });
+
+    let shared = builder.start_node(Kind(1)).token(Kind(0), "ab").finish_node().finish();
+    let kept = builder.start_node(Kind(2)).add(shared.clone()).finish_node().finish();
+    let dropped = builder
+        .start_node(Kind(3))
+        .add(shared.clone())
+        .add(shared)
+        .token(Kind(0), "c")
+        .finish_node()
+        .finish();
+
+    drop(dropped);
+    builder.builder().gc();
+    assert_eq!(*evicted.lock().unwrap(), [(true, 5.into()), (false, 1.into())]);
+
+    drop(kept);
+    builder.builder().gc();
+    assert_eq!(evicted.lock().unwrap().len(), 5);
+    assert_eq!(builder.builder().size(), 0);
+}
+
+#[test]
+fn clear_evicts_everything_and_advances_epoch() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let evicted = Arc::new(AtomicUsize::new(0));
+    let mut builder = green::TreeBuilder::new();
+    builder.builder().on_evict({
+        let evicted = Arc::clone(&evicted);
+        move |_| {
+            evicted.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    let epoch = builder.builder().epoch();
+
+    let tree = builder.start_node(Kind(1)).token(Kind(0), "a").finish_node().finish();
+    builder.builder().clear();
+    assert_eq!(builder.builder().size(), 0);
+    assert_eq!(evicted.load(Ordering::Relaxed), 2);
+    assert_eq!(builder.builder().epoch(), epoch + 1);
+
+    // elements built before clearing are not shared with elements built after
+    let again = builder.start_node(Kind(1)).token(Kind(0), "a").finish_node().finish();
+    assert!(!Arc::ptr_eq(&tree, &again));
+    builder.builder().gc();
+    assert_eq!(builder.builder().epoch(), epoch + 2);
+}
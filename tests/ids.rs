@@ -0,0 +1,33 @@
+use sorbus::{
+    green::{self, ids::IdMap},
+    Kind, NodeOrToken,
+};
+
+const ATOM: Kind = Kind(0);
+const LIST: Kind = Kind(1);
+
+#[test]
+fn ids_are_dense_and_shared_by_identity() {
+    let mut builder = green::TreeBuilder::new();
+    let inner = builder.start_node(LIST).token(ATOM, "a").finish_node().finish();
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .add(inner.clone())
+            .token(ATOM, "b")
+            .add(inner.clone())
+        .finish_node()
+        .finish();
+
+    let ids = IdMap::new(tree.clone());
+    assert_eq!(ids.len(), 4);
+    assert_eq!(ids.id_of(NodeOrToken::Node(&*tree)), Some(0));
+    assert_eq!(ids.id_of(NodeOrToken::Node(&*inner)), Some(1));
+    for id in 0..ids.len() as u32 {
+        assert_eq!(ids.id_of(ids.get(id).unwrap().as_deref()), Some(id));
+    }
+    assert!(ids.get(4).is_none());
+
+    let other = builder.start_node(LIST).token(ATOM, "c").finish_node().finish();
+    assert_eq!(ids.id_of(NodeOrToken::Node(&*other)), None);
+}
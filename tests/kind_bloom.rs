@@ -0,0 +1,51 @@
+use {
+    sorbus::{
+        green::{BloomRoot, KindBloom},
+        testing::{gen_tree, Profile},
+        Kind, NodeOrToken,
+    },
+    std::collections::BTreeSet,
+};
+
+fn kinds(node: &sorbus::green::Node, kinds: &mut BTreeSet<u16>) {
+    kinds.insert(node.kind().0);
+    for child in node.children() {
+        match child {
+            NodeOrToken::Node(node) => self::kinds(&node, kinds),
+            NodeOrToken::Token(token) => drop(kinds.insert(token.kind().0)),
+        }
+    }
+}
+
+#[test]
+fn bloom_has_exactly_the_bits_of_present_kinds() {
+    for seed in 0..16 {
+        let root = BloomRoot::new(gen_tree(seed, Profile::mixed()));
+        let mut present = BTreeSet::new();
+        kinds(&root, &mut present);
+
+        let mut expected = KindBloom::new();
+        for &kind in &present {
+            assert!(root.maybe_contains_kind(Kind(kind)));
+            expected.insert(Kind(kind));
+        }
+        assert_eq!(root.kind_bloom(), expected);
+        assert_eq!(root.kind_bloom(), KindBloom::of(root.node()));
+    }
+}
+
+#[test]
+fn small_kinds_rarely_collide() {
+    let bits: BTreeSet<_> = (0..16).map(|kind| single(kind).bits()).collect();
+    // with one hash into 64 bits, a few collisions among 16 kinds are expected, but not many
+    assert!(bits.len() >= 12, "only {} distinct bits", bits.len());
+    for kind in 0..16 {
+        assert_eq!(single(kind).bits().count_ones(), 1);
+    }
+}
+
+fn single(kind: u16) -> KindBloom {
+    let mut bloom = KindBloom::new();
+    bloom.insert(Kind(kind));
+    bloom
+}
@@ -0,0 +1,143 @@
+use sorbus::{
+    green,
+    language::{AstNode, Language, Names, SyntaxNode},
+    Kind, NodeOrToken,
+};
+
+sorbus::syntax_kind! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum LispKind {
+        Whitespace,
+        Comment,
+        Atom,
+        List,
+    }
+}
+
+enum Lisp {}
+impl Language for Lisp {
+    type Kind = LispKind;
+
+    fn is_trivia(kind: LispKind) -> bool {
+        matches!(kind, LispKind::Whitespace | LispKind::Comment)
+    }
+
+    fn kind_name(kind: LispKind) -> Option<&'static str> {
+        match kind {
+            LispKind::Atom => Some("ATOM"),
+            LispKind::List => Some("LIST"),
+            _ => None,
+        }
+    }
+}
+
+struct List(SyntaxNode<Lisp>);
+impl AstNode<Lisp> for List {
+    fn can_cast(kind: LispKind) -> bool {
+        kind == LispKind::List
+    }
+
+    fn cast(syntax: SyntaxNode<Lisp>) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(List(syntax))
+        } else {
+            None
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode<Lisp> {
+        &self.0
+    }
+}
+
+fn raw(kind: LispKind) -> Kind {
+    sorbus::SyntaxKind::into_raw(kind)
+}
+
+/// `a ;c\n b b`, with the `b`s in lists
+fn make_tree() -> SyntaxNode<Lisp> {
+    #[rustfmt::skip]
+    let tree = green::TreeBuilder::new()
+        .start_node(raw(LispKind::List))
+            .token(raw(LispKind::Atom), "a")
+            .token(raw(LispKind::Whitespace), " ")
+            .token(raw(LispKind::Comment), ";c\n")
+            .token(raw(LispKind::Whitespace), " ")
+            .start_node(raw(LispKind::List))
+                .token(raw(LispKind::Atom), "b")
+            .finish_node()
+            .token(raw(LispKind::Whitespace), " ")
+            .start_node(raw(LispKind::List))
+                .token(raw(LispKind::Atom), "b")
+            .finish_node()
+        .finish_node()
+        .finish();
+    SyntaxNode::new_root(tree)
+}
+
+#[test]
+fn typed_navigation() {
+    let root = make_tree();
+    let lists: Vec<_> = root.children().collect();
+    assert_eq!(lists.len(), 2);
+    // the two inner lists share a green node, but are different syntax nodes
+    assert!(std::sync::Arc::ptr_eq(lists[0].green(), lists[1].green()));
+    assert_ne!(lists[0], lists[1]);
+    assert_eq!(lists[1].parent(), Some(root.clone()));
+    assert_eq!(root.children().nth(1), Some(lists[1].clone()));
+    assert_eq!(lists[1].ancestors().count(), 2);
+    assert_eq!(lists[1].text_range(), sorbus::TextRange::new(8.into(), 9.into()));
+
+    let b = lists[1].child(0).unwrap().unwrap_token();
+    assert_eq!((b.kind(), b.text()), (LispKind::Atom, "b"));
+    assert_eq!(b.text_range(), sorbus::TextRange::new(8.into(), 9.into()));
+    assert_eq!(b.parent(), &lists[1]);
+
+    assert!(root.clone().cast::<List>().is_some());
+    let atom = root.children_with_tokens().find_map(NodeOrToken::into_token).unwrap();
+    assert_eq!(atom.kind(), LispKind::Atom);
+    assert!(!atom.is_trivia());
+}
+
+#[test]
+fn typed_trivia() {
+    let root = make_tree();
+    let lists: Vec<_> = root.children().collect();
+    let leading: Vec<_> = lists[0].leading_trivia().map(|token| token.kind()).collect();
+    assert_eq!(leading, [LispKind::Whitespace, LispKind::Comment, LispKind::Whitespace]);
+    assert!(lists[0].leading_trivia().all(|token| token.is_trivia()));
+    let trailing: Vec<_> =
+        lists[0].trailing_trivia().map(|token| token.text().to_owned()).collect();
+    assert_eq!(trailing, [" "]);
+    assert_eq!(lists[1].trailing_trivia().count(), 0);
+    assert_eq!(root.leading_trivia().count(), 0);
+}
+
+#[test]
+fn typed_debug_tree() {
+    let root = make_tree();
+    assert_eq!(
+        root.debug_tree(),
+        r#"LIST@0..9
+  ATOM@0..1 "a"
+  Whitespace@1..2 " "
+  Comment@2..5 ";c\n"
+  Whitespace@5..6 " "
+  LIST@6..7
+    ATOM@6..7 "b"
+  Whitespace@7..8 " "
+  LIST@8..9
+    ATOM@8..9 "b"
+"#,
+    );
+    assert_eq!(
+        root.green().to_sexpr(&Names::<Lisp>::new()),
+        root.green().to_sexpr(&|kind| {
+            match kind {
+                Kind(2) => Some("ATOM"),
+                Kind(3) => Some("LIST"),
+                _ => None,
+            }
+        })
+    );
+}
@@ -0,0 +1,57 @@
+//! The in-memory sizes of tree elements, so changes to the layout are visible.
+#![cfg(target_pointer_width = "64")]
+
+use {
+    sorbus::{
+        green::{self, ElementRef, Node, Token},
+        Kind, NodeOrToken,
+    },
+    std::{
+        mem::{align_of_val, size_of, size_of_val},
+        sync::Arc,
+    },
+};
+
+const ATOM: Kind = Kind(0);
+const LIST: Kind = Kind(1);
+
+/// The size of the node header, before the children.
+/// The `node-flags` and `provenance` features extend it by 8 bytes.
+const NODE_HEADER: usize =
+    if cfg!(any(feature = "node-flags", feature = "provenance")) { 16 } else { 8 };
+/// The size of each child: a thin tagged pointer and the child's offset.
+const CHILD: usize = 12;
+
+#[test]
+fn node_sizes() {
+    let mut builder = green::Builder::new();
+    let a = builder.token(ATOM, "a");
+    for len in 0..8 {
+        let node = builder.node(LIST, vec![a.clone(); len]);
+        let size = NODE_HEADER + CHILD * len;
+        // nodes are aligned to 8, so an odd number of children leaves 4 bytes of padding
+        assert_eq!(size_of_val(&*node), (size + 7) / 8 * 8, "node with {} children", len);
+        assert_eq!(align_of_val(&*node), 8);
+    }
+}
+
+#[test]
+fn token_sizes() {
+    let mut builder = green::Builder::new();
+    for &text in &["", "a", "ab", "abc", "abcd", "abcde"] {
+        let token = builder.token(ATOM, text);
+        // 4 bytes of length, 2 of kind, 1 of flags, then the text, aligned to 4
+        assert_eq!(size_of_val(&*token), (7 + text.len() + 3) / 4 * 4, "token {:?}", text);
+    }
+}
+
+#[test]
+fn handle_sizes() {
+    // `Node` and `Token` are dynamically sized, so handles to them are fat pointers;
+    // children are stored as thin pointers, recovering the length from the header
+    assert_eq!(size_of::<Arc<Node>>(), 16);
+    assert_eq!(size_of::<Arc<Token>>(), 16);
+    assert_eq!(size_of::<Option<Arc<Node>>>(), 16);
+    assert_eq!(size_of::<NodeOrToken<Arc<Node>, Arc<Token>>>(), 24);
+    assert_eq!(size_of::<ElementRef<'_>>(), 24);
+}
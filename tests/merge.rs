@@ -0,0 +1,112 @@
+use {
+    sorbus::{
+        green::{self, Conflict},
+        Kind, TextRange,
+    },
+    std::sync::Arc,
+};
+
+const WS: Kind = Kind(0);
+const ATOM: Kind = Kind(1);
+const LIST: Kind = Kind(2);
+const OTHER: Kind = Kind(3);
+
+fn range(start: u32, end: u32) -> TextRange {
+    TextRange::new(start.into(), end.into())
+}
+
+/// Build `(LIST (LIST ATOM...) ...)` from space separated groups of atoms.
+fn build(builder: &mut green::TreeBuilder, text: &str) -> Arc<green::Node> {
+    builder.start_node(LIST);
+    for (i, group) in text.split(' ').enumerate() {
+        if i != 0 {
+            builder.token(WS, " ");
+        }
+        builder.start_node(LIST);
+        for atom in group.chars() {
+            builder.token(ATOM, &atom.to_string());
+        }
+        builder.finish_node();
+    }
+    builder.finish_node().finish()
+}
+
+#[test]
+fn merges_changes_to_different_subtrees() {
+    let mut builder = green::TreeBuilder::new();
+    let base = build(&mut builder, "ab cd ef");
+    let left = build(&mut builder, "xb cd ef");
+    let right = build(&mut builder, "ab cd ey g");
+    let expected = build(&mut builder, "xb cd ey g");
+    let merged = green::merge(&base, &left, &right, builder.builder()).unwrap();
+    assert_eq!(merged, expected);
+
+    // changes to different tokens of the same node merge too
+    let right = build(&mut builder, "ay cd ef");
+    let expected = build(&mut builder, "xy cd ef");
+    assert_eq!(green::merge(&base, &left, &right, builder.builder()), Ok(expected));
+}
+
+#[test]
+fn one_sided_and_identical_changes_are_taken() {
+    let mut builder = green::TreeBuilder::new();
+    let base = build(&mut builder, "ab cd");
+    let left = build(&mut builder, "ab");
+    assert!(Arc::ptr_eq(&green::merge(&base, &left, &base, builder.builder()).unwrap(), &left));
+    assert!(Arc::ptr_eq(&green::merge(&base, &base, &left, builder.builder()).unwrap(), &left));
+    assert!(Arc::ptr_eq(&green::merge(&base, &left, &left, builder.builder()).unwrap(), &left));
+}
+
+#[test]
+fn trees_from_different_builders_merge_by_content() {
+    let base = build(&mut green::TreeBuilder::new(), "ab cd ef");
+    let left = build(&mut green::TreeBuilder::new(), "ab cd ef g");
+    let right = build(&mut green::TreeBuilder::new(), "ab xd ef");
+    let mut builder = green::TreeBuilder::new();
+    let expected = build(&mut builder, "ab xd ef g");
+    assert_eq!(green::merge(&base, &left, &right, builder.builder()), Ok(expected));
+}
+
+#[test]
+fn conflicts_are_all_reported() {
+    let mut builder = green::TreeBuilder::new();
+    let base = build(&mut builder, "ab cd ef");
+    let left = build(&mut builder, "xb cd e");
+    let right = build(&mut builder, "yb cd eg");
+    let conflicts = green::merge(&base, &left, &right, builder.builder()).unwrap_err();
+    let ranges: Vec<_> = conflicts.iter().map(|c: &Conflict| (c.base, c.left, c.right)).collect();
+    assert_eq!(
+        ranges,
+        [(range(0, 1), range(0, 1), range(0, 1)), (range(7, 8), range(7, 7), range(7, 8))]
+    );
+
+    // nodes of different kinds conflict as a whole
+    let right = builder.start_node(OTHER).token(ATOM, "a").token(ATOM, "b").finish_node().finish();
+    let conflicts = green::merge(&base, &left, &right, builder.builder()).unwrap_err();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(
+        conflicts[0].to_string(),
+        "conflicting changes to 0..8 of the base: 0..7 on the left, 0..2 on the right"
+    );
+}
+
+#[test]
+fn long_runs_of_changed_children_are_aligned() {
+    let mut builder = green::TreeBuilder::new();
+    let base = build(&mut builder, "a b c d e f g h i j k l m n o p");
+    let left = build(&mut builder, "A b c e f X g h i j k l m n o P");
+    let right = build(&mut builder, "a b c d e f g h i j K l m n o p");
+    let expected = build(&mut builder, "A b c e f X g h i j K l m n o P");
+    assert_eq!(green::merge(&base, &left, &right, builder.builder()), Ok(expected));
+}
+
+#[test]
+fn token_flags_are_ignored() {
+    let mut classified = green::TreeBuilder::new();
+    classified.builder().set_token_classifier(|kind, _| if kind == ATOM { 1 } else { 0 });
+    let base = build(&mut classified, "ab cd");
+    let right = build(&mut classified, "ab xd");
+    let left = build(&mut green::TreeBuilder::new(), "ab cd");
+    assert_ne!(base, left);
+    assert_eq!(green::merge(&base, &left, &right, classified.builder()), Ok(right));
+}
@@ -0,0 +1,26 @@
+use sorbus::NodeOrToken;
+
+#[test]
+fn combinators() {
+    let node: NodeOrToken<u32, &str> = NodeOrToken::Node(1);
+    let token: NodeOrToken<u32, &str> = NodeOrToken::Token("t");
+
+    assert_eq!(node.map_node(|n| n + 1), NodeOrToken::Node(2));
+    assert_eq!(node.map_token(str::len), NodeOrToken::Node(1));
+    assert_eq!(token.map_token(str::len), NodeOrToken::Token(1));
+    assert_eq!(node.either(|n| n.to_string(), str::to_owned), "1");
+    assert_eq!(token.either(|n| n.to_string(), str::to_owned), "t");
+
+    assert_eq!(node.zip(NodeOrToken::<_, ()>::Node('a')), Some(NodeOrToken::Node((1, 'a'))));
+    assert_eq!(node.zip(token), None);
+
+    assert_eq!(node.node_ok_or("token"), Ok(1));
+    assert_eq!(token.node_ok_or("token"), Err("token"));
+    assert_eq!(token.token_ok_or(()), Ok("t"));
+
+    let mut boxed: NodeOrToken<Box<u32>, Box<str>> = NodeOrToken::Node(Box::new(1));
+    if let NodeOrToken::Node(n) = boxed.as_deref_mut() {
+        *n += 1;
+    }
+    assert_eq!(boxed.as_deref(), NodeOrToken::Node(&2));
+}
@@ -0,0 +1,50 @@
+//! Checks that the `paranoid` feature catches corrupted trees.
+//!
+//! The trees here are corrupted by writing through the raw bytes of a uniquely
+//! owned node or token, which is the kind of mistake the checks guard against.
+
+use {
+    sorbus::{green, Kind},
+    std::{mem, sync::Arc},
+};
+
+const ATOM: Kind = Kind(0);
+const LIST: Kind = Kind(1);
+
+/// The raw bytes of a uniquely owned element.
+fn raw_bytes<T: ?Sized>(element: &mut Arc<T>) -> &mut [u8] {
+    let element = Arc::get_mut(element).expect("element should be uniquely owned");
+    let len = mem::size_of_val(element);
+    unsafe { std::slice::from_raw_parts_mut(element as *mut T as *mut u8, len) }
+}
+
+#[test]
+#[should_panic(expected = "node header disagrees with its text length")]
+fn node_with_wrong_text_len() {
+    let mut builder = green::Builder::new();
+    let atom = builder.token(ATOM, "atom");
+    let mut node = builder.node(LIST, vec![atom.clone(), atom]);
+    drop(builder);
+
+    // NB: the text length is the `u32` at offset 4 of the node header
+    let bytes = raw_bytes(&mut node);
+    let mut text_len = [0; 4];
+    text_len.copy_from_slice(&bytes[4..8]);
+    let text_len = u32::from_ne_bytes(text_len) + 1;
+    bytes[4..8].copy_from_slice(&text_len.to_ne_bytes());
+
+    node.len();
+}
+
+#[test]
+#[should_panic(expected = "text token is not UTF-8")]
+fn token_with_invalid_utf8() {
+    let mut builder = green::Builder::new();
+    let mut token = builder.token(ATOM, "atom");
+    drop(builder);
+
+    let offset = token.bytes().as_ptr() as usize - Arc::as_ptr(&token) as *const u8 as usize;
+    raw_bytes(&mut token)[offset] = 0xFF;
+
+    token.text();
+}
@@ -0,0 +1,64 @@
+use sorbus::{
+    green::{
+        self,
+        project::{self, ArchiveError},
+    },
+    Kind,
+};
+
+const WS: Kind = Kind(0);
+const ATOM: Kind = Kind(1);
+const LIST: Kind = Kind(2);
+
+#[test]
+fn shared_subtrees_are_archived_once() {
+    let mut builder = green::TreeBuilder::new();
+    let mut parse = |text: &str| {
+        builder.start_node(LIST);
+        for line in text.lines() {
+            builder.start_node(LIST);
+            for word in line.split(' ') {
+                builder.token(ATOM, word).token(WS, " ");
+            }
+            builder.finish_node();
+        }
+        builder.finish_node().finish()
+    };
+    let common = "use std::sync::Arc\nuse std::fmt\n";
+    let a = parse(&format!("{}fn a", common));
+    let b = parse(&format!("{}fn b", common));
+    let c = parse(common);
+
+    let separate = project::write(vec![("a", &a)]).len() + project::write(vec![("b", &b)]).len();
+    let bytes = project::write(vec![("a", &a), ("b", &b), ("c", &c)]);
+    assert!(bytes.len() < separate);
+
+    let archive = project::Archive::new(&bytes).unwrap();
+    let mut builder = green::Builder::new();
+    let c2 = archive.load("c", &mut builder).unwrap().unwrap();
+    assert_eq!(builder.size(), 1 + 2 + 4); // the root, its lines, and its distinct tokens
+    let a2 = archive.load("a", &mut builder).unwrap().unwrap();
+    assert_eq!(*a2, *a);
+    assert_eq!(*c2, *c);
+    assert!(archive.load("d", &mut builder).is_none());
+}
+
+#[test]
+fn corrupt_archives() {
+    let mut builder = green::TreeBuilder::new();
+    let tree = builder.start_node(LIST).token(ATOM, "a").finish_node().finish();
+    let bytes = project::write(vec![("a", tree)]);
+
+    assert_eq!(project::Archive::new(b"not an archive").unwrap_err(), ArchiveError::BadMagic);
+    assert_eq!(project::Archive::new(&bytes[..20]).unwrap_err(), ArchiveError::Truncated);
+
+    let archive = project::Archive::new(&bytes[..bytes.len() - 1]).unwrap();
+    let err = archive.load("a", builder.builder()).unwrap().unwrap_err();
+    assert_eq!(err, ArchiveError::Truncated);
+
+    let mut bytes = bytes;
+    *bytes.last_mut().unwrap() = 0xFF; // the last byte of the root's only child index
+    let archive = project::Archive::new(&bytes).unwrap();
+    let err = archive.load("a", builder.builder()).unwrap().unwrap_err();
+    assert_eq!(err, ArchiveError::InvalidIndex);
+}
@@ -0,0 +1,101 @@
+use sorbus::{
+    green::{self, query::Pattern},
+    Kind, TextRange,
+};
+
+const WS: Kind = Kind(0);
+const ATOM: Kind = Kind(1);
+const LIST: Kind = Kind(2);
+
+#[test]
+fn rest_backtracks() {
+    #[rustfmt::skip]
+    let tree = green::TreeBuilder::new()
+        .start_node(LIST)
+            .token(ATOM, "a")
+            .token(WS, " ")
+            .start_node(LIST)
+                .token(ATOM, "b")
+            .finish_node()
+            .token(WS, " ")
+            .token(ATOM, "c")
+        .finish_node()
+        .finish();
+
+    // the last atom, after a nested list
+    let pattern = Pattern::Node(
+        LIST,
+        vec![
+            Pattern::Rest,
+            Pattern::Node(LIST, vec![Pattern::Any.capture("inner")]),
+            Pattern::Rest,
+            Pattern::Kind(ATOM).capture("last"),
+        ],
+    );
+    let captures = pattern.matches(&tree).unwrap();
+    let found: Vec<_> = captures
+        .iter()
+        .map(|capture| {
+            (capture.name, capture.element.unwrap_token().text().to_owned(), capture.range)
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("inner", "b".to_owned(), TextRange::new(2.into(), 3.into())),
+            ("last", "c".to_owned(), TextRange::new(4.into(), 5.into()))
+        ],
+    );
+
+    // failed alternatives don't leave captures behind
+    let pattern = Pattern::Node(
+        LIST,
+        vec![Pattern::Rest, Pattern::Kind(ATOM).capture("atom"), Pattern::Kind(WS)],
+    );
+    let captures = pattern.matches(&tree);
+    assert!(captures.is_none());
+
+    let pattern = Pattern::Node(LIST, vec![Pattern::Kind(ATOM).capture("first"), Pattern::Rest]);
+    assert_eq!(pattern.matches(&tree).unwrap().len(), 1);
+    assert!(Pattern::Kind(ATOM).matches(&tree).is_none());
+}
+
+#[test]
+fn replace_all_bottom_up() {
+    use sorbus::green::query;
+    let mut builder = green::TreeBuilder::new();
+
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .start_node(LIST)
+                .start_node(LIST)
+                    .token(ATOM, "a")
+                .finish_node()
+            .finish_node()
+            .start_node(LIST)
+                .token(ATOM, "b")
+                .token(ATOM, "c")
+            .finish_node()
+        .finish_node()
+        .finish();
+
+    // collapse single-child lists into their child, innermost first
+    let pattern = Pattern::Node(LIST, vec![Pattern::Any.capture("only")]);
+    let (rewritten, count) =
+        query::replace_all(builder.builder(), &tree, &pattern, |_, _, captures| {
+            Some(captures[0].element.upgrade()).filter(|el| el.kind() == ATOM)
+        });
+    assert_eq!(count, 2);
+    assert_eq!(rewritten.children().get(0).unwrap().kind(), ATOM);
+    // the untouched list is shared
+    assert!(std::ptr::eq(
+        rewritten.children().get(1).unwrap().as_node().map(|node| &**node).unwrap(),
+        tree.children().get(1).unwrap().as_node().map(|node| &**node).unwrap(),
+    ));
+
+    // no matches returns the same root
+    let (unchanged, count) = query::replace_all(builder.builder(), &tree, &pattern, |_, _, _| None);
+    assert_eq!(count, 0);
+    assert!(std::sync::Arc::ptr_eq(&unchanged, &tree));
+}
@@ -0,0 +1,56 @@
+use {
+    ropey::Rope,
+    sorbus::{
+        green::{self, rope},
+        Kind, TextRange,
+    },
+    std::sync::Arc,
+};
+
+const WORD: Kind = Kind(0);
+const WS: Kind = Kind(1);
+const ROOT: Kind = Kind(2);
+
+#[test]
+fn multi_chunk_rope() {
+    let words: Vec<String> = (0..500).map(|i| format!("wörd{}", i)).collect();
+    let text = words.join(" ");
+    let source = Rope::from_str(&text);
+    assert!(source.chunks().count() > 1);
+
+    let mut builder = green::TreeBuilder::new();
+    builder.start_node(ROOT);
+    for (i, word) in words.iter().enumerate() {
+        if i != 0 {
+            builder.token(WS, " ");
+        }
+        builder.token(WORD, word);
+    }
+    let tree = builder.finish_node().finish();
+    assert_eq!(rope::verify_text(&tree, source.slice(..)), Ok(()));
+
+    let truncated = source.slice(..source.len_chars() / 2);
+    let mismatch = rope::verify_text(&tree, truncated).unwrap_err();
+    assert_eq!(usize::from(mismatch.offset), truncated.len_bytes());
+    assert!(mismatch.expected.is_empty());
+
+    let edited = Rope::from_str(&text.replace("wörd250", "wörd25x"));
+    let mismatch = rope::verify_text(&tree, edited.slice(..)).unwrap_err();
+    let at = text.find("wörd250").unwrap() + "wörd25".len();
+    assert_eq!(usize::from(mismatch.offset), at);
+    assert_eq!(mismatch.expected, b"x");
+    assert_eq!(mismatch.found, b"0");
+
+    let whole = rope::char_range(source.slice(..), TextRange::up_to(tree.len()));
+    assert_eq!(whole, 0..text.chars().count());
+    let range = TextRange::at((at as u32).into(), 1.into());
+    assert_eq!(
+        rope::text_range(source.slice(..), rope::char_range(source.slice(..), range)),
+        range
+    );
+
+    let mut builder = green::Builder::new();
+    let token = builder.token_from_rope(WORD, source.slice(..));
+    assert_eq!(token.text(), text);
+    assert!(Arc::ptr_eq(&token, &builder.token(WORD, &text)));
+}
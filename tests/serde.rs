@@ -166,7 +166,7 @@ fn tree_de_serialization() {
 }
 
 fn make_token() -> Token {
-    Token { raw: green::Builder::new().token(Kind(!0), "no-this-is-patrick") }
+    Token { raw: green::Builder::new().token(Kind(!1), "no-this-is-patrick") }
 }
 
 #[rustfmt::skip]
@@ -174,7 +174,7 @@ const TOKEN_SER: &[T] = &[
     T::Struct { name: "Token", len: 2 },
         T::Str("kind"),
             T::NewtypeStruct { name: "Kind" },
-                T::U16(!0),
+                T::U16(!1),
         T::Str("text"),
             T::Str("no-this-is-patrick"),
     T::StructEnd,
@@ -184,7 +184,7 @@ const TOKEN_SER: &[T] = &[
 const TOKEN_SEQ: &[T] = &[
     T::Seq { len: Some(2) },
         T::NewtypeStruct { name: "Kind" },
-            T::U16(!0),
+            T::U16(!1),
         T::Str("no-this-is-patrick"),
     T::SeqEnd,
 ];
@@ -294,3 +294,286 @@ fn deduplication_of_tokens_happens() {
     let reserialized = serde_json::to_string_pretty(&node).unwrap();
     assert_eq!(tree_json, reserialized);
 }
+
+fn make_binary_tree() -> Node {
+    #[rustfmt::skip]
+    let tree = green::TreeBuilder::new()
+        .start_node(Kind(2))
+            .token(Kind(0), "0")
+            .binary_token(Kind(1), b"\xFF")
+        .finish_node()
+        .finish();
+    Node { raw: tree }
+}
+
+#[rustfmt::skip]
+const BINARY_TREE_SER: &[T] = &[
+    T::Struct { name: "Node", len: 2 },
+        T::Str("kind"),
+            T::NewtypeStruct { name: "Kind" },
+                T::U16(2),
+        T::Str("children"),
+            T::Seq { len: Some(2) },
+                T::StructVariant { name: "NodeOrToken", variant: "Token", len: 2 },
+                    T::Str("kind"),
+                        T::NewtypeStruct { name: "Kind" },
+                            T::U16(0),
+                    T::Str("text"),
+                        T::Str("0"),
+                T::StructVariantEnd,
+                T::StructVariant { name: "NodeOrToken", variant: "BinaryToken", len: 2 },
+                    T::Str("kind"),
+                        T::NewtypeStruct { name: "Kind" },
+                            T::U16(1),
+                    T::Str("bytes"),
+                        T::Bytes(b"\xFF"),
+                T::StructVariantEnd,
+            T::SeqEnd,
+    T::StructEnd,
+];
+
+#[test]
+fn binary_tree_de_serialization() -> serde_json::Result<()> {
+    assert_tokens(&make_binary_tree(), BINARY_TREE_SER);
+
+    let json = serde_json::to_string(&make_binary_tree())?;
+    let node: Node = serde_json::from_str(&json)?;
+    assert_eq!(node, make_binary_tree());
+    assert_eq!(node.raw.children().nth(1).unwrap().unwrap_token().bytes(), b"\xFF");
+    Ok(())
+}
+
+#[test]
+fn token_with_text_and_bytes() {
+    let both = "token has both `text` and `bytes`";
+    for json in &[
+        r#"{"text":"a","bytes":[97],"kind":0}"#,
+        r#"{"bytes":[97],"text":"a","kind":0}"#,
+        r#"{"kind":0,"text":"a","bytes":[97]}"#,
+        r#"{"kind":0,"bytes":[97],"text":"a"}"#,
+    ] {
+        let err = serde_json::from_str::<Token>(json).unwrap_err().to_string();
+        assert!(err.starts_with(both), "{}: {}", json, err);
+    }
+    let err = serde_json::from_str::<Token>(r#"{"kind":0,"text":"a","text":"b"}"#).unwrap_err();
+    assert!(err.to_string().starts_with("duplicate field `text`"));
+}
+
+#[test]
+fn binary_token_de_serialization() {
+    let token = Token { raw: green::Builder::new().binary_token(Kind(0), b"\0bin") };
+    assert_tokens(
+        &token,
+        &[
+            T::Struct { name: "Token", len: 2 },
+            T::Str("kind"),
+            T::NewtypeStruct { name: "Kind" },
+            T::U16(0),
+            T::Str("bytes"),
+            T::Bytes(b"\0bin"),
+            T::StructEnd,
+        ],
+    );
+}
+
+#[test]
+fn rowan_layout_deserialization() -> serde_json::Result<()> {
+    const WS: Kind = Kind(0);
+    const ATOM: Kind = Kind(1);
+    const LIST: Kind = Kind(2);
+    let kinds = |name: &str| match name {
+        "WS" => Some(WS),
+        "ATOM" => Some(ATOM),
+        "LIST" => Some(LIST),
+        _ => None,
+    };
+
+    // fields in any order, and raw numeric kinds
+    let json = r#"{ "kind": "LIST", "text_range": [0, 4], "children": [
+        { "text": "a", "kind": "ATOM", "text_range": [0, 1] },
+        { "kind": 0, "text_range": [1, 2], "text": " " },
+        { "children": [{ "kind": "ATOM", "text_range": [2, 4], "text": "bc" }], "kind": "LIST" }
+    ] }"#;
+    let mut builder = green::TreeBuilder::new();
+    let tree = builder
+        .builder()
+        .deserialize_rowan(&kinds)
+        .deserialize(&mut serde_json::Deserializer::from_str(json))?;
+    #[rustfmt::skip]
+    let expected = builder
+        .start_node(LIST)
+            .token(ATOM, "a")
+            .token(WS, " ")
+            .start_node(LIST)
+                .token(ATOM, "bc")
+            .finish_node()
+        .finish_node()
+        .finish();
+    assert!(ptr::eq(&*tree, &*expected));
+
+    let error = |json: &str| {
+        let mut builder = green::Builder::new();
+        let seed = builder.deserialize_rowan(&kinds);
+        seed.deserialize(&mut serde_json::Deserializer::from_str(json)).unwrap_err().to_string()
+    };
+    assert!(error(r#"{ "kind": "ATOM", "text": "a" }"#).contains("found a token"));
+    assert!(error(r#"{ "kind": "IDENT", "children": [] }"#).contains("invalid value"));
+    assert!(error(r#"{ "kind": "LIST", "children": [], "text": "" }"#).contains("both"));
+    Ok(())
+}
+
+#[test]
+fn tombstone_kind_deserialization() {
+    let result = serde_json::from_str::<Kind>("65535");
+    if cfg!(feature = "tombstone-kind") {
+        assert_eq!(result.unwrap(), Kind::TOMBSTONE);
+    } else {
+        assert!(result.is_err());
+    }
+    assert_eq!(serde_json::from_str::<Kind>("65534").unwrap(), Kind(65534));
+}
+
+#[test]
+fn named_kind_de_serialization() -> serde_json::Result<()> {
+    let names = |kind| match kind {
+        Kind(1) => Some("ONE"),
+        Kind(2) => Some("TWO"),
+        _ => None,
+    };
+    let kinds = |name: &str| match name {
+        "ONE" => Some(Kind(1)),
+        "TWO" => Some(Kind(2)),
+        _ => None,
+    };
+
+    let tree = make_tree();
+    let json = serde_json::to_string(&tree.raw.serialize_named(&names))?;
+    assert_eq!(
+        json,
+        r#"{"kind":"TWO","children":[{"Token":{"kind":0,"text":"0"}},{"Token":{"kind":"ONE","text":"1"}}]}"#,
+    );
+
+    let mut builder = green::Builder::new();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let round_trip = builder.deserialize_node_named(&kinds).deserialize(&mut deserializer)?;
+    assert_eq!(round_trip, tree.raw);
+
+    let json = r#"{"kind":"THREE","children":[]}"#;
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    assert!(builder.deserialize_node_named(&kinds).deserialize(&mut deserializer).is_err());
+    Ok(())
+}
+
+#[test]
+fn kind_map_deserialization() -> serde_json::Result<()> {
+    let tree = make_tree();
+    let json = serde_json::to_string(&*tree.raw)?;
+
+    let mut builder = green::Builder::new();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let round_trip =
+        builder.deserialize_node_with_kind_map(&[0, 1, 2]).deserialize(&mut deserializer)?;
+    assert_eq!(round_trip, tree.raw);
+
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let swapped =
+        builder.deserialize_node_with_kind_map(&[1, 0, 3]).deserialize(&mut deserializer)?;
+    assert_eq!(swapped.kind(), Kind(3));
+    let kinds: Vec<_> = swapped.children().map(|child| child.kind()).collect();
+    assert_eq!(kinds, [Kind(1), Kind(0)]);
+
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    assert!(builder
+        .deserialize_node_with_kind_map(&[0, 1])
+        .deserialize(&mut deserializer)
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn missing_token_de_serialization() -> serde_json::Result<()> {
+    let mut builder = green::Builder::new();
+    let missing = builder.missing(Kind(0));
+    let tree = builder.node(Kind(1), vec![missing.clone()]);
+    let json = serde_json::to_string(&*tree)?;
+    assert_eq!(json, r#"{"kind":1,"children":[{"Token":{"kind":0,"text":""}}]}"#);
+
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let round_trip = builder.deserialize_node().deserialize(&mut deserializer)?;
+    assert!(Arc::ptr_eq(&round_trip, &tree));
+    let child = round_trip.children().next().unwrap().into_token().unwrap();
+    assert!(ptr::eq(&*child, &*missing));
+    Ok(())
+}
+
+#[test]
+fn document_de_serialization() -> serde_json::Result<()> {
+    let mut document = green::Document::new(make_tree().raw);
+    document.meta.path = Some("src/lib.rs".into());
+    document.meta.language = Some("rust".into());
+
+    let json = serde_json::to_string(&document)?;
+    let mut builder = green::Builder::new();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let round_trip = builder.deserialize_document().deserialize(&mut deserializer)?;
+    assert_eq!(round_trip, document);
+
+    // metadata is optional
+    let json = r#"{"root":{"kind":2,"children":[]}}"#;
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let document = builder.deserialize_document().deserialize(&mut deserializer)?;
+    assert_eq!(document.meta, green::Metadata::default());
+    Ok(())
+}
+
+#[test]
+fn corpus_stats_serialization() -> serde_json::Result<()> {
+    let tree = make_tree().raw;
+    let stats = green::analysis::corpus_stats(vec![&*tree]);
+    let json: serde_json::Value = serde_json::to_value(&stats)?;
+    assert_eq!(json["roots"], 1);
+    assert_eq!(json["unique_nodes"], stats.unique_nodes);
+    let lengths = json["token_lengths"].as_object().unwrap();
+    let total: u64 = lengths.values().map(|count| count.as_u64().unwrap()).sum();
+    assert_eq!(total as usize, stats.token_lengths.len());
+    assert!(json["node_dedup_ratio"].as_f64().unwrap() <= 1.0);
+    Ok(())
+}
+
+#[test]
+fn deserialize_into_current_branch() -> serde_json::Result<()> {
+    let mut tree_builder = green::TreeBuilder::new();
+    let cached = make_tree_with(&mut tree_builder);
+    let json = serde_json::to_string(&cached)?;
+
+    tree_builder.start_node(Kind(3)).token(Kind(0), "0");
+    tree_builder.deserialize_into_current(&mut serde_json::Deserializer::from_str(&json))?;
+    let invalid =
+        tree_builder.deserialize_into_current(&mut serde_json::Deserializer::from_str("{"));
+    assert!(invalid.is_err());
+    let tree = tree_builder.token(Kind(1), "1").finish_node().finish();
+
+    assert_eq!(tree.children_len(), 3);
+    let spliced = tree.children().nth(1).unwrap().into_node().unwrap();
+    assert!(std::ptr::eq(&*cached.raw, &*spliced));
+    Ok(())
+}
+
+#[test]
+fn document_symbol_serialization() -> serde_json::Result<()> {
+    let tree = green::TreeBuilder::new()
+        .start_node(Kind(2))
+        .token(Kind(0), "a")
+        .start_node(Kind(2))
+        .token(Kind(0), "b")
+        .finish_node()
+        .finish_node()
+        .finish();
+    let name_of = |node: &green::Node| Some(node.children().next()?.into_token()?.text().into());
+    let symbols = tree.document_symbols(|_| Some("module"), name_of);
+    assert_eq!(
+        serde_json::to_string(&symbols)?,
+        r#"[{"name":"a","kind":"module","range":[0,2],"children":[{"name":"b","kind":"module","range":[1,2],"children":[]}]}]"#,
+    );
+    Ok(())
+}
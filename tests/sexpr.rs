@@ -0,0 +1,69 @@
+use sorbus::{
+    green::{self, SexprError},
+    Kind,
+};
+
+const WS: Kind = Kind(0);
+const ATOM: Kind = Kind(1);
+const LIST: Kind = Kind(2);
+
+fn names(kind: Kind) -> Option<&'static str> {
+    match kind {
+        WS => Some("WS"),
+        ATOM => Some("ATOM"),
+        LIST => Some("LIST"),
+        _ => None,
+    }
+}
+
+fn kinds(name: &str) -> Option<Kind> {
+    match name {
+        "WS" => Some(WS),
+        "ATOM" => Some(ATOM),
+        "LIST" => Some(LIST),
+        _ => None,
+    }
+}
+
+#[test]
+fn sexpr_round_trip() {
+    let mut builder = green::TreeBuilder::new();
+
+    #[rustfmt::skip]
+    let tree = builder
+        .start_node(LIST)
+            .token(ATOM, "\"quoted\"\n")
+            .token(WS, " ")
+            .start_node(Kind(7))
+                .token(ATOM, "ß\u{301}")
+                .binary_token(ATOM, b"\xFF\\\"x")
+            .finish_node()
+            .start_node(LIST)
+            .finish_node()
+        .finish_node()
+        .finish();
+
+    let sexpr = tree.to_sexpr(&names);
+    assert_eq!(
+        sexpr,
+        r#"(LIST ATOM "\"quoted\"\n" WS " " (7 ATOM "ß\u{301}" ATOM b"\xFF\\\"x") (LIST))"#,
+    );
+    let read = green::from_sexpr(&sexpr, kinds, builder.builder()).unwrap();
+    assert_eq!(read, tree);
+}
+
+#[test]
+fn sexpr_errors() {
+    let mut builder = green::Builder::new();
+    let mut read = |text: &str| green::from_sexpr(text, kinds, &mut builder).map(drop);
+
+    assert_eq!(read("(LIST ATOM \"a\""), Err(SexprError::UnexpectedEnd));
+    assert_eq!(read("(LIST) x"), Err(SexprError::Unexpected { offset: 7 }));
+    assert_eq!(read("(LIST ATOM a)"), Err(SexprError::Unexpected { offset: 11 }));
+    assert_eq!(
+        read("(LIST NOPE \"a\")"),
+        Err(SexprError::UnknownKind { offset: 6, name: "NOPE".into() }),
+    );
+    assert_eq!(read(r#"(LIST ATOM "\x41")"#), Err(SexprError::Unexpected { offset: 12 }));
+    assert_eq!(read(r#"(LIST ATOM b"\x41" 3 "\u{41}")"#), Ok(()));
+}
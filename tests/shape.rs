@@ -0,0 +1,104 @@
+use sorbus::{
+    green::{self, Shape, ShapeError},
+    Kind, KindSet,
+};
+
+const WS: Kind = Kind(0);
+const ATOM: Kind = Kind(1);
+const LIST: Kind = Kind(2);
+const PAIR: Kind = Kind(3);
+
+fn kinds(kinds: &[Kind]) -> KindSet {
+    kinds.iter().copied().collect()
+}
+
+#[test]
+fn validate_reports_paths() {
+    let shapes = [
+        Shape::new(LIST).rest(kinds(&[ATOM, WS, LIST, PAIR])),
+        Shape::new(PAIR).children(vec![kinds(&[ATOM]), kinds(&[WS]), kinds(&[ATOM])]),
+    ];
+
+    #[rustfmt::skip]
+    let tree = green::TreeBuilder::new()
+        .start_node(LIST)
+            .start_node(PAIR)
+                .token(ATOM, "a")
+                .token(WS, " ")
+                .token(ATOM, "b")
+            .finish_node()
+            .token(WS, " ")
+            .start_node(LIST)
+                .start_node(PAIR)
+                    .token(ATOM, "c")
+                    .token(ATOM, "d")
+                    .token(ATOM, "e")
+                .finish_node()
+            .finish_node()
+        .finish_node()
+        .finish();
+
+    assert!(tree.conforms(&shapes[0]));
+    assert!(!tree.conforms(&shapes[1]));
+    let err = tree.validate(&shapes).unwrap_err();
+    assert_eq!(err, ShapeError::ChildKind { path: vec![2, 0], index: 1, found: ATOM });
+    assert_eq!(err.path(), &[2, 0]);
+    assert_eq!(err.to_string(), "child 1 of node at [2, 0] is of unexpected kind Kind(1)");
+
+    let pair = green::TreeBuilder::new().start_node(PAIR).token(ATOM, "a").finish_node().finish();
+    assert_eq!(
+        shapes[1].check(&pair).unwrap_err(),
+        ShapeError::Arity { path: vec![], expected: 3, found: 1 },
+    );
+    assert!(pair.validate(&shapes[..1]).is_ok());
+}
+
+#[test]
+fn fields_match_in_order() {
+    use sorbus::green::{FieldSpec, Fields};
+    let spec = FieldSpec::new(LIST)
+        .field("first", kinds(&[ATOM]))
+        .field("nested", kinds(&[LIST]))
+        .field("second", kinds(&[ATOM]))
+        .field("third", kinds(&[ATOM]));
+
+    // (a b (c))
+    #[rustfmt::skip]
+    let tree = green::TreeBuilder::new()
+        .start_node(LIST)
+            .token(ATOM, "a")
+            .token(WS, " ")
+            .token(ATOM, "b")
+            .start_node(LIST)
+                .token(ATOM, "c")
+            .finish_node()
+        .finish_node()
+        .finish();
+
+    let fields = Fields::new(&tree, &spec).unwrap();
+    assert_eq!(fields.index("first"), Some(0));
+    // `b` is skipped to match `nested`, so nothing is left for `second` and `third`
+    assert_eq!(fields.index("nested"), Some(3));
+    assert_eq!(fields.index("second"), None);
+    assert_eq!(fields.field("third"), None);
+    assert_eq!(fields.field("nested").unwrap().kind(), LIST);
+
+    let nested = fields.field("nested").unwrap().into_node().unwrap();
+    let fields = Fields::new(&nested, &spec).unwrap();
+    assert_eq!((fields.index("first"), fields.index("nested")), (Some(0), None));
+    assert_eq!(fields.index("second"), None);
+
+    let atom_spec = FieldSpec::new(ATOM);
+    assert_eq!(
+        Fields::new(&tree, &atom_spec).unwrap_err(),
+        ShapeError::Kind { path: vec![], expected: ATOM, found: LIST }
+    );
+}
+
+#[test]
+#[should_panic(expected = "no field named \"fourth\"")]
+fn fields_unknown_name() {
+    let tree = green::TreeBuilder::new().start_node(LIST).finish_node().finish();
+    let spec = green::FieldSpec::new(LIST);
+    green::Fields::new(&tree, &spec).unwrap().index("fourth");
+}
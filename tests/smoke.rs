@@ -109,17 +109,20 @@ fn make_math_tree() {
     assert!(Arc::ptr_eq(&ws, &builder.token(WS, " ")));
 
     // builder.node accepts iterator of Arc<Node>, Arc<Token>, or NodeOrToken<Arc<Node>, Arc<Token>>
-    // so if you're mixing nodes and tokens, you need to include the type changing boilerplate.
+    // so if you're mixing nodes and tokens, convert them (or references to them) to NodeOrToken.
+    // The first element needs to name NodeOrToken so that the array element type is known.
     // You'll know if you need the bottom-up builder (LR or such). Use TreeBuilder otherwise.
-    let n = |node: &Arc<green::Node>| NodeOrToken::from(node.clone());
-    let t = |token: &Arc<green::Token>| NodeOrToken::from(token.clone());
-
-    // We use vec![] as a quick and easy ExactSizeIterator.
-    // Particular implementations may use specialized iterators for known child array lengths.
-    // (Please, const-generic angels, give us `[_; N]: IntoIterator` sooner rather than later!)
-    let inner_mul = builder.node(EXPR, vec![n2, ws.clone(), mul, ws.clone(), n3]);
-    let left_add = builder.node(EXPR, vec![t(&n1), t(&ws), t(&add), t(&ws), n(&inner_mul)]);
-    let right_add = builder.node(EXPR, vec![n(&left_add), t(&ws), t(&add), t(&ws), t(&n4)]);
+
+    // Arrays of children can be passed directly, without allocating a Vec.
+    let inner_mul = builder.node(EXPR, [n2, ws.clone(), mul, ws.clone(), n3]);
+    let left_add = builder.node(
+        EXPR,
+        [NodeOrToken::from(n1), (&ws).into(), (&add).into(), (&ws).into(), inner_mul.into()],
+    );
+    let right_add = builder.node(
+        EXPR,
+        [NodeOrToken::Node(&left_add).cloned(), (&ws).into(), add.into(), ws.into(), n4.into()],
+    );
 
     let tree = right_add;
 
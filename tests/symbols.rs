@@ -0,0 +1,76 @@
+use sorbus::{
+    green::{self, DocumentSymbol, Node},
+    Kind, TextRange,
+};
+
+const WS: Kind = Kind(0);
+const NAME: Kind = Kind(1);
+const FILE: Kind = Kind(2);
+const ITEM: Kind = Kind(3);
+const BLOCK: Kind = Kind(4);
+
+fn name_of(node: &Node) -> Option<String> {
+    let name = node.children().find(|el| el.kind() == NAME)?;
+    Some(name.into_token()?.text().to_string())
+}
+
+fn outline(symbols: &[DocumentSymbol<Kind>]) -> Vec<(String, TextRange, usize)> {
+    let mut flat = vec![];
+    for symbol in symbols {
+        flat.push((symbol.name.clone(), symbol.range, symbol.children.len()));
+        flat.extend(outline(&symbol.children));
+    }
+    flat
+}
+
+#[test]
+fn symbols_nest_through_other_nodes() {
+    let mut builder = green::TreeBuilder::new();
+    // `b { c }`, shared between two places in the tree
+    let item = builder
+        .start_node(ITEM)
+        .token(NAME, "b")
+        .start_node(BLOCK)
+        .start_node(ITEM)
+        .token(NAME, "c")
+        .finish_node()
+        .finish_node()
+        .finish_node()
+        .finish();
+    // `a { b { c } <unnamed { b { c } }> }`
+    let tree = builder
+        .start_node(FILE)
+        .start_node(ITEM)
+        .token(NAME, "a")
+        .token(WS, " ")
+        .start_node(BLOCK)
+        .add(item.clone())
+        .start_node(ITEM)
+        .token(WS, " ")
+        .add(item)
+        .finish_node()
+        .finish_node()
+        .finish_node()
+        .finish_node()
+        .finish();
+
+    let classify = |kind| if kind == ITEM { Some(kind) } else { None };
+    let symbols = tree.document_symbols(classify, name_of);
+    let range = |start: u32, end: u32| TextRange::new(start.into(), end.into());
+    assert_eq!(
+        outline(&symbols),
+        vec![
+            ("a".into(), range(0, 7), 2),
+            ("b".into(), range(2, 4), 1),
+            ("c".into(), range(3, 4), 0),
+            ("b".into(), range(5, 7), 1),
+            ("c".into(), range(6, 7), 0),
+        ],
+    );
+    assert_eq!(symbols[0].kind, ITEM);
+
+    let nested = tree.children().next().unwrap().into_node().unwrap();
+    let relative = nested.document_symbols(classify, name_of);
+    assert_eq!(relative, symbols);
+    assert!(tree.document_symbols(|_| None::<Kind>, name_of).is_empty());
+}
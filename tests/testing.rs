@@ -0,0 +1,126 @@
+use {
+    sorbus::{
+        green,
+        testing::{gen_tree, Event, EventAssert, Profile},
+        Kind, NodeOrToken,
+    },
+    std::panic,
+};
+
+const ATOM: Kind = Kind(0);
+const PLUS: Kind = Kind(1);
+const EXPR: Kind = Kind(2);
+
+#[test]
+fn events_match_built_tree() {
+    let mut events = EventAssert::new();
+    let start = events.checkpoint();
+    events.token(ATOM, "1").token(PLUS, "+").token(ATOM, "2");
+    events.start_node_at(start, EXPR).finish_node();
+
+    #[rustfmt::skip]
+    let tree = green::TreeBuilder::new()
+        .start_node(EXPR)
+            .token(ATOM, "1")
+            .token(PLUS, "+")
+            .token(ATOM, "2")
+        .finish_node()
+        .finish();
+
+    events.assert_tree(&tree);
+    assert_eq!(events, EventAssert::from_tree(&tree));
+}
+
+#[test]
+fn mismatch_reports_diff() {
+    let mut events = EventAssert::new();
+    events.start_node(EXPR).token(ATOM, "1").token(ATOM, "2").finish_node();
+
+    let expected = [
+        Event::Start(EXPR),
+        Event::Token(ATOM, "1".into()),
+        Event::Token(PLUS, "+".into()),
+        Event::Token(ATOM, "2".into()),
+        Event::Finish,
+    ];
+    let panic = panic::catch_unwind(|| events.assert_events(&expected)).unwrap_err();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert_eq!(
+        message,
+        "events do not match (-expected +actual):
+  START Kind(2)
+    TOKEN Kind(0) \"1\"
+-   TOKEN Kind(1) \"+\"
+    TOKEN Kind(0) \"2\"
+  FINISH
+"
+    );
+}
+
+#[test]
+fn parse_into_either_sink() {
+    use sorbus::parse::{LexerSource, TokenSource, TreeSink};
+
+    fn bump(source: &mut impl TokenSource, sink: &mut impl TreeSink) {
+        let (kind, text) = source.current().unwrap();
+        sink.token(kind, text);
+        source.bump();
+    }
+
+    // atom (+ atom)*, left associative
+    fn parse_sum(source: &mut impl TokenSource, sink: &mut impl TreeSink) {
+        let start = sink.checkpoint();
+        bump(source, sink);
+        while let Some((PLUS, _)) = source.current() {
+            bump(source, sink);
+            bump(source, sink);
+            sink.start_node_at(start, EXPR);
+            sink.finish_node();
+        }
+    }
+
+    let tokens = "a+b+c".chars().map(|c| (if c == '+' { PLUS } else { ATOM }, c.to_string()));
+    let mut builder = green::TreeBuilder::new();
+    parse_sum(&mut LexerSource::new(tokens.clone()), &mut builder);
+    let tree = builder.finish();
+    assert_eq!(tree.children_len(), 3);
+
+    let mut events = EventAssert::new();
+    parse_sum(&mut LexerSource::new(tokens), &mut events);
+    events.assert_tree(&tree);
+}
+
+#[test]
+fn generated_trees_follow_their_profile() {
+    /// The maximum depth, and the numbers of whitespace and other tokens.
+    fn stats(node: &green::Node) -> (usize, usize, usize) {
+        let (mut depth, mut whitespace, mut tokens) = (0, 0, 0);
+        for child in node.children() {
+            match child {
+                NodeOrToken::Node(node) => {
+                    let (d, w, t) = stats(&node);
+                    depth = depth.max(d + 1);
+                    whitespace += w;
+                    tokens += t;
+                }
+                NodeOrToken::Token(token) if token.kind() == Kind(0) => whitespace += 1,
+                NodeOrToken::Token(_) => tokens += 1,
+            }
+        }
+        (depth, whitespace, tokens)
+    }
+
+    for profile in &[Profile::wide_flat(), Profile::deep_narrow(), Profile::mixed()] {
+        let profile = Profile { tokens: 2000, ..*profile };
+        let tree = gen_tree(1, profile);
+        assert_eq!(tree, gen_tree(1, profile));
+        let (depth, whitespace, tokens) = stats(&tree);
+        assert_eq!(tokens, 2000);
+        assert!(depth <= profile.max_depth);
+        assert_eq!(whitespace > 0, profile.whitespace);
+    }
+
+    let (deep, _, _) = stats(&gen_tree(2, Profile::deep_narrow()));
+    let (flat, _, _) = stats(&gen_tree(2, Profile::wide_flat()));
+    assert!(deep > 100 && flat == 1, "deep: {}, flat: {}", deep, flat);
+}
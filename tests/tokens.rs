@@ -0,0 +1,35 @@
+use {
+    sorbus::{
+        green::{self, tokens::StaticAlphabet},
+        Kind,
+    },
+    std::sync::Arc,
+};
+
+const KW: Kind = Kind(0);
+const PUNCT: Kind = Kind(1);
+const IDENT: Kind = Kind(2);
+
+#[test]
+fn static_alphabet_is_shared_by_builders() {
+    StaticAlphabet::new(vec![(KW, "fn"), (PUNCT, ";"), (KW, "fn")]).install().unwrap();
+    assert!(StaticAlphabet::new(vec![(KW, "let")]).install().is_err());
+    let alphabet = StaticAlphabet::global().unwrap();
+    assert_eq!(alphabet.len(), 2);
+    let fn_kw = alphabet.get(KW, "fn").unwrap();
+    assert!(alphabet.get(IDENT, "fn").is_none());
+
+    let mut a = green::Builder::new();
+    let mut b = green::Builder::new();
+    assert!(Arc::ptr_eq(&a.token(KW, "fn"), fn_kw));
+    assert!(Arc::ptr_eq(&b.token_from_chunks(KW, vec!["f", "n"], 2.into()), fn_kw));
+    assert_eq!((a.size(), b.size()), (0, 0));
+    assert!(!Arc::ptr_eq(&a.token(IDENT, "fn"), fn_kw));
+    assert!(!Arc::ptr_eq(&a.binary_token(KW, b"fn"), fn_kw));
+    assert_eq!(a.size(), 2);
+
+    // alphabet tokens have no flags, so classifying builders skip the alphabet
+    let mut classified = green::Builder::new();
+    classified.set_token_classifier(|_, _| 1);
+    assert_eq!(classified.token(KW, "fn").flags(), 1);
+}
@@ -0,0 +1,116 @@
+use {
+    sorbus::{green, Kind, SyntaxKind},
+    std::{collections::BTreeMap, sync::Arc},
+};
+
+const ATOM: Kind = Kind(1);
+const LIST: Kind = Kind(2);
+
+#[test]
+fn green_elements_as_ordered_keys() {
+    let mut builder = green::TreeBuilder::new();
+    let a = builder.start_node(LIST).token(ATOM, "a").finish_node().finish();
+    let b = builder.start_node(LIST).token(ATOM, "b").finish_node().finish();
+    let ab = builder.start_node(LIST).token(ATOM, "a").token(ATOM, "b").finish_node().finish();
+    let nested = builder.start_node(LIST).node(LIST, vec![b.clone()]).finish_node().finish();
+
+    assert!(a < ab && ab < b);
+    assert!(nested < a, "nodes sort before tokens");
+    assert!(Kind::default() < ATOM);
+
+    let mut map: BTreeMap<Arc<green::Node>, usize> = BTreeMap::new();
+    for (i, node) in vec![&b, &a, &ab].into_iter().enumerate() {
+        map.insert(node.clone(), i);
+    }
+    // look up by `&Node` through `Arc<Node>: Borrow<Node>`
+    assert_eq!(map.get(&*a), Some(&1));
+    assert_eq!(map.keys().map(|node| &**node).collect::<Vec<_>>(), [&*a, &*ab, &*b]);
+
+    let x = builder.builder().token(ATOM, "x");
+    let y = builder.builder().token(Kind(0), "y");
+    assert!(y < x, "tokens sort by kind first");
+}
+
+#[test]
+fn binary_tokens() {
+    let mut builder = green::Builder::new();
+    let text = builder.token(ATOM, "a");
+    let binary = builder.binary_token(ATOM, b"a");
+    let invalid = builder.binary_token(ATOM, b"\xFF\xFE");
+
+    assert_ne!(text, binary);
+    assert!(Arc::ptr_eq(&binary, &builder.binary_token(ATOM, b"a")));
+    assert_eq!(text.bytes(), binary.bytes());
+    assert_eq!(binary.try_text(), None);
+    assert_eq!(invalid.len(), 2.into());
+    assert_eq!(format!("{:?}", text), r#"Token { text_len: 1, kind: Kind(1), text: "a" }"#,);
+    assert_eq!(format!("{:?}", invalid), "Token { text_len: 2, kind: Kind(1), bytes: [255, 254] }",);
+
+    let node = builder.node(LIST, vec![text, invalid]);
+    assert_eq!(node.len(), 3.into());
+}
+
+#[test]
+fn interned_roots_compare_by_content() {
+    use green::InternedRoot;
+
+    let make = |builder: &mut green::TreeBuilder, text| {
+        InternedRoot::new(builder.start_node(LIST).token(ATOM, text).finish_node().finish())
+    };
+    let (mut one, mut two) = (green::TreeBuilder::new(), green::TreeBuilder::new());
+    let a = make(&mut one, "a");
+    let a_again = make(&mut two, "a");
+    let b = make(&mut one, "b");
+
+    assert!(!Arc::ptr_eq(a.node(), a_again.node()));
+    assert_eq!(a.content_hash(), a_again.content_hash());
+    assert_eq!(a, a_again);
+    assert_ne!(a, b);
+}
+
+#[test]
+fn token_slices_and_chars() {
+    use sorbus::TextRange;
+
+    let mut builder = green::Builder::new();
+    let token = builder.token(ATOM, "aé");
+    let range = |start: u32, end: u32| TextRange::new(start.into(), end.into());
+
+    assert_eq!(token.slice(range(0, 1)), Some("a"));
+    assert_eq!(token.slice(range(1, 3)), Some("é"));
+    assert_eq!(token.slice(range(3, 3)), Some(""));
+    assert_eq!(token.slice(range(1, 2)), None);
+    assert_eq!(token.slice(range(2, 4)), None);
+    assert_eq!(builder.binary_token(ATOM, b"a").slice(range(0, 1)), None);
+
+    let chars: Vec<_> = token.char_indices().map(|(offset, c)| (u32::from(offset), c)).collect();
+    assert_eq!(chars, [(0, 'a'), (1, 'é')]);
+}
+
+sorbus::syntax_kind! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum LispKind {
+        Whitespace,
+        /// Doc comments on variants are kept.
+        Atom,
+        List,
+        Error = 0x8000,
+    }
+}
+
+#[test]
+fn syntax_kind_conversions() {
+    for &kind in &[LispKind::Whitespace, LispKind::Atom, LispKind::List, LispKind::Error] {
+        assert_eq!(LispKind::from_raw(kind.into_raw()), Some(kind));
+    }
+    assert_eq!(LispKind::Error.into_raw(), Kind(0x8000));
+    assert_eq!(LispKind::from_raw(Kind(3)), None);
+
+    let mut builder = green::Builder::new();
+    let token = builder.token(ATOM, "a");
+    let node = builder.node(LIST, vec![token.clone()]);
+    assert_eq!(token.kind_as(), Some(LispKind::Atom));
+    assert_eq!(node.kind_as(), Some(LispKind::List));
+    assert_eq!(node.kind_as(), Some(LIST));
+    assert_eq!(builder.token(Kind(7), "?").kind_as::<LispKind>(), None);
+}
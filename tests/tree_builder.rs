@@ -0,0 +1,161 @@
+use sorbus::{
+    green::{self, WhitespacePolicy},
+    Kind,
+};
+
+const WS: Kind = Kind(0);
+const ATOM: Kind = Kind(1);
+const LIST: Kind = Kind(2);
+
+#[test]
+fn tree_builder_fragments_oversized_nodes() {
+    const FRAGMENT: Kind = Kind(3);
+
+    let mut builder = green::TreeBuilder::new();
+    builder.builder().set_fragment_kind(Some(FRAGMENT));
+    let len = usize::from(u16::MAX) + 10;
+    builder.start_node(LIST);
+    for _ in 0..len {
+        builder.token(ATOM, "a");
+    }
+    let tree = builder.finish_node().finish();
+    assert_eq!(tree.kind(), LIST);
+    assert_eq!(tree.len(), (len as u32).into());
+    let fragments: Vec<_> = tree.children().map(|child| child.unwrap_node()).collect();
+    assert_eq!(fragments.len(), 2);
+    assert!(fragments.iter().all(|fragment| fragment.kind() == FRAGMENT));
+    assert_eq!(fragments[1].children_len(), 10);
+
+    // the same, but finished at a checkpoint, with a trailing token
+    builder.start_node(LIST).start_node(LIST);
+    for _ in 0..len {
+        builder.token(ATOM, "a");
+    }
+    let end = builder.checkpoint();
+    builder.token(WS, " ");
+    let tree = builder.finish_node_at(end).finish_node().finish();
+    assert_eq!(tree.children_len(), 2);
+    assert_eq!(tree.children().next().unwrap().unwrap_node().children_len(), 2);
+}
+
+#[test]
+fn whitespace_policy_splits_losslessly() {
+    let mut builder = green::TreeBuilder::new();
+    builder.set_whitespace_policy(Some(WhitespacePolicy { kind: WS, max_run: 4 }));
+
+    let whitespace = ["\n      ", "\r\n\r\n  \t", "\n          ", " "];
+    builder.start_node(LIST);
+    for ws in &whitespace {
+        builder.token(ATOM, "x").token(WS, ws);
+    }
+    let tree = builder.finish_node().finish();
+
+    let pieces: Vec<String> = tree
+        .children()
+        .filter(|el| el.kind() == WS)
+        .map(|el| el.into_token().unwrap().text().to_owned())
+        .collect();
+    #[rustfmt::skip]
+    assert_eq!(pieces, [
+        "\n", "    ", "  ",
+        "\r\n", "\r\n", "  ", "\t",
+        "\n", "    ", "    ", "  ",
+        " ",
+    ]);
+    assert_eq!(usize::from(tree.len()), whitespace.concat().len() + 4);
+    let source: String = whitespace.iter().map(|ws| format!("x{}", ws)).collect();
+    assert_eq!(green::verify_text(&tree, &source), Ok(()));
+
+    // only a handful of distinct whitespace tokens are cached
+    let histogram = builder.builder().kind_histogram();
+    assert_eq!(histogram[&WS].tokens, 6);
+}
+
+#[test]
+fn verify_text_mismatches() {
+    let mut builder = green::TreeBuilder::new();
+    builder.start_node(LIST).token(ATOM, "ab").token(WS, " ");
+    let tree = builder.start_node(LIST).token(ATOM, "c").finish_node().finish_node().finish();
+
+    let mismatch = |source| {
+        let mismatch = green::verify_text(&tree, source).unwrap_err();
+        (u32::from(mismatch.offset), mismatch.expected, mismatch.found)
+    };
+    assert_eq!(mismatch("ax c"), (1, &b"x"[..], &b"b"[..]));
+    assert_eq!(mismatch("ab d"), (3, &b"d"[..], &b"c"[..]));
+    // the source ends early
+    assert_eq!(mismatch("ab"), (2, &b""[..], &b" "[..]));
+    // the tree ends early
+    assert_eq!(mismatch("ab cde"), (4, &b"de"[..], &b""[..]));
+    assert_eq!(
+        green::verify_text(&tree, "ab_c").unwrap_err().to_string(),
+        r#"tree text differs from source at offset 2: expected "_", found " ""#,
+    );
+}
+
+#[test]
+fn finish_node_at_keeps_order() {
+    let mut builder = green::TreeBuilder::new();
+    builder.start_node(LIST).token(ATOM, "a").start_node(LIST).token(ATOM, "b").token(ATOM, "c");
+    let end = builder.checkpoint();
+    builder.token(WS, " ").token(ATOM, "d");
+    let tree = builder.finish_node_at(end).finish_node().finish();
+
+    let expected = green::TreeBuilder::new()
+        .start_node(LIST)
+        .token(ATOM, "a")
+        .start_node(LIST)
+        .token(ATOM, "b")
+        .token(ATOM, "c")
+        .finish_node()
+        .token(WS, " ")
+        .token(ATOM, "d")
+        .finish_node()
+        .finish();
+    assert_eq!(tree, expected);
+}
+
+#[test]
+fn max_depth_flattens_deeper_nodes() {
+    let mut builder = green::TreeBuilder::new();
+    builder.set_max_depth(Some(2));
+    #[rustfmt::skip]
+    builder
+        .start_node(LIST)
+            .start_node(LIST)
+                .token(ATOM, "a")
+                .start_node(LIST)
+                    .token(ATOM, "b")
+                .finish_node()
+            .finish_node();
+    let start = builder.checkpoint();
+    builder.token(ATOM, "c");
+    builder.start_node_at(start, LIST).start_node(LIST).token(ATOM, "d").finish_node();
+    let end = builder.checkpoint();
+    builder.token(ATOM, "e");
+    builder.finish_node_at(end).finish_node();
+    assert!(builder.depth_exceeded());
+    let tree = builder.finish();
+    assert!(!builder.depth_exceeded());
+
+    #[rustfmt::skip]
+    let expected = builder
+        .start_node(LIST)
+            .start_node(LIST)
+                .token(ATOM, "a")
+                .token(ATOM, "b")
+            .finish_node()
+            .start_node(LIST)
+                .token(ATOM, "c")
+                .token(ATOM, "d")
+            .finish_node()
+            .token(ATOM, "e")
+        .finish_node()
+        .finish();
+    assert_eq!(tree, expected);
+
+    builder.set_max_depth(Some(0));
+    let tree = builder.start_node(LIST).start_node(LIST).token(ATOM, "a").finish_node();
+    let tree = tree.finish_node().finish();
+    assert_eq!(tree.children().filter(|el| el.is_node()).count(), 0);
+}
@@ -0,0 +1,30 @@
+use sorbus::{
+    green::{self, Utf16Index},
+    Kind,
+};
+
+#[test]
+fn utf16_offsets_match_std() {
+    let pieces = ["let ", "ß", " = ", "\"€🦀\"", ";", "\n", "日本"];
+    let mut builder = green::TreeBuilder::new();
+    builder.start_node(Kind(1));
+    for (i, piece) in pieces.iter().enumerate() {
+        if i % 3 == 0 {
+            builder.start_node(Kind(2)).token(Kind(0), piece).finish_node();
+        } else {
+            builder.token(Kind(0), piece);
+        }
+    }
+    let tree = builder.finish_node().finish();
+    let text: String = pieces.concat();
+
+    let index = Utf16Index::new(&tree);
+    assert_eq!(tree.len_utf16(), text.encode_utf16().count() as u32);
+    assert_eq!(index.len_utf16(), tree.len_utf16());
+
+    for (offset, _) in text.char_indices().chain(Some((text.len(), ' '))) {
+        let utf16 = text[..offset].encode_utf16().count() as u32;
+        assert_eq!(index.to_utf16((offset as u32).into()), utf16, "at {}", offset);
+        assert_eq!(index.from_utf16(utf16), (offset as u32).into(), "at {}", offset);
+    }
+}
@@ -1,18 +1,105 @@
-use sorbus::{green, Kind};
+use {
+    sorbus::{green, Kind},
+    std::{
+        cell::RefCell,
+        sync::{Arc, Barrier},
+        thread,
+    },
+};
 
-#[test]
-fn whoops_linked_list() {
-    // miri runs out of stack during drop at this size
-    const RECURSION_FACTOR: usize = 16;
-    const KIND: Kind = Kind(0);
+// miri runs out of stack during drop at this size
+const RECURSION_FACTOR: usize = 16;
+/// Deep enough to overflow the stack if dropping recursed.
+const DEEP: usize = if cfg!(miri) { RECURSION_FACTOR } else { 1 << 17 };
+const KIND: Kind = Kind(0);
 
-    let mut builder = green::TreeBuilder::new();
-    for _ in 0..RECURSION_FACTOR {
+fn linked_list_of_depth(builder: &mut green::TreeBuilder, depth: usize) -> Arc<green::Node> {
+    for _ in 0..depth {
         builder.start_node(KIND);
     }
     builder.token(KIND, " ");
-    for _ in 0..RECURSION_FACTOR {
+    for _ in 0..depth {
         builder.finish_node();
     }
-    let _tree = builder.finish();
+    builder.finish()
+}
+
+fn linked_list() -> Arc<green::Node> {
+    linked_list_of_depth(&mut green::TreeBuilder::new(), RECURSION_FACTOR)
+}
+
+#[test]
+fn whoops_linked_list() {
+    let _tree = linked_list();
+}
+
+#[test]
+fn whoops_linked_list_dropped_concurrently() {
+    let tree = linked_list();
+    let barrier = Arc::new(Barrier::new(2));
+    let handle = thread::spawn({
+        let (tree, barrier) = (tree.clone(), barrier.clone());
+        move || {
+            barrier.wait();
+            drop(tree);
+        }
+    });
+    barrier.wait();
+    drop(tree);
+    handle.join().unwrap();
+
+    green::drop_in_background(linked_list());
+}
+
+#[test]
+fn whoops_deep_linked_lists_dropped_concurrently() {
+    const THREADS: usize = 8;
+    let mut builder = green::TreeBuilder::new();
+    let tree = linked_list_of_depth(&mut builder, DEEP);
+    // every other tree shares all but its root with `tree`
+    let trees: Vec<_> = (0..THREADS)
+        .map(|i| match i % 2 {
+            0 => tree.clone(),
+            _ => builder.start_node(Kind(1)).add(tree.clone()).finish_node().finish(),
+        })
+        .collect();
+    drop(builder);
+    drop(tree);
+
+    let barrier = Arc::new(Barrier::new(THREADS));
+    let handles: Vec<_> = trees
+        .into_iter()
+        .enumerate()
+        .map(|(i, tree)| {
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                match i % 4 {
+                    0 | 1 => drop(tree),
+                    _ => green::drop_deep(tree),
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+#[test]
+fn whoops_deep_linked_list_dropped_in_thread_local_destructor() {
+    struct DropDeep(Option<Arc<green::Node>>);
+    impl Drop for DropDeep {
+        fn drop(&mut self) {
+            green::drop_deep(self.0.take().unwrap());
+        }
+    }
+    thread_local! {
+        static TREE: RefCell<Option<DropDeep>> = const { RefCell::new(None) };
+    }
+
+    let tree = linked_list_of_depth(&mut green::TreeBuilder::new(), DEEP);
+    thread::spawn(move || TREE.with(|slot| *slot.borrow_mut() = Some(DropDeep(Some(tree)))))
+        .join()
+        .unwrap();
 }